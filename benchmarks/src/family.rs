@@ -548,9 +548,50 @@ fn bench_family_with_string_labels(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_family_concurrent_insertion(c: &mut Criterion) {
+    use fastmetrics::metrics::{
+        concurrent_family::ConcurrentFamily, counter::Counter, family::Family,
+    };
+
+    const THREADS: u64 = 16;
+    const LABEL_SETS_PER_THREAD: u64 = 1000;
+
+    // Every thread inserts its own distinct label sets, so (almost) every call takes the
+    // insertion path.
+    fn run(with_or_new: impl Fn(&u64) + Sync) {
+        std::thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let with_or_new = &with_or_new;
+                scope.spawn(move || {
+                    for i in 0..LABEL_SETS_PER_THREAD {
+                        with_or_new(black_box(&(thread * LABEL_SETS_PER_THREAD + i)));
+                    }
+                });
+            }
+        });
+    }
+
+    let mut group = c.benchmark_group("family with 16 threads inserting distinct labels");
+    group.bench_function("family", |b| {
+        b.iter_batched(
+            Family::<u64, Counter>::default,
+            |family| run(|labels| family.with_or_new(labels, |counter| counter.inc())),
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("concurrent_family", |b| {
+        b.iter_batched(
+            ConcurrentFamily::<u64, Counter>::default,
+            |family| run(|labels| family.with_or_new(labels, |counter| counter.inc())),
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default()/*.with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)))*/;
-    targets = bench_family_with_empty_labels, bench_family_with_custom_labels, bench_family_with_string_labels
+    targets = bench_family_with_empty_labels, bench_family_with_custom_labels, bench_family_with_string_labels, bench_family_concurrent_insertion
 );
 criterion_main!(benches);
@@ -361,9 +361,95 @@ fn bench_text_encoding(c: &mut Criterion) {
     }
 }
 
+macro_rules! twelve_labels {
+    ($($name:ident),* $(,)?) => {
+        // Encoded with a single batched `LabelSetEncoder::encode_all` call.
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        #[derive(fastmetrics::derive::EncodeLabelSet, fastmetrics::derive::LabelSetSchema)]
+        struct BatchedLabels {
+            $($name: u16,)*
+        }
+
+        // Encoded with one `LabelSetEncoder::encode` call per label.
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        #[derive(fastmetrics::derive::LabelSetSchema)]
+        struct PerLabelLabels {
+            $($name: u16,)*
+        }
+
+        impl fastmetrics::encoder::EncodeLabelSet for PerLabelLabels {
+            fn encode(
+                &self,
+                encoder: &mut dyn fastmetrics::encoder::LabelSetEncoder,
+            ) -> fastmetrics::error::Result<()> {
+                $(encoder.encode(&(stringify!($name), self.$name))?;)*
+                Ok(())
+            }
+        }
+
+        impl BatchedLabels {
+            fn new(value: u16) -> Self {
+                Self { $($name: value,)* }
+            }
+        }
+
+        impl PerLabelLabels {
+            fn new(value: u16) -> Self {
+                Self { $($name: value,)* }
+            }
+        }
+    };
+}
+
+twelve_labels!(l0, l1, l2, l3, l4, l5, l6, l7, l8, l9, l10, l11);
+
+fn bench_text_label_set_encoding(c: &mut Criterion) {
+    use fastmetrics::{
+        format::text,
+        metrics::{counter::Counter, family::Family},
+        registry::Registry,
+    };
+
+    const SERIES: u16 = 1_000;
+
+    let mut group = c.benchmark_group("text::encode_label_set");
+
+    group.bench_function("12 labels * 1000 series: per label", |b| {
+        let mut registry = Registry::default();
+        let family = Family::<PerLabelLabels, Counter>::default();
+        registry.register("requests", "Requests", family.clone()).unwrap();
+        for i in 0..SERIES {
+            family.with_or_new(&PerLabelLabels::new(i), |counter| counter.inc());
+        }
+        let mut buffer = String::new();
+        b.iter(|| {
+            buffer.clear();
+            text::encode(&mut buffer, &registry, text::TextProfile::default()).unwrap();
+            black_box(&mut buffer);
+        });
+    });
+
+    group.bench_function("12 labels * 1000 series: encode_all", |b| {
+        let mut registry = Registry::default();
+        let family = Family::<BatchedLabels, Counter>::default();
+        registry.register("requests", "Requests", family.clone()).unwrap();
+        for i in 0..SERIES {
+            family.with_or_new(&BatchedLabels::new(i), |counter| counter.inc());
+        }
+        let mut buffer = String::new();
+        b.iter(|| {
+            buffer.clear();
+            text::encode(&mut buffer, &registry, text::TextProfile::default()).unwrap();
+            black_box(&mut buffer);
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default()/*.with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)))*/;
-    targets = bench_text_encoding
+    targets = bench_text_encoding, bench_text_label_set_encoding
 );
 criterion_main!(benches);
@@ -1,4 +1,4 @@
-use std::{hint::black_box, sync::atomic::AtomicU64};
+use std::{hint::black_box, sync::atomic::AtomicU64, time::Duration};
 
 use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
 // use pprof::criterion::{Output, PProfProfiler};
@@ -508,6 +508,102 @@ fn bench_histogram(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_counter_contended(c: &mut Criterion) {
+    use std::{sync::Barrier, thread, time::Instant};
+
+    use fastmetrics::metrics::counter::{Counter, ShardedCounter};
+
+    const THREADS: usize = 8;
+
+    // Each of the `THREADS` threads increments `iters` times concurrently, returns the wall time.
+    fn run_concurrently(iters: u64, inc: impl Fn() + Sync) -> Duration {
+        let barrier = Barrier::new(THREADS + 1);
+        thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    barrier.wait();
+                    for _ in 0..iters {
+                        inc();
+                    }
+                    barrier.wait();
+                });
+            }
+            barrier.wait();
+            let start = Instant::now();
+            barrier.wait();
+            start.elapsed()
+        })
+    }
+
+    let mut group = c.benchmark_group("counter(u64)::inc(8 threads)");
+    group.bench_function("fastmetrics: Counter", |b| {
+        let counter = <Counter>::default();
+        b.iter_custom(|iters| run_concurrently(iters, || counter.inc()));
+    });
+    group.bench_function("fastmetrics: ShardedCounter", |b| {
+        let counter = <ShardedCounter>::default();
+        b.iter_custom(|iters| run_concurrently(iters, || counter.inc()));
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("counter(f64)::inc(8 threads)");
+    group.bench_function("fastmetrics: Counter", |b| {
+        let counter = Counter::<f64>::default();
+        b.iter_custom(|iters| run_concurrently(iters, || counter.inc()));
+    });
+    group.finish();
+}
+
+fn bench_histogram_observe_many(c: &mut Criterion) {
+    use std::{sync::Barrier, thread, time::Instant};
+
+    use fastmetrics::metrics::histogram::{Histogram, exponential_buckets};
+
+    const THREADS: usize = 4;
+    const BATCH: usize = 64;
+
+    // Each of the `THREADS` threads records `iters` batches concurrently, returns the wall time.
+    fn run_concurrently(iters: u64, record: impl Fn(&Histogram, &[f64]) + Sync) -> Duration {
+        let histogram = Histogram::new(exponential_buckets(0.005f64, 2f64, 10));
+        let batch = (0..BATCH).map(|_| rand::rng().random_range(0f64..100f64)).collect::<Vec<_>>();
+        let barrier = Barrier::new(THREADS + 1);
+        thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    barrier.wait();
+                    for _ in 0..iters {
+                        record(&histogram, black_box(&batch));
+                    }
+                    barrier.wait();
+                });
+            }
+            barrier.wait();
+            let start = Instant::now();
+            barrier.wait();
+            start.elapsed()
+        })
+    }
+
+    let mut group = c.benchmark_group("histogram::observe_batch(64, 4 threads)");
+    group.bench_function("fastmetrics: observe", |b| {
+        b.iter_custom(|iters| {
+            run_concurrently(iters, |histogram, batch| {
+                for value in batch {
+                    histogram.observe(*value);
+                }
+            })
+        });
+    });
+    group.bench_function("fastmetrics: observe_many", |b| {
+        b.iter_custom(|iters| {
+            run_concurrently(iters, |histogram, batch| {
+                histogram.observe_many(batch.iter().copied());
+            })
+        });
+    });
+    group.finish();
+}
+
 /*
 fn bench_gauge_histogram(c: &mut Criterion) {
     let mut group = c.benchmark_group("gauge_histogram::observe");
@@ -563,6 +659,7 @@ fn bench_stateset(c: &mut Criterion) {
 criterion_group!(
     name = benches;
     config = Criterion::default()/*.with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)))*/;
-    targets = bench_counter, bench_gauge, bench_histogram
+    targets = bench_counter, bench_counter_contended, bench_gauge, bench_histogram,
+        bench_histogram_observe_many
 );
 criterion_main!(benches);
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use fastmetrics::{
+    error::Result,
+    format::{
+        protobuf::{self, ProtobufProfile},
+        text::{self, TextProfile},
+    },
+    registry::Registry,
+};
+
+/// Axum handler encoding the shared [`Registry`] in the format negotiated from the `Accept`
+/// request header.
+///
+/// - `application/openmetrics-text` => OpenMetrics text.
+/// - `application/openmetrics-protobuf` => OpenMetrics protobuf.
+/// - `application/x-protobuf` / `application/vnd.google.protobuf` => Prometheus protobuf.
+/// - anything else (or missing header) => Prometheus text 0.0.4.
+///
+/// Encoding errors are reported as `500 Internal Server Error`.
+pub async fn metrics_handler(
+    State(registry): State<Arc<Registry>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let accept = headers.get(header::ACCEPT).and_then(|value| value.to_str().ok());
+    let format = Format::from_accept(accept);
+    match format.encode(&registry) {
+        Ok(body) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, format.content_type()), (header::VARY, "Accept")],
+            body,
+        )
+            .into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Text(TextProfile),
+    Protobuf(ProtobufProfile),
+}
+
+impl Format {
+    const FALLBACK: Self = Self::Text(TextProfile::PrometheusV0_0_4);
+
+    /// Selects the supported media type with the highest quality value, the first one wins ties.
+    fn from_accept(accept: Option<&str>) -> Self {
+        let Some(accept) = accept else {
+            return Self::FALLBACK;
+        };
+
+        let mut best: Option<(Self, f32)> = None;
+        for segment in accept.split(',') {
+            let mut parts = segment.split(';');
+            let media_type = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+
+            let mut version = None;
+            let mut quality = 1.0_f32;
+            for part in parts {
+                match part.trim().split_once('=') {
+                    Some((key, value)) if key.trim().eq_ignore_ascii_case("q") => {
+                        quality = value.trim().parse().unwrap_or(0.0);
+                    },
+                    Some((key, value)) if key.trim().eq_ignore_ascii_case("version") => {
+                        version = Some(value.trim().trim_matches('"'));
+                    },
+                    _ => {},
+                }
+            }
+
+            let format = match media_type.as_str() {
+                "application/openmetrics-text" if version == Some("0.0.1") => {
+                    Self::Text(TextProfile::OpenMetricsV0_0_1)
+                },
+                "application/openmetrics-text" => Self::Text(TextProfile::OpenMetricsV1_0_0 {
+                    escaping_scheme: Default::default(),
+                }),
+                "application/openmetrics-protobuf" => Self::Protobuf(ProtobufProfile::OpenMetrics1),
+                "application/x-protobuf" | "application/vnd.google.protobuf" => {
+                    Self::Protobuf(ProtobufProfile::Prometheus)
+                },
+                "text/plain" => Self::FALLBACK,
+                _ => continue,
+            };
+            if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+                best = Some((format, quality));
+            }
+        }
+
+        best.map_or(Self::FALLBACK, |(format, _)| format)
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Text(profile) => profile.content_type(),
+            Self::Protobuf(profile) => profile.content_type(),
+        }
+    }
+
+    fn encode(self, registry: &Registry) -> Result<Vec<u8>> {
+        match self {
+            Self::Text(profile) => {
+                let mut output = String::new();
+                text::encode(&mut output, registry, profile)?;
+                Ok(output.into_bytes())
+            },
+            Self::Protobuf(profile) => {
+                let mut output = Vec::new();
+                protobuf::encode(&mut output, registry, profile)?;
+                Ok(output)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_accept() {
+        let openmetrics =
+            Format::Text(TextProfile::OpenMetricsV1_0_0 { escaping_scheme: Default::default() });
+
+        assert_eq!(Format::from_accept(None), Format::FALLBACK);
+        assert_eq!(Format::from_accept(Some("*/*")), Format::FALLBACK);
+        assert_eq!(Format::from_accept(Some("text/plain; version=0.0.4")), Format::FALLBACK);
+        assert_eq!(Format::from_accept(Some("application/openmetrics-text")), openmetrics);
+        assert_eq!(
+            Format::from_accept(Some("application/openmetrics-text; version=0.0.1")),
+            Format::Text(TextProfile::OpenMetricsV0_0_1)
+        );
+        assert_eq!(
+            Format::from_accept(Some("application/x-protobuf")),
+            Format::Protobuf(ProtobufProfile::Prometheus)
+        );
+        assert_eq!(
+            Format::from_accept(Some(
+                "application/openmetrics-text;version=1.0.0;q=0.5,application/x-protobuf;q=0.9"
+            )),
+            Format::Protobuf(ProtobufProfile::Prometheus)
+        );
+        assert_eq!(
+            Format::from_accept(Some("application/x-protobuf;q=0,application/openmetrics-text")),
+            openmetrics
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Instant,
+};
+
+use fastmetrics::{
+    error::Result,
+    metrics::{counter::Counter, histogram::Histogram},
+    registry::{Register, Registry, Unit},
+};
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A Tower middleware tracking the request count and latency of the metrics endpoint itself.
+///
+/// The metrics are shared by all clones of the layer and of the services it wraps. They must be
+/// registered (via the [`Register`] implementation) before the registry is shared with
+/// [`metrics_handler`](crate::metrics_handler).
+///
+/// Registered base names:
+///
+/// - `scrape_requests` — Total number of requests served by the metrics endpoint. (type: counter)
+/// - `scrape_duration` — Latency of the metrics endpoint in seconds. (type: histogram,
+///   unit: seconds)
+#[derive(Clone, Debug, Default)]
+pub struct MetricsLayer {
+    requests: Counter,
+    duration: Histogram,
+}
+
+impl MetricsLayer {
+    /// Creates a new layer whose latency histogram uses the given bucket upper bounds.
+    pub fn with_buckets(buckets: impl IntoIterator<Item = f64>) -> Self {
+        Self { requests: Counter::default(), duration: Histogram::new(buckets) }
+    }
+}
+
+impl Register for MetricsLayer {
+    fn register(&self, registry: &mut Registry) -> Result<()> {
+        registry.register(
+            "scrape_requests",
+            "Total number of requests served by the metrics endpoint",
+            self.requests.clone(),
+        )?;
+        registry.register_with_unit(
+            "scrape_duration",
+            "Latency of the metrics endpoint in seconds",
+            Unit::Seconds,
+            self.duration.clone(),
+        )?;
+        Ok(())
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService { inner, layer: self.clone() }
+    }
+}
+
+/// The service created by [`MetricsLayer`].
+#[derive(Clone, Debug)]
+pub struct MetricsService<S> {
+    inner: S,
+    layer: MetricsLayer,
+}
+
+impl<S, R> Service<R> for MetricsService<S>
+where
+    S: Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = MetricsFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        MetricsFuture {
+            inner: self.inner.call(req),
+            start: Instant::now(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+pin_project! {
+    /// The response future of [`MetricsService`].
+    #[derive(Debug)]
+    pub struct MetricsFuture<F> {
+        #[pin]
+        inner: F,
+        start: Instant,
+        layer: MetricsLayer,
+    }
+}
+
+impl<F: Future> Future for MetricsFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = ready!(this.inner.poll(cx));
+        this.layer.requests.inc();
+        this.layer.duration.observe(this.start.elapsed().as_secs_f64());
+        Poll::Ready(output)
+    }
+}
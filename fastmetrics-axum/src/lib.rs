@@ -0,0 +1,113 @@
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+#![deny(unused_crate_dependencies)]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+mod handler;
+mod layer;
+
+pub use self::{
+    handler::metrics_handler,
+    layer::{MetricsFuture, MetricsLayer, MetricsService},
+};
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode, header},
+        routing::get,
+    };
+    use fastmetrics::{
+        format::{protobuf::ProtobufProfile, text::TextProfile},
+        metrics::counter::Counter,
+        registry::{Register, Registry},
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn router() -> Router {
+        let mut registry = Registry::default();
+        let requests = <Counter>::default();
+        registry
+            .register("http_requests", "Total HTTP requests", requests.clone())
+            .unwrap();
+        requests.inc_by(42);
+
+        let layer = MetricsLayer::default();
+        layer.register(registry.subsystem("metrics").unwrap()).unwrap();
+
+        Router::new()
+            .route("/metrics", get(metrics_handler))
+            .with_state(Arc::new(registry))
+            .layer(layer)
+    }
+
+    async fn scrape(router: Router, accept: Option<&str>) -> (String, Vec<u8>) {
+        let mut request = Request::builder().uri("/metrics");
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+        let response = router.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let content_type = response.headers()[header::CONTENT_TYPE].to_str().unwrap().to_owned();
+        let body = response.into_body().collect().await.unwrap().to_bytes().to_vec();
+        (content_type, body)
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_text_fallback() {
+        let router = router();
+        let (content_type, body) = scrape(router, None).await;
+        assert_eq!(content_type, TextProfile::PrometheusV0_0_4.content_type());
+
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains("# TYPE http_requests counter"), "{body}");
+        assert!(body.contains("http_requests 42"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn test_openmetrics_text() {
+        let router = router();
+        let (content_type, body) = scrape(router, Some("application/openmetrics-text")).await;
+        assert_eq!(
+            content_type,
+            TextProfile::OpenMetricsV1_0_0 { escaping_scheme: Default::default() }.content_type()
+        );
+
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains("# TYPE http_requests counter"), "{body}");
+        assert!(body.contains("http_requests_total 42"), "{body}");
+        assert!(body.ends_with("# EOF\n"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn test_protobuf() {
+        let router = router();
+        let (content_type, body) = scrape(router, Some("application/x-protobuf")).await;
+        assert_eq!(content_type, ProtobufProfile::Prometheus.content_type());
+
+        let needle = b"http_requests";
+        assert!(body.windows(needle.len()).any(|window| window == needle));
+    }
+
+    #[tokio::test]
+    async fn test_layer_tracks_scrapes() {
+        let router = router();
+        scrape(router.clone(), None).await;
+        scrape(router.clone(), None).await;
+
+        let (_, body) = scrape(router, None).await;
+        let body = String::from_utf8(body).unwrap();
+        // the in-flight scrape isn't finished yet when the registry is encoded
+        assert!(body.contains("metrics_scrape_requests 2"), "{body}");
+        assert!(body.contains("metrics_scrape_duration_seconds_count 2"), "{body}");
+    }
+}
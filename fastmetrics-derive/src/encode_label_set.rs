@@ -1,20 +1,23 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Data, DeriveInput, Error, Fields, FieldsNamed, Result};
+use syn::{Data, DeriveInput, Error, Fields, Member, Result};
 
-use crate::{label_attributes::LabelAttributes, utils::wrap_in_const};
+use crate::{
+    label_attributes::{ContainerAttributes, LabelAttributes},
+    utils::{StringValue, wrap_in_const},
+};
 
 pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
     let name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
-    // Only works for structs with named fields
+    // Only works for structs with named fields or tuple structs
     let fields = match &input.data {
         Data::Struct(data) => match &data.fields {
-            Fields::Named(FieldsNamed { named, .. }) => named,
-            _ => {
-                let error =
-                    "#[derive(EncodeLabelSet)] can only be used for structs with named fields.";
+            Fields::Named(fields) => &fields.named,
+            Fields::Unnamed(fields) => &fields.unnamed,
+            Fields::Unit => {
+                let error = "#[derive(EncodeLabelSet)] can only be used for structs with named fields or tuple structs.";
                 return Err(Error::new_spanned(name, error));
             },
         },
@@ -24,59 +27,89 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
         },
     };
 
+    let container_attrs = ContainerAttributes::parse(input)?;
+
     // Process all fields with #[label(...)] attributes
     let parsed_fields = fields
         .iter()
-        .map(|field| Ok((field, LabelAttributes::parse(field)?)))
+        .enumerate()
+        .map(|(index, field)| {
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(index.into()),
+            };
+            Ok((field, member, LabelAttributes::parse(field)?))
+        })
         .collect::<Result<Vec<_>>>()?;
 
-    let encode_stmts = parsed_fields
-        .iter()
-        .map(|(field, attrs)| {
-            let ident = field.ident.as_ref().expect("fields must be named");
-
-            // #[label(skip)] -> no encoding for this field
-            if attrs.label.skip {
-                return Ok(quote! { /* skip */ });
+    // Positional label names ("0", "1", ...) are meaningless when a tuple struct has several
+    // fields, so every label of such a struct must be named explicitly.
+    if fields.len() > 1 {
+        for (field, member, attrs) in &parsed_fields {
+            let needs_name = !attrs.label.skip && !attrs.label.flatten;
+            if matches!(member, Member::Unnamed(_)) && needs_name && attrs.label.rename.is_none() {
+                let error = "fields of tuple structs with multiple fields require an explicit label name, e.g. `#[label(name = \"...\")]`";
+                return Err(Error::new_spanned(field, error));
             }
+        }
+    }
+
+    // Consecutive plain labels are batched into a single `encode_all` call, flattened label sets
+    // are encoded in place to preserve field order.
+    let mut encode_stmts = Vec::new();
+    let mut pending_labels = Vec::new();
+    let mut label_names = Vec::new();
+    for (field, member, attrs) in &parsed_fields {
+        // #[label(skip)] -> no encoding for this field
+        if attrs.label.skip {
+            continue;
+        }
 
-            // #[label(flatten)] -> encode nested label set
-            if attrs.label.flatten {
-                return Ok(quote! {
-                    ::fastmetrics::encoder::EncodeLabelSet::encode(&self.#ident, encoder)?
-                });
-            }
+        // #[label(flatten)] -> encode nested label set
+        if attrs.label.flatten {
+            encode_stmts.extend(flush_pending_labels(&mut pending_labels));
+            encode_stmts.push(quote! {
+                ::fastmetrics::encoder::EncodeLabelSet::encode(&self.#member, encoder)?
+            });
+            continue;
+        }
 
-            // Determine the label name: rename override or field ident
-            let field_name_tokens = if let Some(rename) = &attrs.label.rename {
-                rename.to_token_stream()
-            } else {
-                let ident_str = ident.to_string();
-                quote!(#ident_str)
-            };
+        // Determine the label name: rename override, field ident or field position
+        let member_str = match member {
+            Member::Named(ident) => ident.to_string(),
+            Member::Unnamed(index) => index.index.to_string(),
+        };
+        let field_name = attrs.label.name(&member_str, container_attrs.rename_all);
+
+        // Only literal names can be checked for duplicates at compile time
+        if let StringValue::Literal(lit) = &field_name {
+            let value = lit.value();
+            if label_names.contains(&value) {
+                let error = format!("duplicated label name `{value}`");
+                return Err(Error::new_spanned(field, error));
+            }
+            label_names.push(value);
+        }
+        let field_name_tokens = field_name.to_token_stream();
 
-            Ok(quote! {
-                encoder.encode(&(#field_name_tokens, &self.#ident))?
-            })
-        })
-        .collect::<Result<Vec<_>>>()?;
+        pending_labels.push(quote! { (#field_name_tokens, &self.#member) });
+    }
+    encode_stmts.extend(flush_pending_labels(&mut pending_labels));
 
     let is_empty_exprs = parsed_fields
         .iter()
-        .map(|(field, attrs)| {
-            let ident = field.ident.as_ref().expect("fields must be named");
-
+        .map(|(_, member, attrs)| {
             if attrs.label.skip {
                 // Skipped field contributes nothing
                 Ok(quote! { true })
             } else if attrs.label.flatten {
                 Ok(quote! {
-                    ::fastmetrics::encoder::EncodeLabelSet::is_empty(&self.#ident)
+                    ::fastmetrics::encoder::EncodeLabelSet::is_empty(&self.#member)
                 })
             } else {
                 Ok(quote! {{
                     use ::fastmetrics::encoder::EncodeLabelValue;
-                    EncodeLabelValue::skip_encoding(&self.#ident)
+                    EncodeLabelValue::skip_encoding(&self.#member)
                 }})
             }
         })
@@ -103,3 +136,14 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
 
     Ok(wrap_in_const(input, impl_block))
 }
+
+fn flush_pending_labels(pending_labels: &mut Vec<TokenStream>) -> Option<TokenStream> {
+    let labels = std::mem::take(pending_labels);
+    match labels.as_slice() {
+        [] => None,
+        [label] => Some(quote! { encoder.encode(&#label)? }),
+        labels => Some(quote! {
+            encoder.encode_all(&[#(&#labels as &dyn ::fastmetrics::encoder::EncodeLabel),*])?
+        }),
+    }
+}
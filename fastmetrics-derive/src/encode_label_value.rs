@@ -1,14 +1,21 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Data, DeriveInput, Error, Fields, Result, parse_quote};
+use syn::{
+    Data, DeriveInput, Error, Fields, Meta, Result, Token, parse_quote, punctuated::Punctuated,
+};
 
 use crate::utils::wrap_in_const;
 
 pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
     let name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let via_display = parse_via_display(input)?;
 
     let impl_block = match &input.data {
+        Data::Enum(_) if via_display => {
+            let error = "`via_display` attribute can only be used on single-field tuple structs";
+            return Err(Error::new_spanned(name, error));
+        },
         Data::Enum(data_enum) => {
             if data_enum.variants.is_empty() {
                 let error = "#[derive(EncodeLabelValue)] requires at least one variant in the enum";
@@ -51,6 +58,25 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
             }
         },
         Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 && via_display => {
+                let field_ty = &fields.unnamed[0].ty;
+                let mut generics_with_bound = input.generics.clone();
+                generics_with_bound
+                    .make_where_clause()
+                    .predicates
+                    .push(parse_quote!(#field_ty: ::core::fmt::Display));
+                let (impl_generics, ty_generics, where_clause) =
+                    generics_with_bound.split_for_impl();
+
+                quote! {
+                    #[automatically_derived]
+                    impl #impl_generics ::fastmetrics::encoder::EncodeLabelValue for #name #ty_generics #where_clause {
+                        fn encode(&self, encoder: &mut dyn ::fastmetrics::encoder::LabelEncoder) -> ::fastmetrics::error::Result<()> {
+                            encoder.encode_str_value(&::std::format!("{}", self.0))
+                        }
+                    }
+                }
+            },
             Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
                 let field_ty = &fields.unnamed[0].ty;
                 let mut generics_with_bound = input.generics.clone();
@@ -91,3 +117,28 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
 
     Ok(wrap_in_const(input, impl_block))
 }
+
+/// Parses the struct-level `#[label(via_display)]` attribute.
+fn parse_via_display(input: &DeriveInput) -> Result<bool> {
+    let mut via_display = false;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("label")) {
+        let nested = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        for meta in nested {
+            match meta {
+                // #[label(via_display)]
+                Meta::Path(path) if path.is_ident("via_display") => {
+                    if via_display {
+                        return Err(Error::new_spanned(path, "duplicated `via_display` attribute"));
+                    }
+                    via_display = true;
+                },
+
+                // unrecognized label attribute
+                _ => {
+                    return Err(Error::new_spanned(meta, "unrecognized label attribute"));
+                },
+            }
+        }
+    }
+    Ok(via_display)
+}
@@ -1,6 +1,52 @@
-use syn::{Attribute, Error, Field, Meta, Result, Token, punctuated::Punctuated};
+use proc_macro2::Span;
+use syn::{
+    Attribute, DeriveInput, Error, Expr, ExprLit, Field, Lit, LitStr, Meta, Result, Token,
+    punctuated::Punctuated,
+};
 
-use crate::utils::StringValue;
+use crate::{rename_rule::RenameRule, utils::StringValue};
+
+/// Aggregates all supported `#[label(...)]` attributes found on a label set struct.
+#[derive(Default)]
+pub struct ContainerAttributes {
+    /// Case conversion applied to fields without an explicit `rename`.
+    pub rename_all: Option<RenameRule>,
+}
+
+impl ContainerAttributes {
+    /// Parses every `#[label(...)]` attribute that appears on the provided struct.
+    pub fn parse(input: &DeriveInput) -> Result<Self> {
+        let mut attrs = ContainerAttributes::default();
+
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("label")) {
+            let nested = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+            for meta in nested {
+                match meta {
+                    // #[label(rename_all = "...")]
+                    Meta::NameValue(nv) if nv.path.is_ident("rename_all") => {
+                        if attrs.rename_all.is_some() {
+                            return Err(Error::new_spanned(
+                                nv,
+                                "duplicated `rename_all` attribute",
+                            ));
+                        }
+                        let Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) = &nv.value else {
+                            return Err(Error::new_spanned(nv.value, "expect a string literal"));
+                        };
+                        attrs.rename_all = Some(RenameRule::from_lit(lit)?);
+                    },
+
+                    // unrecognized label attribute
+                    _ => {
+                        return Err(Error::new_spanned(meta, "unrecognized label attribute"));
+                    },
+                }
+            }
+        }
+
+        Ok(attrs)
+    }
+}
 
 /// Aggregates all supported `#[label(...)]` attributes found on a field.
 #[derive(Default)]
@@ -83,11 +129,26 @@ pub struct LabelAttribute {
     pub skip: bool,
     /// Flattens the field, delegating to the nested label set implementation.
     pub flatten: bool,
-    /// Overrides the generated label name.
+    /// Overrides the generated label name, set by `rename = "..."` or `name = "..."`.
     pub rename: Option<StringValue>,
 }
 
 impl LabelAttribute {
+    /// Returns the label name of a field: the `rename` override if any, otherwise the field
+    /// name converted by the struct-level `rename_all` rule.
+    pub fn name(&self, field_name: &str, rename_all: Option<RenameRule>) -> StringValue {
+        match &self.rename {
+            Some(rename) => rename.clone(),
+            None => {
+                let name = match rename_all {
+                    Some(rule) => rule.apply(field_name),
+                    None => field_name.to_owned(),
+                };
+                StringValue::Literal(LitStr::new(&name, Span::call_site()))
+            },
+        }
+    }
+
     /// Parse a `#[label(...)]` attribute.
     fn parse(attr: &Attribute) -> Result<Self> {
         let mut parsed = Self::default();
@@ -123,8 +184,8 @@ impl LabelAttribute {
                     parsed.flatten = true;
                 },
 
-                // #[label(rename = "...")]
-                Meta::NameValue(nv) if nv.path.is_ident("rename") => {
+                // #[label(rename = "...")] or its alias #[label(name = "...")]
+                Meta::NameValue(nv) if nv.path.is_ident("rename") || nv.path.is_ident("name") => {
                     if parsed.rename.is_some() {
                         return Err(Error::new_spanned(nv, "duplicated `rename` attribute"));
                     }
@@ -1,38 +1,46 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Data, DeriveInput, Error, Fields, FieldsNamed, Result};
+use syn::{Data, DeriveInput, Error, Fields, Result};
 
-use crate::{label_attributes::LabelAttributes, utils::wrap_in_const};
+use crate::{
+    label_attributes::{ContainerAttributes, LabelAttributes},
+    utils::wrap_in_const,
+};
 
-/// Expands `#[derive(LabelSetSchema)]` for structs with named fields.
+/// Expands `#[derive(LabelSetSchema)]` for structs with named fields or tuple structs.
 pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
     let name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
-    // Only works for structs with named fields.
+    // Only works for structs with named fields or tuple structs, like `EncodeLabelSet`.
     let fields = match &input.data {
         Data::Struct(data) => match &data.fields {
-            Fields::Named(FieldsNamed { named, .. }) => named,
-            _ => {
-                let error =
-                    "#[derive(LabelSetSchema)] can only be derived for structs with named fields.";
+            Fields::Named(fields) => &fields.named,
+            Fields::Unnamed(fields) => &fields.unnamed,
+            Fields::Unit => {
+                let error = "#[derive(LabelSetSchema)] can only be derived for structs with named fields or tuple structs.";
                 return Err(Error::new_spanned(name, error));
             },
         },
         _ => {
-            let error =
-                "#[derive(LabelSetSchema)] can only be derived for structs with named fields.";
+            let error = "#[derive(LabelSetSchema)] can only be derived for structs.";
             return Err(Error::new_spanned(name, error));
         },
     };
 
+    let container_attrs = ContainerAttributes::parse(input)?;
+
     let parsed_fields = fields
         .iter()
         .map(|field| Ok((field, LabelAttributes::parse(field)?)))
         .collect::<Result<Vec<_>>>()?;
 
-    let push_stmts = parsed_fields.iter().map(|(field, attrs)| {
-        let ident = field.ident.as_ref().expect("fields must be named");
+    let push_stmts = parsed_fields.iter().enumerate().map(|(index, (field, attrs))| {
+        // Same naming as `EncodeLabelSet`: rename override, field ident or field position
+        let member_str = match &field.ident {
+            Some(ident) => ident.to_string(),
+            None => index.to_string(),
+        };
 
         if attrs.label.skip {
             quote! { /*  skip */ }
@@ -44,12 +52,8 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
                 }
             }
         } else {
-            let field_name_tokens = if let Some(rename) = &attrs.label.rename {
-                rename.to_token_stream()
-            } else {
-                let ident_str = ident.to_string();
-                quote!(#ident_str)
-            };
+            let field_name_tokens =
+                attrs.label.name(&member_str, container_attrs.rename_all).to_token_stream();
 
             quote! {
                 names.push(#field_name_tokens);
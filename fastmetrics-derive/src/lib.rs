@@ -12,6 +12,7 @@ mod label_attributes;
 mod label_set;
 mod label_set_schema;
 mod register;
+mod rename_rule;
 mod state_set_value;
 mod utils;
 
@@ -60,6 +61,34 @@ use syn::{DeriveInput, Error, parse_macro_input};
 ///    Fail,
 /// }
 /// ```
+///
+/// Field names can be converted with the struct-level `#[label(rename_all = "...")]` attribute,
+/// which accepts `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case` and
+/// `SCREAMING_SNAKE_CASE`; fields with an explicit `rename` keep their name. Two fields encoded
+/// with the same label name are rejected at compile time.
+///
+/// ```rust
+/// # use fastmetrics_derive::EncodeLabelSet;
+/// #[derive(Clone, Eq, PartialEq, Hash, EncodeLabelSet)]
+/// #[label(rename_all = "SCREAMING_SNAKE_CASE")]
+/// struct Labels {
+///    request_path: &'static str,
+///    #[label(rename = "method")]
+///    http_method: &'static str,
+/// }
+/// ```
+///
+/// Tuple structs are supported as well. A single field is encoded as the label `"0"` unless it
+/// is named with `#[label(name = "...")]`; with multiple fields, every label field must be named.
+///
+/// ```rust
+/// # use fastmetrics_derive::EncodeLabelSet;
+/// #[derive(Clone, Eq, PartialEq, Hash, EncodeLabelSet)]
+/// struct Port(#[label(name = "port")] u16);
+///
+/// #[derive(Clone, Eq, PartialEq, Hash, EncodeLabelSet)]
+/// struct Endpoint(#[label(name = "host")] String, #[label(name = "port")] u16);
+/// ```
 #[proc_macro_derive(EncodeLabelSet, attributes(label))]
 pub fn derive_encode_label_set(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -109,6 +138,15 @@ pub fn derive_encode_label_set(input: TokenStream) -> TokenStream {
 ///    Fail,
 /// }
 /// ```
+///
+/// The label names follow the same rules as `#[derive(EncodeLabelSet)]` (`rename`, `rename_all`,
+/// `name` for tuple struct fields), in field order, so both derives always agree.
+///
+/// ```rust
+/// # use fastmetrics_derive::{EncodeLabelSet, LabelSetSchema};
+/// #[derive(Clone, Eq, PartialEq, Hash, EncodeLabelSet, LabelSetSchema)]
+/// struct Endpoint(#[label(name = "host")] String, #[label(name = "port")] u16);
+/// ```
 #[proc_macro_derive(LabelSetSchema, attributes(label))]
 pub fn derive_label_set_schema(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -183,7 +221,19 @@ pub fn derive_label_set(input: TokenStream) -> TokenStream {
 /// #[derive(EncodeLabelValue)]
 /// struct HttpStatus(u16);
 /// ```
-#[proc_macro_derive(EncodeLabelValue)]
+///
+/// Newtype structs delegate to the `EncodeLabelValue` implementation of the inner type. With
+/// `#[label(via_display)]`, the inner value is encoded as a string using its `Display`
+/// implementation instead, for types that don't implement `EncodeLabelValue`.
+///
+/// ```rust
+/// # use fastmetrics_derive::EncodeLabelValue;
+/// # use std::net::Ipv4Addr;
+/// #[derive(EncodeLabelValue)]
+/// #[label(via_display)]
+/// struct PeerAddr(Ipv4Addr);
+/// ```
+#[proc_macro_derive(EncodeLabelValue, attributes(label))]
 pub fn derive_encode_label_value(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     encode_label_value::expand_derive(&input)
@@ -295,6 +345,55 @@ pub fn derive_state_set_value(input: TokenStream) -> TokenStream {
 /// text::encode(&mut output, &registry, TextProfile::default()).unwrap();
 /// // println!("{}", output);
 /// ```
+///
+/// Fields without `rename`, `help` or `unit` attributes whose type implements `Register` (e.g.
+/// another struct deriving it) are registered through it, the same as with `#[register(flatten)]`:
+///
+/// ```rust
+/// # use fastmetrics::metrics::counter::Counter;
+/// #[derive(Default, fastmetrics_derive::Register)]
+/// struct ServiceMetrics {
+///     /// Total requests
+///     requests: Counter,
+///     // registers `cache_hits`
+///     cache: CacheMetrics,
+/// }
+///
+/// #[derive(Default, fastmetrics_derive::Register)]
+/// struct CacheMetrics {
+///     /// Total cache hits
+///     cache_hits: Counter,
+/// }
+/// ```
+///
+/// Generic structs are supported as well: fields whose type depends on a type parameter get the
+/// required bounds in the generated `impl`, i.e. `Metric + Clone` for metrics and `Register` for
+/// `flatten`/`subsystem` fields.
+///
+/// ```rust
+/// # use fastmetrics::metrics::counter::Counter;
+/// #[derive(fastmetrics_derive::Register)]
+/// struct GenericMetrics<C> {
+///     /// Total requests
+///     requests: C,
+/// }
+///
+/// let metrics = GenericMetrics { requests: <Counter>::default() };
+/// ```
+///
+/// Metric names can be converted with the struct-level `#[register(rename_all = "...")]`
+/// attribute, which accepts `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case` and
+/// `SCREAMING_SNAKE_CASE`; fields with an explicit `rename` keep their name.
+///
+/// ```rust
+/// # use fastmetrics::metrics::counter::Counter;
+/// #[derive(Default, fastmetrics_derive::Register)]
+/// #[register(rename_all = "camelCase")]
+/// struct CacheMetrics {
+///     /// Total cache hits, registered as `cacheHits`
+///     cache_hits: Counter,
+/// }
+/// ```
 #[proc_macro_derive(Register, attributes(register))]
 pub fn derive_register_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -1,15 +1,17 @@
-use proc_macro2::TokenStream;
-use quote::quote;
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use quote::{ToTokens, quote};
 use syn::{
     Attribute, Data, DeriveInput, Error, Expr, ExprLit, Field, Fields, FieldsNamed, Lit, LitStr,
-    Meta, MetaNameValue, Path, Result, Token, punctuated::Punctuated,
+    Meta, MetaNameValue, Path, Result, Token, WherePredicate, parse_quote, punctuated::Punctuated,
 };
 
-use crate::utils::{StringValue, wrap_in_const};
+use crate::{
+    rename_rule::RenameRule,
+    utils::{StringValue, wrap_in_const},
+};
 
 pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
     let name = &input.ident;
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
     // Only works for structs with named fields
     let fields = match &input.data {
@@ -26,11 +28,20 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
         },
     };
 
+    let rename_all = parse_rename_all(input)?;
+
+    // Bounds required by the fields whose type depends on the type parameters of the struct,
+    // e.g. `C: Metric + Clone` for a `counter: C` field
+    let mut generics = input.generics.clone();
+    let type_params = input.generics.type_params().map(|param| &param.ident).collect::<Vec<_>>();
+    let mut field_bounds: Vec<WherePredicate> = Vec::new();
+
     // Generate register code for each field
     let register_stmts = fields
         .into_iter()
         .map(|field| {
             let field_ident = field.ident.as_ref().expect("fields must be named");
+            let field_ty = &field.ty;
             let field_attrs = FieldAttributes::parse(field)?;
 
             // #[register(skip)] -> no encoding for this field
@@ -38,6 +49,21 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
                 return Ok(quote! { /* skip */ });
             }
 
+            if type_params
+                .iter()
+                .any(|param| mentions_type_param(field_ty.to_token_stream(), param))
+            {
+                field_bounds.push(
+                    if field_attrs.register.flatten || field_attrs.register.subsystem.is_some() {
+                        parse_quote!(#field_ty: ::fastmetrics::registry::Register)
+                    } else {
+                        parse_quote!(
+                            #field_ty: ::fastmetrics::registry::Metric + ::core::clone::Clone
+                        )
+                    },
+                );
+            }
+
             // #[register(flatten)] or #[register(subsystem)] -> encode nested or subsystem metrics
             // (both need to call register on the field)
             let is_flatten =
@@ -60,11 +86,14 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
             }
 
             // #[register(rename = "...")] -> override metric name
-            // Get the metric name from rename attribute or field ident
+            // Get the metric name from rename attribute or field ident (converted by `rename_all`)
             let name = match &field_attrs.register.rename {
                 Some(rename) => rename.to_token_stream(),
                 None => {
-                    let field_name = field_ident.to_string();
+                    let field_name = match rename_all {
+                        Some(rule) => rule.apply(&field_ident.to_string()),
+                        None => field_ident.to_string(),
+                    };
                     let name_lit_str = LitStr::new(&field_name, field_ident.span());
                     quote!(#name_lit_str)
                 },
@@ -107,6 +136,15 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
                         )?;
                     }
                 },
+                // no `rename`/`help` attribute either -> the field may implement `Register`
+                None if field_attrs.register.rename.is_none()
+                    && field_attrs.register.help.is_none() =>
+                {
+                    quote! {
+                        (&::fastmetrics::registry::__private::RegisterField(&self.#field_ident))
+                            .register_field(registry, #name, #help)?;
+                    }
+                },
                 None => {
                     quote! {
                         registry.register(
@@ -121,11 +159,17 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
         })
         .collect::<Result<Vec<_>>>()?;
 
+    generics.make_where_clause().predicates.extend(field_bounds);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
     // Generate the `Register` trait implementation
     let impl_block = quote! {
         #[automatically_derived]
         impl #impl_generics ::fastmetrics::registry::Register for #name #ty_generics #where_clause {
             fn register(&self, registry: &mut ::fastmetrics::registry::Registry) -> ::core::result::Result<(), ::fastmetrics::error::Error> {
+                #[allow(unused_imports)]
+                use ::fastmetrics::registry::__private::{RegisterViaMetric as _, RegisterViaRegister as _};
+
                 #(#register_stmts)*
 
                 ::core::result::Result::Ok(())
@@ -136,6 +180,43 @@ pub fn expand_derive(input: &DeriveInput) -> Result<TokenStream> {
     Ok(wrap_in_const(input, impl_block))
 }
 
+/// Checks whether a type refers to the given type parameter, e.g. `Family<L, C>` to `C`.
+fn mentions_type_param(tokens: TokenStream, type_param: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == *type_param,
+        TokenTree::Group(group) => mentions_type_param(group.stream(), type_param),
+        _ => false,
+    })
+}
+
+/// Parses the struct-level `#[register(rename_all = "...")]` attribute.
+fn parse_rename_all(input: &DeriveInput) -> Result<Option<RenameRule>> {
+    let mut rename_all = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("register")) {
+        let nested = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        for meta in nested {
+            match meta {
+                // #[register(rename_all = "...")]
+                Meta::NameValue(nv) if nv.path.is_ident("rename_all") => {
+                    if rename_all.is_some() {
+                        return Err(Error::new_spanned(nv, "duplicated `rename_all` attribute"));
+                    }
+                    let Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) = &nv.value else {
+                        return Err(Error::new_spanned(nv.value, "expect a string literal"));
+                    };
+                    rename_all = Some(RenameRule::from_lit(lit)?);
+                },
+
+                // unrecognized
+                _ => {
+                    return Err(Error::new_spanned(meta, "unrecognized register attribute"));
+                },
+            }
+        }
+    }
+    Ok(rename_all)
+}
+
 #[derive(Default)]
 struct FieldAttributes {
     // #[register(...)]
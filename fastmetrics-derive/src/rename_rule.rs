@@ -0,0 +1,96 @@
+use syn::{Error, LitStr, Result};
+
+/// Case conversion applied by `#[label(rename_all = "...")]` to label names.
+#[derive(Clone, Copy)]
+pub enum RenameRule {
+    /// `lowercase`
+    Lower,
+    /// `UPPERCASE`
+    Upper,
+    /// `PascalCase`
+    Pascal,
+    /// `camelCase`
+    Camel,
+    /// `snake_case`
+    Snake,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+}
+
+impl RenameRule {
+    const RULES: &[(&str, Self)] = &[
+        ("lowercase", Self::Lower),
+        ("UPPERCASE", Self::Upper),
+        ("PascalCase", Self::Pascal),
+        ("camelCase", Self::Camel),
+        ("snake_case", Self::Snake),
+        ("SCREAMING_SNAKE_CASE", Self::ScreamingSnake),
+    ];
+
+    /// Parses the rule from a string literal, e.g. `"snake_case"`.
+    pub fn from_lit(lit: &LitStr) -> Result<Self> {
+        let value = lit.value();
+        Self::RULES
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, rule)| *rule)
+            .ok_or_else(|| {
+                let expected =
+                    Self::RULES.iter().map(|(name, _)| format!("`{name}`")).collect::<Vec<_>>();
+                let error = format!("unknown rename rule, expected one of {}", expected.join(", "));
+                Error::new_spanned(lit, error)
+            })
+    }
+
+    /// Applies the rule to a field name.
+    pub fn apply(self, name: &str) -> String {
+        match self {
+            Self::Lower => name.to_ascii_lowercase(),
+            Self::Upper => name.to_ascii_uppercase(),
+            Self::Pascal => words(name).iter().map(|word| capitalize(word)).collect(),
+            Self::Camel => {
+                let pascal = Self::Pascal.apply(name);
+                let mut chars = pascal.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => pascal,
+                }
+            },
+            Self::Snake => words(name).join("_"),
+            Self::ScreamingSnake => words(name).join("_").to_ascii_uppercase(),
+        }
+    }
+}
+
+/// Splits a `snake_case` or `PascalCase` name into lowercase words.
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for ch in name.chars() {
+        if ch == '_' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if ch.is_ascii_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = ch.is_ascii_lowercase() || ch.is_ascii_digit();
+        current.push(ch.to_ascii_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
@@ -24,6 +24,7 @@ pub fn is_lint_attribute(attr: &Attribute) -> bool {
 }
 
 /// Represents either a string literal or an arbitrary expression that evaluates to a string.
+#[derive(Clone)]
 pub enum StringValue {
     /// String literal: "name"
     Literal(LitStr),
@@ -0,0 +1,12 @@
+use fastmetrics_derive::EncodeLabelSet;
+
+// This should fail because both fields are encoded as the `method` label
+#[derive(EncodeLabelSet)]
+#[label(rename_all = "lowercase")]
+struct Labels {
+    method: &'static str,
+    #[label(rename = "method")]
+    http_method: &'static str,
+}
+
+fn main() {}
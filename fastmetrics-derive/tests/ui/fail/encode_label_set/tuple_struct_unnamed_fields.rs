@@ -0,0 +1,7 @@
+use fastmetrics_derive::EncodeLabelSet;
+
+// This should fail because the fields of a multi-field tuple struct have no meaningful label names
+#[derive(EncodeLabelSet)]
+struct Endpoint(#[label(name = "host")] &'static str, u16);
+
+fn main() {}
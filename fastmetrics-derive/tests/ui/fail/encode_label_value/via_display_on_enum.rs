@@ -0,0 +1,11 @@
+use fastmetrics_derive::EncodeLabelValue;
+
+// This should fail because `via_display` only applies to newtype structs
+#[derive(EncodeLabelValue)]
+#[label(via_display)]
+enum Status {
+    Success,
+    Error,
+}
+
+fn main() {}
@@ -1,6 +1,6 @@
 use fastmetrics_derive::LabelSetSchema;
 
-// This should fail because LabelSetSchema can only be derived for structs with named fields.
+// This should fail because LabelSetSchema can only be derived for structs.
 #[derive(LabelSetSchema)]
 enum NotAStruct {
     Variant,
@@ -32,6 +32,17 @@ enum Error {
     Fail,
 }
 
+#[derive(Clone, Eq, PartialEq, Hash, EncodeLabelSet)]
+struct Port(u16);
+
+#[derive(Clone, Eq, PartialEq, Hash, EncodeLabelSet)]
+struct Endpoint(
+    #[label(name = "host")] &'static str,
+    #[label(rename = "port")] u16,
+    #[label(flatten)] ExtraLabels,
+    #[label(skip)] u64,
+);
+
 fn main() {
     // This just verifies compilation succeeds
     let _labels = Labels {
@@ -40,4 +51,6 @@ fn main() {
         extra: ExtraLabels { region: "us-east-1" },
         _skip: 42,
     };
+    let _port = Port(8080);
+    let _endpoint = Endpoint("localhost", 8080, ExtraLabels { region: "us-east-1" }, 42);
 }
@@ -1,3 +1,10 @@
+use std::net::Ipv4Addr;
+
+use fastmetrics::{
+    format::text::{self, TextProfile},
+    metrics::info::Info,
+    registry::Registry,
+};
 use fastmetrics_derive::EncodeLabelValue;
 
 #[derive(EncodeLabelValue)]
@@ -16,10 +23,29 @@ struct OptionalError(Option<&'static str>);
 #[derive(EncodeLabelValue)]
 struct Wrapper<T>(T);
 
+#[derive(EncodeLabelValue)]
+struct ServiceId(u32);
+
+#[derive(EncodeLabelValue)]
+#[label(via_display)]
+struct PeerAddr(Ipv4Addr);
+
 fn main() {
-    // This just verifies compilation succeeds
     let _status = Status::Success;
     let _code = HttpStatus(200);
     let _error = OptionalError(None);
     let _wrapper = Wrapper("ok");
+
+    let mut registry = Registry::default();
+    registry
+        .register("service", "Service info", Info::new(vec![("id", ServiceId(42))]))
+        .unwrap();
+    registry
+        .register("peer", "Peer info", Info::new(vec![("addr", PeerAddr(Ipv4Addr::LOCALHOST))]))
+        .unwrap();
+
+    let mut output = String::new();
+    text::encode(&mut output, &registry, TextProfile::OpenMetricsV0_0_1).unwrap();
+    assert!(output.contains(r#"service_info{id="42"} 1"#), "{output}");
+    assert!(output.contains(r#"peer_info{addr="127.0.0.1"} 1"#), "{output}");
 }
@@ -1,3 +1,8 @@
+use fastmetrics::{
+    format::text::{self, TextProfile},
+    metrics::{counter::Counter, family::Family},
+    registry::Registry,
+};
 use fastmetrics_derive::{EncodeLabelValue, LabelSet};
 
 #[derive(Clone, Eq, PartialEq, Hash, LabelSet)]
@@ -45,4 +50,18 @@ fn main() {
         <Labels as fastmetrics::raw::LabelSetSchema>::names(),
         Some(&["op", "error", "region"][..])
     );
+
+    // Skipped fields are still part of the family key, but never encoded
+    let mut registry = Registry::default();
+    let operations = Family::<Labels, Counter>::default();
+    registry.register("operations", "Total operations", operations.clone()).unwrap();
+    operations.with_or_new(&labels, |counter| counter.inc());
+
+    let mut output = String::new();
+    text::encode(&mut output, &registry, TextProfile::PrometheusV0_0_4).unwrap();
+    assert!(
+        output.contains(r#"operations{op="Read",error="NotFound",region="us-east-1"} 1"#),
+        "{output}"
+    );
+    assert!(!output.contains("_skip"), "{output}");
 }
@@ -0,0 +1,32 @@
+use fastmetrics::raw::LabelSetSchema as _;
+use fastmetrics_derive::{EncodeLabelSet, LabelSetSchema};
+
+#[derive(Clone, Eq, PartialEq, Hash, EncodeLabelSet, LabelSetSchema)]
+#[label(rename_all = "SCREAMING_SNAKE_CASE")]
+struct Labels {
+    request_path: &'static str,
+    #[label(rename = "method")]
+    http_method: &'static str,
+    #[label(flatten)]
+    endpoint: Endpoint,
+    status_code: u16,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, EncodeLabelSet, LabelSetSchema)]
+struct Endpoint(
+    #[label(name = "host")] &'static str,
+    #[label(skip)] u64,
+    #[label(name = "port")] u16,
+);
+
+#[derive(Clone, Eq, PartialEq, Hash, EncodeLabelSet, LabelSetSchema)]
+struct Port(u16);
+
+fn main() {
+    assert_eq!(
+        Labels::names(),
+        Some(&["REQUEST_PATH", "method", "host", "port", "STATUS_CODE"][..])
+    );
+    assert_eq!(Endpoint::names(), Some(&["host", "port"][..]));
+    assert_eq!(Port::names(), Some(&["0"][..]));
+}
@@ -70,6 +70,17 @@ struct FlattenMetrics {
     flatten_gauge: Gauge,
 }
 
+#[derive(Default, Register)]
+#[register(rename_all = "camelCase")]
+struct CamelCaseMetrics {
+    /// Total cache hits
+    cache_hits: Counter,
+
+    /// Total cache misses
+    #[register(rename = "cache_misses")]
+    cache_misses: Counter,
+}
+
 fn main() {
     let mut registry = Registry::builder().with_namespace("demo").build().unwrap();
 
@@ -79,4 +90,12 @@ fn main() {
     let mut output = String::new();
     text::encode(&mut output, &registry, TextProfile::default()).unwrap();
     // println!("{}", output);
+
+    let mut registry = Registry::default();
+    CamelCaseMetrics::default().register(&mut registry).unwrap();
+
+    let mut output = String::new();
+    text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+    assert!(output.contains("# TYPE cacheHits counter"), "{output}");
+    assert!(output.contains("# TYPE cache_misses counter"), "{output}");
 }
@@ -0,0 +1,57 @@
+use fastmetrics::{
+    error::Result,
+    format::text::{self, TextProfile},
+    metrics::{counter::Counter, gauge::Gauge},
+    registry::{Register, Registry},
+};
+use fastmetrics_derive::Register;
+
+#[derive(Default, Register)]
+struct ServiceMetrics {
+    /// Total requests
+    requests: Counter,
+
+    // implements `Register`, so it's registered through it without `#[register(flatten)]`
+    component: ComponentMetrics,
+
+    // a manual `Register` implementation works the same way
+    pool: PoolMetrics,
+}
+
+#[derive(Default, Register)]
+struct ComponentMetrics {
+    /// Component errors
+    component_errors: Counter,
+
+    nested: NestedMetrics,
+}
+
+#[derive(Default, Register)]
+struct NestedMetrics {
+    /// Nested gauge
+    nested_gauge: Gauge,
+}
+
+#[derive(Default)]
+struct PoolMetrics {
+    connections: Gauge,
+}
+
+impl Register for PoolMetrics {
+    fn register(&self, registry: &mut Registry) -> Result<()> {
+        registry.register("pool_connections", "Open connections", self.connections.clone())?;
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut registry = Registry::default();
+    ServiceMetrics::default().register(&mut registry).unwrap();
+
+    let mut output = String::new();
+    text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+    assert!(output.contains("# TYPE requests counter"), "{output}");
+    assert!(output.contains("# TYPE component_errors counter"), "{output}");
+    assert!(output.contains("# TYPE nested_gauge gauge"), "{output}");
+    assert!(output.contains("# TYPE pool_connections gauge"), "{output}");
+}
@@ -0,0 +1,46 @@
+use fastmetrics::{
+    format::text::{self, TextProfile},
+    metrics::{counter::Counter, family::Family, gauge::Gauge},
+    registry::{Register, Registry},
+};
+use fastmetrics_derive::Register;
+
+// metric implementations are type parameters, e.g. to be replaced in tests
+#[derive(Default, Register)]
+struct Metrics<C: Clone + 'static, G, I> {
+    /// Total requests
+    requests: C,
+
+    /// Requests by method
+    #[register(rename = "requests_by_method")]
+    requests_by_method: Family<(), C>,
+
+    /// In-flight requests
+    #[register(unit(Bytes))]
+    in_flight: G,
+
+    #[register(flatten)]
+    inner: I,
+
+    #[register(subsystem = "sub")]
+    sub: InnerMetrics<G>,
+}
+
+#[derive(Default, Register)]
+struct InnerMetrics<G> {
+    /// Inner gauge
+    inner_gauge: G,
+}
+
+fn main() {
+    let mut registry = Registry::default();
+    let metrics = Metrics::<Counter, Gauge, InnerMetrics<Gauge>>::default();
+    metrics.register(&mut registry).unwrap();
+
+    let mut output = String::new();
+    text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+    assert!(output.contains("# TYPE requests counter"), "{output}");
+    assert!(output.contains("# TYPE in_flight_bytes gauge"), "{output}");
+    assert!(output.contains("# TYPE inner_gauge gauge"), "{output}");
+    assert!(output.contains("# TYPE sub_inner_gauge gauge"), "{output}");
+}
@@ -0,0 +1,36 @@
+use fastmetrics::{
+    format::text::{self, TextProfile},
+    metrics::{counter::Counter, family::Family},
+    raw::LabelSetSchema,
+    registry::Registry,
+};
+use fastmetrics_derive::LabelSet;
+
+#[allow(non_snake_case)]
+#[derive(Clone, Eq, PartialEq, Hash, LabelSet)]
+#[label(rename_all = "lowercase")]
+struct Labels {
+    HttpStatus: u16,
+    #[label(rename = "http_method")]
+    Method: &'static str,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, LabelSet)]
+#[label(rename_all = "SCREAMING_SNAKE_CASE")]
+struct ScreamingLabels {
+    request_path: &'static str,
+}
+
+fn main() {
+    assert_eq!(Labels::names(), Some(&["httpstatus", "http_method"][..]));
+    assert_eq!(ScreamingLabels::names(), Some(&["REQUEST_PATH"][..]));
+
+    let mut registry = Registry::default();
+    let requests = Family::<Labels, Counter>::default();
+    registry.register("requests", "Total requests", requests.clone()).unwrap();
+    requests.with_or_new(&Labels { HttpStatus: 200, Method: "GET" }, |counter| counter.inc());
+
+    let mut output = String::new();
+    text::encode(&mut output, &registry, TextProfile::PrometheusV0_0_4).unwrap();
+    assert!(output.contains(r#"requests{httpstatus="200",http_method="GET"} 1"#), "{output}");
+}
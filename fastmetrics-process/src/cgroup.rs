@@ -0,0 +1,186 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use fastmetrics::{
+    error::Result,
+    metrics::{counter::LazyCounter, gauge::LazyGauge, lazy_group::LazyGroup},
+    registry::{Register, Registry, Unit},
+};
+
+use crate::u64_to_i64_saturating;
+
+/// The environment variable overriding the mount point of the cgroup v2 hierarchy.
+pub const CGROUP_PATH_ENV: &str = "CGROUP_PATH";
+
+const DEFAULT_CGROUP_PATH: &str = "/sys/fs/cgroup";
+
+/// A set of cgroup v2 metrics, exposing the resource limits and usage of the container the
+/// process runs in (Linux only).
+///
+/// This type implements [`fastmetrics::registry::Register`].
+///
+/// To get `cgroup_*` metric names, register into `registry.subsystem("cgroup")?`. The values are
+/// read from the cgroup v2 files under `/sys/fs/cgroup` (or the `CGROUP_PATH` environment
+/// variable); values of missing files fall back to `0`, and unlimited limits are exposed as `-1`.
+#[derive(Clone)]
+pub struct CgroupMetrics {
+    memory_limit_bytes: LazyGauge<i64>,
+    memory_usage_bytes: LazyGauge<i64>,
+    cpu_quota_micros: LazyGauge<i64>,
+    cpu_period_micros: LazyGauge<i64>,
+    cpu_usage_usec_total: LazyCounter<u64>,
+}
+
+impl Default for CgroupMetrics {
+    fn default() -> Self {
+        let path = env::var_os(CGROUP_PATH_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CGROUP_PATH));
+        Self::with_path(path)
+    }
+}
+
+impl CgroupMetrics {
+    /// Creates the cgroup metrics reading the cgroup v2 files under `path`.
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        let path = Arc::new(path.into());
+        let group = LazyGroup::new(move || CgroupSample::read(&path));
+        Self {
+            memory_limit_bytes: group.gauge(|s| s.memory_limit_bytes),
+            memory_usage_bytes: group.gauge(|s| s.memory_usage_bytes),
+            cpu_quota_micros: group.gauge(|s| s.cpu_quota_micros),
+            cpu_period_micros: group.gauge(|s| s.cpu_period_micros),
+            cpu_usage_usec_total: group.counter(|s| s.cpu_usage_usec),
+        }
+    }
+}
+
+impl Register for CgroupMetrics {
+    fn register(&self, registry: &mut Registry) -> Result<()> {
+        registry.register_with_unit(
+            "memory_limit",
+            "Memory limit of the cgroup in bytes, -1 if unlimited.",
+            Unit::Bytes,
+            self.memory_limit_bytes.clone(),
+        )?;
+        registry.register_with_unit(
+            "memory_usage",
+            "Memory usage of the cgroup in bytes.",
+            Unit::Bytes,
+            self.memory_usage_bytes.clone(),
+        )?;
+        registry.register(
+            "cpu_quota_micros",
+            "CPU time the cgroup may use per period in microseconds, -1 if unlimited.",
+            self.cpu_quota_micros.clone(),
+        )?;
+        registry.register(
+            "cpu_period_micros",
+            "Length of the CPU quota period of the cgroup in microseconds.",
+            self.cpu_period_micros.clone(),
+        )?;
+        registry.register(
+            "cpu_usage_usec",
+            "Total CPU time consumed by the cgroup in microseconds.",
+            self.cpu_usage_usec_total.clone(),
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct CgroupSample {
+    memory_limit_bytes: i64,
+    memory_usage_bytes: i64,
+    cpu_quota_micros: i64,
+    cpu_period_micros: i64,
+    cpu_usage_usec: u64,
+}
+
+impl CgroupSample {
+    fn read(path: &Path) -> Self {
+        let read = |file: &str| fs::read_to_string(path.join(file)).ok();
+
+        let mut sample = Self::default();
+        if let Some(memory_max) = read("memory.max") {
+            sample.memory_limit_bytes = parse_limit(memory_max.trim());
+        }
+        if let Some(memory_current) = read("memory.current") {
+            sample.memory_usage_bytes = parse_limit(memory_current.trim());
+        }
+        // `$MAX $PERIOD`, where `$MAX` may be `max`
+        if let Some(cpu_max) = read("cpu.max") {
+            let mut fields = cpu_max.split_whitespace();
+            sample.cpu_quota_micros = fields.next().map(parse_limit).unwrap_or(0);
+            sample.cpu_period_micros = fields.next().map(parse_limit).unwrap_or(0);
+        }
+        if let Some(cpu_stat) = read("cpu.stat") {
+            sample.cpu_usage_usec = cpu_stat
+                .lines()
+                .find_map(|line| line.strip_prefix("usage_usec "))
+                .and_then(|usage| usage.trim().parse().ok())
+                .unwrap_or(0);
+        }
+        sample
+    }
+}
+
+/// Parses a cgroup value, where `max` means unlimited (`-1`) and invalid values fall back to `0`.
+fn parse_limit(value: &str) -> i64 {
+    if value == "max" { -1 } else { value.parse().map(u64_to_i64_saturating).unwrap_or(0) }
+}
+
+#[cfg(test)]
+mod tests {
+    use fastmetrics::format::text::{self, TextProfile};
+
+    use super::*;
+
+    fn encode(metrics: &CgroupMetrics) -> String {
+        let mut registry = Registry::default();
+        metrics.register(registry.subsystem("cgroup").unwrap()).unwrap();
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_cgroup_metrics() {
+        let dir = env::temp_dir().join(format!("fastmetrics-cgroup-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("memory.max"), "536870912\n").unwrap();
+        fs::write(dir.join("memory.current"), "104857600\n").unwrap();
+        fs::write(dir.join("cpu.max"), "max 100000\n").unwrap();
+        fs::write(dir.join("cpu.stat"), "usage_usec 123456\nuser_usec 100000\n").unwrap();
+
+        let output = encode(&CgroupMetrics::with_path(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+
+        for line in [
+            "cgroup_memory_limit_bytes 536870912\n",
+            "cgroup_memory_usage_bytes 104857600\n",
+            "cgroup_cpu_quota_micros -1\n",
+            "cgroup_cpu_period_micros 100000\n",
+            "cgroup_cpu_usage_usec_total 123456\n",
+        ] {
+            assert!(output.contains(line), "missing `{line}`: {output}");
+        }
+    }
+
+    #[test]
+    fn test_cgroup_metrics_without_cgroup_files() {
+        let output = encode(&CgroupMetrics::with_path("/nonexistent/cgroup"));
+        for line in [
+            "cgroup_memory_limit_bytes 0\n",
+            "cgroup_memory_usage_bytes 0\n",
+            "cgroup_cpu_quota_micros 0\n",
+            "cgroup_cpu_period_micros 0\n",
+            "cgroup_cpu_usage_usec_total 0\n",
+        ] {
+            assert!(output.contains(line), "missing `{line}`: {output}");
+        }
+    }
+}
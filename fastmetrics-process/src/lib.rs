@@ -18,6 +18,13 @@ use fastmetrics::{
 use parking_lot::Mutex;
 use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
 
+#[cfg(target_os = "linux")]
+mod cgroup;
+
+#[cfg(target_os = "linux")]
+#[cfg_attr(docsrs, doc(cfg(target_os = "linux")))]
+pub use self::cgroup::{CGROUP_PATH_ENV, CgroupMetrics};
+
 /// A set of process metrics aligned with Prometheus' standard naming conventions.
 ///
 /// This type implements [`fastmetrics::registry::Register`].
@@ -29,6 +36,8 @@ pub struct ProcessMetrics {
     pid: ConstGauge<i64>,
     cpu_seconds_total: LazyCounter<f64>,
     cpu_usage_percent: LazyGauge<f32>,
+    read_bytes_total: LazyCounter<u64>,
+    written_bytes_total: LazyCounter<u64>,
     resident_memory_bytes: LazyGauge<i64>,
     virtual_memory_bytes: LazyGauge<i64>,
     start_time_seconds: LazyGauge<i64>,
@@ -47,6 +56,8 @@ impl Default for ProcessMetrics {
             pid: ConstGauge::new(PROCESS_SAMPLER.pid.as_u32() as i64),
             cpu_seconds_total: group.counter(|s| s.cpu_seconds_total),
             cpu_usage_percent: group.gauge(|s| s.cpu_usage_percent),
+            read_bytes_total: group.counter(|s| s.read_bytes),
+            written_bytes_total: group.counter(|s| s.written_bytes),
             resident_memory_bytes: group.gauge(|s| s.resident_memory_bytes),
             virtual_memory_bytes: group.gauge(|s| s.virtual_memory_bytes),
             start_time_seconds: group.gauge(|s| s.start_time_seconds),
@@ -71,6 +82,18 @@ impl Register for ProcessMetrics {
             "CPU usage of the process in percent.",
             self.cpu_usage_percent.clone(),
         )?;
+        registry.register_with_unit(
+            "read",
+            "Total number of bytes read from disk by the process.",
+            Unit::Bytes,
+            self.read_bytes_total.clone(),
+        )?;
+        registry.register_with_unit(
+            "written",
+            "Total number of bytes written to disk by the process.",
+            Unit::Bytes,
+            self.written_bytes_total.clone(),
+        )?;
         registry.register_with_unit(
             "resident_memory",
             "Resident memory size in bytes.",
@@ -114,6 +137,8 @@ impl Register for ProcessMetrics {
 struct ProcessSample {
     cpu_seconds_total: f64,
     cpu_usage_percent: f32,
+    read_bytes: u64,
+    written_bytes: u64,
     resident_memory_bytes: i64,
     virtual_memory_bytes: i64,
     start_time_seconds: i64,
@@ -155,9 +180,13 @@ fn sample(system: &mut System, pid: Pid) -> ProcessSample {
         return ProcessSample::default();
     };
 
+    let (read_bytes, written_bytes) = disk_usage(process);
+
     ProcessSample {
         cpu_seconds_total: process.accumulated_cpu_time() as f64 / 1_000.0,
         cpu_usage_percent: process.cpu_usage(),
+        read_bytes,
+        written_bytes,
         resident_memory_bytes: u64_to_i64_saturating(process.memory()),
         virtual_memory_bytes: u64_to_i64_saturating(process.virtual_memory()),
         start_time_seconds: u64_to_i64_saturating(process.start_time()),
@@ -168,7 +197,62 @@ fn sample(system: &mut System, pid: Pid) -> ProcessSample {
     }
 }
 
+/// Returns the total number of bytes read from and written to disk.
+///
+/// `sysinfo` only tracks disk I/O on the platforms below, both values fall back to `0` elsewhere.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "windows",
+    target_os = "freebsd"
+))]
+fn disk_usage(process: &sysinfo::Process) -> (u64, u64) {
+    let usage = process.disk_usage();
+    (usage.total_read_bytes, usage.total_written_bytes)
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "windows",
+    target_os = "freebsd"
+)))]
+fn disk_usage(_process: &sysinfo::Process) -> (u64, u64) {
+    (0, 0)
+}
+
 #[inline]
 fn u64_to_i64_saturating(v: u64) -> i64 {
     if v > i64::MAX as u64 { i64::MAX } else { v as i64 }
 }
+
+#[cfg(test)]
+mod tests {
+    use fastmetrics::format::text::{self, TextProfile};
+
+    use super::*;
+
+    #[test]
+    fn test_disk_io_metrics_are_encoded() {
+        let mut registry = Registry::default();
+        ProcessMetrics::default()
+            .register(registry.subsystem("process").unwrap())
+            .unwrap();
+
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+
+        for line in [
+            "# TYPE process_read_bytes counter",
+            "# UNIT process_read_bytes bytes",
+            "# TYPE process_written_bytes counter",
+            "# UNIT process_written_bytes bytes",
+        ] {
+            assert!(output.contains(line), "missing `{line}`: {output}");
+        }
+        assert!(output.contains("\nprocess_read_bytes_total "), "{output}");
+        assert!(output.contains("\nprocess_written_bytes_total "), "{output}");
+    }
+}
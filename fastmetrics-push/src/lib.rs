@@ -0,0 +1,227 @@
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+#![deny(unused_crate_dependencies)]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+pub mod transport;
+
+use std::fmt;
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use fastmetrics::{
+    error::{Error, Result},
+    format::text::{self, TextProfile},
+    registry::Registry,
+};
+pub use url::Url;
+
+use self::transport::{Method, PushRequest, Transport};
+
+/// Text profile used to encode pushed metrics.
+const PROFILE: TextProfile = TextProfile::PrometheusV0_0_4;
+
+/// A client pushing metrics to a [Prometheus Push Gateway](https://github.com/prometheus/pushgateway).
+///
+/// Metrics are pushed to `<url>/metrics/job/<job>[/<key>/<value>]*`, where the key/value pairs
+/// are the grouping key added by [`with_grouping_key`](Self::with_grouping_key).
+pub struct PushGateway {
+    url: Url,
+    job: String,
+    grouping_key: Vec<(String, String)>,
+    transport: Box<dyn Transport>,
+}
+
+impl fmt::Debug for PushGateway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PushGateway")
+            .field("url", &self.url)
+            .field("job", &self.job)
+            .field("grouping_key", &self.grouping_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PushGateway {
+    /// Creates a client for the Push Gateway at `url`, pushing metrics of the given `job`.
+    ///
+    /// The HTTP transport is [`UreqTransport`](transport::UreqTransport) if the `ureq` feature is
+    /// enabled, and [`ReqwestTransport`](transport::ReqwestTransport) otherwise.
+    #[cfg(any(feature = "ureq", feature = "reqwest"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "ureq", feature = "reqwest"))))]
+    pub fn new(url: Url, job: &str) -> Self {
+        #[cfg(feature = "ureq")]
+        let transport = transport::UreqTransport::default();
+        #[cfg(not(feature = "ureq"))]
+        let transport = transport::ReqwestTransport::default();
+        Self::with_transport(url, job, transport)
+    }
+
+    /// Creates a client for the Push Gateway at `url` using a custom HTTP transport.
+    pub fn with_transport(url: Url, job: &str, transport: impl Transport + 'static) -> Self {
+        Self { url, job: job.to_owned(), grouping_key: Vec::new(), transport: Box::new(transport) }
+    }
+
+    /// Adds a `key`/`value` pair to the grouping key.
+    pub fn with_grouping_key(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.grouping_key.push((key.into(), value.into()));
+        self
+    }
+
+    /// Pushes all metrics of the registry, replacing all metrics of the same grouping key.
+    ///
+    /// This is sent as a `PUT` request, as required by the Push Gateway API for replacing pushes.
+    pub fn push(&self, registry: &Registry) -> Result<()> {
+        self.send(Method::Put, registry)
+    }
+
+    /// Pushes all metrics of the registry, only replacing metrics with the same name in the same
+    /// grouping key.
+    ///
+    /// This is sent as a `POST` request.
+    pub fn push_add(&self, registry: &Registry) -> Result<()> {
+        self.send(Method::Post, registry)
+    }
+
+    fn send(&self, method: Method, registry: &Registry) -> Result<()> {
+        let url = self.push_url()?;
+        let mut body = String::new();
+        text::encode(&mut body, registry, PROFILE)?;
+        self.transport.send(PushRequest {
+            method,
+            url: &url,
+            content_type: PROFILE.content_type(),
+            body: body.as_bytes(),
+        })
+    }
+
+    fn push_url(&self) -> Result<Url> {
+        let mut url = self.url.clone();
+        {
+            let mut segments = url.path_segments_mut().map_err(|()| {
+                Error::invalid("push gateway URL cannot be a base").with_context("url", &self.url)
+            })?;
+            segments.pop_if_empty().extend(["metrics"]);
+            for (key, value) in [("job", self.job.as_str())]
+                .into_iter()
+                .chain(self.grouping_key.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+            {
+                // Values that can't be used as a path segment are base64 encoded.
+                if value.is_empty() || value.contains('/') {
+                    let value = if value.is_empty() {
+                        "=".to_owned()
+                    } else {
+                        URL_SAFE_NO_PAD.encode(value)
+                    };
+                    segments.extend([format!("{key}@base64"), value]);
+                } else {
+                    segments.extend([key, value]);
+                }
+            }
+        }
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use fastmetrics::{error::ErrorKind, metrics::counter::Counter};
+    use httpmock::{Method::POST, Method::PUT, MockServer};
+
+    use super::*;
+
+    fn registry() -> Registry {
+        let mut registry = Registry::default();
+        let jobs = <Counter>::default();
+        registry.register("jobs", "Total processed jobs", jobs.clone()).unwrap();
+        jobs.inc_by(3);
+        registry
+    }
+
+    fn expected_body(registry: &Registry) -> String {
+        let mut body = String::new();
+        text::encode(&mut body, registry, PROFILE).unwrap();
+        body
+    }
+
+    #[test]
+    fn test_push() {
+        let server = MockServer::start();
+        let registry = registry();
+        let mock = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/metrics/job/batch/instance/host1")
+                .header("content-type", PROFILE.content_type())
+                .body(expected_body(&registry));
+            then.status(200);
+        });
+
+        let gateway = PushGateway::new(server.base_url().parse().unwrap(), "batch")
+            .with_grouping_key("instance", "host1");
+        gateway.push(&registry).unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_push_add() {
+        let server = MockServer::start();
+        let registry = registry();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/prefix/metrics/job/batch")
+                .header("content-type", PROFILE.content_type())
+                .body_includes("jobs 3");
+            then.status(202);
+        });
+
+        let gateway = PushGateway::new(server.url("/prefix/").parse().unwrap(), "batch");
+        gateway.push_add(&registry).unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_push_error_status() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(PUT);
+            then.status(500);
+        });
+
+        let gateway = PushGateway::new(server.base_url().parse().unwrap(), "batch");
+        let err = gateway.push(&registry()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unexpected);
+    }
+
+    #[test]
+    fn test_grouping_key_encoding() {
+        #[derive(Clone, Default)]
+        struct RecordingTransport(Arc<Mutex<Vec<String>>>);
+
+        impl Transport for RecordingTransport {
+            fn send(&self, request: PushRequest<'_>) -> Result<()> {
+                self.0.lock().unwrap().push(request.url.to_string());
+                Ok(())
+            }
+        }
+
+        let transport = RecordingTransport::default();
+        let gateway = PushGateway::with_transport(
+            "http://localhost:9091".parse().unwrap(),
+            "batch/nightly",
+            transport.clone(),
+        )
+        .with_grouping_key("path", "/var/tmp")
+        .with_grouping_key("empty", "")
+        .with_grouping_key("name", "a b");
+        gateway.push(&registry()).unwrap();
+
+        assert_eq!(
+            transport.0.lock().unwrap().as_slice(),
+            [
+                "http://localhost:9091/metrics/job@base64/YmF0Y2gvbmlnaHRseQ/path@base64/L3Zhci90bXA/empty@base64/=/name/a%20b"
+            ]
+        );
+    }
+}
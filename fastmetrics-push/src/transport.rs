@@ -0,0 +1,126 @@
+//! HTTP transports used to send metrics to the Push Gateway.
+
+#[cfg(any(feature = "ureq", feature = "reqwest"))]
+use fastmetrics::error::Error;
+use fastmetrics::error::Result;
+use url::Url;
+
+/// HTTP method of a push request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Method {
+    /// `PUT`, replaces all metrics of the grouping key.
+    Put,
+    /// `POST`, only replaces metrics with the same name as the pushed ones.
+    Post,
+}
+
+impl Method {
+    /// Returns the string representation of the method.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Put => "PUT",
+            Self::Post => "POST",
+        }
+    }
+}
+
+/// A push request sent by [`PushGateway`](crate::PushGateway).
+#[derive(Clone, Copy, Debug)]
+pub struct PushRequest<'a> {
+    /// HTTP method.
+    pub method: Method,
+    /// Full URL, including the job and grouping key path segments.
+    pub url: &'a Url,
+    /// Value of the `Content-Type` header.
+    pub content_type: &'static str,
+    /// Encoded metrics.
+    pub body: &'a [u8],
+}
+
+/// An HTTP transport sending push requests.
+///
+/// Implementations must return an error if the request fails or if the Push Gateway doesn't
+/// answer with a success status code.
+pub trait Transport: Send + Sync {
+    /// Sends the request.
+    fn send(&self, request: PushRequest<'_>) -> Result<()>;
+}
+
+#[cfg(any(feature = "ureq", feature = "reqwest"))]
+fn push_error(request: &PushRequest<'_>) -> Error {
+    Error::unexpected("failed to push metrics")
+        .with_context("method", request.method.as_str())
+        .with_context("url", request.url)
+}
+
+/// A [`Transport`] using a blocking [`ureq`] agent.
+#[cfg(feature = "ureq")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ureq")))]
+#[derive(Clone, Debug)]
+pub struct UreqTransport {
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "ureq")]
+impl UreqTransport {
+    /// Creates a transport using the given agent.
+    pub fn new(agent: ureq::Agent) -> Self {
+        Self { agent }
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl Default for UreqTransport {
+    fn default() -> Self {
+        Self::new(ureq::Agent::new_with_defaults())
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl Transport for UreqTransport {
+    fn send(&self, request: PushRequest<'_>) -> Result<()> {
+        let builder = match request.method {
+            Method::Put => self.agent.put(request.url.as_str()),
+            Method::Post => self.agent.post(request.url.as_str()),
+        };
+        builder
+            .header("Content-Type", request.content_type)
+            .send(request.body)
+            .map_err(|err| push_error(&request).set_source(err))?;
+        Ok(())
+    }
+}
+
+/// A [`Transport`] using a blocking [`reqwest`] client.
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestTransport {
+    /// Creates a transport using the given client.
+    pub fn new(client: reqwest::blocking::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl Transport for ReqwestTransport {
+    fn send(&self, request: PushRequest<'_>) -> Result<()> {
+        let method = match request.method {
+            Method::Put => reqwest::Method::PUT,
+            Method::Post => reqwest::Method::POST,
+        };
+        self.client
+            .request(method, request.url.as_str())
+            .header(reqwest::header::CONTENT_TYPE, request.content_type)
+            .body(request.body.to_vec())
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| push_error(&request).set_source(err))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+#![deny(unused_crate_dependencies)]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+use std::sync::Arc;
+
+#[cfg(tokio_unstable)]
+use fastmetrics::metrics::histogram::{HistogramConfig, HistogramSample, LazyHistogram};
+use fastmetrics::{
+    error::Result,
+    metrics::{counter::LazyCounter, gauge::LazyGauge, lazy_group::LazyGroup},
+    registry::{Register, Registry, Unit},
+};
+use parking_lot::Mutex;
+use tokio::runtime::Handle;
+use tokio_metrics::{RuntimeIntervals, RuntimeMonitor};
+
+/// A set of Tokio runtime metrics, sampled from [`tokio_metrics::RuntimeMonitor`].
+///
+/// This type implements [`fastmetrics::registry::Register`].
+///
+/// To get `tokio_*` metric names, register into `registry.subsystem("tokio")?`. Some metrics are
+/// only available when building with `--cfg tokio_unstable`, see the crate documentation.
+#[derive(Clone)]
+pub struct TokioRuntimeMetrics {
+    workers_count: LazyGauge<i64>,
+    live_tasks_count: LazyGauge<i64>,
+    global_queue_depth: LazyGauge<i64>,
+    park_count_total: LazyCounter<u64>,
+    busy_duration_seconds_total: LazyCounter<f64>,
+    #[cfg(tokio_unstable)]
+    spawned_tasks_count_total: LazyCounter<u64>,
+    #[cfg(tokio_unstable)]
+    noop_count_total: LazyCounter<u64>,
+    #[cfg(tokio_unstable)]
+    budget_forced_yield_count_total: LazyCounter<u64>,
+    #[cfg(tokio_unstable)]
+    mean_poll_duration_seconds: LazyHistogram,
+}
+
+impl TokioRuntimeMetrics {
+    /// Creates the metrics of the runtime behind `handle`.
+    ///
+    /// The runtime is sampled once per scrape, all metrics read from the same sample.
+    pub fn new(handle: &Handle) -> Self {
+        let sampler = Arc::new(RuntimeSampler::new(handle));
+        #[cfg(tokio_unstable)]
+        let poll_duration_config = Arc::new(sampler.poll_duration_config());
+        let group: LazyGroup<RuntimeSample> = LazyGroup::new(move || sampler.sample());
+        Self {
+            workers_count: group.gauge(|s| s.workers_count),
+            live_tasks_count: group.gauge(|s| s.live_tasks_count),
+            global_queue_depth: group.gauge(|s| s.global_queue_depth),
+            park_count_total: group.counter(|s| s.park_count),
+            busy_duration_seconds_total: group.counter(|s| s.busy_duration_seconds),
+            #[cfg(tokio_unstable)]
+            spawned_tasks_count_total: group.counter(|s| s.spawned_tasks_count),
+            #[cfg(tokio_unstable)]
+            noop_count_total: group.counter(|s| s.noop_count),
+            #[cfg(tokio_unstable)]
+            budget_forced_yield_count_total: group.counter(|s| s.budget_forced_yield_count),
+            #[cfg(tokio_unstable)]
+            mean_poll_duration_seconds: group
+                .histogram(|s| s.poll_duration.clone(), poll_duration_config),
+        }
+    }
+
+    /// Creates the metrics of the runtime the current thread is running on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime, see [`Handle::current`].
+    pub fn current() -> Self {
+        Self::new(&Handle::current())
+    }
+}
+
+impl Register for TokioRuntimeMetrics {
+    fn register(&self, registry: &mut Registry) -> Result<()> {
+        registry.register(
+            "workers_count",
+            "Number of worker threads used by the runtime.",
+            self.workers_count.clone(),
+        )?;
+        registry.register(
+            "live_tasks_count",
+            "Number of alive tasks in the runtime.",
+            self.live_tasks_count.clone(),
+        )?;
+        registry.register(
+            "global_queue_depth",
+            "Number of tasks currently scheduled in the global queue of the runtime.",
+            self.global_queue_depth.clone(),
+        )?;
+        registry.register(
+            "park_count",
+            "Total number of times worker threads parked.",
+            self.park_count_total.clone(),
+        )?;
+        registry.register_with_unit(
+            "busy_duration",
+            "Total time worker threads have been busy in seconds.",
+            Unit::Seconds,
+            self.busy_duration_seconds_total.clone(),
+        )?;
+        #[cfg(tokio_unstable)]
+        {
+            registry.register(
+                "spawned_tasks_count",
+                "Total number of tasks spawned in the runtime.",
+                self.spawned_tasks_count_total.clone(),
+            )?;
+            registry.register(
+                "noop_count",
+                "Total number of times worker threads unparked but performed no work.",
+                self.noop_count_total.clone(),
+            )?;
+            registry.register(
+                "budget_forced_yield_count",
+                "Total number of times tasks were forced to yield after exhausting their budgets.",
+                self.budget_forced_yield_count_total.clone(),
+            )?;
+            registry.register_with_unit(
+                "mean_poll_duration",
+                "Duration of task polls in seconds.",
+                Unit::Seconds,
+                self.mean_poll_duration_seconds.clone(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+struct RuntimeSample {
+    workers_count: i64,
+    live_tasks_count: i64,
+    global_queue_depth: i64,
+    park_count: u64,
+    busy_duration_seconds: f64,
+    #[cfg(tokio_unstable)]
+    spawned_tasks_count: u64,
+    #[cfg(tokio_unstable)]
+    noop_count: u64,
+    #[cfg(tokio_unstable)]
+    budget_forced_yield_count: u64,
+    #[cfg(tokio_unstable)]
+    poll_duration: HistogramSample,
+}
+
+struct RuntimeSampler {
+    #[cfg(tokio_unstable)]
+    runtime: tokio::runtime::RuntimeMetrics,
+    // `RuntimeIntervals` yields the changes since the previous interval, which are accumulated
+    // into the totals of the last sample.
+    state: Mutex<(RuntimeIntervals, RuntimeSample)>,
+}
+
+impl RuntimeSampler {
+    fn new(handle: &Handle) -> Self {
+        let intervals = RuntimeMonitor::new(handle).intervals();
+        Self {
+            #[cfg(tokio_unstable)]
+            runtime: handle.metrics(),
+            state: Mutex::new((intervals, RuntimeSample::default())),
+        }
+    }
+
+    /// Buckets of the poll time histogram of the runtime, a single `+Inf` bucket if it's not
+    /// enabled.
+    #[cfg(tokio_unstable)]
+    fn poll_duration_config(&self) -> HistogramConfig {
+        // the last bucket of the runtime is unbounded, and becomes the `+Inf` bucket
+        let buckets = self.runtime.poll_time_histogram_num_buckets().saturating_sub(1);
+        HistogramConfig::new(
+            (0..buckets).map(|bucket| {
+                self.runtime.poll_time_histogram_bucket_range(bucket).end.as_secs_f64()
+            }),
+        )
+    }
+
+    fn sample(&self) -> RuntimeSample {
+        let mut state = self.state.lock();
+        let (intervals, sample) = &mut *state;
+        let Some(interval) = intervals.next() else {
+            return sample.clone();
+        };
+
+        sample.workers_count = usize_to_i64_saturating(interval.workers_count);
+        sample.live_tasks_count = usize_to_i64_saturating(interval.live_tasks_count);
+        sample.global_queue_depth = usize_to_i64_saturating(interval.global_queue_depth);
+        sample.park_count = sample.park_count.saturating_add(interval.total_park_count);
+        sample.busy_duration_seconds += interval.total_busy_duration.as_secs_f64();
+        #[cfg(tokio_unstable)]
+        {
+            sample.spawned_tasks_count = self.runtime.spawned_tasks_count();
+            sample.noop_count = sample.noop_count.saturating_add(interval.total_noop_count);
+            sample.budget_forced_yield_count = sample
+                .budget_forced_yield_count
+                .saturating_add(interval.budget_forced_yield_count);
+            accumulate_poll_durations(&mut sample.poll_duration, &interval);
+        }
+        sample.clone()
+    }
+}
+
+/// Adds the task polls of `interval` to the poll duration histogram.
+///
+/// Tokio doesn't track the total poll time, so the sum is estimated from the mean poll duration
+/// of the interval. Without the poll time histogram of the runtime, all polls are counted in the
+/// `+Inf` bucket.
+#[cfg(tokio_unstable)]
+fn accumulate_poll_durations(
+    histogram: &mut HistogramSample,
+    interval: &tokio_metrics::RuntimeMetrics,
+) {
+    let polls: &[u64] = if interval.poll_time_histogram.is_empty() {
+        &[interval.total_polls_count]
+    } else {
+        &interval.poll_time_histogram
+    };
+    if histogram.buckets.len() < polls.len() {
+        histogram.buckets.resize(polls.len(), 0);
+    }
+    for (bucket, count) in histogram.buckets.iter_mut().zip(polls) {
+        *bucket = bucket.saturating_add(*count);
+    }
+    // the count matches the buckets, even if polls happened while the interval was sampled
+    let count = polls.iter().fold(0_u64, |total, count| total.saturating_add(*count));
+    histogram.count = histogram.count.saturating_add(count);
+    histogram.sum += interval.mean_poll_duration.as_secs_f64() * count as f64;
+}
+
+#[inline]
+fn usize_to_i64_saturating(v: usize) -> i64 {
+    i64::try_from(v).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use fastmetrics::format::text::{self, TextProfile};
+
+    use super::*;
+
+    fn sample_value(output: &str, name: &str) -> f64 {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| panic!("missing `{name}`: {output}"))
+    }
+
+    #[test]
+    fn test_runtime_metrics_are_encoded() {
+        let runtime =
+            tokio::runtime::Builder::new_multi_thread().worker_threads(2).build().unwrap();
+
+        let mut registry = Registry::default();
+        TokioRuntimeMetrics::new(runtime.handle())
+            .register(registry.subsystem("tokio").unwrap())
+            .unwrap();
+
+        runtime.block_on(async {
+            let tasks = (0..16)
+                .map(|i| tokio::spawn(async move { (0..1000u64).map(|n| n * i).sum::<u64>() }))
+                .collect::<Vec<_>>();
+            for task in tasks {
+                task.await.unwrap();
+            }
+        });
+
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+
+        // parking and busy time depend on scheduling, only check that they are exposed
+        assert_eq!(sample_value(&output, "tokio_workers_count"), 2.0);
+        assert!(sample_value(&output, "tokio_park_count_total") >= 0.0);
+        assert!(sample_value(&output, "tokio_busy_duration_seconds_total") >= 0.0);
+        assert!(output.contains("\ntokio_live_tasks_count "), "{output}");
+        assert!(output.contains("\ntokio_global_queue_depth "), "{output}");
+        #[cfg(tokio_unstable)]
+        assert_eq!(sample_value(&output, "tokio_spawned_tasks_count_total"), 16.0);
+    }
+
+    #[cfg(tokio_unstable)]
+    #[test]
+    fn test_poll_duration_histogram() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_metrics_poll_time_histogram()
+            .metrics_poll_time_histogram_configuration(
+                tokio::runtime::HistogramConfiguration::linear(
+                    std::time::Duration::from_millis(1),
+                    3,
+                ),
+            )
+            .build()
+            .unwrap();
+
+        let mut registry = Registry::default();
+        TokioRuntimeMetrics::new(runtime.handle())
+            .register(registry.subsystem("tokio").unwrap())
+            .unwrap();
+
+        runtime.block_on(async {
+            let tasks = (0..16).map(|_| tokio::spawn(async {})).collect::<Vec<_>>();
+            for task in tasks {
+                task.await.unwrap();
+            }
+        });
+
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+
+        let name = "tokio_mean_poll_duration_seconds";
+        assert!(output.contains(&format!("\n{name}_bucket{{le=\"0.001\"}} ")), "{output}");
+        assert!(output.contains(&format!("\n{name}_bucket{{le=\"0.002\"}} ")), "{output}");
+        // workers publish their poll counts when they park, so only check the buckets are
+        // consistent with the count
+        let count = sample_value(&output, &format!("{name}_count"));
+        assert!(sample_value(&output, &format!("{name}_sum")) >= 0.0);
+        assert_eq!(sample_value(&output, &format!("{name}_bucket{{le=\"+Inf\"}}")), count);
+    }
+}
@@ -0,0 +1,98 @@
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+#![deny(unused_crate_dependencies)]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+use std::time::SystemTime;
+
+use fastmetrics::raw::exemplar::Exemplar;
+use opentelemetry::trace::TraceContextExt;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// The exemplar label holding the trace ID of the span.
+pub const TRACE_ID_LABEL: &str = "trace_id";
+/// The exemplar label holding the span ID of the span.
+pub const SPAN_ID_LABEL: &str = "span_id";
+
+/// Extracts [`Exemplar`]s from [`tracing`] spans.
+///
+/// The trace and span IDs are only available when a [`tracing_opentelemetry`] layer is installed
+/// in the subscriber of the span.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExemplarExtractor;
+
+impl ExemplarExtractor {
+    /// Extracts an exemplar from the current span, see [`ExemplarExtractor::from_span`].
+    pub fn from_current_span() -> Option<Exemplar> {
+        Self::from_span(&Span::current())
+    }
+
+    /// Extracts an exemplar labeled with the `trace_id` and `span_id` of `span`, timestamped with
+    /// the current time.
+    ///
+    /// The value of the exemplar is set by [`Counter::inc_with_exemplar`] or
+    /// [`Histogram::observe_with_exemplar`]. Returns `None` if the span is disabled, or if no
+    /// [`tracing_opentelemetry`] layer is installed.
+    ///
+    /// [`Counter::inc_with_exemplar`]: fastmetrics::metrics::counter::Counter::inc_with_exemplar
+    /// [`Histogram::observe_with_exemplar`]: fastmetrics::metrics::histogram::Histogram::observe_with_exemplar
+    pub fn from_span(span: &Span) -> Option<Exemplar> {
+        let context = span.context();
+        let otel_span = context.span();
+        let span_context = otel_span.span_context();
+        if !span_context.is_valid() {
+            return None;
+        }
+
+        let labels = [
+            (TRACE_ID_LABEL, span_context.trace_id().to_string()),
+            (SPAN_ID_LABEL, span_context.span_id().to_string()),
+        ];
+        let exemplar = Exemplar::new(labels, 0.0);
+        match SystemTime::UNIX_EPOCH.elapsed() {
+            Ok(timestamp) => Some(exemplar.with_timestamp(timestamp)),
+            Err(_) => Some(exemplar),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    #[test]
+    #[traced_test]
+    fn test_exemplar_from_current_span() {
+        // the subscriber installed by `traced_test` has no OpenTelemetry layer
+        let span = tracing::info_span!("request");
+        let _guard = span.enter();
+        assert!(ExemplarExtractor::from_current_span().is_none());
+
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request");
+            let _guard = span.enter();
+
+            let exemplar = ExemplarExtractor::from_current_span().expect("active span");
+            let span_context = span.context().span().span_context().clone();
+            assert_eq!(
+                exemplar.labels(),
+                [
+                    (TRACE_ID_LABEL.into(), span_context.trace_id().to_string().into()),
+                    (SPAN_ID_LABEL.into(), span_context.span_id().to_string().into()),
+                ]
+            );
+            assert_eq!(exemplar.labels()[0].1.len(), 32);
+            assert!(exemplar.timestamp().is_some());
+        });
+    }
+}
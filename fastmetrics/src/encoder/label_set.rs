@@ -1,8 +1,10 @@
 use std::{
     borrow::Cow,
     collections::{BTreeMap, BTreeSet, LinkedList, VecDeque},
+    fmt::{self, Write as _},
     rc::Rc,
     sync::Arc,
+    time::Duration,
 };
 
 use crate::error::Result;
@@ -11,6 +13,18 @@ use crate::error::Result;
 pub trait LabelSetEncoder {
     /// Encodes a single label.
     fn encode(&mut self, label: &dyn EncodeLabel) -> Result<()>;
+
+    /// Encodes a batch of labels in order.
+    ///
+    /// This is equivalent to calling [`encode`](LabelSetEncoder::encode) for each label, but
+    /// costs a single dynamic dispatch for the whole batch. Encoders can override it to write
+    /// all labels in one pass.
+    fn encode_all(&mut self, labels: &[&dyn EncodeLabel]) -> Result<()> {
+        for label in labels {
+            self.encode(*label)?;
+        }
+        Ok(())
+    }
 }
 
 /// Trait for types that can be encoded as a set of labels.
@@ -71,12 +85,23 @@ macro_rules! impl_encode_label_set_for_container {
 }
 
 impl_encode_label_set_for_container! { <T: EncodeLabel> EncodeLabelSet for [T] }
-impl_encode_label_set_for_container! { <T: EncodeLabel, const N: usize> EncodeLabelSet for [T; N] }
 impl_encode_label_set_for_container! { <T: EncodeLabel> EncodeLabelSet for Vec<T> }
 impl_encode_label_set_for_container! { <T: EncodeLabel> EncodeLabelSet for VecDeque<T> }
 impl_encode_label_set_for_container! { <T: EncodeLabel> EncodeLabelSet for LinkedList<T> }
 impl_encode_label_set_for_container! { <T: EncodeLabel> EncodeLabelSet for BTreeSet<T> }
 
+impl<T: EncodeLabel, const N: usize> EncodeLabelSet for [T; N] {
+    #[inline]
+    fn encode(&self, encoder: &mut dyn LabelSetEncoder) -> Result<()> {
+        encoder.encode_all(&self.each_ref().map(|label| label as &dyn EncodeLabel))
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        N == 0
+    }
+}
+
 impl<K: EncodeLabelName, V: EncodeLabelValue> EncodeLabelSet for BTreeMap<K, V> {
     fn encode(&self, encoder: &mut dyn LabelSetEncoder) -> Result<()> {
         for label in self.iter() {
@@ -152,6 +177,46 @@ pub trait LabelEncoder {
     fn encode_f32_value(&mut self, value: f32) -> Result<()>;
     /// Encodes a 64-bit floating point as a label value.
     fn encode_f64_value(&mut self, value: f64) -> Result<()>;
+
+    /// Encodes a [`Display`](fmt::Display) type as a label value.
+    ///
+    /// The default implementation formats `value` into a small stack buffer, falling back to a
+    /// `String` for longer values, and delegates to [`LabelEncoder::encode_str_value`].
+    /// Encoders that can write the formatted value directly should override it.
+    fn encode_display_value(&mut self, value: &dyn fmt::Display) -> Result<()> {
+        let mut buffer = StackBuffer { buf: [0; STACK_BUFFER_SIZE], len: 0 };
+        if write!(buffer, "{value}").is_ok() {
+            self.encode_str_value(buffer.as_str())
+        } else {
+            self.encode_str_value(&value.to_string())
+        }
+    }
+}
+
+const STACK_BUFFER_SIZE: usize = 64;
+
+/// A fixed-size [`fmt::Write`] buffer, failing when the output doesn't fit.
+struct StackBuffer {
+    buf: [u8; STACK_BUFFER_SIZE],
+    len: usize,
+}
+
+impl StackBuffer {
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.buf[..self.len]).expect("only whole `str`s are written")
+    }
+}
+
+impl fmt::Write for StackBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > STACK_BUFFER_SIZE {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
 }
 
 /// Trait for types that represent complete labels (name-value pairs).
@@ -295,6 +360,92 @@ impl_encode_label_value_for! {
     f32, f64
 }
 
+macro_rules! impl_encode_label_value_for_display {
+    ($($ty:ty),*) => ($(
+        #[cfg(feature = "std-net-labels")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "std-net-labels")))]
+        impl EncodeLabelValue for $ty {
+            #[inline]
+            fn encode(&self, encoder: &mut dyn LabelEncoder) -> Result<()> {
+                encoder.encode_display_value(self)
+            }
+        }
+    )*)
+}
+
+// `std::net` addresses are encoded in their `Display` notation, e.g. `127.0.0.1`, `::1` or
+// `127.0.0.1:8080`.
+impl_encode_label_value_for_display! {
+    std::net::IpAddr, std::net::Ipv4Addr, std::net::Ipv6Addr,
+    std::net::SocketAddr, std::net::SocketAddrV4, std::net::SocketAddrV6
+}
+
+/// Encodes as fractional seconds, e.g. `30` or `0.1`, following the Prometheus convention.
+///
+/// Use [`DurationAsHuman`] or [`DurationAsMillis`] for other notations.
+impl EncodeLabelValue for Duration {
+    #[inline]
+    fn encode(&self, encoder: &mut dyn LabelEncoder) -> Result<()> {
+        if self.subsec_nanos() == 0 {
+            encoder.encode_u64_value(self.as_secs())
+        } else {
+            encoder.encode_f64_value(self.as_secs_f64())
+        }
+    }
+}
+
+/// A [`Duration`] label value encoded in the Prometheus duration notation, e.g. `30s`, `5m30s` or
+/// `1d2h`.
+///
+/// Units from years (`y`, 365 days) down to milliseconds (`ms`) are used, anything smaller is
+/// truncated. The zero duration is encoded as `0s`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DurationAsHuman(pub Duration);
+
+impl fmt::Display for DurationAsHuman {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [(&str, u128); 7] = [
+            ("y", 365 * 24 * 60 * 60 * 1000),
+            ("w", 7 * 24 * 60 * 60 * 1000),
+            ("d", 24 * 60 * 60 * 1000),
+            ("h", 60 * 60 * 1000),
+            ("m", 60 * 1000),
+            ("s", 1000),
+            ("ms", 1),
+        ];
+
+        let mut millis = self.0.as_millis();
+        if millis == 0 {
+            return f.write_str("0s");
+        }
+        for (unit, unit_millis) in UNITS {
+            if millis >= unit_millis {
+                write!(f, "{}{unit}", millis / unit_millis)?;
+                millis %= unit_millis;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EncodeLabelValue for DurationAsHuman {
+    #[inline]
+    fn encode(&self, encoder: &mut dyn LabelEncoder) -> Result<()> {
+        encoder.encode_display_value(self)
+    }
+}
+
+/// A [`Duration`] label value encoded as integer milliseconds, e.g. `1500`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DurationAsMillis(pub Duration);
+
+impl EncodeLabelValue for DurationAsMillis {
+    #[inline]
+    fn encode(&self, encoder: &mut dyn LabelEncoder) -> Result<()> {
+        encoder.encode_u128_value(self.0.as_millis())
+    }
+}
+
 impl<T> EncodeLabelValue for Option<T>
 where
     T: EncodeLabelValue,
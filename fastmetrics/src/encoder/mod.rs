@@ -4,11 +4,11 @@ mod exemplar;
 mod label_set;
 mod value;
 
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 pub use self::{exemplar::*, label_set::*, value::*};
 use crate::{
-    error::Result,
+    error::{Error, Result},
     raw::{Metadata, bucket::Bucket, quantile::Quantile},
 };
 
@@ -89,10 +89,36 @@ pub trait MetricEncoder {
         created: Option<Duration>,
     ) -> Result<()>;
 
+    /// Encodes arbitrary samples of a custom metric type.
+    ///
+    /// This is an escape hatch for metrics that don't map to a single standard type, e.g. a rate
+    /// counter emitting both `_total` and `_rate` samples. The text encoder writes each sample as
+    /// `<name><suffix>{<labels>} <value> [<timestamp>]`, the other encoders expose them as
+    /// unknown/untyped samples of the metric family, which can't carry the suffix.
+    ///
+    /// The default implementation returns an [`Unsupported`](crate::error::ErrorKind::Unsupported) error, so
+    /// encoders implemented outside of this crate keep compiling.
+    fn encode_raw(&mut self, _samples: &[RawSample<'_>]) -> Result<()> {
+        Err(Error::unsupported("raw samples are not supported by this encoder"))
+    }
+
     /// Encodes a metric with the specified label set.
     fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()>;
 }
 
+/// A sample of a custom metric type, encoded by [`MetricEncoder::encode_raw`].
+#[derive(Clone, Copy)]
+pub struct RawSample<'a> {
+    /// Suffix appended to the metric name, e.g. `_rate`.
+    pub suffix: Option<&'a str>,
+    /// Labels of the sample, in addition to the constant and family labels.
+    pub labels: &'a dyn EncodeLabelSet,
+    /// Value of the sample.
+    pub value: f64,
+    /// Timestamp of the sample, falling back to [`EncodeMetric::timestamp`] if not set.
+    pub timestamp: Option<Duration>,
+}
+
 /// Trait for types that can be encoded as metrics.
 ///
 /// This trait is implemented by all metric types and provides methods for encoding
@@ -141,3 +167,17 @@ impl EncodeMetric for Box<dyn EncodeMetric> {
         (**self).is_empty()
     }
 }
+
+impl EncodeMetric for Arc<dyn EncodeMetric> {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        (**self).encode(encoder)
+    }
+
+    fn timestamp(&self) -> Option<Duration> {
+        (**self).timestamp()
+    }
+
+    fn is_empty(&self) -> bool {
+        (**self).is_empty()
+    }
+}
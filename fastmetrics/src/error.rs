@@ -22,6 +22,10 @@ pub enum ErrorKind {
     Invalid,
     /// The operation is duplicated.
     Duplicated,
+    /// The target is sealed and can't be modified anymore.
+    Sealed,
+    /// A configured limit has been reached.
+    LimitExceeded,
 }
 
 impl fmt::Display for ErrorKind {
@@ -31,6 +35,8 @@ impl fmt::Display for ErrorKind {
             Self::Unsupported => f.write_str("Unsupported"),
             Self::Invalid => f.write_str("Invalid"),
             Self::Duplicated => f.write_str("Duplicated"),
+            Self::Sealed => f.write_str("Sealed"),
+            Self::LimitExceeded => f.write_str("LimitExceeded"),
         }
     }
 }
@@ -171,6 +177,16 @@ impl Error {
         Self::new(ErrorKind::Duplicated, message)
     }
 
+    /// Create a new sealed [`Error`] with message.
+    pub fn sealed(message: impl Into<Cow<'static, str>>) -> Self {
+        Self::new(ErrorKind::Sealed, message)
+    }
+
+    /// Create a new limit exceeded [`Error`] with message.
+    pub fn limit_exceeded(message: impl Into<Cow<'static, str>>) -> Self {
+        Self::new(ErrorKind::LimitExceeded, message)
+    }
+
     /// Add more context in error.
     pub fn with_context(mut self, key: &'static str, value: impl ToString) -> Self {
         self.context.push((key, value.to_string()));
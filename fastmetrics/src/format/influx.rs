@@ -0,0 +1,637 @@
+//! [InfluxDB line protocol](https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/)
+//! exposition format.
+//!
+//! Every sample is written as a line `measurement,tag=value field=value timestamp`:
+//! - the measurement is the fully-qualified name of the metric family
+//! - the constant and variable labels are the tags (labels with an empty value are omitted)
+//! - the sample suffix is the field name, e.g. `total` for counters, `sum`, `count` and `bucket`
+//!   (with an `le` tag) for histograms, and `value` for samples without suffix
+//! - the timestamp is the timestamp of the metric, or the time of the encoding
+//!
+//! State sets are written as a single line with one boolean field per state, and info metrics as
+//! an `info=1i` field tagged with the info labels. Integer values are written as integer fields
+//! (`42i`); non-finite floats can't be represented and are skipped.
+
+use std::{
+    borrow::Cow,
+    fmt::Write as _,
+    io,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    encoder::{
+        self, EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel, EncodeLabelSet,
+        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _, RawSample,
+    },
+    error::Result,
+    raw::{Metadata, bucket::Bucket, quantile::Quantile},
+    registry::Registry,
+};
+
+/// Precision of the timestamps written in line protocol.
+///
+/// It must match the `precision` parameter of the InfluxDB write request.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Precision {
+    /// Nanoseconds (`ns`), the InfluxDB default.
+    #[default]
+    Nanoseconds,
+    /// Microseconds (`us`).
+    Microseconds,
+    /// Milliseconds (`ms`).
+    Milliseconds,
+    /// Seconds (`s`).
+    Seconds,
+}
+
+impl Precision {
+    /// Returns the value of the `precision` parameter of the InfluxDB write API.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Nanoseconds => "ns",
+            Self::Microseconds => "us",
+            Self::Milliseconds => "ms",
+            Self::Seconds => "s",
+        }
+    }
+
+    fn format(self, timestamp: Duration) -> u128 {
+        match self {
+            Self::Nanoseconds => timestamp.as_nanos(),
+            Self::Microseconds => timestamp.as_micros(),
+            Self::Milliseconds => timestamp.as_millis(),
+            Self::Seconds => u128::from(timestamp.as_secs()),
+        }
+    }
+}
+
+/// Encodes metrics from a [`Registry`] into InfluxDB line protocol.
+///
+/// Encoding errors are wrapped into an [`io::Error`] of kind [`io::ErrorKind::Other`].
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     format::influx::{self, Precision},
+/// #     metrics::counter::Counter,
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut registry = Registry::builder().with_const_labels([("host", "server01")]).build()?;
+/// let requests = <Counter>::default();
+/// registry.register("requests", "Total requests", requests.clone())?;
+/// requests.inc_by(3);
+///
+/// let mut output = Vec::new();
+/// influx::encode(&mut output, &registry, Precision::Seconds)?;
+/// let output = String::from_utf8(output)?;
+/// assert!(output.starts_with("requests,host=server01 total=3i "));
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode(
+    writer: &mut impl io::Write,
+    registry: &Registry,
+    precision: Precision,
+) -> io::Result<()> {
+    encode_with(writer, registry, precision, crate::metrics::lazy_group::enter_scope)
+}
+
+/// Encodes metrics in InfluxDB line protocol with an explicit scope hook.
+pub fn encode_with<G>(
+    writer: &mut impl io::Write,
+    registry: &Registry,
+    precision: Precision,
+    enter_scope: impl FnOnce() -> G,
+) -> io::Result<()> {
+    // The returned value is kept alive for the duration of encoding and then dropped.
+    let _guard = enter_scope();
+
+    let now = SystemTime::UNIX_EPOCH.elapsed().unwrap_or_default();
+    let mut output = String::new();
+    encode_registry(&mut output, registry, precision, now).map_err(io::Error::other)?;
+    writer.write_all(output.as_bytes())
+}
+
+fn encode_registry(
+    output: &mut String,
+    registry: &Registry,
+    precision: Precision,
+    now: Duration,
+) -> Result<()> {
+    let mut family_encoder = MetricFamilyEncoder {
+        output,
+        namespace: registry.namespace(),
+        const_labels: registry.constant_labels(),
+        precision,
+        now,
+    };
+    for (metadata, metric) in &registry.metrics {
+        family_encoder.encode(metadata, metric)?;
+    }
+    registry.collect_sources(&mut family_encoder)?;
+    for subsystem in registry.subsystems.values() {
+        encode_registry(output, subsystem, precision, now)?;
+    }
+    Ok(())
+}
+
+struct MetricFamilyEncoder<'a> {
+    output: &'a mut String,
+    namespace: Option<&'a str>,
+    const_labels: &'a [(Cow<'static, str>, Cow<'static, str>)],
+    precision: Precision,
+    now: Duration,
+}
+
+impl encoder::MetricFamilyEncoder for MetricFamilyEncoder<'_> {
+    fn encode(&mut self, metadata: &Metadata, metric: &dyn EncodeMetric) -> Result<()> {
+        if metric.is_empty() {
+            // skip empty metric family
+            return Ok(());
+        }
+
+        let mut measurement = String::new();
+        escape_into(&mut measurement, &metadata.qualified_name(self.namespace), &[',', ' ']);
+        let mut tags = String::new();
+        self.const_labels.encode(&mut TagsEncoder { tags: &mut tags })?;
+
+        metric.encode(&mut MetricEncoder {
+            output: self.output,
+            measurement: &measurement,
+            tags,
+            timestamp: metric.timestamp().unwrap_or(self.now),
+            precision: self.precision,
+        })
+    }
+}
+
+struct MetricEncoder<'a> {
+    output: &'a mut String,
+    measurement: &'a str,
+    // `,name=value` pairs
+    tags: String,
+    timestamp: Duration,
+    precision: Precision,
+}
+
+impl MetricEncoder<'_> {
+    /// Writes a line with the given extra `tags` and `fields`, skipping fields without value.
+    fn write_line(&mut self, tags: &str, fields: &[(&str, Option<String>)]) -> Result<()> {
+        let mut fields = fields.iter().filter_map(|(name, value)| Some((name, value.as_ref()?)));
+        let Some((name, value)) = fields.next() else {
+            // a line requires at least one field
+            return Ok(());
+        };
+
+        self.output.push_str(self.measurement);
+        self.output.push_str(&self.tags);
+        self.output.push_str(tags);
+        self.output.push(' ');
+        escape_into(self.output, name, &[',', '=', ' ']);
+        self.output.push('=');
+        self.output.push_str(value);
+        for (name, value) in fields {
+            self.output.push(',');
+            escape_into(self.output, name, &[',', '=', ' ']);
+            self.output.push('=');
+            self.output.push_str(value);
+        }
+        writeln!(self.output, " {}", self.precision.format(self.timestamp))?;
+        Ok(())
+    }
+
+    fn write_buckets(&mut self, buckets: &[Bucket]) -> Result<()> {
+        let mut cumulative_count = 0;
+        for bucket in buckets {
+            cumulative_count += bucket.count();
+            let mut tags = String::from(",le=");
+            write_le(&mut tags, bucket.upper_bound());
+            self.write_line(&tags, &[("bucket", Some(integer_field(cumulative_count)))])?;
+        }
+        Ok(())
+    }
+}
+
+impl encoder::MetricEncoder for MetricEncoder<'_> {
+    fn encode_unknown(&mut self, value: &dyn EncodeUnknownValue) -> Result<()> {
+        let mut field = FieldValueEncoder { value: None };
+        value.encode(&mut field)?;
+        self.write_line("", &[("value", field.value)])
+    }
+
+    fn encode_gauge(&mut self, value: &dyn EncodeGaugeValue) -> Result<()> {
+        let mut field = FieldValueEncoder { value: None };
+        value.encode(&mut field)?;
+        self.write_line("", &[("value", field.value)])
+    }
+
+    fn encode_counter(
+        &mut self,
+        total: &dyn EncodeCounterValue,
+        _exemplar: Option<&dyn EncodeExemplar>,
+        _created: Option<Duration>,
+    ) -> Result<()> {
+        let mut field = FieldValueEncoder { value: None };
+        total.encode(&mut field)?;
+        self.write_line("", &[("total", field.value)])
+    }
+
+    fn encode_stateset(&mut self, states: Vec<(&str, bool)>) -> Result<()> {
+        let fields = states
+            .into_iter()
+            .map(|(state, enabled)| (state, Some(enabled.to_string())))
+            .collect::<Vec<_>>();
+        self.write_line("", &fields)
+    }
+
+    fn encode_info(&mut self, label_set: &dyn EncodeLabelSet) -> Result<()> {
+        let mut tags = String::new();
+        label_set.encode(&mut TagsEncoder { tags: &mut tags })?;
+        self.write_line(&tags, &[("info", Some(integer_field(1)))])
+    }
+
+    fn encode_histogram(
+        &mut self,
+        buckets: &[Bucket],
+        _exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+        _created: Option<Duration>,
+    ) -> Result<()> {
+        self.write_buckets(buckets)?;
+        self.write_line("", &[("sum", float_field(sum)), ("count", Some(integer_field(count)))])
+    }
+
+    fn encode_gauge_histogram(
+        &mut self,
+        buckets: &[Bucket],
+        _exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+    ) -> Result<()> {
+        self.write_buckets(buckets)?;
+        self.write_line("", &[("gsum", float_field(sum)), ("gcount", Some(integer_field(count)))])
+    }
+
+    fn encode_summary(
+        &mut self,
+        quantiles: &[Quantile],
+        sum: f64,
+        count: u64,
+        _created: Option<Duration>,
+    ) -> Result<()> {
+        for quantile in quantiles {
+            let mut tags = String::from(",quantile=");
+            write_le(&mut tags, quantile.quantile());
+            self.write_line(&tags, &[("value", float_field(quantile.value()))])?;
+        }
+        self.write_line("", &[("sum", float_field(sum)), ("count", Some(integer_field(count)))])
+    }
+
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        let timestamp = self.timestamp;
+        for sample in samples {
+            let mut tags = String::new();
+            sample.labels.encode(&mut TagsEncoder { tags: &mut tags })?;
+            let field = sample.suffix.map_or("value", |suffix| suffix.trim_start_matches('_'));
+            self.timestamp = sample.timestamp.unwrap_or(timestamp);
+            let result = self.write_line(&tags, &[(field, float_field(sample.value))]);
+            self.timestamp = timestamp;
+            result?;
+        }
+        Ok(())
+    }
+
+    fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
+        let mut tags = self.tags.clone();
+        label_set.encode(&mut TagsEncoder { tags: &mut tags })?;
+        metric.encode(&mut MetricEncoder {
+            output: self.output,
+            measurement: self.measurement,
+            tags,
+            timestamp: metric.timestamp().unwrap_or(self.timestamp),
+            precision: self.precision,
+        })
+    }
+}
+
+/// Writes `value` into `output`, escaping the `special` characters and backslashes.
+fn escape_into(output: &mut String, value: &str, special: &[char]) {
+    for c in value.chars() {
+        match c {
+            '\n' => output.push_str("\\n"),
+            c if c == '\\' || special.contains(&c) => {
+                output.push('\\');
+                output.push(c);
+            },
+            c => output.push(c),
+        }
+    }
+}
+
+fn write_le(tags: &mut String, upper_bound: f64) {
+    if upper_bound == f64::INFINITY {
+        tags.push_str("+Inf");
+    } else {
+        tags.push_str(zmij::Buffer::new().format(upper_bound));
+    }
+}
+
+fn integer_field(value: u64) -> String {
+    match i64::try_from(value) {
+        Ok(value) => format!("{}i", itoa::Buffer::new().format(value)),
+        // out of range of the signed integer fields
+        Err(_) => zmij::Buffer::new().format(value as f64).to_owned(),
+    }
+}
+
+fn float_field(value: f64) -> Option<String> {
+    value.is_finite().then(|| zmij::Buffer::new().format(value).to_owned())
+}
+
+struct TagsEncoder<'a> {
+    tags: &'a mut String,
+}
+
+impl encoder::LabelSetEncoder for TagsEncoder<'_> {
+    fn encode(&mut self, label: &dyn EncodeLabel) -> Result<()> {
+        let mut encoder = TagEncoder { name: String::new(), value: String::new() };
+        label.encode(&mut encoder)?;
+        // tags can't have an empty key or value
+        if !encoder.name.is_empty() && !encoder.value.is_empty() {
+            self.tags.push(',');
+            escape_into(self.tags, &encoder.name, &[',', '=', ' ']);
+            self.tags.push('=');
+            escape_into(self.tags, &encoder.value, &[',', '=', ' ']);
+        }
+        Ok(())
+    }
+}
+
+struct TagEncoder {
+    name: String,
+    value: String,
+}
+
+macro_rules! encode_integer_value_impls {
+    ($($integer:ty),*) => (
+        paste::paste! { $(
+            fn [<encode_ $integer _value>](&mut self, value: $integer) -> Result<()> {
+                self.value.push_str(itoa::Buffer::new().format(value));
+                Ok(())
+            }
+        )* }
+    )
+}
+
+macro_rules! encode_float_value_impls {
+    ($($float:ty),*) => (
+        paste::paste! { $(
+            fn [<encode_ $float _value>](&mut self, value: $float) -> Result<()> {
+                self.value.push_str(zmij::Buffer::new().format(value));
+                Ok(())
+            }
+        )* }
+    )
+}
+
+impl encoder::LabelEncoder for TagEncoder {
+    fn encode_label_name(&mut self, name: &str) -> Result<()> {
+        self.name.push_str(name);
+        Ok(())
+    }
+
+    fn encode_str_value(&mut self, value: &str) -> Result<()> {
+        self.value.push_str(value);
+        Ok(())
+    }
+
+    fn encode_bool_value(&mut self, value: bool) -> Result<()> {
+        self.value.push_str(if value { "true" } else { "false" });
+        Ok(())
+    }
+
+    encode_integer_value_impls! {
+        i8, i16, i32, i64, i128, isize,
+        u8, u16, u32, u64, u128, usize
+    }
+
+    encode_float_value_impls! { f32, f64 }
+}
+
+/// Encodes a metric value as a field value, `None` if it can't be represented.
+struct FieldValueEncoder {
+    value: Option<String>,
+}
+
+impl FieldValueEncoder {
+    fn set_i64(&mut self, value: i64) -> Result<()> {
+        self.value = Some(format!("{}i", itoa::Buffer::new().format(value)));
+        Ok(())
+    }
+
+    fn set_u64(&mut self, value: u64) -> Result<()> {
+        self.value = Some(integer_field(value));
+        Ok(())
+    }
+
+    fn set_f64(&mut self, value: f64) -> Result<()> {
+        self.value = float_field(value);
+        Ok(())
+    }
+}
+
+impl encoder::UnknownValueEncoder for FieldValueEncoder {
+    fn encode_i32(&mut self, value: i32) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_i64(&mut self, value: i64) -> Result<()> {
+        self.set_i64(value)
+    }
+
+    fn encode_isize(&mut self, value: isize) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_u32(&mut self, value: u32) -> Result<()> {
+        self.set_u64(value as u64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.set_f64(value as f64)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.set_f64(value)
+    }
+}
+
+impl encoder::GaugeValueEncoder for FieldValueEncoder {
+    fn encode_i32(&mut self, value: i32) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_i64(&mut self, value: i64) -> Result<()> {
+        self.set_i64(value)
+    }
+
+    fn encode_isize(&mut self, value: isize) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.set_f64(value as f64)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.set_f64(value)
+    }
+}
+
+impl encoder::CounterValueEncoder for FieldValueEncoder {
+    fn encode_u32(&mut self, value: u32) -> Result<()> {
+        self.set_u64(value as u64)
+    }
+
+    fn encode_u64(&mut self, value: u64) -> Result<()> {
+        self.set_u64(value)
+    }
+
+    fn encode_usize(&mut self, value: usize) -> Result<()> {
+        self.set_u64(value as u64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.set_f64(value as f64)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.set_f64(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::{
+            counter::Counter,
+            family::Family,
+            gauge::Gauge,
+            histogram::{Histogram, linear_buckets},
+        },
+        raw::LabelSetSchema,
+    };
+
+    #[derive(Clone, Eq, PartialEq, Hash)]
+    struct Labels {
+        method: &'static str,
+    }
+
+    impl LabelSetSchema for Labels {
+        fn names() -> Option<&'static [&'static str]> {
+            Some(&["method"])
+        }
+    }
+
+    impl EncodeLabelSet for Labels {
+        fn encode(&self, encoder: &mut dyn encoder::LabelSetEncoder) -> Result<()> {
+            encoder.encode(&("method", self.method))
+        }
+    }
+
+    fn encode_lines(registry: &Registry, precision: Precision) -> Vec<String> {
+        let mut output = Vec::new();
+        encode(&mut output, registry, precision).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.is_empty() || output.ends_with('\n'));
+        let mut lines = output.lines().map(str::to_owned).collect::<Vec<_>>();
+        lines.sort();
+        lines
+    }
+
+    /// Splits a line into its measurement and tags, fields, and timestamp.
+    fn split_line(line: &str) -> (&str, &str, u128) {
+        let mut parts = line.split(' ');
+        let (series, fields, timestamp) =
+            (parts.next().unwrap(), parts.next().unwrap(), parts.next().unwrap());
+        assert_eq!(parts.next(), None, "{line}");
+        (series, fields, timestamp.parse().unwrap())
+    }
+
+    #[test]
+    fn test_encode_counter() -> Result<()> {
+        let mut registry = Registry::builder()
+            .with_namespace("myapp")
+            .with_const_labels([("host", "server,01")])
+            .build()?;
+        let requests = Family::<Labels, Counter>::default();
+        registry.register("requests", "Total requests", requests.clone())?;
+        requests.with_or_new(&Labels { method: "GET" }, |counter| counter.inc_by(42));
+        requests.with_or_new(&Labels { method: "" }, |counter| counter.inc());
+
+        let before = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_secs();
+        let lines = encode_lines(&registry, Precision::Seconds);
+        let after = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_secs();
+        assert_eq!(lines.len(), 2);
+
+        // tags with an empty value are omitted
+        let (series, fields, timestamp) = split_line(&lines[0]);
+        assert_eq!(series, r"myapp_requests,host=server\,01");
+        assert_eq!(fields, "total=1i");
+        assert!((u128::from(before)..=u128::from(after)).contains(&timestamp));
+
+        let (series, fields, _) = split_line(&lines[1]);
+        assert_eq!(series, r"myapp_requests,host=server\,01,method=GET");
+        assert_eq!(fields, "total=42i");
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_gauges_and_histograms() -> Result<()> {
+        let mut registry = Registry::default();
+        registry.register("temperature", "", Gauge::<f64>::new(21.5))?;
+        registry.register("connections", "", Gauge::<i64>::new(-3))?;
+        registry.register("missing", "", Gauge::<f64>::new(f64::NAN))?;
+        let histogram = Histogram::new(linear_buckets(1.0, 1.0, 2));
+        registry.subsystem("http")?.register("latency", "", histogram.clone())?;
+        histogram.observe(1.5);
+
+        let lines = encode_lines(&registry, Precision::Nanoseconds)
+            .into_iter()
+            .map(|line| {
+                let (series, fields, _) = split_line(&line);
+                format!("{series} {fields}")
+            })
+            .collect::<Vec<_>>();
+        // non-finite values can't be represented
+        assert_eq!(
+            lines,
+            [
+                "connections value=-3i",
+                "http_latency sum=1.5,count=1i",
+                "http_latency,le=+Inf bucket=1i",
+                "http_latency,le=1.0 bucket=0i",
+                "http_latency,le=2.0 bucket=1i",
+                "temperature value=21.5",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_precision() {
+        let timestamp = Duration::new(1_234_567_890, 123_456_789);
+        assert_eq!(Precision::Nanoseconds.format(timestamp), 1_234_567_890_123_456_789);
+        assert_eq!(Precision::Microseconds.format(timestamp), 1_234_567_890_123_456);
+        assert_eq!(Precision::Milliseconds.format(timestamp), 1_234_567_890_123);
+        assert_eq!(Precision::Seconds.format(timestamp), 1_234_567_890);
+        assert_eq!(Precision::default().as_str(), "ns");
+    }
+}
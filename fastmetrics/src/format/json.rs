@@ -0,0 +1,638 @@
+//! JSON Lines (NDJSON) exposition format.
+//!
+//! Each metric family is written as a single JSON object on its own line:
+//!
+//! ```json
+//! {"name":"http_requests","type":"counter","help":"Total HTTP requests","samples":[{"labels":{"method":"GET"},"value":42,"timestamp":null}]}
+//! ```
+//!
+//! The `value` of a sample depends on the metric type:
+//! - `unknown`, `gauge`, `counter`: a number
+//! - `stateset`: an object mapping each state to a boolean
+//! - `info`: an object of the info labels
+//! - `histogram`, `gaugehistogram`: an object with `buckets` (`le`/cumulative `count` pairs),
+//!   `count` and `sum`
+//! - `summary`: an object with `quantiles` (`quantile`/`value` pairs), `count` and `sum`
+//!
+//! Non-finite floats are written as the strings `"NaN"`, `"+Inf"` and `"-Inf"`.
+//! Timestamps are written as UNIX seconds.
+
+use std::{borrow::Cow, io, time::Duration};
+
+use serde::{Serialize, Serializer, ser::SerializeMap};
+
+use crate::{
+    encoder::{
+        self, EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel, EncodeLabelSet,
+        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _, RawSample,
+    },
+    error::{Error, Result},
+    raw::{Metadata, bucket::Bucket, quantile::Quantile},
+    registry::Registry,
+};
+
+/// Encodes metrics from a registry into JSON Lines format.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     format::json,
+/// #     metrics::counter::Counter,
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let mut registry = Registry::default();
+/// let requests = <Counter>::default();
+/// registry.register("requests", "Total requests", requests.clone())?;
+/// requests.inc();
+///
+/// let mut output = Vec::new();
+/// json::encode(&mut output, &registry)?;
+/// assert_eq!(
+///     String::from_utf8(output).unwrap(),
+///     "{\"name\":\"requests\",\"type\":\"counter\",\"help\":\"Total requests\",\
+///      \"samples\":[{\"labels\":{},\"value\":1,\"timestamp\":null}]}\n"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode(writer: &mut impl io::Write, registry: &Registry) -> Result<()> {
+    encode_with(writer, registry, crate::metrics::lazy_group::enter_scope)
+}
+
+/// Encodes metrics in JSON Lines format with an explicit scope hook.
+pub fn encode_with<G>(
+    writer: &mut impl io::Write,
+    registry: &Registry,
+    enter_scope: impl FnOnce() -> G,
+) -> Result<()> {
+    // The returned value is kept alive for the duration of encoding and then dropped.
+    let _guard = enter_scope();
+
+    encode_registry(writer, registry)
+}
+
+fn json_error(err: serde_json::Error) -> Error {
+    Error::unexpected(err.to_string()).set_source(err)
+}
+
+fn io_error(err: io::Error) -> Error {
+    Error::unexpected(err.to_string()).set_source(err)
+}
+
+fn encode_registry(writer: &mut impl io::Write, registry: &Registry) -> Result<()> {
+    for (metadata, metric) in &registry.metrics {
+        MetricFamilyEncoder {
+            writer: &mut *writer,
+            namespace: registry.namespace(),
+            const_labels: registry.constant_labels(),
+        }
+        .encode(metadata, metric.as_ref())?;
+    }
+    registry.collect_sources(&mut MetricFamilyEncoder {
+        writer: &mut *writer,
+        namespace: registry.namespace(),
+        const_labels: registry.constant_labels(),
+    })?;
+    for subsystem in registry.subsystems.values() {
+        encode_registry(writer, subsystem)?;
+    }
+    Ok(())
+}
+
+/// A metric family, written as one line. The fields are serialized in declaration order.
+#[derive(Serialize)]
+struct Family<'a> {
+    name: String,
+    #[serde(rename = "type")]
+    metric_type: String,
+    help: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<&'a str>,
+    samples: Vec<Sample>,
+}
+
+#[derive(Serialize)]
+struct Sample {
+    labels: Labels,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suffix: Option<String>,
+    value: SampleValue,
+    timestamp: Option<Float>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SampleValue {
+    Number(Number),
+    StateSet(States),
+    Info(Labels),
+    Histogram { buckets: Vec<HistogramBucket>, count: u64, sum: Float },
+    Summary { quantiles: Vec<SummaryQuantile>, count: u64, sum: Float },
+}
+
+#[derive(Serialize)]
+struct HistogramBucket {
+    le: Float,
+    count: u64,
+}
+
+#[derive(Serialize)]
+struct SummaryQuantile {
+    quantile: Float,
+    value: Float,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(untagged)]
+enum Number {
+    I64(i64),
+    U64(u64),
+    F64(Float),
+}
+
+/// A float, written as a string if it's not finite.
+#[derive(Clone, Copy)]
+struct Float(f64);
+
+impl Serialize for Float {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            value if value.is_finite() => serializer.serialize_f64(value),
+            value if value.is_nan() => serializer.serialize_str("NaN"),
+            value if value.is_sign_positive() => serializer.serialize_str("+Inf"),
+            _ => serializer.serialize_str("-Inf"),
+        }
+    }
+}
+
+/// Labels, written as an object in the order they were encoded.
+#[derive(Clone, Default)]
+struct Labels(Vec<(String, String)>);
+
+impl Labels {
+    fn insert(&mut self, name: String, value: String) {
+        match self.0.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((name, value)),
+        }
+    }
+}
+
+impl Serialize for Labels {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+struct States(Vec<(String, bool)>);
+
+impl Serialize for States {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (state, enabled) in &self.0 {
+            map.serialize_entry(state, enabled)?;
+        }
+        map.end()
+    }
+}
+
+struct MetricFamilyEncoder<'a, W> {
+    writer: &'a mut W,
+    namespace: Option<&'a str>,
+    const_labels: &'a [(Cow<'static, str>, Cow<'static, str>)],
+}
+
+impl<W: io::Write> encoder::MetricFamilyEncoder for MetricFamilyEncoder<'_, W> {
+    fn encode(&mut self, metadata: &Metadata, metric: &dyn EncodeMetric) -> Result<()> {
+        if metric.is_empty() {
+            // skip empty metric family
+            return Ok(());
+        }
+
+        let mut labels = Labels::default();
+        self.const_labels.encode(&mut LabelSetEncoder { labels: &mut labels })?;
+
+        let mut samples = Vec::new();
+        metric.encode(&mut MetricEncoder {
+            samples: &mut samples,
+            labels,
+            timestamp: metric.timestamp(),
+        })?;
+
+        let family = Family {
+            name: metadata.qualified_name(self.namespace),
+            metric_type: metadata.metric_type().to_string(),
+            help: metadata.help(),
+            unit: metadata.unit().map(|unit| unit.as_str()),
+            samples,
+        };
+        serde_json::to_writer(&mut *self.writer, &family).map_err(json_error)?;
+        self.writer.write_all(b"\n").map_err(io_error)
+    }
+}
+
+struct MetricEncoder<'a> {
+    samples: &'a mut Vec<Sample>,
+    labels: Labels,
+    timestamp: Option<Duration>,
+}
+
+impl MetricEncoder<'_> {
+    fn push_sample(&mut self, value: SampleValue) {
+        self.samples.push(Sample {
+            labels: self.labels.clone(),
+            suffix: None,
+            value,
+            timestamp: timestamp_value(self.timestamp),
+        });
+    }
+}
+
+fn timestamp_value(timestamp: Option<Duration>) -> Option<Float> {
+    timestamp.map(|ts| Float(ts.as_secs_f64()))
+}
+
+impl encoder::MetricEncoder for MetricEncoder<'_> {
+    fn encode_unknown(&mut self, value: &dyn EncodeUnknownValue) -> Result<()> {
+        let mut v = Number::I64(0);
+        value.encode(&mut NumberValueEncoder { value: &mut v })?;
+        self.push_sample(SampleValue::Number(v));
+        Ok(())
+    }
+
+    fn encode_gauge(&mut self, value: &dyn EncodeGaugeValue) -> Result<()> {
+        let mut v = Number::I64(0);
+        value.encode(&mut NumberValueEncoder { value: &mut v })?;
+        self.push_sample(SampleValue::Number(v));
+        Ok(())
+    }
+
+    fn encode_counter(
+        &mut self,
+        total: &dyn EncodeCounterValue,
+        _exemplar: Option<&dyn EncodeExemplar>,
+        _created: Option<Duration>,
+    ) -> Result<()> {
+        let mut v = Number::U64(0);
+        total.encode(&mut NumberValueEncoder { value: &mut v })?;
+        self.push_sample(SampleValue::Number(v));
+        Ok(())
+    }
+
+    fn encode_stateset(&mut self, states: Vec<(&str, bool)>) -> Result<()> {
+        let states =
+            states.into_iter().map(|(state, enabled)| (state.to_owned(), enabled)).collect();
+        self.push_sample(SampleValue::StateSet(States(states)));
+        Ok(())
+    }
+
+    fn encode_info(&mut self, label_set: &dyn EncodeLabelSet) -> Result<()> {
+        let mut info = Labels::default();
+        label_set.encode(&mut LabelSetEncoder { labels: &mut info })?;
+        self.push_sample(SampleValue::Info(info));
+        Ok(())
+    }
+
+    fn encode_histogram(
+        &mut self,
+        buckets: &[Bucket],
+        _exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+        _created: Option<Duration>,
+    ) -> Result<()> {
+        let mut cumulative_count = 0;
+        let buckets = buckets
+            .iter()
+            .map(|bucket| {
+                cumulative_count += bucket.count();
+                HistogramBucket { le: Float(bucket.upper_bound()), count: cumulative_count }
+            })
+            .collect();
+        self.push_sample(SampleValue::Histogram { buckets, count, sum: Float(sum) });
+        Ok(())
+    }
+
+    fn encode_gauge_histogram(
+        &mut self,
+        buckets: &[Bucket],
+        exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+    ) -> Result<()> {
+        self.encode_histogram(buckets, exemplars, count, sum, None)
+    }
+
+    fn encode_summary(
+        &mut self,
+        quantiles: &[Quantile],
+        sum: f64,
+        count: u64,
+        _created: Option<Duration>,
+    ) -> Result<()> {
+        let quantiles = quantiles
+            .iter()
+            .map(|q| SummaryQuantile { quantile: Float(q.quantile()), value: Float(q.value()) })
+            .collect();
+        self.push_sample(SampleValue::Summary { quantiles, count, sum: Float(sum) });
+        Ok(())
+    }
+
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        for sample in samples {
+            let mut labels = self.labels.clone();
+            sample.labels.encode(&mut LabelSetEncoder { labels: &mut labels })?;
+            self.samples.push(Sample {
+                labels,
+                suffix: sample.suffix.map(str::to_owned),
+                value: SampleValue::Number(Number::F64(Float(sample.value))),
+                timestamp: timestamp_value(sample.timestamp.or(self.timestamp)),
+            });
+        }
+        Ok(())
+    }
+
+    fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
+        let mut labels = self.labels.clone();
+        label_set.encode(&mut LabelSetEncoder { labels: &mut labels })?;
+        metric.encode(&mut MetricEncoder {
+            samples: self.samples,
+            labels,
+            timestamp: metric.timestamp(),
+        })
+    }
+}
+
+struct LabelSetEncoder<'a> {
+    labels: &'a mut Labels,
+}
+
+impl encoder::LabelSetEncoder for LabelSetEncoder<'_> {
+    fn encode(&mut self, label: &dyn EncodeLabel) -> Result<()> {
+        let mut encoder = LabelEncoder { name: String::new(), value: String::new() };
+        label.encode(&mut encoder)?;
+        if !encoder.name.is_empty() {
+            self.labels.insert(encoder.name, encoder.value);
+        }
+        Ok(())
+    }
+}
+
+struct LabelEncoder {
+    name: String,
+    value: String,
+}
+
+macro_rules! encode_integer_value_impls {
+    ($($integer:ty),*) => (
+        paste::paste! { $(
+            fn [<encode_ $integer _value>](&mut self, value: $integer) -> Result<()> {
+                self.value.push_str(itoa::Buffer::new().format(value));
+                Ok(())
+            }
+        )* }
+    )
+}
+
+macro_rules! encode_float_value_impls {
+    ($($float:ty),*) => (
+        paste::paste! { $(
+            fn [<encode_ $float _value>](&mut self, value: $float) -> Result<()> {
+                self.value.push_str(zmij::Buffer::new().format(value));
+                Ok(())
+            }
+        )* }
+    )
+}
+
+impl encoder::LabelEncoder for LabelEncoder {
+    fn encode_label_name(&mut self, name: &str) -> Result<()> {
+        self.name.push_str(name);
+        Ok(())
+    }
+
+    fn encode_str_value(&mut self, value: &str) -> Result<()> {
+        self.value.push_str(value);
+        Ok(())
+    }
+
+    fn encode_bool_value(&mut self, value: bool) -> Result<()> {
+        self.value.push_str(if value { "true" } else { "false" });
+        Ok(())
+    }
+
+    encode_integer_value_impls! {
+        i8, i16, i32, i64, i128, isize,
+        u8, u16, u32, u64, u128, usize
+    }
+
+    encode_float_value_impls! { f32, f64 }
+}
+
+struct NumberValueEncoder<'a> {
+    value: &'a mut Number,
+}
+
+impl NumberValueEncoder<'_> {
+    fn set_i64(&mut self, value: i64) -> Result<()> {
+        *self.value = Number::I64(value);
+        Ok(())
+    }
+
+    fn set_u64(&mut self, value: u64) -> Result<()> {
+        *self.value = Number::U64(value);
+        Ok(())
+    }
+
+    fn set_f64(&mut self, value: f64) -> Result<()> {
+        *self.value = Number::F64(Float(value));
+        Ok(())
+    }
+}
+
+impl encoder::UnknownValueEncoder for NumberValueEncoder<'_> {
+    fn encode_i32(&mut self, value: i32) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_i64(&mut self, value: i64) -> Result<()> {
+        self.set_i64(value)
+    }
+
+    fn encode_isize(&mut self, value: isize) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_u32(&mut self, value: u32) -> Result<()> {
+        self.set_u64(value as u64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.set_f64(value as f64)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.set_f64(value)
+    }
+}
+
+impl encoder::GaugeValueEncoder for NumberValueEncoder<'_> {
+    fn encode_i32(&mut self, value: i32) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_i64(&mut self, value: i64) -> Result<()> {
+        self.set_i64(value)
+    }
+
+    fn encode_isize(&mut self, value: isize) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.set_f64(value as f64)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.set_f64(value)
+    }
+}
+
+impl encoder::CounterValueEncoder for NumberValueEncoder<'_> {
+    fn encode_u32(&mut self, value: u32) -> Result<()> {
+        self.set_u64(value as u64)
+    }
+
+    fn encode_u64(&mut self, value: u64) -> Result<()> {
+        self.set_u64(value)
+    }
+
+    fn encode_usize(&mut self, value: usize) -> Result<()> {
+        self.set_u64(value as u64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.set_f64(value as f64)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.set_f64(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::{
+            counter::Counter,
+            family::Family,
+            gauge::Gauge,
+            histogram::{Histogram, linear_buckets},
+        },
+        raw::LabelSetSchema,
+    };
+    use serde_json::Value;
+
+    #[derive(Clone, Eq, PartialEq, Hash)]
+    struct Labels {
+        method: &'static str,
+    }
+
+    impl LabelSetSchema for Labels {
+        fn names() -> Option<&'static [&'static str]> {
+            Some(&["method"])
+        }
+    }
+
+    impl EncodeLabelSet for Labels {
+        fn encode(&self, encoder: &mut dyn encoder::LabelSetEncoder) -> Result<()> {
+            encoder.encode(&("method", self.method))
+        }
+    }
+
+    fn encode_lines(registry: &Registry) -> Vec<Value> {
+        let mut output = Vec::new();
+        encode(&mut output, registry).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with('\n'));
+        output.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_encode_json_lines() -> Result<()> {
+        let mut registry = Registry::builder().with_namespace("myapp").build()?;
+
+        let requests = Family::<Labels, Counter>::default();
+        registry.register("requests", "Total requests", requests.clone())?;
+        requests.with_or_new(&Labels { method: "GET" }, |c| c.inc_by(42));
+
+        let temperature = Gauge::<f64>::new(f64::NAN);
+        registry.subsystem("env")?.register("temperature", "", temperature)?;
+
+        let lines = encode_lines(&registry);
+        assert_eq!(lines.len(), 2);
+
+        let requests = lines.iter().find(|l| l["name"] == "myapp_requests").unwrap();
+        assert_eq!(requests["type"], "counter");
+        assert_eq!(requests["help"], "Total requests");
+        assert_eq!(requests["samples"][0]["labels"]["method"], "GET");
+        assert_eq!(requests["samples"][0]["value"], 42);
+        assert_eq!(requests["samples"][0]["timestamp"], Value::Null);
+
+        let temperature = lines.iter().find(|l| l["name"] == "myapp_env_temperature").unwrap();
+        assert_eq!(temperature["type"], "gauge");
+        assert_eq!(temperature["samples"][0]["value"], "NaN");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_json_lines_histogram() -> Result<()> {
+        let mut registry = Registry::default();
+
+        let histogram = Histogram::new(linear_buckets(1.0, 1.0, 2));
+        registry.register("latency", "", histogram.clone())?;
+        histogram.observe(1.5);
+
+        let lines = encode_lines(&registry);
+        let value = &lines[0]["samples"][0]["value"];
+        assert_eq!(value["count"], 1);
+        assert_eq!(value["sum"], 1.5);
+        assert_eq!(value["buckets"][0]["le"], 1.0);
+        assert_eq!(value["buckets"][0]["count"], 0);
+        assert_eq!(value["buckets"][1]["count"], 1);
+        assert_eq!(value["buckets"][2]["le"], "+Inf");
+        assert_eq!(value["buckets"][2]["count"], 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_json_lines_field_order() -> Result<()> {
+        let mut registry = Registry::builder().with_const_labels([("zone", "b")]).build()?;
+
+        let requests = Family::<Labels, Counter>::default();
+        registry.register("requests", "Total requests", requests.clone())?;
+        requests.with_or_new(&Labels { method: "GET" }, |c| c.inc());
+
+        let mut output = Vec::new();
+        encode(&mut output, &registry)?;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"name\":\"requests\",\"type\":\"counter\",\"help\":\"Total requests\",\
+             \"samples\":[{\"labels\":{\"zone\":\"b\",\"method\":\"GET\"},\"value\":1,\
+             \"timestamp\":null}]}\n"
+        );
+
+        Ok(())
+    }
+}
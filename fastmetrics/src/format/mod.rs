@@ -7,12 +7,21 @@
 //! - [`text`] is always available.
 //! - [`prost`] is available with feature `prost`.
 //! - [`protobuf`] is available with feature `protobuf`.
+//! - [`json`] is available with feature `json`.
+//! - [`influx`] is available with feature `influx`.
+//! - [`statsd`] is available with feature `statsd`.
 //!
 //! ## Text format
 //!
 //! The [`text`] module exposes the API:
 //! - `encode(buffer, registry, profile)`
 //! - `encode_with(buffer, registry, profile, enter_scope)`.
+//! - `encode_with_options(buffer, registry, options)`, e.g. with sorted output
+//! - `encode_metric_family(buffer, name, help, &metric, profile)`, encoding a single metric
+//! - `encode_to_writer(writer, registry, profile)`
+//! - `encode_compressed(writer, registry, profile)`, gzip-compressed (feature `gzip`)
+//! - `encode_to_bytes(registry, profile)`, returning `bytes::Bytes` (feature `bytes`)
+//! - `validate(registry, profile)`, reporting conformance issues without encoding
 //!
 //! Text profiles:
 //! - `PrometheusV0_0_4`: [Prometheus text format]
@@ -38,6 +47,32 @@
 //!   - [OpenMetrics protobuf format]
 //!   - [OpenMetrics protobuf schema]
 //!
+//! ## JSON Lines format
+//!
+//! The [`json`] module (feature `json`) exposes the API:
+//! - `encode(writer, registry)`
+//! - `encode_with(writer, registry, enter_scope)`
+//!
+//! Each metric family is written as one JSON object per line, which is convenient for log
+//! aggregation systems that ingest NDJSON.
+//!
+//! ## InfluxDB line protocol
+//!
+//! The [`influx`] module (feature `influx`) exposes the API:
+//! - `encode(writer, registry, precision)`
+//! - `encode_with(writer, registry, precision, enter_scope)`
+//!
+//! Each sample is written as a `measurement,tags fields timestamp` line, with the metric family as
+//! measurement and the labels as tags.
+//!
+//! ## StatsD format
+//!
+//! The [`statsd`] module (feature `statsd`) exposes the API:
+//! - `encode(writer, registry, sample_rate)`
+//! - `encode_with(writer, registry, sample_rate, enter_scope)`
+//!
+//! Each sample is written as a `name:value|type` line, with the labels as DogStatsD tags.
+//!
 //! [OpenMetrics text format]: https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#text-format
 //! [Prometheus text format]: https://prometheus.io/docs/instrumenting/exposition_formats/#text-format-details
 //! [OpenMetrics protobuf format]: https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#protobuf-format
@@ -47,8 +82,35 @@
 
 mod profile;
 
+#[cfg(feature = "influx")]
+pub mod influx;
+#[cfg(feature = "json")]
+pub mod json;
 #[cfg(feature = "prost")]
 pub mod prost;
 #[cfg(feature = "protobuf")]
 pub mod protobuf;
+#[cfg(feature = "statsd")]
+pub mod statsd;
 pub mod text;
+
+#[cfg(any(feature = "prost", feature = "protobuf"))]
+use crate::{encoder::EncodeLabel, error::Result};
+
+/// Appends a batch of labels to the label list of a protobuf message, encoding each one into a
+/// new default entry with `encode`.
+///
+/// Shared by the `encode_all` overrides of the [`prost`] and [`protobuf`] label set encoders.
+#[cfg(any(feature = "prost", feature = "protobuf"))]
+pub(crate) fn encode_protobuf_labels<L: Default>(
+    labels: &mut Vec<L>,
+    batch: &[&dyn EncodeLabel],
+    mut encode: impl FnMut(&mut L, &dyn EncodeLabel) -> Result<()>,
+) -> Result<()> {
+    labels.reserve(batch.len());
+    for label in batch {
+        labels.push(L::default());
+        encode(labels.last_mut().expect("labels must not be none"), *label)?;
+    }
+    Ok(())
+}
@@ -70,6 +70,20 @@ pub enum EscapingScheme {
     Values,
 }
 
+/// Format of the sample and exemplar timestamps in text exposition.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TimestampFormat {
+    /// Seconds since the Unix epoch with a millisecond fraction, e.g. `1234567890.123`.
+    ///
+    /// This is the default of the OpenMetrics profiles.
+    SecondsMillis,
+    /// Milliseconds since the Unix epoch as an integer, e.g. `1234567890123`.
+    ///
+    /// This is the default of the Prometheus profiles.
+    MillisecondsInteger,
+}
+
 impl Default for TextProfile {
     fn default() -> Self {
         Self::OpenMetricsV1_0_0 { escaping_scheme: EscapingScheme::default() }
@@ -117,6 +131,160 @@ impl TextProfile {
     }
 }
 
+impl TextProfile {
+    /// Returns [`TextEncodeOptions`] for this profile, with metric families sorted by their fully
+    /// qualified names if `sorted_output` is `true`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::format::text::TextProfile;
+    /// let options = TextProfile::default().with_sorted_output(true);
+    /// assert!(options.sorted_output());
+    /// assert_eq!(options.profile(), TextProfile::default());
+    /// ```
+    pub const fn with_sorted_output(self, sorted_output: bool) -> TextEncodeOptions {
+        TextEncodeOptions {
+            profile: self,
+            sorted_output,
+            timestamp_format: None,
+            suppress_metadata_lines: false,
+        }
+    }
+
+    /// Returns [`TextEncodeOptions`] for this profile, with timestamps written in
+    /// `timestamp_format` instead of the default format of the profile.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::format::text::{TextProfile, TimestampFormat};
+    /// let options = TextProfile::default().with_timestamp_format(TimestampFormat::MillisecondsInteger);
+    /// assert_eq!(options.timestamp_format(), TimestampFormat::MillisecondsInteger);
+    /// assert_eq!(TextProfile::default().timestamp_format(), TimestampFormat::SecondsMillis);
+    /// ```
+    pub const fn with_timestamp_format(
+        self,
+        timestamp_format: TimestampFormat,
+    ) -> TextEncodeOptions {
+        TextEncodeOptions {
+            profile: self,
+            sorted_output: false,
+            timestamp_format: Some(timestamp_format),
+            suppress_metadata_lines: false,
+        }
+    }
+
+    /// Returns [`TextEncodeOptions`] for this profile, without the `# TYPE`, `# HELP` and `# UNIT`
+    /// lines if `suppress_metadata_lines` is `true`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::format::text::TextProfile;
+    /// let options = TextProfile::PrometheusV0_0_4.with_suppress_metadata_lines(true);
+    /// assert!(options.suppress_metadata_lines());
+    /// ```
+    pub const fn with_suppress_metadata_lines(
+        self,
+        suppress_metadata_lines: bool,
+    ) -> TextEncodeOptions {
+        TextEncodeOptions {
+            profile: self,
+            sorted_output: false,
+            timestamp_format: None,
+            suppress_metadata_lines,
+        }
+    }
+
+    /// Returns the default timestamp format of this profile.
+    pub const fn timestamp_format(self) -> TimestampFormat {
+        match self {
+            Self::PrometheusV0_0_4 | Self::PrometheusV1_0_0 { .. } => {
+                TimestampFormat::MillisecondsInteger
+            },
+            Self::OpenMetricsV0_0_1 | Self::OpenMetricsV1_0_0 { .. } => {
+                TimestampFormat::SecondsMillis
+            },
+        }
+    }
+}
+
+/// Text encoding options: a [`TextProfile`] plus settings independent of the profile, used by
+/// [`text::encode_with_options`](crate::format::text::encode_with_options).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TextEncodeOptions {
+    profile: TextProfile,
+    sorted_output: bool,
+    // `None` keeps the default format of the profile
+    timestamp_format: Option<TimestampFormat>,
+    suppress_metadata_lines: bool,
+}
+
+impl From<TextProfile> for TextEncodeOptions {
+    fn from(profile: TextProfile) -> Self {
+        Self {
+            profile,
+            sorted_output: false,
+            timestamp_format: None,
+            suppress_metadata_lines: false,
+        }
+    }
+}
+
+impl TextEncodeOptions {
+    /// Sets whether metric families (including the ones of subsystems) are sorted by their fully
+    /// qualified names.
+    ///
+    /// The registry doesn't keep its metrics in a stable order, so the output order may vary
+    /// between runs by default. Sorting makes the output deterministic, e.g. for snapshot tests,
+    /// at the cost of collecting and sorting the metric families on every encode.
+    pub const fn with_sorted_output(mut self, sorted_output: bool) -> Self {
+        self.sorted_output = sorted_output;
+        self
+    }
+
+    /// Sets the format of the sample and exemplar timestamps, overriding the default format of
+    /// the profile.
+    pub const fn with_timestamp_format(mut self, timestamp_format: TimestampFormat) -> Self {
+        self.timestamp_format = Some(timestamp_format);
+        self
+    }
+
+    /// Sets whether the `# TYPE`, `# HELP` and `# UNIT` lines are left out, so that only the
+    /// samples (and the `# EOF` line of the OpenMetrics profiles) are written.
+    ///
+    /// This is useful for receivers which accept the Prometheus text format but reject comment
+    /// lines, e.g. some push gateways and import endpoints.
+    pub const fn with_suppress_metadata_lines(mut self, suppress_metadata_lines: bool) -> Self {
+        self.suppress_metadata_lines = suppress_metadata_lines;
+        self
+    }
+
+    /// Returns the text profile.
+    pub const fn profile(self) -> TextProfile {
+        self.profile
+    }
+
+    /// Returns whether metric families are sorted by their fully qualified names.
+    pub const fn sorted_output(self) -> bool {
+        self.sorted_output
+    }
+
+    /// Returns whether the `# TYPE`, `# HELP` and `# UNIT` lines are left out.
+    pub const fn suppress_metadata_lines(self) -> bool {
+        self.suppress_metadata_lines
+    }
+
+    /// Returns the format of the sample and exemplar timestamps.
+    pub const fn timestamp_format(self) -> TimestampFormat {
+        match self.timestamp_format {
+            Some(timestamp_format) => timestamp_format,
+            None => self.profile.timestamp_format(),
+        }
+    }
+}
+
 /// Protobuf exposition profile shared by protobuf backends.
 ///
 /// This type is re-exported by both `format::prost` and `format::protobuf`.
@@ -145,3 +313,55 @@ impl ProtobufProfile {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_profile_content_type() {
+        assert_eq!(
+            TextProfile::PrometheusV0_0_4.content_type(),
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+        assert_eq!(
+            TextProfile::OpenMetricsV0_0_1.content_type(),
+            "application/openmetrics-text; version=0.0.1; charset=utf-8"
+        );
+        assert_eq!(
+            TextProfile::default().content_type(),
+            "application/openmetrics-text; version=1.0.0; charset=utf-8; escaping=underscores"
+        );
+        assert_eq!(
+            TextProfile::PrometheusV1_0_0 { escaping_scheme: EscapingScheme::AllowUtf8 }
+                .content_type(),
+            "text/plain; version=1.0.0; charset=utf-8; escaping=allow-utf-8"
+        );
+        for (escaping_scheme, escaping) in [
+            (EscapingScheme::AllowUtf8, "allow-utf-8"),
+            (EscapingScheme::Underscores, "underscores"),
+            (EscapingScheme::Dots, "dots"),
+            (EscapingScheme::Values, "values"),
+        ] {
+            assert_eq!(
+                TextProfile::OpenMetricsV1_0_0 { escaping_scheme }.content_type(),
+                format!(
+                    "application/openmetrics-text; version=1.0.0; charset=utf-8; escaping={escaping}"
+                )
+            );
+        }
+    }
+
+    #[cfg(any(feature = "prost", feature = "protobuf"))]
+    #[test]
+    fn test_protobuf_profile_content_type() {
+        assert_eq!(
+            ProtobufProfile::Prometheus.content_type(),
+            "application/vnd.google.protobuf; proto=io.prometheus.client.MetricFamily; encoding=delimited"
+        );
+        assert_eq!(
+            ProtobufProfile::OpenMetrics1.content_type(),
+            "application/openmetrics-protobuf; version=1.0.0; proto=openmetrics.MetricSet"
+        );
+    }
+}
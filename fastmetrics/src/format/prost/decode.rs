@@ -0,0 +1,426 @@
+//! Protobuf exposition format decoder.
+//!
+//! The decoder turns the output of the protobuf encoder back into structured data, so that tests
+//! can assert on metric names, labels and values instead of generated message types.
+
+use super::{ProtobufProfile, openmetrics_data_model, prometheus_data_model};
+use crate::{
+    error::{Error, Result},
+    raw::{MetricType, bucket::BUCKET_LABEL, quantile::QUANTILE_LABEL},
+};
+
+type Labels = Vec<(String, String)>;
+
+/// A metric family decoded from the protobuf exposition format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedMetricFamily {
+    /// Name of the metric family.
+    pub name: String,
+    /// Type of the metric family, `untyped` is mapped to [`MetricType::Unknown`].
+    pub metric_type: MetricType,
+    /// Help text, if any.
+    pub help: Option<String>,
+    /// Unit, if any.
+    pub unit: Option<String>,
+    /// Samples of the metric family, in exposition order.
+    pub samples: Vec<DecodedSample>,
+}
+
+/// A sample decoded from the protobuf exposition format.
+///
+/// Samples are flattened as in the text exposition format, e.g. every histogram bucket is a
+/// `_bucket` sample with a `le` label holding its cumulative count.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedSample {
+    /// Full sample name, including suffixes such as `_total` or `_bucket`.
+    pub name: String,
+    /// Label pairs, in exposition order.
+    pub labels: Vec<(String, String)>,
+    /// Sample value.
+    pub value: f64,
+}
+
+/// Decodes the protobuf exposition format of `profile`, as produced by [`encode`](super::encode).
+///
+/// # Examples
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     format::prost::{self, ProtobufProfile},
+/// #     metrics::counter::Counter,
+/// #     raw::MetricType,
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let mut registry = Registry::default();
+/// let requests = <Counter>::default();
+/// registry.register("http_requests", "Total number of HTTP requests", requests.clone())?;
+/// requests.inc_by(3);
+///
+/// let mut output = Vec::new();
+/// prost::encode(&mut output, &registry, ProtobufProfile::OpenMetrics1)?;
+///
+/// let families = prost::decode(&output, ProtobufProfile::OpenMetrics1)?;
+/// assert_eq!(families[0].name, "http_requests");
+/// assert_eq!(families[0].metric_type, MetricType::Counter);
+/// assert_eq!(families[0].samples[0].name, "http_requests_total");
+/// assert_eq!(families[0].samples[0].value, 3.0);
+/// # Ok(())
+/// # }
+/// ```
+pub fn decode(buf: &[u8], profile: ProtobufProfile) -> Result<Vec<DecodedMetricFamily>> {
+    match profile {
+        ProtobufProfile::OpenMetrics1 => decode_openmetrics(buf),
+        ProtobufProfile::Prometheus => decode_prometheus(buf),
+    }
+}
+
+fn decode_error(err: prost::DecodeError) -> Error {
+    Error::invalid(err.to_string()).set_source(err)
+}
+
+fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() { None } else { Some(s) }
+}
+
+fn format_bound(bound: f64) -> String {
+    if bound == f64::INFINITY {
+        "+Inf".to_owned()
+    } else if bound == f64::NEG_INFINITY {
+        "-Inf".to_owned()
+    } else {
+        bound.to_string()
+    }
+}
+
+fn with_label(labels: &Labels, name: &str, value: String) -> Labels {
+    let mut labels = labels.clone();
+    labels.push((name.to_owned(), value));
+    labels
+}
+
+// ============================================================================
+// OpenMetrics
+// ============================================================================
+
+fn decode_openmetrics(buf: &[u8]) -> Result<Vec<DecodedMetricFamily>> {
+    use openmetrics_data_model as om;
+
+    let metric_set = <om::MetricSet as prost::Message>::decode(buf).map_err(decode_error)?;
+
+    let mut families = Vec::with_capacity(metric_set.metric_families.len());
+    for family in metric_set.metric_families {
+        let metric_type = match om::MetricType::try_from(family.r#type) {
+            Ok(om::MetricType::Unknown) => MetricType::Unknown,
+            Ok(om::MetricType::Gauge) => MetricType::Gauge,
+            Ok(om::MetricType::Counter) => MetricType::Counter,
+            Ok(om::MetricType::StateSet) => MetricType::StateSet,
+            Ok(om::MetricType::Info) => MetricType::Info,
+            Ok(om::MetricType::Histogram) => MetricType::Histogram,
+            Ok(om::MetricType::GaugeHistogram) => MetricType::GaugeHistogram,
+            Ok(om::MetricType::Summary) => MetricType::Summary,
+            Err(_) => {
+                return Err(Error::invalid("unknown metric type")
+                    .with_context("metric_name", family.name)
+                    .with_context("type", family.r#type.to_string()));
+            },
+        };
+
+        let name = family.name;
+        let mut samples = vec![];
+        for metric in family.metrics {
+            let labels: Labels =
+                metric.labels.into_iter().map(|label| (label.name, label.value)).collect();
+            for point in metric.metric_points {
+                let Some(value) = point.value else {
+                    continue;
+                };
+                decode_openmetrics_point(&name, &labels, value, &mut samples);
+            }
+        }
+
+        families.push(DecodedMetricFamily {
+            name,
+            metric_type,
+            help: non_empty(family.help),
+            unit: non_empty(family.unit),
+            samples,
+        });
+    }
+    Ok(families)
+}
+
+fn decode_openmetrics_point(
+    name: &str,
+    labels: &Labels,
+    value: openmetrics_data_model::metric_point::Value,
+    samples: &mut Vec<DecodedSample>,
+) {
+    use openmetrics_data_model::{
+        self as om, counter_value, gauge_value, histogram_value, metric_point::Value,
+        summary_value, unknown_value,
+    };
+
+    let mut push = |name: String, labels: Labels, value: f64| {
+        samples.push(DecodedSample { name, labels, value });
+    };
+
+    match value {
+        Value::UnknownValue(om::UnknownValue { value }) => {
+            let value = match value {
+                Some(unknown_value::Value::DoubleValue(v)) => v,
+                Some(unknown_value::Value::IntValue(v)) => v as f64,
+                None => 0.0,
+            };
+            push(name.to_owned(), labels.clone(), value);
+        },
+        Value::GaugeValue(om::GaugeValue { value }) => {
+            let value = match value {
+                Some(gauge_value::Value::DoubleValue(v)) => v,
+                Some(gauge_value::Value::IntValue(v)) => v as f64,
+                None => 0.0,
+            };
+            push(name.to_owned(), labels.clone(), value);
+        },
+        Value::CounterValue(counter) => {
+            let total = match counter.total {
+                Some(counter_value::Total::DoubleValue(v)) => v,
+                Some(counter_value::Total::IntValue(v)) => v as f64,
+                None => 0.0,
+            };
+            push(format!("{name}_total"), labels.clone(), total);
+        },
+        Value::StateSetValue(state_set) => {
+            for state in state_set.states {
+                let labels = with_label(labels, name, state.name);
+                push(name.to_owned(), labels, if state.enabled { 1.0 } else { 0.0 });
+            }
+        },
+        Value::InfoValue(info) => {
+            let mut labels = labels.clone();
+            labels.extend(info.info.into_iter().map(|label| (label.name, label.value)));
+            push(format!("{name}_info"), labels, 1.0);
+        },
+        Value::HistogramValue(histogram) => {
+            // OpenMetrics protobuf buckets hold non-cumulative counts.
+            let mut cumulative = 0_u64;
+            for bucket in histogram.buckets {
+                cumulative = cumulative.saturating_add(bucket.count);
+                let labels = with_label(labels, BUCKET_LABEL, format_bound(bucket.upper_bound));
+                push(format!("{name}_bucket"), labels, cumulative as f64);
+            }
+            let sum = match histogram.sum {
+                Some(histogram_value::Sum::DoubleValue(v)) => v,
+                Some(histogram_value::Sum::IntValue(v)) => v as f64,
+                None => 0.0,
+            };
+            push(format!("{name}_count"), labels.clone(), histogram.count as f64);
+            push(format!("{name}_sum"), labels.clone(), sum);
+        },
+        Value::SummaryValue(summary) => {
+            for quantile in summary.quantile {
+                let labels = with_label(labels, QUANTILE_LABEL, quantile.quantile.to_string());
+                push(name.to_owned(), labels, quantile.value);
+            }
+            let sum = match summary.sum {
+                Some(summary_value::Sum::DoubleValue(v)) => v,
+                Some(summary_value::Sum::IntValue(v)) => v as f64,
+                None => 0.0,
+            };
+            push(format!("{name}_count"), labels.clone(), summary.count as f64);
+            push(format!("{name}_sum"), labels.clone(), sum);
+        },
+    }
+}
+
+// ============================================================================
+// Prometheus
+// ============================================================================
+
+fn decode_prometheus(mut buf: &[u8]) -> Result<Vec<DecodedMetricFamily>> {
+    use prometheus_data_model as prom;
+
+    let mut families = vec![];
+    while !buf.is_empty() {
+        let family = <prom::MetricFamily as prost::Message>::decode_length_delimited(&mut buf)
+            .map_err(decode_error)?;
+
+        let name = family.name.unwrap_or_default();
+        let r#type = family.r#type.unwrap_or_default();
+        let metric_type = match prom::MetricType::try_from(r#type) {
+            Ok(prom::MetricType::Counter) => MetricType::Counter,
+            Ok(prom::MetricType::Gauge) => MetricType::Gauge,
+            Ok(prom::MetricType::Summary) => MetricType::Summary,
+            Ok(prom::MetricType::Untyped) => MetricType::Unknown,
+            Ok(prom::MetricType::Histogram) => MetricType::Histogram,
+            Ok(prom::MetricType::GaugeHistogram) => MetricType::GaugeHistogram,
+            Err(_) => {
+                return Err(Error::invalid("unknown metric type")
+                    .with_context("metric_name", name)
+                    .with_context("type", r#type.to_string()));
+            },
+        };
+
+        let mut samples = vec![];
+        for metric in family.metric {
+            let labels: Labels = metric
+                .label
+                .into_iter()
+                .map(|label| (label.name.unwrap_or_default(), label.value.unwrap_or_default()))
+                .collect();
+            let mut push = |name: String, labels: Labels, value: f64| {
+                samples.push(DecodedSample { name, labels, value });
+            };
+
+            if let Some(gauge) = metric.gauge {
+                push(name.clone(), labels.clone(), gauge.value.unwrap_or_default());
+            }
+            if let Some(counter) = metric.counter {
+                push(name.clone(), labels.clone(), counter.value.unwrap_or_default());
+            }
+            if let Some(untyped) = metric.untyped {
+                push(name.clone(), labels.clone(), untyped.value.unwrap_or_default());
+            }
+            if let Some(histogram) = metric.histogram {
+                // Prometheus protobuf buckets hold cumulative counts already.
+                for bucket in histogram.bucket {
+                    let bound = format_bound(bucket.upper_bound.unwrap_or(f64::INFINITY));
+                    let labels = with_label(&labels, BUCKET_LABEL, bound);
+                    let count = bucket.cumulative_count.unwrap_or_default();
+                    push(format!("{name}_bucket"), labels, count as f64);
+                }
+                let count = histogram.sample_count.unwrap_or_default();
+                push(format!("{name}_count"), labels.clone(), count as f64);
+                push(
+                    format!("{name}_sum"),
+                    labels.clone(),
+                    histogram.sample_sum.unwrap_or_default(),
+                );
+            }
+            if let Some(summary) = metric.summary {
+                for quantile in summary.quantile {
+                    let q = quantile.quantile.unwrap_or_default().to_string();
+                    let labels = with_label(&labels, QUANTILE_LABEL, q);
+                    push(name.clone(), labels, quantile.value.unwrap_or_default());
+                }
+                let count = summary.sample_count.unwrap_or_default();
+                push(format!("{name}_count"), labels.clone(), count as f64);
+                push(format!("{name}_sum"), labels.clone(), summary.sample_sum.unwrap_or_default());
+            }
+        }
+
+        families.push(DecodedMetricFamily {
+            name,
+            metric_type,
+            help: family.help.and_then(non_empty),
+            unit: family.unit.and_then(non_empty),
+            samples,
+        });
+    }
+    Ok(families)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format::prost::encode,
+        metrics::{counter::Counter, gauge::Gauge, histogram::Histogram},
+        registry::{Registry, Unit},
+    };
+
+    fn registry() -> Registry {
+        let mut registry = Registry::builder().with_namespace("app").build().unwrap();
+
+        let requests = <Counter>::default();
+        registry.register("requests", "Total requests", requests.clone()).unwrap();
+        requests.inc_by(3);
+
+        let in_flight = <Gauge>::default();
+        registry.register("in_flight", "In-flight requests", in_flight.clone()).unwrap();
+        in_flight.set(-2);
+
+        let latency = Histogram::new([0.5, 1.0]);
+        registry
+            .register_with_unit("latency", "Request latency", Unit::Seconds, latency.clone())
+            .unwrap();
+        latency.observe(0.25);
+        latency.observe(0.75);
+        latency.observe(4.0);
+
+        registry
+    }
+
+    fn sample(family: &DecodedMetricFamily, name: &str, le: Option<&str>) -> f64 {
+        family
+            .samples
+            .iter()
+            .find(|s| {
+                s.name == name
+                    && le
+                        .is_none_or(|le| s.labels.iter().any(|(k, v)| k == BUCKET_LABEL && v == le))
+            })
+            .unwrap_or_else(|| panic!("missing sample `{name}`: {family:?}"))
+            .value
+    }
+
+    fn family<'a>(families: &'a [DecodedMetricFamily], name: &str) -> &'a DecodedMetricFamily {
+        families.iter().find(|f| f.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_decode_openmetrics_round_trip() {
+        let mut output = Vec::new();
+        encode(&mut output, &registry(), ProtobufProfile::OpenMetrics1).unwrap();
+        let families = decode(&output, ProtobufProfile::OpenMetrics1).unwrap();
+        assert_eq!(families.len(), 3);
+
+        let requests = family(&families, "app_requests");
+        assert_eq!(requests.metric_type, MetricType::Counter);
+        assert_eq!(requests.help.as_deref(), Some("Total requests"));
+        assert_eq!(sample(requests, "app_requests_total", None), 3.0);
+
+        let in_flight = family(&families, "app_in_flight");
+        assert_eq!(in_flight.metric_type, MetricType::Gauge);
+        assert_eq!(sample(in_flight, "app_in_flight", None), -2.0);
+
+        let latency = family(&families, "app_latency");
+        assert_eq!(latency.metric_type, MetricType::Histogram);
+        assert_eq!(latency.unit.as_deref(), Some("seconds"));
+        assert_eq!(sample(latency, "app_latency_bucket", Some("0.5")), 1.0);
+        assert_eq!(sample(latency, "app_latency_bucket", Some("1")), 2.0);
+        assert_eq!(sample(latency, "app_latency_bucket", Some("+Inf")), 3.0);
+        assert_eq!(sample(latency, "app_latency_count", None), 3.0);
+        assert_eq!(sample(latency, "app_latency_sum", None), 5.0);
+    }
+
+    #[test]
+    fn test_decode_prometheus_round_trip() {
+        let mut output = Vec::new();
+        encode(&mut output, &registry(), ProtobufProfile::Prometheus).unwrap();
+        let families = decode(&output, ProtobufProfile::Prometheus).unwrap();
+        assert_eq!(families.len(), 3);
+
+        let requests = family(&families, "app_requests");
+        assert_eq!(requests.metric_type, MetricType::Counter);
+        assert_eq!(sample(requests, "app_requests", None), 3.0);
+
+        let in_flight = family(&families, "app_in_flight");
+        assert_eq!(in_flight.metric_type, MetricType::Gauge);
+        assert_eq!(sample(in_flight, "app_in_flight", None), -2.0);
+
+        let latency = family(&families, "app_latency");
+        assert_eq!(latency.metric_type, MetricType::Histogram);
+        assert_eq!(sample(latency, "app_latency_bucket", Some("0.5")), 1.0);
+        assert_eq!(sample(latency, "app_latency_bucket", Some("+Inf")), 3.0);
+        assert_eq!(sample(latency, "app_latency_count", None), 3.0);
+    }
+
+    #[test]
+    fn test_decode_invalid_input() {
+        let err = decode(&[0xff, 0xff, 0xff], ProtobufProfile::Prometheus).unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::Invalid);
+    }
+}
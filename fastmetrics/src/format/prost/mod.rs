@@ -1,8 +1,10 @@
 //! Protobuf exposition format using [prost](https://github.com/tokio-rs/prost) crate.
 
+mod decode;
 mod openmetrics;
 mod prometheus;
 
+pub use self::decode::{DecodedMetricFamily, DecodedSample, decode};
 pub use super::profile::ProtobufProfile;
 use crate::{error::Result, registry::Registry};
 
@@ -4,7 +4,7 @@ use super::openmetrics_data_model;
 use crate::{
     encoder::{
         self, EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel, EncodeLabelSet,
-        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _,
+        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _, RawSample,
     },
     error::{Error, Result},
     raw::{Metadata, MetricType, bucket::Bucket, quantile::Quantile},
@@ -45,6 +45,11 @@ impl<'a> Encoder<'a> {
             }
             .encode(metadata, metric)?;
         }
+        registry.collect_sources(&mut MetricFamilyEncoder {
+            metric_families: &mut self.metric_set.metric_families,
+            namespace: registry.namespace(),
+            const_labels: registry.constant_labels(),
+        })?;
         for subsystem in registry.subsystems.values() {
             self.encode_registry(subsystem)?;
         }
@@ -331,6 +336,28 @@ impl encoder::MetricEncoder for MetricEncoder<'_> {
         Ok(())
     }
 
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        for sample in samples {
+            let mut labels = self.labels.clone();
+            sample.labels.encode(&mut LabelSetEncoder { labels: &mut labels })?;
+
+            self.metrics.push(openmetrics_data_model::Metric {
+                labels,
+                metric_points: vec![openmetrics_data_model::MetricPoint {
+                    value: Some(openmetrics_data_model::metric_point::Value::UnknownValue(
+                        openmetrics_data_model::UnknownValue {
+                            value: Some(openmetrics_data_model::unknown_value::Value::DoubleValue(
+                                sample.value,
+                            )),
+                        },
+                    )),
+                    timestamp: sample.timestamp.or(self.timestamp).map(into_prost_timestamp),
+                }],
+            });
+        }
+        Ok(())
+    }
+
     fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
         let mut labels = self.labels.clone();
         label_set.encode(&mut LabelSetEncoder { labels: &mut labels })?;
@@ -353,6 +380,12 @@ impl encoder::LabelSetEncoder for LabelSetEncoder<'_> {
             label: self.labels.last_mut().expect("labels must not be none"),
         })
     }
+
+    fn encode_all(&mut self, labels: &[&dyn EncodeLabel]) -> Result<()> {
+        crate::format::encode_protobuf_labels(self.labels, labels, |label, value| {
+            value.encode(&mut LabelEncoder { label })
+        })
+    }
 }
 
 struct LabelEncoder<'a> {
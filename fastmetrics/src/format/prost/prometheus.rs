@@ -4,7 +4,7 @@ use super::prometheus_data_model;
 use crate::{
     encoder::{
         self, EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel, EncodeLabelSet,
-        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _,
+        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _, RawSample,
     },
     error::{Error, Result},
     raw::{Metadata, MetricType, bucket::Bucket, quantile::Quantile},
@@ -49,6 +49,11 @@ impl<'a> Encoder<'a> {
             }
             .encode(metadata, metric)?;
         }
+        registry.collect_sources(&mut MetricFamilyEncoder {
+            metric_families: self.metric_families,
+            namespace: registry.namespace(),
+            const_labels: registry.constant_labels(),
+        })?;
         for subsystem in registry.subsystems.values() {
             self.encode_registry(subsystem)?;
         }
@@ -319,6 +324,24 @@ impl encoder::MetricEncoder for MetricEncoder<'_> {
         Ok(())
     }
 
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        for sample in samples {
+            let mut labels = self.labels.clone();
+            sample.labels.encode(&mut LabelSetEncoder { labels: &mut labels })?;
+
+            self.metrics.push(prometheus_data_model::Metric {
+                label: labels,
+                untyped: Some(prometheus_data_model::Untyped { value: Some(sample.value) }),
+                timestamp_ms: sample
+                    .timestamp
+                    .map(into_prometheus_timestamp_millis)
+                    .or(self.timestamp_ms),
+                ..Default::default()
+            });
+        }
+        Ok(())
+    }
+
     fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
         let mut labels = self.labels.clone();
         label_set.encode(&mut LabelSetEncoder { labels: &mut labels })?;
@@ -343,6 +366,12 @@ impl encoder::LabelSetEncoder for LabelSetEncoder<'_> {
             label: self.labels.last_mut().expect("labels must not be none"),
         })
     }
+
+    fn encode_all(&mut self, labels: &[&dyn EncodeLabel]) -> Result<()> {
+        crate::format::encode_protobuf_labels(self.labels, labels, |label, value| {
+            value.encode(&mut LabelEncoder { label })
+        })
+    }
 }
 
 struct LabelEncoder<'a> {
@@ -4,7 +4,7 @@ use super::generated_data_model::openmetrics_data_model;
 use crate::{
     encoder::{
         self, EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel, EncodeLabelSet,
-        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _,
+        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _, RawSample,
     },
     error::{Error, Result},
     raw::{Metadata, MetricType, bucket::Bucket, quantile::Quantile},
@@ -45,6 +45,11 @@ impl<'a> Encoder<'a> {
             }
             .encode(metadata, metric)?;
         }
+        registry.collect_sources(&mut MetricFamilyEncoder {
+            metric_families: &mut self.metric_set.metric_families,
+            namespace: registry.namespace(),
+            const_labels: registry.constant_labels(),
+        })?;
         for subsystem in registry.subsystems.values() {
             self.encode_registry(subsystem)?;
         }
@@ -363,6 +368,35 @@ impl encoder::MetricEncoder for MetricEncoder<'_> {
         Ok(())
     }
 
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        for sample in samples {
+            let mut labels = self.labels.clone();
+            sample.labels.encode(&mut LabelSetEncoder { labels: &mut labels })?;
+
+            self.metrics.push(openmetrics_data_model::Metric {
+                labels,
+                metric_points: vec![openmetrics_data_model::MetricPoint {
+                    value: Some(openmetrics_data_model::metric_point::Value::UnknownValue(
+                        openmetrics_data_model::UnknownValue {
+                            value: Some(openmetrics_data_model::unknown_value::Value::DoubleValue(
+                                sample.value,
+                            )),
+                            special_fields: protobuf::SpecialFields::new(),
+                        },
+                    )),
+                    timestamp: sample
+                        .timestamp
+                        .or(self.timestamp)
+                        .map(into_protobuf_timestamp)
+                        .into(),
+                    special_fields: protobuf::SpecialFields::new(),
+                }],
+                special_fields: protobuf::SpecialFields::new(),
+            });
+        }
+        Ok(())
+    }
+
     fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
         let mut labels = self.labels.clone();
         label_set.encode(&mut LabelSetEncoder { labels: &mut labels })?;
@@ -385,6 +419,12 @@ impl encoder::LabelSetEncoder for LabelSetEncoder<'_> {
             label: self.labels.last_mut().expect("labels must not be none"),
         })
     }
+
+    fn encode_all(&mut self, labels: &[&dyn EncodeLabel]) -> Result<()> {
+        crate::format::encode_protobuf_labels(self.labels, labels, |label, value| {
+            value.encode(&mut LabelEncoder { label })
+        })
+    }
 }
 
 struct LabelEncoder<'a> {
@@ -542,3 +582,42 @@ impl encoder::ExemplarEncoder for ExemplarEncoder<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::gauge_histogram::GaugeHistogram;
+
+    #[test]
+    fn encode_openmetrics_gauge_histogram() {
+        let mut registry = Registry::default();
+        let hist = GaugeHistogram::new([1.0, 10.0]);
+        registry.register("queue_age", "Age of queued items", hist.clone()).unwrap();
+        for value in [0.5, 5.0, 50.0] {
+            hist.observe(value);
+        }
+
+        let mut output = Vec::new();
+        super::encode(&mut output, &registry).unwrap();
+
+        let metric_set =
+            <openmetrics_data_model::MetricSet as protobuf::Message>::parse_from_bytes(&output)
+                .expect("must decode a MetricSet");
+        let family = metric_set.metric_families.first().expect("missing metric family");
+        assert_eq!(family.name, "queue_age");
+        assert_eq!(
+            family.type_.enum_value(),
+            Ok(openmetrics_data_model::MetricType::GAUGE_HISTOGRAM)
+        );
+
+        let point = &family.metrics[0].metric_points[0];
+        let histogram = point.histogram_value();
+        assert_eq!(histogram.count, 3);
+        assert_eq!(histogram.double_value(), 55.5);
+        let buckets =
+            histogram.buckets.iter().map(|b| (b.upper_bound, b.count)).collect::<Vec<_>>();
+        assert_eq!(buckets, [(1.0, 1), (10.0, 1), (f64::INFINITY, 1)]);
+        // gauge histograms don't have a created timestamp
+        assert!(histogram.created.is_none());
+    }
+}
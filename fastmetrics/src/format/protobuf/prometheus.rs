@@ -4,7 +4,7 @@ use super::generated_data_model::prometheus_data_model;
 use crate::{
     encoder::{
         self, EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel, EncodeLabelSet,
-        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _,
+        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _, RawSample,
     },
     error::{Error, Result},
     raw::{Metadata, MetricType, bucket::Bucket, quantile::Quantile},
@@ -49,6 +49,11 @@ impl<'a> Encoder<'a> {
             }
             .encode(metadata, metric)?;
         }
+        registry.collect_sources(&mut MetricFamilyEncoder {
+            metric_families: self.metric_families,
+            namespace: registry.namespace(),
+            const_labels: registry.constant_labels(),
+        })?;
         for subsystem in registry.subsystems.values() {
             self.encode_registry(subsystem)?;
         }
@@ -344,6 +349,28 @@ impl encoder::MetricEncoder for MetricEncoder<'_> {
         Ok(())
     }
 
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        for sample in samples {
+            let mut labels = self.labels.clone();
+            sample.labels.encode(&mut LabelSetEncoder { labels: &mut labels })?;
+
+            self.metrics.push(prometheus_data_model::Metric {
+                label: labels,
+                timestamp_ms: sample
+                    .timestamp
+                    .map(into_prometheus_timestamp_millis)
+                    .or(self.timestamp_ms),
+                untyped: Some(prometheus_data_model::Untyped {
+                    value: Some(sample.value),
+                    special_fields: protobuf::SpecialFields::new(),
+                })
+                .into(),
+                ..Default::default()
+            });
+        }
+        Ok(())
+    }
+
     fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
         let mut labels = self.labels.clone();
         label_set.encode(&mut LabelSetEncoder { labels: &mut labels })?;
@@ -368,6 +395,12 @@ impl encoder::LabelSetEncoder for LabelSetEncoder<'_> {
             label: self.labels.last_mut().expect("labels must not be none"),
         })
     }
+
+    fn encode_all(&mut self, labels: &[&dyn EncodeLabel]) -> Result<()> {
+        crate::format::encode_protobuf_labels(self.labels, labels, |label, value| {
+            value.encode(&mut LabelEncoder { label })
+        })
+    }
 }
 
 struct LabelEncoder<'a> {
@@ -544,7 +577,12 @@ impl encoder::ExemplarEncoder for ExemplarEncoder<'_> {
 mod tests {
     use super::*;
     use crate::{
-        metrics::{counter::Counter, info::Info},
+        metrics::{
+            counter::Counter,
+            gauge_histogram::GaugeHistogram,
+            info::Info,
+            summary::{Summary, SummaryConfig},
+        },
         registry::Registry,
     };
 
@@ -606,4 +644,65 @@ mod tests {
                 .any(|label| label.name() == "version" && label.value() == "1.0.0")
         );
     }
+
+    #[test]
+    fn encode_prometheus_summary_profile() {
+        let mut registry = Registry::default();
+        let config = SummaryConfig::new().with_quantiles([(0.5, 0.05), (0.99, 0.001)]);
+        let summary = Summary::with_created(config, Duration::from_secs(12345));
+        registry
+            .register("request_duration", "Request duration", summary.clone())
+            .unwrap();
+        for value in [1.0, 2.0, 3.0] {
+            summary.observe(value);
+        }
+
+        let mut output = Vec::new();
+        super::encode(&mut output, &registry).unwrap();
+
+        let family = decode_single_prometheus_metric_family(&output)
+            .expect("must decode a single length-delimited MetricFamily");
+        assert_eq!(family.name(), "request_duration");
+        assert_eq!(family.type_(), prometheus_data_model::MetricType::SUMMARY);
+
+        let metric = family.metric.first().expect("missing metric sample");
+        let summary = metric.summary.as_ref().expect("summary payload is required");
+        assert_eq!(summary.sample_count(), 3);
+        assert_eq!(summary.sample_sum(), 6.0);
+        let quantiles =
+            summary.quantile.iter().map(|q| (q.quantile(), q.value())).collect::<Vec<_>>();
+        assert_eq!(quantiles, [(0.5, 2.0), (0.99, 3.0)]);
+        assert_eq!(summary.created_timestamp.seconds, 12345);
+    }
+
+    #[test]
+    fn encode_prometheus_gauge_histogram_profile() {
+        let mut registry = Registry::default();
+        let hist = GaugeHistogram::new([1.0, 10.0]);
+        registry.register("queue_age", "Age of queued items", hist.clone()).unwrap();
+        for value in [0.5, 5.0, 50.0] {
+            hist.observe(value);
+        }
+
+        let mut output = Vec::new();
+        super::encode(&mut output, &registry).unwrap();
+
+        let family = decode_single_prometheus_metric_family(&output)
+            .expect("must decode a single length-delimited MetricFamily");
+        assert_eq!(family.name(), "queue_age");
+        assert_eq!(family.type_(), prometheus_data_model::MetricType::GAUGE_HISTOGRAM);
+
+        let metric = family.metric.first().expect("missing metric sample");
+        let histogram = metric.histogram.as_ref().expect("histogram payload is required");
+        assert_eq!(histogram.sample_count(), 3);
+        assert_eq!(histogram.sample_sum(), 55.5);
+        let buckets = histogram
+            .bucket
+            .iter()
+            .map(|b| (b.upper_bound(), b.cumulative_count()))
+            .collect::<Vec<_>>();
+        assert_eq!(buckets, [(1.0, 1), (10.0, 2), (f64::INFINITY, 3)]);
+        // gauge histograms don't have a created timestamp
+        assert!(histogram.created_timestamp.is_none());
+    }
 }
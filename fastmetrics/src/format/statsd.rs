@@ -0,0 +1,531 @@
+//! [StatsD](https://github.com/statsd/statsd/blob/master/docs/metric_types.md) exposition format,
+//! with [DogStatsD](https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/) tags.
+//!
+//! Every sample is written as a line `name:value|type|@sample_rate|#tag:value,...`:
+//! - counters are written as counts (`|c`), with their current total as value
+//! - gauges, unknown metrics, state sets (one line per state, tagged with the state) and info
+//!   metrics (tagged with the info labels) are written as gauges (`|g`)
+//! - histograms and summaries are written as timers (`|ms`), using the average of the
+//!   observations (`sum / count`) as value; metrics without any observation are skipped
+//! - the constant and variable labels are written as DogStatsD tags
+//!
+//! The `@sample_rate` is only written when it's less than `1.0`. Characters which are reserved by
+//! the protocol (`:`, `|`, `@`, `#`, `,` and line breaks) are replaced with `_` in names and tags,
+//! and non-finite values are skipped.
+
+use std::{borrow::Cow, fmt::Write as _, io, time::Duration};
+
+use crate::{
+    encoder::{
+        self, EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel, EncodeLabelSet,
+        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _, RawSample,
+    },
+    error::Result,
+    raw::{Metadata, bucket::Bucket, quantile::Quantile},
+    registry::Registry,
+};
+
+/// Encodes metrics from a [`Registry`] into StatsD lines with DogStatsD tags.
+///
+/// `sample_rate` is appended to every line as `|@<sample_rate>` when it's less than `1.0`.
+/// Encoding errors are wrapped into an [`io::Error`] of kind [`io::ErrorKind::Other`].
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     format::statsd,
+/// #     metrics::{counter::Counter, gauge::Gauge},
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut registry = Registry::builder().with_const_labels([("env", "prod")]).build()?;
+/// let requests = <Counter>::default();
+/// registry.register("requests", "Total requests", requests.clone())?;
+/// requests.inc_by(3);
+///
+/// let mut output = Vec::new();
+/// statsd::encode(&mut output, &registry, 1.0)?;
+/// assert_eq!(String::from_utf8(output)?, "requests:3|c|#env:prod\n");
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode(
+    writer: &mut impl io::Write,
+    registry: &Registry,
+    sample_rate: f64,
+) -> io::Result<()> {
+    encode_with(writer, registry, sample_rate, crate::metrics::lazy_group::enter_scope)
+}
+
+/// Encodes metrics in StatsD format with an explicit scope hook.
+pub fn encode_with<G>(
+    writer: &mut impl io::Write,
+    registry: &Registry,
+    sample_rate: f64,
+    enter_scope: impl FnOnce() -> G,
+) -> io::Result<()> {
+    // The returned value is kept alive for the duration of encoding and then dropped.
+    let _guard = enter_scope();
+
+    let sample_rate =
+        (sample_rate < 1.0).then(|| format!("|@{}", zmij::Buffer::new().format(sample_rate)));
+    let mut output = String::new();
+    encode_registry(&mut output, registry, sample_rate.as_deref().unwrap_or_default())
+        .map_err(io::Error::other)?;
+    writer.write_all(output.as_bytes())
+}
+
+fn encode_registry(output: &mut String, registry: &Registry, sample_rate: &str) -> Result<()> {
+    let mut family_encoder = MetricFamilyEncoder {
+        output,
+        namespace: registry.namespace(),
+        const_labels: registry.constant_labels(),
+        sample_rate,
+    };
+    for (metadata, metric) in &registry.metrics {
+        family_encoder.encode(metadata, metric)?;
+    }
+    registry.collect_sources(&mut family_encoder)?;
+    for subsystem in registry.subsystems.values() {
+        encode_registry(output, subsystem, sample_rate)?;
+    }
+    Ok(())
+}
+
+struct MetricFamilyEncoder<'a> {
+    output: &'a mut String,
+    namespace: Option<&'a str>,
+    const_labels: &'a [(Cow<'static, str>, Cow<'static, str>)],
+    sample_rate: &'a str,
+}
+
+impl encoder::MetricFamilyEncoder for MetricFamilyEncoder<'_> {
+    fn encode(&mut self, metadata: &Metadata, metric: &dyn EncodeMetric) -> Result<()> {
+        if metric.is_empty() {
+            // skip empty metric family
+            return Ok(());
+        }
+
+        let mut name = String::new();
+        sanitize_into(&mut name, &metadata.qualified_name(self.namespace));
+        let mut tags = Vec::new();
+        self.const_labels.encode(&mut TagsEncoder { tags: &mut tags })?;
+
+        metric.encode(&mut MetricEncoder {
+            output: self.output,
+            name: &name,
+            tags,
+            sample_rate: self.sample_rate,
+        })
+    }
+}
+
+struct MetricEncoder<'a> {
+    output: &'a mut String,
+    name: &'a str,
+    // sanitized `key:value` tags
+    tags: Vec<String>,
+    sample_rate: &'a str,
+}
+
+impl MetricEncoder<'_> {
+    /// Writes a line with the given `suffix` and extra `tags`, unless there is no value.
+    fn write_line(
+        &mut self,
+        suffix: &str,
+        tags: &[String],
+        value: Option<String>,
+        metric_type: &str,
+    ) -> Result<()> {
+        let Some(value) = value else {
+            return Ok(());
+        };
+
+        self.output.push_str(self.name);
+        sanitize_into(self.output, suffix);
+        write!(self.output, ":{value}|{metric_type}{}", self.sample_rate)?;
+        let mut tags = self.tags.iter().chain(tags);
+        if let Some(tag) = tags.next() {
+            self.output.push_str("|#");
+            self.output.push_str(tag);
+            for tag in tags {
+                self.output.push(',');
+                self.output.push_str(tag);
+            }
+        }
+        self.output.push('\n');
+        Ok(())
+    }
+
+    fn write_timer(&mut self, sum: f64, count: u64) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+        self.write_line("", &[], float_value(sum / count as f64), "ms")
+    }
+}
+
+impl encoder::MetricEncoder for MetricEncoder<'_> {
+    fn encode_unknown(&mut self, value: &dyn EncodeUnknownValue) -> Result<()> {
+        let mut encoder = ValueEncoder { value: None };
+        value.encode(&mut encoder)?;
+        self.write_line("", &[], encoder.value, "g")
+    }
+
+    fn encode_gauge(&mut self, value: &dyn EncodeGaugeValue) -> Result<()> {
+        let mut encoder = ValueEncoder { value: None };
+        value.encode(&mut encoder)?;
+        self.write_line("", &[], encoder.value, "g")
+    }
+
+    fn encode_counter(
+        &mut self,
+        total: &dyn EncodeCounterValue,
+        _exemplar: Option<&dyn EncodeExemplar>,
+        _created: Option<Duration>,
+    ) -> Result<()> {
+        let mut encoder = ValueEncoder { value: None };
+        total.encode(&mut encoder)?;
+        self.write_line("", &[], encoder.value, "c")
+    }
+
+    fn encode_stateset(&mut self, states: Vec<(&str, bool)>) -> Result<()> {
+        for (state, enabled) in states {
+            let tag = tag(self.name, state);
+            let value = if enabled { "1" } else { "0" };
+            self.write_line("", &[tag], Some(value.to_owned()), "g")?;
+        }
+        Ok(())
+    }
+
+    fn encode_info(&mut self, label_set: &dyn EncodeLabelSet) -> Result<()> {
+        let mut tags = Vec::new();
+        label_set.encode(&mut TagsEncoder { tags: &mut tags })?;
+        self.write_line("_info", &tags, Some("1".to_owned()), "g")
+    }
+
+    fn encode_histogram(
+        &mut self,
+        _buckets: &[Bucket],
+        _exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+        _created: Option<Duration>,
+    ) -> Result<()> {
+        self.write_timer(sum, count)
+    }
+
+    fn encode_gauge_histogram(
+        &mut self,
+        _buckets: &[Bucket],
+        _exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+    ) -> Result<()> {
+        self.write_timer(sum, count)
+    }
+
+    fn encode_summary(
+        &mut self,
+        _quantiles: &[Quantile],
+        sum: f64,
+        count: u64,
+        _created: Option<Duration>,
+    ) -> Result<()> {
+        self.write_timer(sum, count)
+    }
+
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        for sample in samples {
+            let mut tags = Vec::new();
+            sample.labels.encode(&mut TagsEncoder { tags: &mut tags })?;
+            let suffix = sample.suffix.unwrap_or_default();
+            self.write_line(suffix, &tags, float_value(sample.value), "g")?;
+        }
+        Ok(())
+    }
+
+    fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
+        let mut tags = self.tags.clone();
+        label_set.encode(&mut TagsEncoder { tags: &mut tags })?;
+        metric.encode(&mut MetricEncoder {
+            output: self.output,
+            name: self.name,
+            tags,
+            sample_rate: self.sample_rate,
+        })
+    }
+}
+
+/// Writes `value` into `output`, replacing the characters reserved by the protocol with `_`.
+fn sanitize_into(output: &mut String, value: &str) {
+    output.extend(value.chars().map(|c| match c {
+        ':' | '|' | '@' | '#' | ',' | '\n' | '\r' => '_',
+        c => c,
+    }));
+}
+
+fn tag(name: &str, value: &str) -> String {
+    let mut tag = String::new();
+    sanitize_into(&mut tag, name);
+    tag.push(':');
+    sanitize_into(&mut tag, value);
+    tag
+}
+
+fn float_value(value: f64) -> Option<String> {
+    value.is_finite().then(|| zmij::Buffer::new().format(value).to_owned())
+}
+
+struct TagsEncoder<'a> {
+    tags: &'a mut Vec<String>,
+}
+
+impl encoder::LabelSetEncoder for TagsEncoder<'_> {
+    fn encode(&mut self, label: &dyn EncodeLabel) -> Result<()> {
+        let mut encoder = TagEncoder { name: String::new(), value: String::new() };
+        label.encode(&mut encoder)?;
+        if !encoder.name.is_empty() {
+            self.tags.push(tag(&encoder.name, &encoder.value));
+        }
+        Ok(())
+    }
+}
+
+struct TagEncoder {
+    name: String,
+    value: String,
+}
+
+macro_rules! encode_integer_value_impls {
+    ($($integer:ty),*) => (
+        paste::paste! { $(
+            fn [<encode_ $integer _value>](&mut self, value: $integer) -> Result<()> {
+                self.value.push_str(itoa::Buffer::new().format(value));
+                Ok(())
+            }
+        )* }
+    )
+}
+
+macro_rules! encode_float_value_impls {
+    ($($float:ty),*) => (
+        paste::paste! { $(
+            fn [<encode_ $float _value>](&mut self, value: $float) -> Result<()> {
+                self.value.push_str(zmij::Buffer::new().format(value));
+                Ok(())
+            }
+        )* }
+    )
+}
+
+impl encoder::LabelEncoder for TagEncoder {
+    fn encode_label_name(&mut self, name: &str) -> Result<()> {
+        self.name.push_str(name);
+        Ok(())
+    }
+
+    fn encode_str_value(&mut self, value: &str) -> Result<()> {
+        self.value.push_str(value);
+        Ok(())
+    }
+
+    fn encode_bool_value(&mut self, value: bool) -> Result<()> {
+        self.value.push_str(if value { "true" } else { "false" });
+        Ok(())
+    }
+
+    encode_integer_value_impls! {
+        i8, i16, i32, i64, i128, isize,
+        u8, u16, u32, u64, u128, usize
+    }
+
+    encode_float_value_impls! { f32, f64 }
+}
+
+/// Encodes a metric value, `None` if it can't be represented.
+struct ValueEncoder {
+    value: Option<String>,
+}
+
+impl ValueEncoder {
+    fn set_i64(&mut self, value: i64) -> Result<()> {
+        self.value = Some(itoa::Buffer::new().format(value).to_owned());
+        Ok(())
+    }
+
+    fn set_u64(&mut self, value: u64) -> Result<()> {
+        self.value = Some(itoa::Buffer::new().format(value).to_owned());
+        Ok(())
+    }
+
+    fn set_f64(&mut self, value: f64) -> Result<()> {
+        self.value = float_value(value);
+        Ok(())
+    }
+}
+
+impl encoder::UnknownValueEncoder for ValueEncoder {
+    fn encode_i32(&mut self, value: i32) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_i64(&mut self, value: i64) -> Result<()> {
+        self.set_i64(value)
+    }
+
+    fn encode_isize(&mut self, value: isize) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_u32(&mut self, value: u32) -> Result<()> {
+        self.set_u64(value as u64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.set_f64(value as f64)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.set_f64(value)
+    }
+}
+
+impl encoder::GaugeValueEncoder for ValueEncoder {
+    fn encode_i32(&mut self, value: i32) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_i64(&mut self, value: i64) -> Result<()> {
+        self.set_i64(value)
+    }
+
+    fn encode_isize(&mut self, value: isize) -> Result<()> {
+        self.set_i64(value as i64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.set_f64(value as f64)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.set_f64(value)
+    }
+}
+
+impl encoder::CounterValueEncoder for ValueEncoder {
+    fn encode_u32(&mut self, value: u32) -> Result<()> {
+        self.set_u64(value as u64)
+    }
+
+    fn encode_u64(&mut self, value: u64) -> Result<()> {
+        self.set_u64(value)
+    }
+
+    fn encode_usize(&mut self, value: usize) -> Result<()> {
+        self.set_u64(value as u64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.set_f64(value as f64)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.set_f64(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::{
+            counter::Counter,
+            family::Family,
+            gauge::Gauge,
+            histogram::{Histogram, linear_buckets},
+        },
+        raw::LabelSetSchema,
+    };
+
+    #[derive(Clone, Eq, PartialEq, Hash)]
+    struct Labels {
+        method: &'static str,
+        status: u16,
+    }
+
+    impl LabelSetSchema for Labels {
+        fn names() -> Option<&'static [&'static str]> {
+            Some(&["method", "status"])
+        }
+    }
+
+    impl EncodeLabelSet for Labels {
+        fn encode(&self, encoder: &mut dyn encoder::LabelSetEncoder) -> Result<()> {
+            encoder.encode(&("method", self.method))?;
+            encoder.encode(&("status", self.status))
+        }
+    }
+
+    fn encode_lines(registry: &Registry, sample_rate: f64) -> Vec<String> {
+        let mut output = Vec::new();
+        encode(&mut output, registry, sample_rate).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let mut lines = output.lines().map(str::to_owned).collect::<Vec<_>>();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn test_encode_counters_and_gauges() -> Result<()> {
+        let mut registry = Registry::builder()
+            .with_namespace("myapp")
+            .with_const_labels([("env", "prod")])
+            .build()?;
+        let requests = Family::<Labels, Counter>::default();
+        registry.register("requests", "Total requests", requests.clone())?;
+        requests.with_or_new(&Labels { method: "GET", status: 200 }, |counter| counter.inc_by(42));
+        registry.subsystem("pool")?.register("connections", "", Gauge::<i64>::new(-3))?;
+        registry.register("temperature", "", Gauge::<f64>::new(21.5))?;
+        registry.register("missing", "", Gauge::<f64>::new(f64::NAN))?;
+
+        // non-finite values can't be represented
+        assert_eq!(
+            encode_lines(&registry, 1.0),
+            [
+                "myapp_pool_connections:-3|g|#env:prod",
+                "myapp_requests:42|c|#env:prod,method:GET,status:200",
+                "myapp_temperature:21.5|g|#env:prod",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_histograms_and_sample_rate() -> Result<()> {
+        let mut registry = Registry::default();
+        let latency = Histogram::new(linear_buckets(1.0, 1.0, 2));
+        registry.register("latency", "", latency.clone())?;
+        registry.register("idle", "", Histogram::new(linear_buckets(1.0, 1.0, 2)))?;
+        latency.observe(1.0);
+        latency.observe(2.0);
+        let jobs = <Counter>::default();
+        registry.register("jobs", "", jobs.clone())?;
+        jobs.inc();
+
+        // histograms without observations are skipped
+        assert_eq!(encode_lines(&registry, 1.0), ["jobs:1|c", "latency:1.5|ms"]);
+        assert_eq!(encode_lines(&registry, 0.25), ["jobs:1|c|@0.25", "latency:1.5|ms|@0.25"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitize_reserved_characters() -> Result<()> {
+        let mut registry = Registry::builder().with_const_labels([("path", "/a:b|c,d")]).build()?;
+        registry.register("requests", "", <Counter>::default())?;
+        assert_eq!(encode_lines(&registry, 1.0), ["requests:0|c|#path:/a_b_c_d"]);
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use super::{EscapingScheme, TextProfile};
+use super::{EscapingScheme, TextEncodeOptions, TextProfile, TimestampFormat};
 
 #[derive(Clone, Copy)]
 pub(super) struct ProfileConfig {
@@ -10,12 +10,8 @@ pub(super) struct ProfileConfig {
     pub(super) prometheus_type_compat: bool,
     pub(super) timestamp_format: TimestampFormat,
     pub(super) name_policy: NamePolicy,
-}
-
-#[derive(Clone, Copy)]
-pub(super) enum TimestampFormat {
-    SecondsMillis,
-    MillisecondsInteger,
+    pub(super) sorted_output: bool,
+    pub(super) suppress_metadata_lines: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -40,8 +36,10 @@ impl From<TextProfile> for ProfileConfig {
                 emit_created_series: false,
                 emit_exemplars: false,
                 prometheus_type_compat: true,
-                timestamp_format: TimestampFormat::MillisecondsInteger,
+                timestamp_format: profile.timestamp_format(),
                 name_policy: NamePolicy::Legacy,
+                sorted_output: false,
+                suppress_metadata_lines: false,
             },
             TextProfile::PrometheusV1_0_0 { escaping_scheme } => Self {
                 emit_eof: false,
@@ -50,8 +48,10 @@ impl From<TextProfile> for ProfileConfig {
                 emit_created_series: false,
                 emit_exemplars: false,
                 prometheus_type_compat: true,
-                timestamp_format: TimestampFormat::MillisecondsInteger,
+                timestamp_format: profile.timestamp_format(),
                 name_policy: NamePolicy::V1Escaping(escaping_scheme),
+                sorted_output: false,
+                suppress_metadata_lines: false,
             },
             TextProfile::OpenMetricsV0_0_1 => Self {
                 emit_eof: true,
@@ -60,8 +60,10 @@ impl From<TextProfile> for ProfileConfig {
                 emit_created_series: true,
                 emit_exemplars: true,
                 prometheus_type_compat: false,
-                timestamp_format: TimestampFormat::SecondsMillis,
+                timestamp_format: profile.timestamp_format(),
                 name_policy: NamePolicy::Legacy,
+                sorted_output: false,
+                suppress_metadata_lines: false,
             },
             TextProfile::OpenMetricsV1_0_0 { escaping_scheme } => Self {
                 emit_eof: true,
@@ -70,9 +72,22 @@ impl From<TextProfile> for ProfileConfig {
                 emit_created_series: true,
                 emit_exemplars: true,
                 prometheus_type_compat: false,
-                timestamp_format: TimestampFormat::SecondsMillis,
+                timestamp_format: profile.timestamp_format(),
                 name_policy: NamePolicy::V1Escaping(escaping_scheme),
+                sorted_output: false,
+                suppress_metadata_lines: false,
             },
         }
     }
 }
+
+impl From<TextEncodeOptions> for ProfileConfig {
+    fn from(options: TextEncodeOptions) -> Self {
+        Self {
+            sorted_output: options.sorted_output(),
+            timestamp_format: options.timestamp_format(),
+            suppress_metadata_lines: options.suppress_metadata_lines(),
+            ..options.profile().into()
+        }
+    }
+}
@@ -1,18 +1,20 @@
 use std::{borrow::Cow, collections::HashMap, fmt, time::Duration};
 
 use super::{
-    config::{NamePolicy, ProfileConfig, TimestampFormat},
+    TimestampFormat,
+    config::{NamePolicy, ProfileConfig},
     names::{escape_label_name, escape_metric_name},
 };
 use crate::{
     encoder::{
         self, EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel, EncodeLabelSet,
-        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _,
+        EncodeMetric, EncodeUnknownValue, MetricFamilyEncoder as _, RawSample,
     },
     error::{Error, Result},
     raw::{
         Metadata, MetricType, Unit,
         bucket::{BUCKET_LABEL, Bucket},
+        exemplar,
         quantile::{QUANTILE_LABEL, Quantile},
     },
     registry::{NameRule, Registry},
@@ -23,21 +25,58 @@ pub(super) fn encode(
     registry: &Registry,
     config: ProfileConfig,
 ) -> Result<()> {
-    Encoder::new(writer, registry, config).encode()
+    Encoder::new(writer, registry, config, None).encode()
+}
+
+/// Encodes the metric families of `registry` whose fully qualified name satisfies `filter`.
+pub(super) fn encode_filtered(
+    writer: &mut impl fmt::Write,
+    registry: &Registry,
+    config: ProfileConfig,
+    filter: &dyn Fn(&str) -> bool,
+) -> Result<()> {
+    Encoder::new(writer, registry, config, Some(filter)).encode()
+}
+
+/// Encodes a single metric family of `registry`, as [`encode`] would.
+pub(super) fn encode_family(
+    writer: &mut impl fmt::Write,
+    registry: &Registry,
+    metadata: &Metadata,
+    metric: &dyn EncodeMetric,
+    config: ProfileConfig,
+) -> Result<()> {
+    MetricFamilyEncoder {
+        writer,
+        namespace: registry.namespace(),
+        const_labels: registry.constant_labels(),
+        config,
+        check_label_name_collisions: registry.name_rule() == NameRule::Utf8
+            && config.name_policy.is_lossy(),
+        check_exemplar_label_name_collisions: config.name_policy.is_lossy(),
+        filter: None,
+    }
+    .encode(metadata, metric)
 }
 
 struct Encoder<'a, W> {
     writer: &'a mut W,
     registry: &'a Registry,
     config: ProfileConfig,
+    filter: Option<&'a dyn Fn(&str) -> bool>,
 }
 
 impl<'a, W> Encoder<'a, W>
 where
     W: fmt::Write,
 {
-    fn new(writer: &'a mut W, registry: &'a Registry, config: ProfileConfig) -> Self {
-        Self { writer, registry, config }
+    fn new(
+        writer: &'a mut W,
+        registry: &'a Registry,
+        config: ProfileConfig,
+        filter: Option<&'a dyn Fn(&str) -> bool>,
+    ) -> Self {
+        Self { writer, registry, config, filter }
     }
 
     fn encode(&mut self) -> Result<()> {
@@ -89,6 +128,32 @@ where
         check_label_name_collisions: bool,
         check_exemplar_label_name_collisions: bool,
     ) -> Result<()> {
+        if self.config.sorted_output {
+            let mut families = Vec::new();
+            collect_families(registry, &mut families);
+            families.sort_by(|a, b| a.0.cmp(&b.0));
+            for (_, registry, metadata, metric) in families {
+                MetricFamilyEncoder {
+                    writer: self.writer,
+                    namespace: registry.namespace(),
+                    const_labels: registry.constant_labels(),
+                    config: self.config,
+                    check_label_name_collisions,
+                    check_exemplar_label_name_collisions,
+                    filter: self.filter,
+                }
+                .encode(metadata, metric)?;
+            }
+            // the families of sources are only known when collecting them, so they follow the
+            // sorted families
+            return self.encode_sources(
+                registry,
+                check_label_name_collisions,
+                check_exemplar_label_name_collisions,
+                true,
+            );
+        }
+
         for (metadata, metric) in &registry.metrics {
             MetricFamilyEncoder {
                 writer: self.writer,
@@ -97,9 +162,16 @@ where
                 config: self.config,
                 check_label_name_collisions,
                 check_exemplar_label_name_collisions,
+                filter: self.filter,
             }
             .encode(metadata, metric)?;
         }
+        self.encode_sources(
+            registry,
+            check_label_name_collisions,
+            check_exemplar_label_name_collisions,
+            false,
+        )?;
         for subsystem in registry.subsystems.values() {
             self.encode_registry(
                 subsystem,
@@ -110,6 +182,35 @@ where
         Ok(())
     }
 
+    fn encode_sources(
+        &mut self,
+        registry: &Registry,
+        check_label_name_collisions: bool,
+        check_exemplar_label_name_collisions: bool,
+        recursive: bool,
+    ) -> Result<()> {
+        registry.collect_sources(&mut MetricFamilyEncoder {
+            writer: self.writer,
+            namespace: registry.namespace(),
+            const_labels: registry.constant_labels(),
+            config: self.config,
+            check_label_name_collisions,
+            check_exemplar_label_name_collisions,
+            filter: self.filter,
+        })?;
+        if recursive {
+            for subsystem in registry.subsystems.values() {
+                self.encode_sources(
+                    subsystem,
+                    check_label_name_collisions,
+                    check_exemplar_label_name_collisions,
+                    true,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     fn encode_eof(&mut self) -> Result<()> {
         self.writer.write_str("# EOF\n")?;
         Ok(())
@@ -205,6 +306,20 @@ where
     }
 }
 
+/// A metric family with its fully qualified name and the (sub)registry it belongs to.
+pub(super) type Family<'a> = (Cow<'a, str>, &'a Registry, &'a Metadata, &'a dyn EncodeMetric);
+
+/// Collects the metric families of `registry` and all its subsystems.
+pub(super) fn collect_families<'a>(registry: &'a Registry, families: &mut Vec<Family<'a>>) {
+    for (metadata, metric) in &registry.metrics {
+        let name = metric_name(registry.namespace(), metadata.name(), metadata.unit());
+        families.push((name, registry, metadata, metric.as_ref()));
+    }
+    for subsystem in registry.subsystems.values() {
+        collect_families(subsystem, families);
+    }
+}
+
 struct MetricFamilyEncoder<'a, W> {
     writer: &'a mut W,
     namespace: Option<&'a str>,
@@ -216,6 +331,8 @@ struct MetricFamilyEncoder<'a, W> {
     check_label_name_collisions: bool,
     // Check label-name collisions only within exemplar labels.
     check_exemplar_label_name_collisions: bool,
+    // Skip the metric families whose fully qualified name doesn't satisfy the filter.
+    filter: Option<&'a dyn Fn(&str) -> bool>,
 }
 
 impl<W> MetricFamilyEncoder<'_, W>
@@ -275,7 +392,11 @@ where
     }
 }
 
-fn metric_name<'a>(namespace: Option<&str>, name: &'a str, unit: Option<&Unit>) -> Cow<'a, str> {
+pub(super) fn metric_name<'a>(
+    namespace: Option<&str>,
+    name: &'a str,
+    unit: Option<&Unit>,
+) -> Cow<'a, str> {
     match (namespace, unit) {
         (Some(namespace), Some(unit)) => {
             Cow::Owned(format!("{namespace}_{}_{}", name, unit.as_str()))
@@ -320,13 +441,20 @@ where
         }
 
         let metric_name = metric_name(self.namespace, metadata.name(), metadata.unit());
+        if let Some(filter) = self.filter {
+            if !filter(metric_name.as_ref()) {
+                return Ok(());
+            }
+        }
         let canonical_metric_name = metric_name.clone();
         let metric_name = escape_metric_name(metric_name, self.config.name_policy)?;
         let ty = metric_type_name(metadata.metric_type(), self.config.prometheus_type_compat)?;
 
-        self.encode_type(metric_name.as_ref(), ty)?;
-        self.encode_help(metric_name.as_ref(), metadata.help())?;
-        self.encode_unit(metric_name.as_ref(), metadata.unit())?;
+        if !self.config.suppress_metadata_lines {
+            self.encode_type(metric_name.as_ref(), ty)?;
+            self.encode_help(metric_name.as_ref(), metadata.help())?;
+            self.encode_unit(metric_name.as_ref(), metadata.unit())?;
+        }
 
         metric.encode(&mut MetricEncoder {
             writer: self.writer,
@@ -387,6 +515,15 @@ fn write_escaped_label_value(writer: &mut impl fmt::Write, value: &str) -> Resul
     Ok(())
 }
 
+/// A [`fmt::Write`] adapter escaping everything written through it as a label value.
+struct LabelValueEscaper<'a, W>(&'a mut W);
+
+impl<W: fmt::Write> fmt::Write for LabelValueEscaper<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_escaped_label_value(self.0, s).map_err(|_| fmt::Error)
+    }
+}
+
 impl<W> MetricEncoder<'_, W>
 where
     W: fmt::Write,
@@ -878,6 +1015,22 @@ where
         Ok(())
     }
 
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        for sample in samples {
+            self.encode_metric_name()?;
+            if let Some(suffix) = sample.suffix {
+                self.writer.write_str(suffix)?;
+            }
+            self.encode_label_set(Some(sample.labels))?;
+            self.writer.write_str(zmij::Buffer::new().format(sample.value))?;
+            if let Some(timestamp) = sample.timestamp.or(self.timestamp) {
+                write_timestamp(self.writer, timestamp, self.config.timestamp_format)?;
+            }
+            self.encode_newline()?;
+        }
+        Ok(())
+    }
+
     fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
         debug_assert!(self.family_labels.is_none(), "family labels already set");
         metric.encode(&mut MetricEncoder {
@@ -950,6 +1103,28 @@ where
             collision_guard,
         })
     }
+
+    fn encode_all(&mut self, labels: &[&dyn EncodeLabel]) -> Result<()> {
+        let Some((head, tail)) = labels.split_first() else {
+            return Ok(());
+        };
+        // only the first label of the segment can be written without a leading separator
+        encoder::LabelSetEncoder::encode(self, *head)?;
+        for label in tail {
+            label.encode(&mut LabelEncoder {
+                writer: self.writer,
+                first: false,
+                name_policy: self.name_policy,
+                collision_guard: self.collision_seen.as_deref_mut().map(|collision_seen| {
+                    LabelNameCollisionGuard {
+                        existing: self.collision_existing,
+                        seen: collision_seen,
+                    }
+                }),
+            })?;
+        }
+        Ok(())
+    }
 }
 
 struct LabelNameCollisionGuard<'a> {
@@ -1051,6 +1226,15 @@ where
         Ok(())
     }
 
+    #[inline]
+    fn encode_display_value(&mut self, value: &dyn fmt::Display) -> Result<()> {
+        self.writer.write_str("=\"")?;
+        // formatted straight into the output, without an intermediate buffer
+        fmt::Write::write_fmt(&mut LabelValueEscaper(self.writer), format_args!("{value}"))?;
+        self.writer.write_str("\"")?;
+        Ok(())
+    }
+
     #[inline]
     fn encode_bool_value(&mut self, value: bool) -> Result<()> {
         self.writer.write_str("=\"")?;
@@ -1162,6 +1346,11 @@ where
         value: f64,
         timestamp: Option<Duration>,
     ) -> Result<()> {
+        if let Err(err) = exemplar::validate(labels, value) {
+            log::warn!("skipping the encoding of an invalid exemplar: {err}");
+            return Ok(());
+        }
+
         // # { labels } value [timestamp]
         self.writer.write_str(" # {")?;
 
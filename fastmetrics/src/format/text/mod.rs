@@ -3,13 +3,22 @@
 mod config;
 mod encoder;
 mod names;
+mod parse;
 #[cfg(test)]
 mod tests;
+mod validate;
 
-use std::fmt;
+use std::{fmt, io};
 
-pub use super::profile::{EscapingScheme, TextProfile};
-use crate::{error::Result, registry::Registry};
+pub use self::parse::{
+    ParseMode, ParsedExemplar, ParsedMetricFamily, ParsedSample, parse, parse_with,
+};
+pub use self::validate::{ValidationWarning, validate};
+pub use super::profile::{EscapingScheme, TextEncodeOptions, TextProfile, TimestampFormat};
+use crate::{
+    error::Result,
+    registry::{Metric, Registry},
+};
 
 /// Encodes metrics from a [`Registry`] into text format with an explicit profile.
 ///
@@ -24,6 +33,308 @@ pub fn encode(
     encode_with(writer, registry, profile, crate::metrics::lazy_group::enter_scope)
 }
 
+/// Encodes metrics from a [`Registry`] into text format with explicit [`TextEncodeOptions`].
+///
+/// This is [`encode`] with additional options independent of the profile, e.g. sorting the metric
+/// families for a deterministic output.
+///
+/// # Examples
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     format::text::{self, TextProfile},
+/// #     metrics::counter::Counter,
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let mut registry = Registry::default();
+/// registry.register("requests", "Total requests", <Counter>::default())?;
+/// registry.register("errors", "Total errors", <Counter>::default())?;
+///
+/// let mut output = String::new();
+/// let options = TextProfile::default().with_sorted_output(true);
+/// text::encode_with_options(&mut output, &registry, options)?;
+/// assert!(output.find("# TYPE errors").unwrap() < output.find("# TYPE requests").unwrap());
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode_with_options(
+    writer: &mut impl fmt::Write,
+    registry: &Registry,
+    options: TextEncodeOptions,
+) -> Result<()> {
+    let _guard = crate::metrics::lazy_group::enter_scope();
+    encoder::encode(writer, registry, options.into())
+}
+
+/// Encodes the metric families of a [`Registry`] whose fully qualified name satisfies `filter`.
+///
+/// The filter is called with the name of every metric family, including the namespace and unit
+/// suffix (e.g. `http_request_duration_seconds`), before anything is written for the family, so
+/// skipped families don't cost any encoding. This is useful to serve the subset of metrics
+/// requested by a scraper, e.g. through a `match[]` query parameter.
+///
+/// # Examples
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     format::text::{self, TextProfile},
+/// #     metrics::{counter::Counter, gauge::Gauge},
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let mut registry = Registry::default();
+/// registry.register("http_requests", "Total HTTP requests", <Counter>::default())?;
+/// registry.register("db_connections", "Open DB connections", <Gauge>::default())?;
+///
+/// let mut output = String::new();
+/// text::encode_filtered(&mut output, &registry, TextProfile::default(), |name| {
+///     name.starts_with("http_")
+/// })?;
+/// assert!(output.contains("# TYPE http_requests counter\n"));
+/// assert!(!output.contains("db_connections"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode_filtered(
+    writer: &mut impl fmt::Write,
+    registry: &Registry,
+    profile: TextProfile,
+    filter: impl Fn(&str) -> bool,
+) -> Result<()> {
+    let _guard = crate::metrics::lazy_group::enter_scope();
+    encoder::encode_filtered(writer, registry, profile.into(), &filter)
+}
+
+/// Encodes a single metric, registered as `name` with `help`, without setting up a [`Registry`].
+///
+/// The output is identical to registering a clone of `metric` into an empty [`Registry`] and
+/// encoding it with [`encode`], which is convenient to check the encoding of a metric in unit
+/// tests.
+///
+/// # Errors
+///
+/// Returns an error if `name` is invalid or if the metric fails to encode.
+///
+/// # Examples
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     format::text::{self, TextProfile},
+/// #     metrics::counter::Counter,
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let requests = <Counter>::default();
+/// requests.inc_by(3);
+///
+/// let mut output = String::new();
+/// text::encode_metric_family(
+///     &mut output,
+///     "http_requests",
+///     "Total HTTP requests",
+///     &requests,
+///     TextProfile::default(),
+/// )?;
+/// assert!(output.contains("# TYPE http_requests counter\n"));
+/// assert!(output.contains("http_requests_total 3\n"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode_metric_family<M>(
+    writer: &mut impl fmt::Write,
+    name: &str,
+    help: &str,
+    metric: &M,
+    profile: TextProfile,
+) -> Result<()>
+where
+    M: Metric + Clone,
+{
+    let mut registry = Registry::default();
+    registry.register(name.to_owned(), help.to_owned(), metric.clone())?;
+    encode(writer, &registry, profile)
+}
+
+/// Encodes metrics from a [`Registry`] into text format, writing directly to an [`io::Write`].
+///
+/// Unlike [`encode`], the output is not buffered as a whole: it goes through a fixed-size stack
+/// buffer and is written to `writer` line by line, so it can be streamed into a `TcpStream`, a
+/// `File` or an HTTP response body, whatever the size of the registry.
+///
+/// The first I/O error encountered is returned as is, encoding errors are wrapped into an
+/// [`io::Error`] of kind [`io::ErrorKind::Other`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     format::text::{self, TextProfile},
+/// #     metrics::counter::Counter,
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut registry = Registry::default();
+/// registry.register("http_requests", "Total number of HTTP requests", <Counter>::default())?;
+///
+/// let mut output = Vec::<u8>::new();
+/// text::encode_to_writer(&mut output, &registry, TextProfile::default())?;
+/// assert!(output.ends_with(b"# EOF\n"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode_to_writer(
+    writer: &mut impl io::Write,
+    registry: &Registry,
+    profile: TextProfile,
+) -> io::Result<()> {
+    let mut shim = IoWriter::new(writer);
+    let result = encode(&mut shim, registry, profile);
+    if let Some(err) = shim.error.take() {
+        return Err(err);
+    }
+    result.map_err(io::Error::other)?;
+    shim.flush()
+}
+
+/// Encodes metrics from a [`Registry`] into text format, returning the output as [`bytes::Bytes`].
+///
+/// The output is written directly into a [`bytes::BytesMut`] pre-allocated from the number of
+/// metric families, so it can be used as an HTTP response body without an extra copy.
+///
+/// # Examples
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     format::text::{self, TextProfile},
+/// #     metrics::counter::Counter,
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let mut registry = Registry::default();
+/// registry.register("http_requests", "Total number of HTTP requests", <Counter>::default())?;
+///
+/// let body = text::encode_to_bytes(&registry, TextProfile::default())?;
+/// assert!(body.ends_with(b"# EOF\n"));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "bytes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bytes")))]
+pub fn encode_to_bytes(registry: &Registry, profile: TextProfile) -> Result<bytes::Bytes> {
+    // a rough estimate of the encoded size of a metric family
+    const BYTES_PER_METRIC: usize = 64;
+
+    fn count_metrics(registry: &Registry) -> usize {
+        registry.metrics.len() + registry.subsystems.values().map(count_metrics).sum::<usize>()
+    }
+
+    let mut buffer = bytes::BytesMut::with_capacity(count_metrics(registry) * BYTES_PER_METRIC);
+    encode(&mut buffer, registry, profile)?;
+    Ok(buffer.freeze())
+}
+
+/// Encodes metrics from a [`Registry`] into gzip-compressed text format, writing directly to an
+/// [`io::Write`].
+///
+/// This is [`encode_to_writer`] through a [`flate2::write::GzEncoder`], e.g. for scrapers sending
+/// `Accept-Encoding: gzip`. The gzip stream is finished before returning.
+///
+/// # Examples
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     format::text::{self, TextProfile},
+/// #     metrics::counter::Counter,
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut registry = Registry::default();
+/// registry.register("http_requests", "Total number of HTTP requests", <Counter>::default())?;
+///
+/// let mut body = Vec::<u8>::new();
+/// text::encode_compressed(&mut body, &registry, TextProfile::default())?;
+/// // gzip magic bytes
+/// assert_eq!(body[..2], [0x1f, 0x8b]);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "gzip")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+pub fn encode_compressed(
+    writer: &mut impl io::Write,
+    registry: &Registry,
+    profile: TextProfile,
+) -> io::Result<()> {
+    use flate2::{Compression, write::GzEncoder};
+
+    let mut encoder = GzEncoder::new(writer, Compression::default());
+    encode_to_writer(&mut encoder, registry, profile)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Size of the stack buffer used by [`IoWriter`].
+const IO_BUFFER_SIZE: usize = 8192;
+
+/// Adapts an [`io::Write`] to [`fmt::Write`], flushing the buffered output on each newline.
+///
+/// `fmt::Write` can only return [`fmt::Error`], so the first I/O error is kept in `error` and
+/// surfaced by the caller once encoding returns.
+struct IoWriter<'a, W> {
+    inner: &'a mut W,
+    buffer: [u8; IO_BUFFER_SIZE],
+    len: usize,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write> IoWriter<'a, W> {
+    fn new(inner: &'a mut W) -> Self {
+        Self { inner, buffer: [0; IO_BUFFER_SIZE], len: 0, error: None }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.buffer[..self.len])?;
+        self.len = 0;
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if self.len + bytes.len() > IO_BUFFER_SIZE {
+            self.flush()?;
+        }
+        if bytes.len() > IO_BUFFER_SIZE {
+            // too large to be buffered, e.g. a huge help text
+            return self.inner.write_all(bytes);
+        }
+        self.buffer[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        if bytes.contains(&b'\n') {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // keep the first error, encoding stops as soon as `fmt::Error` is returned
+        self.write_bytes(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
 /// Encodes metrics from a [`Registry`] into text format with explicit profile and scope hook.
 ///
 /// This is the advanced text-encoding entrypoint. The [`encode`] helper is a thin wrapper around
@@ -106,3 +417,26 @@ pub fn encode_with<G>(
 
     encoder::encode(writer, registry, profile.into())
 }
+
+/// Encodes every metric family of `registry` on its own, returning the fully-qualified family
+/// names together with their sorted text lines.
+///
+/// The lines are sorted so that the output doesn't depend on the iteration order of families,
+/// e.g. when comparing two snapshots of the same registry.
+pub(crate) fn encode_families(registry: &Registry) -> Result<Vec<(String, Vec<String>)>> {
+    let _guard = crate::metrics::lazy_group::enter_scope();
+    let config = config::ProfileConfig::from(TextProfile::default());
+
+    let mut families = Vec::new();
+    encoder::collect_families(registry, &mut families);
+    families
+        .into_iter()
+        .map(|(name, registry, metadata, metric)| {
+            let mut output = String::new();
+            encoder::encode_family(&mut output, registry, metadata, metric, config)?;
+            let mut lines = output.lines().map(str::to_owned).collect::<Vec<_>>();
+            lines.sort_unstable();
+            Ok((name.into_owned(), lines))
+        })
+        .collect()
+}
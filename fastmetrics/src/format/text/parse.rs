@@ -0,0 +1,598 @@
+//! Text exposition format parser.
+//!
+//! The parser turns the output of the text encoder back into structured data, so that tests can
+//! assert on metric names, labels and values instead of matching strings.
+//!
+//! It understands both the Prometheus and the OpenMetrics text formats, but it's not a fully
+//! validating parser: it doesn't check that samples are consistent with the declared metric type.
+
+use crate::{
+    error::{Error, Result},
+    raw::MetricType,
+};
+
+/// Sample name suffixes that may follow the name of the metric family a sample belongs to.
+const SAMPLE_SUFFIXES: &[&str] =
+    &["_total", "_created", "_bucket", "_count", "_sum", "_gcount", "_gsum", "_info"];
+
+type Labels = Vec<(String, String)>;
+
+/// How the parser handles malformed lines.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ParseMode {
+    /// Return an error on the first malformed line.
+    #[default]
+    Strict,
+    /// Skip malformed lines.
+    Lenient,
+}
+
+/// A metric family parsed from the text exposition format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedMetricFamily {
+    /// Name of the metric family, as written in the `# TYPE`/`# HELP`/`# UNIT` lines.
+    ///
+    /// For samples without any metadata line, this is the sample name.
+    pub name: String,
+    /// Type of the metric family, `untyped` is mapped to [`MetricType::Unknown`].
+    pub metric_type: MetricType,
+    /// Unescaped help text, if any.
+    pub help: Option<String>,
+    /// Unit, if any.
+    pub unit: Option<String>,
+    /// Samples of the metric family, in exposition order.
+    pub samples: Vec<ParsedSample>,
+}
+
+/// A sample parsed from the text exposition format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedSample {
+    /// Full sample name, including suffixes such as `_total` or `_bucket`.
+    pub name: String,
+    /// Unescaped label pairs, in exposition order.
+    pub labels: Vec<(String, String)>,
+    /// Sample value.
+    pub value: f64,
+    /// Sample timestamp in seconds, if any.
+    pub timestamp: Option<f64>,
+    /// Exemplar attached to the sample, if any.
+    pub exemplar: Option<ParsedExemplar>,
+}
+
+/// An exemplar parsed from the text exposition format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedExemplar {
+    /// Unescaped label pairs, in exposition order.
+    pub labels: Vec<(String, String)>,
+    /// Exemplar value.
+    pub value: f64,
+    /// Exemplar timestamp in seconds, if any.
+    pub timestamp: Option<f64>,
+}
+
+/// Parses text exposition format in [`ParseMode::Strict`] mode.
+///
+/// # Examples
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     format::text::{self, TextProfile},
+/// #     metrics::counter::Counter,
+/// #     raw::MetricType,
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let mut registry = Registry::default();
+/// let requests = <Counter>::default();
+/// registry.register("http_requests", "Total number of HTTP requests", requests.clone())?;
+/// requests.inc_by(3);
+///
+/// let mut output = String::new();
+/// text::encode(&mut output, &registry, TextProfile::default())?;
+///
+/// let families = text::parse(&output)?;
+/// assert_eq!(families[0].name, "http_requests");
+/// assert_eq!(families[0].metric_type, MetricType::Counter);
+/// assert_eq!(families[0].samples[0].name, "http_requests_total");
+/// assert_eq!(families[0].samples[0].value, 3.0);
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse(input: &str) -> Result<Vec<ParsedMetricFamily>> {
+    parse_with(input, ParseMode::Strict)
+}
+
+/// Parses text exposition format with an explicit [`ParseMode`].
+///
+/// Parsing stops at the `# EOF` line, if any.
+pub fn parse_with(input: &str, mode: ParseMode) -> Result<Vec<ParsedMetricFamily>> {
+    let mut families = Vec::<ParsedMetricFamily>::new();
+
+    for (index, line) in input.lines().enumerate() {
+        if line == "# EOF" {
+            break;
+        }
+        match parse_line(line, &mut families) {
+            Ok(()) => {},
+            Err(err) => match mode {
+                ParseMode::Strict => {
+                    return Err(err.with_context("line", index + 1).with_context("content", line));
+                },
+                ParseMode::Lenient => continue,
+            },
+        }
+    }
+
+    Ok(families)
+}
+
+fn parse_line(line: &str, families: &mut Vec<ParsedMetricFamily>) -> Result<()> {
+    if line.trim().is_empty() {
+        return Ok(());
+    }
+
+    if let Some(comment) = line.strip_prefix('#') {
+        return parse_metadata(comment, families);
+    }
+
+    let sample = parse_sample(line)?;
+    match families.last_mut() {
+        Some(family) if belongs_to(&sample.name, &family.name) => family.samples.push(sample),
+        _ => families.push(ParsedMetricFamily {
+            name: sample.name.clone(),
+            metric_type: MetricType::Unknown,
+            help: None,
+            unit: None,
+            samples: vec![sample],
+        }),
+    }
+    Ok(())
+}
+
+fn belongs_to(sample_name: &str, family_name: &str) -> bool {
+    match sample_name.strip_prefix(family_name) {
+        Some("") => true,
+        Some(suffix) => SAMPLE_SUFFIXES.contains(&suffix),
+        None => false,
+    }
+}
+
+fn parse_metadata(comment: &str, families: &mut Vec<ParsedMetricFamily>) -> Result<()> {
+    let Some(comment) = comment.strip_prefix(' ') else {
+        // plain comment
+        return Ok(());
+    };
+    let (keyword, rest) = comment.split_once(' ').unwrap_or((comment, ""));
+    if !matches!(keyword, "TYPE" | "HELP" | "UNIT") {
+        // plain comment
+        return Ok(());
+    }
+
+    let (name, value) = rest.split_once(' ').unwrap_or((rest, ""));
+    if name.is_empty() {
+        return Err(Error::invalid(format!("missing metric name in `# {keyword}` line")));
+    }
+    let metric_type = if keyword == "TYPE" { Some(parse_metric_type(value)?) } else { None };
+
+    let family = match families.last_mut() {
+        Some(family) if family.name == name && family.samples.is_empty() => family,
+        _ => {
+            families.push(ParsedMetricFamily {
+                name: name.to_owned(),
+                metric_type: MetricType::Unknown,
+                help: None,
+                unit: None,
+                samples: Vec::new(),
+            });
+            families.last_mut().expect("family was just pushed")
+        },
+    };
+
+    match (keyword, metric_type) {
+        (_, Some(metric_type)) => family.metric_type = metric_type,
+        ("HELP", _) => family.help = Some(unescape(value, false)?),
+        _ => family.unit = Some(value.to_owned()),
+    }
+    Ok(())
+}
+
+fn parse_metric_type(value: &str) -> Result<MetricType> {
+    Ok(match value {
+        "unknown" | "untyped" => MetricType::Unknown,
+        "gauge" => MetricType::Gauge,
+        "counter" => MetricType::Counter,
+        "stateset" => MetricType::StateSet,
+        "info" => MetricType::Info,
+        "histogram" => MetricType::Histogram,
+        "gaugehistogram" => MetricType::GaugeHistogram,
+        "summary" => MetricType::Summary,
+        _ => return Err(Error::invalid(format!("unknown metric type `{value}`"))),
+    })
+}
+
+fn parse_sample(line: &str) -> Result<ParsedSample> {
+    let mut cursor = Cursor { rest: line };
+
+    let mut name = cursor.take_name().map(str::to_owned);
+    let labels = if cursor.eat('{') {
+        let (quoted_name, labels) = cursor.take_labels()?;
+        if let Some(quoted_name) = quoted_name {
+            if name.is_some() {
+                return Err(Error::invalid("metric name is specified twice"));
+            }
+            name = Some(quoted_name);
+        }
+        labels
+    } else {
+        Vec::new()
+    };
+    let name = name.ok_or_else(|| Error::invalid("missing metric name"))?;
+
+    cursor.expect_space()?;
+    let value = cursor.take_number()?;
+    let timestamp = cursor.take_timestamp()?;
+
+    let exemplar = if cursor.eat_str(" # ") {
+        if !cursor.eat('{') {
+            return Err(Error::invalid("missing exemplar labels"));
+        }
+        let (quoted_name, labels) = cursor.take_labels()?;
+        if quoted_name.is_some() {
+            return Err(Error::invalid("exemplar labels must not contain a metric name"));
+        }
+        cursor.expect_space()?;
+        let value = cursor.take_number()?;
+        let timestamp = cursor.take_timestamp()?;
+        Some(ParsedExemplar { labels, value, timestamp })
+    } else {
+        None
+    };
+
+    if !cursor.rest.is_empty() {
+        return Err(Error::invalid(format!("unexpected trailing content `{}`", cursor.rest)));
+    }
+
+    Ok(ParsedSample { name, labels, value, timestamp, exemplar })
+}
+
+struct Cursor<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Cursor<'a> {
+    fn eat(&mut self, ch: char) -> bool {
+        match self.rest.strip_prefix(ch) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        match self.rest.strip_prefix(s) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, ch: char) -> Result<()> {
+        if self.eat(ch) { Ok(()) } else { Err(Error::invalid(format!("expected `{ch}`"))) }
+    }
+
+    fn expect_space(&mut self) -> Result<()> {
+        self.expect(' ')
+    }
+
+    // Takes an unquoted metric or label name.
+    fn take_name(&mut self) -> Option<&'a str> {
+        let end = self
+            .rest
+            .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == ':'))
+            .unwrap_or(self.rest.len());
+        let (name, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        (!name.is_empty()).then_some(name)
+    }
+
+    // Takes a token up to the next space or the end of the line.
+    fn take_token(&mut self) -> &'a str {
+        let end = self.rest.find(' ').unwrap_or(self.rest.len());
+        let (token, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        token
+    }
+
+    fn take_number(&mut self) -> Result<f64> {
+        let token = self.take_token();
+        token.parse().map_err(|_| Error::invalid(format!("invalid number `{token}`")))
+    }
+
+    fn take_timestamp(&mut self) -> Result<Option<f64>> {
+        if self.rest.starts_with(' ') && !self.rest.starts_with(" #") {
+            self.expect_space()?;
+            self.take_number().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn take_quoted(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut end = None;
+        let mut escaped = false;
+        for (i, ch) in self.rest.char_indices() {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => {
+                    end = Some(i);
+                    break;
+                },
+                _ => {},
+            }
+        }
+        let end = end.ok_or_else(|| Error::invalid("unterminated quoted string"))?;
+        let value = unescape(&self.rest[..end], true)?;
+        self.rest = &self.rest[end + 1..];
+        Ok(value)
+    }
+
+    // Takes the labels after `{`, including the closing `}`.
+    //
+    // Returns the quoted metric name (UTF-8 names may be written inside the braces) and the
+    // label pairs.
+    fn take_labels(&mut self) -> Result<(Option<String>, Labels)> {
+        let mut quoted_name = None;
+        let mut labels = Vec::new();
+
+        while !self.eat('}') {
+            let label_name = match self.take_name() {
+                Some(name) => name.to_owned(),
+                None if self.rest.starts_with('"') => {
+                    let name = self.take_quoted()?;
+                    if !self.rest.starts_with('=') {
+                        if quoted_name.is_some() || !labels.is_empty() {
+                            return Err(Error::invalid("unexpected quoted metric name"));
+                        }
+                        quoted_name = Some(name);
+                        if !self.eat(',') && !self.rest.starts_with('}') {
+                            return Err(Error::invalid("expected `,` or `}`"));
+                        }
+                        continue;
+                    }
+                    name
+                },
+                None => return Err(Error::invalid("expected label name")),
+            };
+            self.expect('=')?;
+            let label_value = self.take_quoted()?;
+            labels.push((label_name, label_value));
+
+            if !self.eat(',') && !self.rest.starts_with('}') {
+                return Err(Error::invalid("expected `,` or `}`"));
+            }
+        }
+
+        Ok((quoted_name, labels))
+    }
+}
+
+// Unescapes `\\`, `\n` and `\"`.
+//
+// Quoted strings must escape every backslash, while help texts may contain unknown escape
+// sequences that are kept as is.
+fn unescape(value: &str, strict: bool) -> Result<String> {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            output.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => output.push('\\'),
+            Some('n') => output.push('\n'),
+            Some('"') => output.push('"'),
+            Some(other) if !strict => {
+                output.push('\\');
+                output.push(other);
+            },
+            None if !strict => output.push('\\'),
+            _ => return Err(Error::invalid("invalid escape sequence")),
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::ErrorKind,
+        format::text::{EscapingScheme, TextProfile, encode},
+        metrics::{
+            counter::Counter, gauge::Gauge, histogram::Histogram, info::Info, summary::Summary,
+        },
+        registry::{Registry, Unit},
+    };
+
+    fn labels(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    fn round_trip(registry: &Registry, profile: TextProfile) -> Vec<ParsedMetricFamily> {
+        let mut output = String::new();
+        encode(&mut output, registry, profile).unwrap();
+        parse(&output).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_openmetrics() {
+        let mut registry = Registry::builder()
+            .with_namespace("app")
+            .with_const_labels([("env", "prod")])
+            .build()
+            .unwrap();
+
+        let requests = <Counter>::default();
+        registry
+            .register("requests", r#"Total \"requests\" served"#, requests.clone())
+            .unwrap();
+        requests.inc_by(3);
+
+        let temperature = <Gauge<f64>>::default();
+        registry
+            .register_with_unit("temperature", "Temperature", Unit::Celsius, temperature.clone())
+            .unwrap();
+        temperature.set(-1.5);
+
+        let latency = Histogram::new([0.1, 1.0]);
+        registry.register("latency", "Latency", latency.clone()).unwrap();
+        latency.observe(0.05);
+        latency.observe(0.5);
+        latency.observe(5.0);
+
+        let info = Info::new(vec![("version", "1.0 \"beta\"\\")]);
+        registry.register("build", "Build info", info).unwrap();
+
+        let families = round_trip(
+            &registry,
+            TextProfile::OpenMetricsV1_0_0 { escaping_scheme: EscapingScheme::AllowUtf8 },
+        );
+        let mut names = families.iter().map(|family| family.name.as_str()).collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, ["app_build", "app_latency", "app_requests", "app_temperature_celsius"]);
+        let family = |name: &str| families.iter().find(|family| family.name == name).unwrap();
+
+        let requests = family("app_requests");
+        assert_eq!(requests.metric_type, MetricType::Counter);
+        assert_eq!(requests.help.as_deref(), Some(r#"Total "requests" served"#));
+        assert_eq!(requests.samples[0].name, "app_requests_total");
+        assert_eq!(requests.samples[0].labels, labels(&[("env", "prod")]));
+        assert_eq!(requests.samples[0].value, 3.0);
+        assert_eq!(requests.samples.len(), 1);
+
+        let temperature = family("app_temperature_celsius");
+        assert_eq!(temperature.metric_type, MetricType::Gauge);
+        assert_eq!(temperature.unit.as_deref(), Some("celsius"));
+        assert_eq!(temperature.samples.len(), 1);
+        assert_eq!(temperature.samples[0].value, -1.5);
+
+        let latency = family("app_latency");
+        assert_eq!(latency.metric_type, MetricType::Histogram);
+        let buckets = latency
+            .samples
+            .iter()
+            .filter(|sample| sample.name == "app_latency_bucket")
+            .map(|sample| (sample.labels[1].1.as_str(), sample.value))
+            .collect::<Vec<_>>();
+        assert_eq!(buckets, [("0.1", 1.0), ("1.0", 2.0), ("+Inf", 3.0)]);
+        let count = latency.samples.iter().find(|s| s.name == "app_latency_count").unwrap();
+        assert_eq!(count.value, 3.0);
+        let sum = latency.samples.iter().find(|s| s.name == "app_latency_sum").unwrap();
+        assert_eq!(sum.value, 5.55);
+
+        let build = family("app_build");
+        assert_eq!(build.metric_type, MetricType::Info);
+        assert_eq!(build.samples[0].name, "app_build_info");
+        assert_eq!(
+            build.samples[0].labels,
+            labels(&[("env", "prod"), ("version", "1.0 \"beta\"\\")])
+        );
+        assert_eq!(build.samples[0].value, 1.0);
+    }
+
+    #[test]
+    fn test_round_trip_prometheus() {
+        let mut registry = Registry::default();
+
+        let requests = <Counter>::default();
+        registry.register("requests", "Total requests", requests.clone()).unwrap();
+        requests.inc_by(7);
+
+        let latency = Summary::default();
+        registry.register("latency", "Latency", latency.clone()).unwrap();
+        latency.observe(1.0);
+
+        let families = round_trip(&registry, TextProfile::PrometheusV0_0_4);
+        assert_eq!(families.len(), 2);
+        let family = |name: &str| families.iter().find(|family| family.name == name).unwrap();
+
+        let requests = family("requests");
+        assert_eq!(requests.metric_type, MetricType::Counter);
+        assert_eq!(requests.samples.len(), 1);
+        assert_eq!(requests.samples[0].name, "requests");
+        assert_eq!(requests.samples[0].value, 7.0);
+
+        let latency = family("latency");
+        assert_eq!(latency.metric_type, MetricType::Summary);
+        let quantiles = latency
+            .samples
+            .iter()
+            .filter(|sample| sample.name == "latency")
+            .map(|sample| (sample.labels[0].1.as_str(), sample.value))
+            .collect::<Vec<_>>();
+        assert_eq!(quantiles, [("0.5", 1.0), ("0.9", 1.0), ("0.99", 1.0)]);
+    }
+
+    #[test]
+    fn test_parse_exemplars_and_timestamps() {
+        let input = indoc::indoc! {r#"
+            # TYPE requests counter
+            requests_total{path="/"} 2 1700000000.5 # {trace_id="abc"} 1.5 1700000000.25
+            requests_total{path="/a"} 1 # {} 1
+            {"my.metric",code="200"} NaN
+            # EOF
+            ignored 1
+        "#};
+
+        let families = parse(input).unwrap();
+        assert_eq!(families.len(), 2);
+
+        let sample = &families[0].samples[0];
+        assert_eq!(sample.timestamp, Some(1700000000.5));
+        assert_eq!(
+            sample.exemplar,
+            Some(ParsedExemplar {
+                labels: labels(&[("trace_id", "abc")]),
+                value: 1.5,
+                timestamp: Some(1700000000.25),
+            })
+        );
+        let sample = &families[0].samples[1];
+        assert_eq!(sample.timestamp, None);
+        assert_eq!(sample.exemplar.as_ref().unwrap().labels, labels(&[]));
+
+        assert_eq!(families[1].name, "my.metric");
+        assert_eq!(families[1].samples[0].labels, labels(&[("code", "200")]));
+        assert!(families[1].samples[0].value.is_nan());
+    }
+
+    #[test]
+    fn test_parse_modes() {
+        let input = indoc::indoc! {r#"
+            # TYPE up gauge
+            up 1
+            up{instance="a} 1
+            up{instance="b"} one
+            # TYPE down nonsense
+            up{instance="c"} 0
+        "#};
+
+        let err = parse(input).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Invalid);
+        assert!(err.to_string().contains("line: 3"), "{err}");
+
+        let families = parse_with(input, ParseMode::Lenient).unwrap();
+        assert_eq!(families.len(), 1);
+        let values = families[0].samples.iter().map(|sample| sample.value).collect::<Vec<_>>();
+        assert_eq!(values, [1.0, 0.0]);
+    }
+}
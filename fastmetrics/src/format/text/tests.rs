@@ -6,11 +6,12 @@ use crate::{
         counter::Counter,
         family::Family,
         gauge_histogram::GaugeHistogram,
+        histogram::Histogram,
         info::Info,
         state_set::{StateSet, StateSetValue},
         unknown::Unknown,
     },
-    raw::{LabelSetSchema, MetricLabelSet, MetricType, TypedMetric},
+    raw::{LabelSetSchema, MetricLabelSet, MetricType, TypedMetric, Unit},
     registry::{NameRule, Registry},
 };
 
@@ -354,6 +355,45 @@ fn v1_dots_rejects_family_label_name_collisions_after_escaping() {
     assert_eq!(err.message(), "label names collide after escaping");
 }
 
+#[test]
+fn batched_labels_are_separated_and_checked_for_collisions() {
+    #[derive(Clone, Eq, PartialEq, Hash)]
+    struct BatchedLabels {
+        names: [&'static str; 3],
+    }
+
+    impl LabelSetSchema for BatchedLabels {
+        fn names() -> Option<&'static [&'static str]> {
+            None
+        }
+    }
+
+    impl EncodeLabelSet for BatchedLabels {
+        fn encode(&self, encoder: &mut dyn LabelSetEncoder) -> Result<()> {
+            encoder.encode(&("first", "0"))?;
+            let [a, b, c] = self.names.map(|name| (name, "1"));
+            encoder.encode_all(&[&a, &b, &c])
+        }
+    }
+
+    let profile = TextProfile::OpenMetricsV1_0_0 { escaping_scheme: EscapingScheme::Underscores };
+    let mut registry = Registry::builder().with_name_rule(NameRule::Utf8).build().unwrap();
+    let family = Family::<BatchedLabels, Counter>::default();
+    registry.register("req", "help", family.clone()).unwrap();
+
+    family.with_or_new(&BatchedLabels { names: ["a", "b", "c"] }, |counter| counter.inc());
+    let mut output = String::new();
+    encode(&mut output, &registry, profile).unwrap();
+    assert!(output.contains("req_total{first=\"0\",a=\"1\",b=\"1\",c=\"1\"} 1\n"), "{output}");
+
+    family.clear();
+    family.with_or_new(&BatchedLabels { names: ["a", "b-c", "b/c"] }, |counter| counter.inc());
+    let mut output = String::new();
+    let err = encode(&mut output, &registry, profile).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Duplicated);
+    assert_eq!(err.message(), "label names collide after escaping");
+}
+
 #[test]
 fn v1_underscores_rejects_stateset_label_name_collisions_after_escaping() {
     #[derive(Copy, Clone, Debug, PartialEq)]
@@ -510,3 +550,670 @@ fn legacy_profiles_reject_exemplar_label_name_collisions_after_escaping() {
     assert_eq!(err.kind(), ErrorKind::Duplicated);
     assert_eq!(err.message(), "label names collide after escaping");
 }
+
+#[test]
+fn invalid_exemplars_are_skipped() {
+    struct RawExemplar(Vec<(&'static str, String)>, f64);
+
+    impl EncodeExemplar for RawExemplar {
+        fn encode(&self, encoder: &mut dyn ExemplarEncoder) -> Result<()> {
+            encoder.encode(&self.0, self.1, None)
+        }
+    }
+
+    struct ExemplarCounterMetric(RawExemplar);
+
+    impl TypedMetric for ExemplarCounterMetric {
+        const TYPE: MetricType = MetricType::Counter;
+    }
+
+    impl MetricLabelSet for ExemplarCounterMetric {
+        type LabelSet = ();
+    }
+
+    impl EncodeMetric for ExemplarCounterMetric {
+        fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+            encoder.encode_counter(&1_u64, Some(&self.0), None)
+        }
+    }
+
+    let mut registry = Registry::default();
+    let valid = RawExemplar(vec![("trace_id", "abc".to_owned())], 1.0);
+    registry.register("valid", "help", ExemplarCounterMetric(valid)).unwrap();
+    let too_long = RawExemplar(vec![("trace_id", "a".repeat(121))], 1.0);
+    registry.register("too_long", "help", ExemplarCounterMetric(too_long)).unwrap();
+    let nan = RawExemplar(vec![("trace_id", "abc".to_owned())], f64::NAN);
+    registry.register("nan", "help", ExemplarCounterMetric(nan)).unwrap();
+    let infinite = RawExemplar(vec![("trace_id", "abc".to_owned())], f64::INFINITY);
+    registry.register("infinite", "help", ExemplarCounterMetric(infinite)).unwrap();
+
+    let mut output = String::new();
+    encode(&mut output, &registry, TextProfile::default()).unwrap();
+
+    assert!(output.contains("valid_total 1 # {trace_id=\"abc\"} 1.0\n"), "{output}");
+    assert!(output.contains("too_long_total 1\n"), "{output}");
+    assert!(output.contains("nan_total 1\n"), "{output}");
+    assert!(output.contains("infinite_total 1\n"), "{output}");
+}
+
+#[test]
+fn encode_to_writer_matches_string_output() {
+    let counter = <Counter>::default();
+    counter.inc_by(3);
+
+    for profile in [
+        TextProfile::PrometheusV0_0_4,
+        TextProfile::OpenMetricsV0_0_1,
+        TextProfile::OpenMetricsV1_0_0 { escaping_scheme: EscapingScheme::AllowUtf8 },
+    ] {
+        let mut registry = Registry::default();
+        registry.register("requests", r#"Total \"requests\""#, counter.clone()).unwrap();
+        if profile != TextProfile::PrometheusV0_0_4 {
+            registry.register("build", "Build info", Info::new(vec![("v", "1")])).unwrap();
+        }
+
+        let mut expected = String::new();
+        encode(&mut expected, &registry, profile).unwrap();
+
+        let mut output = Vec::<u8>::new();
+        encode_to_writer(&mut output, &registry, profile).unwrap();
+        assert_eq!(output, expected.as_bytes(), "profile: {profile:?}");
+    }
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn encode_compressed_roundtrip() {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    let mut registry = Registry::default();
+    for (i, name) in ["requests", "errors", "retries"].into_iter().enumerate() {
+        let counter = <Counter>::default();
+        counter.inc_by(i as u64 * 7);
+        registry.register(name, "Total count", counter).unwrap();
+    }
+    registry.register("build", "Build info", Info::new(vec![("v", "1")])).unwrap();
+
+    let mut expected = Vec::<u8>::new();
+    encode_to_writer(&mut expected, &registry, TextProfile::default()).unwrap();
+
+    let mut compressed = Vec::<u8>::new();
+    encode_compressed(&mut compressed, &registry, TextProfile::default()).unwrap();
+
+    let mut output = Vec::<u8>::new();
+    GzDecoder::new(compressed.as_slice()).read_to_end(&mut output).unwrap();
+    assert_eq!(output, expected);
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn encode_to_bytes_matches_string_output() {
+    let mut registry = Registry::default();
+    registry.register("requests", "Total requests", <Counter>::default()).unwrap();
+    registry
+        .subsystem("db")
+        .unwrap()
+        .register("queries", "Help", <Counter>::default())
+        .unwrap();
+
+    for profile in [TextProfile::PrometheusV0_0_4, TextProfile::default()] {
+        let mut expected = String::new();
+        encode(&mut expected, &registry, profile).unwrap();
+
+        let output = encode_to_bytes(&registry, profile).unwrap();
+        assert!(!output.is_empty());
+        assert_eq!(output, expected.as_bytes(), "profile: {profile:?}");
+    }
+}
+
+#[test]
+fn encode_to_writer_propagates_io_errors() {
+    struct FailingWriter;
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "broken pipe"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut registry = Registry::default();
+    registry.register("requests", "Total requests", <Counter>::default()).unwrap();
+
+    let err = encode_to_writer(&mut FailingWriter, &registry, TextProfile::default()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+
+    // encoding errors are wrapped
+    registry.register("build", "Build info", Info::new(vec![("v", "1")])).unwrap();
+    let err =
+        encode_to_writer(&mut Vec::new(), &registry, TextProfile::PrometheusV0_0_4).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+}
+
+#[test]
+fn encode_to_writer_streams_large_registries() {
+    // accepts at most `CHUNK` bytes per write, like a socket with a small send buffer
+    struct ChunkedWriter {
+        output: Vec<u8>,
+        writes: usize,
+    }
+
+    impl std::io::Write for ChunkedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            const CHUNK: usize = 100;
+            let len = buf.len().min(CHUNK);
+            self.output.extend_from_slice(&buf[..len]);
+            self.writes += 1;
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const METRICS: usize = 10_000;
+    let mut registry = Registry::default();
+    for i in 0..METRICS {
+        let counter = <Counter>::default();
+        counter.inc_by(i as u64);
+        registry.register(format!("requests_{i}"), "Total requests", counter).unwrap();
+    }
+
+    let mut writer = ChunkedWriter { output: Vec::new(), writes: 0 };
+    encode_to_writer(&mut writer, &registry, TextProfile::default()).unwrap();
+    assert!(writer.writes > METRICS, "output should be written incrementally");
+
+    let output = String::from_utf8(writer.output).unwrap();
+    let mut expected = String::new();
+    encode(&mut expected, &registry, TextProfile::default()).unwrap();
+    assert_eq!(output, expected);
+    assert!(output.ends_with("# EOF\n"));
+
+    let families = parse(&output).unwrap();
+    assert_eq!(families.len(), METRICS);
+    for family in families {
+        let i = family.name.strip_prefix("requests_").unwrap().parse::<f64>().unwrap();
+        assert_eq!(family.samples[0].value, i);
+    }
+}
+
+#[test]
+fn encode_to_writer_handles_lines_larger_than_the_buffer() {
+    let mut registry = Registry::default();
+    let help = "x".repeat(3 * IO_BUFFER_SIZE);
+    registry.register("requests", help, <Counter>::default()).unwrap();
+
+    let mut output = Vec::new();
+    encode_to_writer(&mut output, &registry, TextProfile::default()).unwrap();
+
+    let mut expected = String::new();
+    encode(&mut expected, &registry, TextProfile::default()).unwrap();
+    assert_eq!(output, expected.as_bytes());
+}
+
+#[test]
+fn sorted_output_is_deterministic() {
+    let mut registry = Registry::builder().with_namespace("app").build().unwrap();
+    for name in ["zeta", "alpha", "mu", "beta", "omega", "gamma", "kappa"] {
+        registry.register(name, "Help", <Counter>::default()).unwrap();
+    }
+    let db = registry.subsystem("db").unwrap();
+    db.register("queries", "Help", <Counter>::default()).unwrap();
+    db.register("connections", "Help", <Counter>::default()).unwrap();
+
+    let options = TextProfile::PrometheusV0_0_4.with_sorted_output(true);
+    let mut first = String::new();
+    encode_with_options(&mut first, &registry, options).unwrap();
+    let mut second = String::new();
+    encode_with_options(&mut second, &registry, options).unwrap();
+    assert_eq!(first, second);
+
+    let names = first
+        .lines()
+        .filter_map(|line| line.strip_prefix("# TYPE "))
+        .map(|line| line.split(' ').next().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "app_alpha",
+            "app_beta",
+            "app_db_connections",
+            "app_db_queries",
+            "app_gamma",
+            "app_kappa",
+            "app_mu",
+            "app_omega",
+            "app_zeta",
+        ]
+    );
+
+    // the same lines as the unsorted output
+    let mut unsorted = String::new();
+    encode(&mut unsorted, &registry, TextProfile::PrometheusV0_0_4).unwrap();
+    let mut sorted_lines = first.lines().collect::<Vec<_>>();
+    let mut unsorted_lines = unsorted.lines().collect::<Vec<_>>();
+    sorted_lines.sort_unstable();
+    unsorted_lines.sort_unstable();
+    assert_eq!(sorted_lines, unsorted_lines);
+}
+
+#[test]
+fn encode_filtered_skips_unmatched_families() {
+    let mut registry = Registry::default();
+    registry
+        .register("http_requests", "Total HTTP requests", <Counter>::default())
+        .unwrap();
+    registry
+        .register_with_unit(
+            "http_request_duration",
+            "HTTP request duration",
+            Unit::Seconds,
+            Histogram::new([0.1, 1.0]),
+        )
+        .unwrap();
+    registry
+        .register("db_queries", "Total DB queries", <Counter>::default())
+        .unwrap();
+    registry
+        .subsystem("http")
+        .unwrap()
+        .register("errors", "Total HTTP errors", <Counter>::default())
+        .unwrap();
+
+    let mut output = String::new();
+    encode_filtered(&mut output, &registry, TextProfile::default(), |name| {
+        name.starts_with("http_")
+    })
+    .unwrap();
+
+    let mut names = output
+        .lines()
+        .filter_map(|line| line.strip_prefix("# TYPE "))
+        .map(|line| line.split(' ').next().unwrap())
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["http_errors", "http_request_duration_seconds", "http_requests"]);
+    for name in names {
+        assert!(output.contains(&format!("# HELP {name} ")), "{output}");
+    }
+    assert!(output.contains("# UNIT http_request_duration_seconds seconds\n"));
+    assert!(!output.contains("db_queries"));
+    assert!(output.ends_with("# EOF\n"));
+
+    // nothing but the EOF marker when no family matches
+    let mut output = String::new();
+    encode_filtered(&mut output, &registry, TextProfile::default(), |_| false).unwrap();
+    assert_eq!(output, "# EOF\n");
+}
+
+#[test]
+fn validate_reports_injected_violations() {
+    let mut registry = Registry::default();
+    registry.register("requests", "", <Counter>::default()).unwrap();
+    registry
+        .register_with_unit(
+            "latency_seconds",
+            "Request latency",
+            Unit::Seconds,
+            Histogram::default(),
+        )
+        .unwrap();
+    registry
+        .register("build", "Build info", Info::new(vec![("__internal", "x"), ("le", "1")]))
+        .unwrap();
+    registry.register("jobs", "Jobs", <Counter>::default()).unwrap();
+    registry.register("jobs_total", "Jobs", <Counter>::default()).unwrap();
+
+    let mut warnings = validate(&registry, TextProfile::default()).unwrap();
+    warnings.sort_by_key(|warning| (warning.metric_name.clone(), warning.issue));
+    let mut warnings = warnings
+        .iter()
+        .map(|warning| (warning.metric_name.as_str(), warning.issue))
+        .collect::<Vec<_>>();
+    // collision is reported for whichever of the two counters comes second
+    let collision = warnings
+        .iter()
+        .position(|(_, issue)| *issue == "counter sample names collide after suffix normalization")
+        .expect("counter collision should be reported");
+    assert!(matches!(warnings.remove(collision).0, "jobs" | "jobs_total"));
+
+    assert_eq!(
+        warnings,
+        vec![
+            ("build", "`le` label name is reserved for histograms"),
+            ("build", "label name is reserved"),
+            ("latency_seconds_seconds", "metric name already ends with its unit"),
+            ("requests", "help text is empty"),
+        ]
+    );
+}
+
+#[test]
+fn validate_reports_profile_specific_violations() {
+    let mut registry = Registry::builder()
+        .with_name_rule(NameRule::Utf8)
+        .with_const_labels([("a.b", "v"), ("a_b", "v")])
+        .build()
+        .unwrap();
+    registry.register("cpu.usage", "CPU usage", Unknown::new(1_i64)).unwrap();
+    registry.register("cpu_usage", "CPU usage", Unknown::new(1_i64)).unwrap();
+    registry
+        .register("build", "Build info", Info::new(vec![("version", "1.0.0")]))
+        .unwrap();
+
+    let warnings = validate(&registry, TextProfile::PrometheusV0_0_4).unwrap();
+    let issues = warnings.iter().map(|warning| warning.issue).collect::<Vec<_>>();
+    assert!(issues.contains(&"metric name is not valid for the text profile"), "{warnings:?}");
+    assert!(issues.contains(&"metric type is not supported by the text profile"), "{warnings:?}");
+
+    let profile = TextProfile::PrometheusV1_0_0 { escaping_scheme: EscapingScheme::Underscores };
+    let warnings = validate(&registry, profile).unwrap();
+    let issues = warnings.iter().map(|warning| warning.issue).collect::<Vec<_>>();
+    assert!(issues.contains(&"metric family names collide after escaping"), "{warnings:?}");
+    assert!(issues.contains(&"label names collide after escaping"), "{warnings:?}");
+
+    let profile = TextProfile::OpenMetricsV1_0_0 { escaping_scheme: EscapingScheme::AllowUtf8 };
+    assert_eq!(validate(&registry, profile).unwrap(), vec![]);
+}
+
+#[cfg(feature = "std-net-labels")]
+#[test]
+fn encode_std_net_label_values() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    let mut registry = Registry::default();
+    let info = Info::new(vec![
+        ("v4", IpAddr::V4(Ipv4Addr::LOCALHOST)),
+        ("v6", IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))),
+    ]);
+    registry.register("peer", "Peer addresses", info).unwrap();
+    let backend = SocketAddr::from((Ipv4Addr::new(192, 168, 1, 1), 8080));
+    registry
+        .register("backend", "Backend address", Info::new(vec![("backend", backend)]))
+        .unwrap();
+
+    let mut output = String::new();
+    encode(&mut output, &registry, TextProfile::default()).unwrap();
+    assert!(output.contains(r#"peer_info{v4="127.0.0.1",v6="2001:db8::1"} 1"#), "{output}");
+    assert!(output.contains(r#"backend_info{backend="192.168.1.1:8080"} 1"#), "{output}");
+}
+
+#[test]
+fn encode_duration_label_values() {
+    use std::time::Duration;
+
+    fn encode_label(value: impl EncodeLabelValue + Send + Sync + 'static) -> String {
+        let mut registry = Registry::default();
+        registry
+            .register("policy", "Policy", Info::new(vec![("timeout", value)]))
+            .unwrap();
+        let mut output = String::new();
+        encode(&mut output, &registry, TextProfile::default()).unwrap();
+        let families = parse(&output).unwrap();
+        families[0].samples[0].labels[0].1.clone()
+    }
+
+    // fractional seconds
+    assert_eq!(encode_label(Duration::ZERO), "0");
+    assert_eq!(encode_label(Duration::from_millis(100)), "0.1");
+    assert_eq!(encode_label(Duration::from_secs(30)), "30");
+    assert_eq!(encode_label(Duration::from_millis(90_000_500)), "90000.5");
+    assert_eq!(encode_label(Duration::from_secs(3 * 24 * 60 * 60)), "259200");
+
+    assert_eq!(encode_label(DurationAsHuman(Duration::ZERO)), "0s");
+    assert_eq!(encode_label(DurationAsHuman(Duration::from_micros(10))), "0s");
+    assert_eq!(encode_label(DurationAsHuman(Duration::from_secs(30))), "30s");
+    assert_eq!(encode_label(DurationAsHuman(Duration::from_secs(330))), "5m30s");
+    assert_eq!(encode_label(DurationAsHuman(Duration::from_millis(1_500))), "1s500ms");
+    assert_eq!(encode_label(DurationAsHuman(Duration::from_secs(90_061))), "1d1h1m1s");
+
+    assert_eq!(encode_label(DurationAsMillis(Duration::ZERO)), "0");
+    assert_eq!(encode_label(DurationAsMillis(Duration::from_micros(1_500_900))), "1500");
+}
+
+#[test]
+fn encode_optional_label_values() {
+    let mut registry = Registry::default();
+    let info = Info::new(vec![("env", Some("prod")), ("region", None)]);
+    registry.register("deployment", "Deployment", info).unwrap();
+
+    let mut output = String::new();
+    encode(&mut output, &registry, TextProfile::default()).unwrap();
+    // an absent label is equivalent to an empty one in Prometheus, so `None` is omitted
+    assert!(output.contains(r#"deployment_info{env="prod"} 1"#), "{output}");
+}
+
+#[test]
+fn encode_display_label_values() {
+    // written in several pieces, each of them needing escaping
+    struct Path(Vec<&'static str>);
+
+    impl std::fmt::Display for Path {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            self.0.iter().try_for_each(|segment| write!(f, "/{segment}"))
+        }
+    }
+
+    impl EncodeLabelValue for Path {
+        fn encode(&self, encoder: &mut dyn LabelEncoder) -> Result<()> {
+            encoder.encode_display_value(self)
+        }
+    }
+
+    let long = "x".repeat(100).leak();
+    let mut registry = Registry::default();
+    registry
+        .register("route", "Route", Info::new(vec![("path", Path(vec!["a\"b", "c\nd"]))]))
+        .unwrap();
+    registry
+        .register("long", "Long", Info::new(vec![("path", Path(vec![long]))]))
+        .unwrap();
+
+    // the text encoder writes the value directly
+    let mut output = String::new();
+    encode(&mut output, &registry, TextProfile::default()).unwrap();
+    assert!(output.contains(r#"route_info{path="/a\"b/c\nd"} 1"#), "{output}");
+    assert!(output.contains(&format!(r#"long_info{{path="/{long}"}} 1"#)), "{output}");
+
+    // the snapshot recorder relies on the default implementation, through the stack buffer for
+    // short values and a `String` for long ones
+    let snapshot = registry.snapshot().unwrap();
+    let mut snapshot_output = String::new();
+    encode(&mut snapshot_output, &snapshot, TextProfile::default()).unwrap();
+    assert!(snapshot_output.contains(r#"route_info{path="/a\"b/c\nd"} 1"#), "{snapshot_output}");
+    assert!(snapshot_output.contains(&format!(r#"long_info{{path="/{long}"}} 1"#)));
+}
+
+#[test]
+fn encode_raw_samples() {
+    use std::{sync::Arc, time::Duration};
+
+    struct RateCounter {
+        total: f64,
+        rate: f64,
+    }
+
+    impl EncodeMetric for RateCounter {
+        fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+            encoder.encode_raw(&[
+                RawSample {
+                    suffix: Some("_total"),
+                    labels: &(),
+                    value: self.total,
+                    timestamp: None,
+                },
+                RawSample {
+                    suffix: Some("_rate"),
+                    labels: &[("window", "1m")],
+                    value: self.rate,
+                    timestamp: Some(Duration::from_millis(1_500)),
+                },
+            ])
+        }
+    }
+
+    let mut registry = Registry::builder().with_const_labels([("env", "prod")]).build().unwrap();
+    let requests = RateCounter { total: 120.0, rate: 2.5 };
+    registry
+        .register_boxed("requests", "Requests", MetricType::Unknown, None, Arc::new(requests))
+        .unwrap();
+
+    let mut output = String::new();
+    encode(&mut output, &registry, TextProfile::default()).unwrap();
+    assert!(output.contains("requests_total{env=\"prod\"} 120.0\n"), "{output}");
+    assert!(output.contains("requests_rate{env=\"prod\",window=\"1m\"} 2.5 1.500\n"), "{output}");
+
+    // raw samples survive snapshots
+    let mut snapshot_output = String::new();
+    encode(&mut snapshot_output, &registry.snapshot().unwrap(), TextProfile::default()).unwrap();
+    assert_eq!(snapshot_output, output);
+}
+
+#[test]
+fn encode_with_timestamp_format() {
+    use std::{sync::Arc, time::Duration};
+
+    struct Temperature;
+
+    impl EncodeMetric for Temperature {
+        fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+            encoder.encode_raw(&[RawSample {
+                suffix: None,
+                labels: &(),
+                value: 21.5,
+                timestamp: Some(Duration::from_millis(1_234_567_890_123)),
+            }])
+        }
+    }
+
+    let mut registry = Registry::default();
+    registry
+        .register_boxed("temperature", "help", MetricType::Gauge, None, Arc::new(Temperature))
+        .unwrap();
+
+    let mut output = String::new();
+    encode(&mut output, &registry, TextProfile::default()).unwrap();
+    assert!(output.contains("temperature 21.5 1234567890.123\n"), "{output}");
+
+    let options =
+        TextProfile::default().with_timestamp_format(TimestampFormat::MillisecondsInteger);
+    let mut output = String::new();
+    encode_with_options(&mut output, &registry, options).unwrap();
+    assert!(output.contains("temperature 21.5 1234567890123\n"), "{output}");
+    assert!(output.ends_with("# EOF\n"), "{output}");
+
+    // the Prometheus profiles default to milliseconds, which can be overridden too
+    let mut output = String::new();
+    encode(&mut output, &registry, TextProfile::PrometheusV0_0_4).unwrap();
+    assert!(output.contains("temperature 21.5 1234567890123\n"), "{output}");
+
+    let options = TextProfile::PrometheusV0_0_4
+        .with_sorted_output(true)
+        .with_timestamp_format(TimestampFormat::SecondsMillis);
+    let mut output = String::new();
+    encode_with_options(&mut output, &registry, options).unwrap();
+    assert!(output.contains("temperature 21.5 1234567890.123\n"), "{output}");
+}
+
+#[test]
+fn lazy_metrics_are_fetched_once_per_scrape() {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use crate::metrics::{counter::LazyCounter, gauge::LazyGauge};
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut registry = Registry::default();
+    let gauge = LazyGauge::new({
+        let calls = calls.clone();
+        move || {
+            calls.fetch_add(1, Ordering::Relaxed);
+            7_i64
+        }
+    });
+    let counter = LazyCounter::new({
+        let calls = calls.clone();
+        move || {
+            calls.fetch_add(1, Ordering::Relaxed);
+            3_u64
+        }
+    });
+    registry.register("open_fds", "Open fds", gauge).unwrap();
+    registry.register("reads", "Total reads", counter).unwrap();
+
+    for profile in [TextProfile::PrometheusV0_0_4, TextProfile::default()] {
+        calls.store(0, Ordering::Relaxed);
+        let mut output = String::new();
+        encode(&mut output, &registry, profile).unwrap();
+        assert!(output.contains("open_fds 7\n"), "{output}");
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+}
+
+#[test]
+fn encode_with_suppress_metadata_lines() {
+    let mut registry = Registry::default();
+    let counter = <Counter>::default();
+    counter.inc();
+    registry
+        .register_with_unit("requests", "Total requests", Unit::Seconds, counter)
+        .unwrap();
+
+    let options = TextProfile::PrometheusV0_0_4.with_suppress_metadata_lines(true);
+    let mut output = String::new();
+    encode_with_options(&mut output, &registry, options).unwrap();
+    assert_eq!(output, "requests_seconds 1\n");
+
+    // `# EOF` is still written by the OpenMetrics profiles
+    let options = TextProfile::default().with_suppress_metadata_lines(true);
+    let mut output = String::new();
+    encode_with_options(&mut output, &registry, options).unwrap();
+    assert_eq!(output, "requests_seconds_total 1\n# EOF\n");
+}
+
+#[test]
+fn encode_metric_family_matches_registry_encoding() {
+    let histogram = Histogram::new([0.1, 1.0]);
+    histogram.observe(0.5);
+    let requests = Family::<Vec<(&str, &str)>, Counter>::default();
+    requests.with_or_new(&vec![("method", "GET")], |counter| counter.inc());
+
+    for profile in [TextProfile::PrometheusV0_0_4, TextProfile::default()] {
+        let mut registry = Registry::default();
+        registry.register("latency", "Request latency", histogram.clone()).unwrap();
+        let mut expected = String::new();
+        encode(&mut expected, &registry, profile).unwrap();
+
+        let mut output = String::new();
+        encode_metric_family(&mut output, "latency", "Request latency", &histogram, profile)
+            .unwrap();
+        assert_eq!(output, expected);
+        assert!(output.contains("# HELP latency Request latency\n"), "{output}");
+        assert!(output.contains("# TYPE latency histogram\n"), "{output}");
+        assert!(output.contains("latency_bucket{le=\"1.0\"} 1\n"), "{output}");
+
+        let mut output = String::new();
+        encode_metric_family(&mut output, "requests", "Total requests", &requests, profile)
+            .unwrap();
+        let mut registry = Registry::default();
+        registry.register("requests", "Total requests", requests.clone()).unwrap();
+        let mut expected = String::new();
+        encode(&mut expected, &registry, profile).unwrap();
+        assert_eq!(output, expected);
+        assert!(output.contains("{method=\"GET\"} 1\n"), "{output}");
+    }
+
+    let mut output = String::new();
+    let err = encode_metric_family(&mut output, "", "help", &histogram, TextProfile::default())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Invalid);
+}
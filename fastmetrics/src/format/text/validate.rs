@@ -0,0 +1,156 @@
+//! Offline conformance checks for the text exposition format.
+
+use std::{borrow::Cow, collections::HashMap, fmt};
+
+use super::{
+    TextProfile,
+    config::ProfileConfig,
+    encoder::{collect_families, encode_family},
+    names::escape_metric_name,
+};
+use crate::{
+    error::{ErrorKind, Result},
+    raw::{MetricType, bucket::BUCKET_LABEL, quantile::QUANTILE_LABEL},
+    registry::{Registry, metric_label_names},
+};
+
+/// A conformance issue found by [`validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationWarning {
+    /// Fully qualified name of the metric family, before profile escaping.
+    pub metric_name: String,
+    /// Description of the issue.
+    pub issue: &'static str,
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.metric_name, self.issue)
+    }
+}
+
+/// Checks that the metrics of a [`Registry`] can be exposed in the text format of `profile`.
+///
+/// Unlike [`encode`](super::encode), this doesn't stop at the first issue: every metric family is
+/// checked for
+///
+/// - metric and label names that are not valid for the profile,
+/// - empty help texts,
+/// - metric names that already end with their unit, e.g. `latency_seconds` in seconds,
+/// - reserved label names, i.e. names starting with `__`, or `le`/`quantile` outside of
+///   histograms and summaries,
+/// - metric family and label names colliding after escaping,
+/// - metric types the profile doesn't support,
+///
+/// and all the findings are returned. An error is only returned if the metrics can't be encoded
+/// for other reasons.
+///
+/// # Examples
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     format::text::{self, TextProfile},
+/// #     metrics::counter::Counter,
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let mut registry = Registry::default();
+/// registry.register("http_requests", "", <Counter>::default())?;
+///
+/// let warnings = text::validate(&registry, TextProfile::default())?;
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].metric_name, "http_requests");
+/// assert_eq!(warnings[0].issue, "help text is empty");
+/// # Ok(())
+/// # }
+/// ```
+pub fn validate(registry: &Registry, profile: TextProfile) -> Result<Vec<ValidationWarning>> {
+    let _guard = crate::metrics::lazy_group::enter_scope();
+    let config = ProfileConfig::from(profile);
+
+    let mut families = Vec::new();
+    collect_families(registry, &mut families);
+
+    let mut warnings = Vec::new();
+    // mapping: escaped metric family name => canonical metric family name
+    let mut escaped_family_to_canonical = HashMap::<String, String>::new();
+    // mapping: emitted counter sample metric name => canonical metric family name
+    let mut counter_sample_to_canonical = HashMap::<String, String>::new();
+
+    for (name, registry, metadata, metric) in families {
+        let mut warn = |issue| {
+            warnings.push(ValidationWarning { metric_name: name.to_string(), issue });
+        };
+
+        if metadata.help().is_empty() {
+            warn("help text is empty");
+        }
+        if let Some(unit) = metadata.unit() {
+            if metadata.name().ends_with(&format!("_{}", unit.as_str())) {
+                warn("metric name already ends with its unit");
+            }
+        }
+
+        let Ok(escaped) = escape_metric_name(Cow::Borrowed(name.as_ref()), config.name_policy)
+        else {
+            warn("metric name is not valid for the text profile");
+            continue;
+        };
+        match escaped_family_to_canonical.get(escaped.as_ref()) {
+            Some(existing) if existing != name.as_ref() => {
+                warn("metric family names collide after escaping");
+            },
+            Some(_) => {},
+            None => {
+                escaped_family_to_canonical.insert(escaped.to_string(), name.to_string());
+            },
+        }
+        if config.append_counter_total_suffix && metadata.metric_type() == MetricType::Counter {
+            let sample_name = if name.ends_with("_total") {
+                escaped.to_string()
+            } else {
+                format!("{escaped}_total")
+            };
+            match counter_sample_to_canonical.get(&sample_name) {
+                Some(existing) if existing != name.as_ref() => {
+                    warn("counter sample names collide after suffix normalization");
+                },
+                Some(_) => {},
+                None => {
+                    counter_sample_to_canonical.insert(sample_name, name.to_string());
+                },
+            }
+        }
+
+        let mut output = String::new();
+        if let Err(err) = encode_family(&mut output, registry, metadata, metric, config) {
+            match err.kind() {
+                ErrorKind::Invalid => warn("label name is not valid for the text profile"),
+                ErrorKind::Duplicated => warn("label names collide after escaping"),
+                ErrorKind::Unsupported => warn("metric type is not supported by the text profile"),
+                _ => return Err(err),
+            }
+            continue;
+        }
+
+        let const_label_names = registry.constant_labels().iter().map(|(name, _)| name.to_string());
+        let mut label_names = metric_label_names(metric)?;
+        label_names.extend(const_label_names);
+        for label_name in label_names {
+            let metric_type = metadata.metric_type();
+            if label_name.starts_with("__") {
+                warn("label name is reserved");
+            } else if label_name == BUCKET_LABEL
+                && !matches!(metric_type, MetricType::Histogram | MetricType::GaugeHistogram)
+            {
+                warn("`le` label name is reserved for histograms");
+            } else if label_name == QUANTILE_LABEL && metric_type != MetricType::Summary {
+                warn("`quantile` label name is reserved for summaries");
+            }
+        }
+    }
+
+    Ok(warnings)
+}
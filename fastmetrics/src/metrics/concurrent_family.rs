@@ -0,0 +1,384 @@
+//! A metric family with sharded locking, for workloads inserting many label sets concurrently.
+//!
+//! See [`ConcurrentFamily`] for more details.
+
+use std::{
+    fmt::{self, Debug},
+    hash::{BuildHasher, Hash},
+    sync::Arc,
+};
+
+use dashmap::{DashMap, iter::Iter, mapref::multiple::RefMulti};
+
+use super::family::{HasCardinality, RandomState};
+use crate::{
+    encoder::{EncodeLabelSet, EncodeMetric, MetricEncoder},
+    error::Result,
+    raw::{LabelSetSchema, MetricLabelSet, MetricType, TypedMetric},
+};
+
+type MetricFactory<LS, M> = dyn Fn(&LS) -> M + Send + Sync + 'static;
+
+/// A collection of metrics that share the same name but have different label values, backed by
+/// a sharded concurrent map.
+///
+/// It provides the same core API as [`Family`](super::family::Family), but instead of a single
+/// `RwLock<HashMap>` the label sets are spread over several independently locked shards. Threads
+/// inserting distinct label sets at the same time therefore rarely block each other, which makes
+/// it a better fit for families with high-cardinality labels arriving concurrently (e.g. per
+/// tenant or per connection metrics). For families with a handful of label sets that are mostly
+/// read, [`Family`](super::family::Family) is usually as fast and supports more features (e.g.
+/// TTL based eviction).
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     metrics::{concurrent_family::ConcurrentFamily, counter::Counter},
+/// #     registry::Registry,
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let mut registry = Registry::default();
+///
+/// let requests = ConcurrentFamily::<Vec<(&'static str, String)>, Counter>::default();
+/// registry.register("requests", "Total requests by tenant", requests.clone())?;
+///
+/// std::thread::scope(|scope| {
+///     for tenant in 0..4 {
+///         let requests = &requests;
+///         scope.spawn(move || {
+///             let labels = vec![("tenant", tenant.to_string())];
+///             requests.with_or_new(&labels, |counter| counter.inc());
+///         });
+///     }
+/// });
+/// assert_eq!(requests.len(), 4);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ConcurrentFamily<LS, M, S = RandomState> {
+    // label set => metric points
+    metrics: Arc<DashMap<LS, M, S>>,
+    metric_factory: Arc<MetricFactory<LS, M>>,
+}
+
+impl<LS, M, S> Debug for ConcurrentFamily<LS, M, S>
+where
+    LS: Debug + Eq + Hash,
+    M: Debug,
+    S: BuildHasher + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConcurrentMetricFamily")
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+impl<LS, M, S> Default for ConcurrentFamily<LS, M, S>
+where
+    LS: Eq + Hash,
+    M: Default + 'static,
+    S: BuildHasher + Clone + Default,
+{
+    fn default() -> Self {
+        Self::new(M::default)
+    }
+}
+
+impl<LS, M, S> ConcurrentFamily<LS, M, S>
+where
+    LS: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Creates a new concurrent metric family with a custom metric factory.
+    ///
+    /// See [`Family::new`](super::family::Family::new) for more details.
+    pub fn new(metric_factory: impl Fn() -> M + Send + Sync + 'static) -> Self
+    where
+        S: Default,
+    {
+        Self::new_with_labels(move |_| metric_factory())
+    }
+
+    /// Creates a new concurrent metric family with a label-aware factory.
+    ///
+    /// See [`Family::new_with_labels`](super::family::Family::new_with_labels) for more details.
+    pub fn new_with_labels(metric_factory: impl Fn(&LS) -> M + Send + Sync + 'static) -> Self
+    where
+        S: Default,
+    {
+        Self {
+            metrics: Arc::new(DashMap::with_hasher(S::default())),
+            metric_factory: Arc::new(metric_factory),
+        }
+    }
+
+    /// Returns the number of label sets in the family.
+    ///
+    /// The shards are counted one after another, so the returned value is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    /// Returns `true` if no label set has been observed yet.
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    /// Returns `true` if the family holds a metric for `labels`.
+    pub fn contains(&self, labels: &LS) -> bool {
+        self.metrics.contains_key(labels)
+    }
+
+    /// Gets a reference to the metric with the specified labels and applies a function to it.
+    ///
+    /// Returns `None` if no metric exists for the given label set. The shard holding `labels`
+    /// is read-locked while `func` runs, so `func` must not insert into or remove from this
+    /// family.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::{concurrent_family::ConcurrentFamily, counter::Counter};
+    /// let requests = ConcurrentFamily::<u16, Counter>::default();
+    /// assert_eq!(requests.with(&200, |counter| counter.total()), None);
+    ///
+    /// requests.with_or_new(&200, |counter| counter.inc());
+    /// assert_eq!(requests.with(&200, |counter| counter.total()), Some(1));
+    /// ```
+    pub fn with<R, F>(&self, labels: &LS, func: F) -> Option<R>
+    where
+        F: FnOnce(&M) -> R,
+    {
+        self.metrics.get(labels).map(|metric| func(metric.value()))
+    }
+
+    /// Gets a reference to an existing metric or creates a new one using this family's metric
+    /// factory if it doesn't exist, then applies a function to it.
+    ///
+    /// Only the shard holding `labels` is locked, and the metric is constructed outside of the
+    /// lock. Like [`ConcurrentFamily::with`], `func` must not insert into or remove from this
+    /// family.
+    pub fn with_or_new<R, F>(&self, labels: &LS, func: F) -> R
+    where
+        LS: Clone,
+        F: FnOnce(&M) -> R,
+    {
+        if let Some(metric) = self.metrics.get(labels) {
+            return func(metric.value());
+        }
+
+        let metric = (self.metric_factory)(labels);
+        // another thread may have inserted the same labels in the meantime, then its metric wins
+        let entry = self.metrics.entry(labels.clone()).or_insert(metric).downgrade();
+        func(entry.value())
+    }
+
+    /// Removes the metric identified by `labels` from the family.
+    ///
+    /// Returns `true` if the metric was present. See
+    /// [`Family::remove`](super::family::Family::remove) for what happens to clones of the removed
+    /// metric.
+    pub fn remove(&self, labels: &LS) -> bool {
+        self.metrics.remove(labels).is_some()
+    }
+
+    /// Retains only the metrics for which `func` returns `true`, removing all others.
+    ///
+    /// The shards are write-locked one after another.
+    pub fn retain<F>(&self, mut func: F)
+    where
+        F: FnMut(&LS, &M) -> bool,
+    {
+        self.metrics.retain(|labels, metric| func(labels, metric));
+    }
+
+    /// Removes all metrics from the family.
+    pub fn clear(&self) {
+        self.metrics.clear();
+    }
+
+    /// Returns an iterator over the label sets and metrics of the family.
+    ///
+    /// Unlike [`Family::iter`](super::family::Family::iter), only the shard currently being
+    /// iterated is read-locked, so the iteration isn't a consistent snapshot: label sets inserted
+    /// or removed concurrently may or may not be visited.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::{concurrent_family::ConcurrentFamily, counter::Counter};
+    /// let requests = ConcurrentFamily::<u16, Counter>::default();
+    /// requests.with_or_new(&200, |counter| counter.inc_by(3));
+    /// requests.with_or_new(&404, |counter| counter.inc());
+    ///
+    /// let mut totals = requests
+    ///     .iter()
+    ///     .map(|entry| (*entry.labels(), entry.metric().total()))
+    ///     .collect::<Vec<_>>();
+    /// totals.sort();
+    /// assert_eq!(totals, [(200, 3), (404, 1)]);
+    /// ```
+    pub fn iter(&self) -> ConcurrentFamilyIter<'_, LS, M, S> {
+        ConcurrentFamilyIter { inner: self.metrics.iter() }
+    }
+}
+
+/// An iterator over the members of a [`ConcurrentFamily`], created by [`ConcurrentFamily::iter`].
+pub struct ConcurrentFamilyIter<'a, LS, M, S = RandomState> {
+    inner: Iter<'a, LS, M, S>,
+}
+
+impl<'a, LS, M, S> Iterator for ConcurrentFamilyIter<'a, LS, M, S>
+where
+    LS: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    type Item = ConcurrentFamilyEntry<'a, LS, M>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|inner| ConcurrentFamilyEntry { inner })
+    }
+}
+
+/// A label set and its metric, yielded by [`ConcurrentFamilyIter`].
+///
+/// The shard holding the entry stays read-locked until the entry is dropped.
+pub struct ConcurrentFamilyEntry<'a, LS, M> {
+    inner: RefMulti<'a, LS, M>,
+}
+
+impl<LS: Eq + Hash, M> ConcurrentFamilyEntry<'_, LS, M> {
+    /// Returns the label set of the entry.
+    pub fn labels(&self) -> &LS {
+        self.inner.key()
+    }
+
+    /// Returns the metric of the entry.
+    pub fn metric(&self) -> &M {
+        self.inner.value()
+    }
+}
+
+impl<LS, M: TypedMetric, S> TypedMetric for ConcurrentFamily<LS, M, S> {
+    const TYPE: MetricType = <M as TypedMetric>::TYPE;
+}
+
+impl<LS, M, S> MetricLabelSet for ConcurrentFamily<LS, M, S>
+where
+    LS: LabelSetSchema + Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    type LabelSet = LS;
+
+    fn into_cardinality(self: Arc<Self>) -> Option<Arc<dyn HasCardinality + Send + Sync>>
+    where
+        Self: Send + Sync + 'static,
+    {
+        Some(self)
+    }
+}
+
+impl<LS, M, S> EncodeMetric for ConcurrentFamily<LS, M, S>
+where
+    LS: EncodeLabelSet + Eq + Hash + Send + Sync,
+    M: EncodeMetric,
+    S: BuildHasher + Clone + Send + Sync,
+{
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        for entry in self.metrics.iter() {
+            encoder.encode(entry.key(), entry.value())?;
+        }
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+}
+
+impl<LS, M, S> HasCardinality for ConcurrentFamily<LS, M, S>
+where
+    LS: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    fn cardinality(&self) -> usize {
+        self.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{check_text_encoding, counter::Counter};
+
+    #[test]
+    fn test_concurrent_family_api() {
+        let family = ConcurrentFamily::<u16, Counter>::default();
+        assert!(family.is_empty());
+        assert_eq!(family.with(&200, |counter| counter.total()), None);
+
+        family.with_or_new(&200, |counter| counter.inc());
+        family.with_or_new(&200, |counter| counter.inc());
+        family.with_or_new(&404, |counter| counter.inc());
+        assert_eq!(family.len(), 2);
+        assert!(family.contains(&404));
+        assert_eq!(family.with(&200, |counter| counter.total()), Some(2));
+
+        assert!(family.remove(&404));
+        assert!(!family.remove(&404));
+        assert_eq!(family.iter().count(), 1);
+
+        family.with_or_new(&500, |counter| counter.inc());
+        family.retain(|status, _| *status != 500);
+        assert!(!family.contains(&500));
+
+        family.clear();
+        assert!(family.is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_family_with_or_new_from_many_threads() {
+        let family = ConcurrentFamily::<u32, Counter>::default();
+
+        std::thread::scope(|scope| {
+            for thread in 0..16 {
+                let family = &family;
+                scope.spawn(move || {
+                    for i in 0..100 {
+                        family.with_or_new(&(thread * 100 + i), |counter| counter.inc());
+                        family.with_or_new(&i, |counter| counter.inc());
+                    }
+                });
+            }
+        });
+
+        assert_eq!(family.len(), 1600);
+        let total = family.iter().map(|entry| entry.metric().total()).sum::<u64>();
+        assert_eq!(total, 3200);
+    }
+
+    #[test]
+    fn test_concurrent_family_encoding() {
+        check_text_encoding(
+            |registry| {
+                let requests = ConcurrentFamily::<Vec<(&'static str, u16)>, Counter>::default();
+                registry.register("requests", "Requests by status", requests.clone()).unwrap();
+                requests.with_or_new(&vec![("status", 200)], |counter| counter.inc_by(2));
+                requests.with_or_new(&vec![("status", 404)], |counter| counter.inc());
+
+                let empty = ConcurrentFamily::<Vec<(&'static str, u16)>, Counter>::default();
+                registry.register("empty", "Never observed", empty).unwrap();
+            },
+            |output| {
+                assert!(output.contains(r#"requests_total{status="200"} 2"#));
+                assert!(output.contains(r#"requests_total{status="404"} 1"#));
+                assert!(!output.contains("empty"));
+            },
+        );
+    }
+}
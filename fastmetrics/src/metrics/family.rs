@@ -5,10 +5,17 @@
 //! See [`Family`] for more details.
 
 use std::{
-    collections::{HashMap, hash_map::Entry},
+    collections::{
+        HashMap,
+        hash_map::{self, Entry},
+    },
     fmt::{self, Debug},
     hash::{BuildHasher, Hash},
-    sync::Arc,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
@@ -16,16 +23,18 @@ use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use crate::{
     encoder::{EncodeLabelSet, EncodeMetric, MetricEncoder},
     error::Result,
+    metrics::counter::Counter,
     raw::{LabelSetSchema, MetricLabelSet, MetricType, TypedMetric},
+    registry::Clock,
 };
 
 type MetricFactory<LS, M> = dyn Fn(&LS) -> M + Send + Sync + 'static;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "foldhash")] {
-        type RandomState = foldhash::fast::RandomState;
+        pub(crate) type RandomState = foldhash::fast::RandomState;
     } else {
-        type RandomState = std::hash::RandomState;
+        pub(crate) type RandomState = std::hash::RandomState;
     }
 }
 
@@ -91,8 +100,98 @@ cfg_if::cfg_if! {
 #[derive(Clone)]
 pub struct Family<LS, M, S = RandomState> {
     // label set => metric points
-    metrics: Arc<RwLock<HashMap<LS, M, S>>>,
+    metrics: Arc<RwLock<HashMap<LS, Member<M>, S>>>,
     metric_factory: Arc<MetricFactory<LS, M>>,
+    expiry: Option<Arc<Expiry>>,
+    limit: Option<Arc<CardinalityLimit<M>>>,
+}
+
+struct Member<M> {
+    metric: M,
+    // nanoseconds of the expiry clock, only maintained when the family has a TTL
+    last_access: AtomicU64,
+}
+
+impl<M: Debug> Debug for Member<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.metric.fmt(f)
+    }
+}
+
+struct Expiry {
+    ttl: Duration,
+    clock: Box<dyn Clock>,
+    // clock time of the last full sweep of expired members, in nanoseconds
+    last_sweep: AtomicU64,
+}
+
+impl Expiry {
+    // Insertions sweep the expired members at most once per `ttl / SWEEP_INTERVAL_DIVISOR`.
+    const SWEEP_INTERVAL_DIVISOR: u32 = 4;
+
+    fn now(&self) -> u64 {
+        u64::try_from(self.clock.now().as_nanos()).unwrap_or(u64::MAX)
+    }
+
+    fn is_expired<M>(&self, member: &Member<M>, now: u64) -> bool {
+        let idle = now.saturating_sub(member.last_access.load(Ordering::Relaxed));
+        Duration::from_nanos(idle) > self.ttl
+    }
+
+    fn is_sweep_due(&self, now: u64) -> bool {
+        let interval = self.ttl / Self::SWEEP_INTERVAL_DIVISOR;
+        let interval = u64::try_from(interval.as_nanos()).unwrap_or(u64::MAX);
+        now.saturating_sub(self.last_sweep.load(Ordering::Relaxed)) >= interval
+    }
+}
+
+/// Metric families reporting how many label sets they hold.
+///
+/// It's implemented by [`Family`] (and `ConcurrentFamily`), and used by
+/// [`Registry::cardinality_report`](crate::registry::Registry::cardinality_report).
+pub trait HasCardinality {
+    /// Returns the current number of label sets.
+    fn cardinality(&self) -> usize;
+
+    /// Returns the overflow counter of the cardinality limit of the family, if any.
+    ///
+    /// A [`Registry`](crate::registry::Registry) registers it next to the family, as
+    /// `<name>_cardinality_overflow`. See [`Family::with_max_entries`].
+    ///
+    /// By default, this method returns `None`.
+    fn cardinality_overflow(&self) -> Option<Counter> {
+        None
+    }
+}
+
+/// What a [`Family`] does when a new label set would exceed its limit, see
+/// [`Family::with_max_entries`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CardinalityPolicy {
+    /// Rejects the new label set.
+    ///
+    /// The function passed to [`Family::with_or_new`] is applied to a discard sink instead: a
+    /// single detached metric per family, created by the metric factory on the first rejection
+    /// and shared by all rejected label sets. It is never encoded, and its value mixes the
+    /// observations of every rejected caller, so it must not be relied on.
+    ///
+    /// The overflow counter is incremented on every rejected call, a rejected label set observed
+    /// `n` times counts `n` times.
+    Block,
+    /// Evicts the least recently used label set to make room for the new one.
+    ///
+    /// The overflow counter is incremented on every eviction.
+    DropOldest,
+}
+
+struct CardinalityLimit<M> {
+    max_entries: usize,
+    policy: CardinalityPolicy,
+    overflow: Counter,
+    // discard sink receiving the observations of all rejected label sets, never encoded
+    discarded: OnceLock<M>,
+    // starting point of the access times, only used by `CardinalityPolicy::DropOldest`
+    start: Instant,
 }
 
 impl<LS, M, S> Debug for Family<LS, M, S>
@@ -116,13 +215,80 @@ where
 }
 
 impl<LS, M, S> Family<LS, M, S> {
-    pub(crate) fn read(&self) -> RwLockReadGuard<'_, HashMap<LS, M, S>> {
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<LS, Member<M>, S>> {
         self.metrics.read()
     }
 
-    pub(crate) fn write(&self) -> RwLockWriteGuard<'_, HashMap<LS, M, S>> {
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<LS, Member<M>, S>> {
         self.metrics.write()
     }
+
+    // Returns the current access time, if access times are maintained by the family.
+    fn access_time(&self) -> Option<u64> {
+        match (&self.expiry, &self.limit) {
+            (Some(expiry), _) => Some(expiry.now()),
+            (None, Some(limit)) if limit.policy == CardinalityPolicy::DropOldest => {
+                Some(u64::try_from(limit.start.elapsed().as_nanos()).unwrap_or(u64::MAX))
+            },
+            _ => None,
+        }
+    }
+
+    fn new_member(&self, metric: M) -> Member<M> {
+        let now = self.access_time().unwrap_or(0);
+        Member { metric, last_access: AtomicU64::new(now) }
+    }
+
+    fn touch<'a>(&self, member: &'a Member<M>) -> Option<&'a M> {
+        if let Some(expiry) = &self.expiry {
+            let now = expiry.now();
+            if expiry.is_expired(member, now) {
+                return None;
+            }
+            member.last_access.store(now, Ordering::Relaxed);
+        } else if let Some(now) = self.access_time() {
+            member.last_access.store(now, Ordering::Relaxed);
+        }
+        Some(&member.metric)
+    }
+
+    fn evict_oldest(metrics: &mut HashMap<LS, Member<M>, S>)
+    where
+        LS: Clone + Eq + Hash,
+        S: BuildHasher,
+    {
+        let oldest = metrics
+            .iter()
+            .min_by_key(|(_, member)| member.last_access.load(Ordering::Relaxed))
+            .map(|(labels, _)| labels.clone());
+        if let Some(labels) = oldest {
+            metrics.remove(&labels);
+        }
+    }
+
+    // Returns whether a member has expired, evaluated at the time of the call.
+    fn expired_now(&self) -> impl Fn(&Member<M>) -> bool + '_ {
+        let now = self.expiry.as_deref().map(|expiry| (expiry, expiry.now()));
+        move |member| now.is_some_and(|(expiry, now)| expiry.is_expired(member, now))
+    }
+
+    fn evict_expired(&self, metrics: &mut HashMap<LS, Member<M>, S>) {
+        if let Some(expiry) = &self.expiry {
+            let now = expiry.now();
+            expiry.last_sweep.store(now, Ordering::Relaxed);
+            metrics.retain(|_, member| !expiry.is_expired(member, now));
+        }
+    }
+
+    // Evicts the expired members, unless the last sweep is too recent, so that inserting new
+    // label sets doesn't scan the whole family every time.
+    fn evict_expired_throttled(&self, metrics: &mut HashMap<LS, Member<M>, S>) {
+        if let Some(expiry) = &self.expiry {
+            if expiry.is_sweep_due(expiry.now()) {
+                self.evict_expired(metrics);
+            }
+        }
+    }
 }
 
 impl<LS, M, S> Family<LS, M, S> {
@@ -187,7 +353,8 @@ impl<LS, M, S> Family<LS, M, S> {
 
     /// Creates a new metric family with a label-aware factory.
     ///
-    /// This is useful for metric types whose constructor needs label values.
+    /// This is useful for metric types whose constructor needs label values, e.g. histograms whose
+    /// bucket boundaries depend on the labels.
     ///
     /// # Example
     ///
@@ -229,6 +396,7 @@ impl<LS, M, S> Family<LS, M, S> {
     /// # Ok(())
     /// # }
     /// ```
+    #[doc(alias = "with_factory")]
     pub fn new_with_labels(metric_factory: impl Fn(&LS) -> M + Send + Sync + 'static) -> Self
     where
         S: Default,
@@ -236,6 +404,154 @@ impl<LS, M, S> Family<LS, M, S> {
         Self {
             metrics: Arc::new(RwLock::new(HashMap::default())),
             metric_factory: Arc::new(metric_factory),
+            expiry: None,
+            limit: None,
+        }
+    }
+
+    /// Configures the family to evict metrics that haven't been accessed for longer than `ttl`.
+    ///
+    /// Every [`Family::with`] and [`Family::with_or_new`] call refreshes the last access time of
+    /// the metric. Expired metrics are hidden right away and evicted lazily, on the next encoding
+    /// or, at most once per quarter of `ttl`, when a new label set is inserted. This keeps
+    /// families with dynamic labels (e.g. job names) from growing unboundedly, without scanning
+    /// the whole family on every insertion.
+    ///
+    /// This should be configured before the family is cloned, existing clones are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// #
+    /// # use fastmetrics::metrics::{counter::Counter, family::Family};
+    /// let jobs = Family::<String, Counter>::default().with_ttl(Duration::from_secs(300));
+    /// assert_eq!(jobs.ttl(), Some(Duration::from_secs(300)));
+    /// ```
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        let start = Instant::now();
+        self.with_ttl_and_clock(ttl, move || start.elapsed())
+    }
+
+    /// Configures the family to evict metrics that haven't been accessed for longer than `ttl`,
+    /// using `clock` as the time source, see [`Family::with_ttl`] for details.
+    pub fn with_ttl_and_clock(mut self, ttl: Duration, clock: impl Clock + 'static) -> Self {
+        self.expiry =
+            Some(Arc::new(Expiry { ttl, clock: Box::new(clock), last_sweep: AtomicU64::new(0) }));
+        self
+    }
+
+    /// Limits the family to at most `max_entries` label sets.
+    ///
+    /// Unbounded label cardinality can exhaust the memory of the process and slow down every
+    /// scrape. When a new label set would exceed the limit, `policy` decides what happens, see
+    /// [`CardinalityPolicy`]. Either way a counter is incremented, once per rejected call or
+    /// eviction rather than once per distinct label set, and registering the family in a
+    /// [`Registry`](crate::registry::Registry) also registers this counter as
+    /// `<name>_cardinality_overflow`.
+    ///
+    /// Expired label sets (see [`Family::with_ttl`]) count towards the limit until they are
+    /// evicted. Like [`Family::with_ttl`], this should be configured before the family is cloned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{
+    /// #     error::Result,
+    /// #     metrics::{
+    /// #         counter::Counter,
+    /// #         family::{CardinalityPolicy, Family},
+    /// #     },
+    /// #     registry::Registry,
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    ///
+    /// let requests = Family::<Vec<(&'static str, &'static str)>, Counter>::default()
+    ///     .with_max_entries(2, CardinalityPolicy::Block);
+    /// // also registers `requests_cardinality_overflow`
+    /// registry.register("requests", "Total requests by path", requests.clone())?;
+    ///
+    /// for path in ["/", "/users", "/orders"] {
+    ///     requests.with_or_new(&vec![("path", path)], |counter| counter.inc());
+    /// }
+    /// assert_eq!(requests.len(), 2);
+    /// assert!(!requests.contains(&vec![("path", "/orders")]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_max_entries(mut self, max_entries: usize, policy: CardinalityPolicy) -> Self {
+        self.limit = Some(Arc::new(CardinalityLimit {
+            max_entries,
+            policy,
+            overflow: Counter::default(),
+            discarded: OnceLock::new(),
+            start: Instant::now(),
+        }));
+        self
+    }
+
+    /// Returns the maximum number of label sets, if configured via [`Family::with_max_entries`].
+    pub fn max_entries(&self) -> Option<usize> {
+        self.limit.as_ref().map(|limit| limit.max_entries)
+    }
+
+    /// Returns how many calls were rejected or caused an eviction because of the limit configured
+    /// via [`Family::with_max_entries`], see [`CardinalityPolicy`].
+    pub fn cardinality_overflows(&self) -> Option<u64> {
+        self.limit.as_ref().map(|limit| limit.overflow.total())
+    }
+
+    /// Returns the time-to-live of idle metrics, if configured via [`Family::with_ttl`].
+    pub fn ttl(&self) -> Option<Duration> {
+        self.expiry.as_ref().map(|expiry| expiry.ttl)
+    }
+
+    /// Returns the number of label sets in the family, not counting expired ones.
+    ///
+    /// The returned value is a snapshot, concurrent insertions and removals may make it stale
+    /// immediately.
+    pub fn len(&self) -> usize {
+        let guard = self.read();
+        match &self.expiry {
+            Some(expiry) => {
+                let now = expiry.now();
+                guard.values().filter(|member| !expiry.is_expired(member, now)).count()
+            },
+            None => guard.len(),
+        }
+    }
+
+    /// Returns `true` if no label set has been observed yet (or all of them expired).
+    ///
+    /// Empty families are skipped entirely by the encoders, without any `# TYPE` / `# HELP`
+    /// lines. Like [`Family::len`], the returned value is a snapshot.
+    pub fn is_empty(&self) -> bool {
+        let guard = self.read();
+        match &self.expiry {
+            Some(expiry) => {
+                let now = expiry.now();
+                guard.values().all(|member| expiry.is_expired(member, now))
+            },
+            None => guard.is_empty(),
+        }
+    }
+
+    /// Returns `true` if the family holds a (non-expired) metric for `labels`.
+    ///
+    /// Unlike [`Family::with`], this doesn't refresh the idle time of the metric. Like
+    /// [`Family::len`], the returned value is a snapshot.
+    pub fn contains(&self, labels: &LS) -> bool
+    where
+        LS: Eq + Hash,
+        S: BuildHasher,
+    {
+        let guard = self.read();
+        match (guard.get(labels), &self.expiry) {
+            (Some(member), Some(expiry)) => !expiry.is_expired(member, expiry.now()),
+            (Some(_), None) => true,
+            (None, _) => false,
         }
     }
 
@@ -307,7 +623,7 @@ impl<LS, M, S> Family<LS, M, S> {
         S: BuildHasher,
     {
         let guard = self.read();
-        guard.get(labels).map(func)
+        guard.get(labels).and_then(|member| self.touch(member)).map(func)
     }
 
     /// Gets a reference to an existing metric or creates a new one using this family's metric
@@ -380,7 +696,7 @@ impl<LS, M, S> Family<LS, M, S> {
         S: BuildHasher,
     {
         let read_guard = self.read();
-        if let Some(metric) = read_guard.get(labels) {
+        if let Some(metric) = read_guard.get(labels).and_then(|member| self.touch(member)) {
             return func(metric);
         }
         drop(read_guard);
@@ -400,11 +716,40 @@ impl<LS, M, S> Family<LS, M, S> {
             // Acquire the write lock only for entry inspection/insertion; construction happens
             // after dropping it.
             let mut write_guard = self.write();
+            self.evict_expired_throttled(&mut write_guard);
+            if let Some(limit) = &self.limit {
+                if write_guard.len() >= limit.max_entries && !write_guard.contains_key(labels) {
+                    match limit.policy {
+                        CardinalityPolicy::Block => {
+                            drop(write_guard);
+                            limit.overflow.inc();
+                            let discarded = limit.discarded.get_or_init(|| {
+                                new_metric.take().unwrap_or_else(|| (self.metric_factory)(labels))
+                            });
+                            return func(discarded);
+                        },
+                        // evict only once the new metric has been constructed and is inserted
+                        CardinalityPolicy::DropOldest if new_metric.is_some() => {
+                            limit.overflow.inc();
+                            Self::evict_oldest(&mut write_guard);
+                        },
+                        CardinalityPolicy::DropOldest => {},
+                    }
+                }
+            }
             match write_guard.entry(labels.clone()) {
-                Entry::Occupied(entry) => return func(entry.get()),
-                Entry::Vacant(entry) => {
+                Entry::Occupied(entry) if !self.expired_now()(entry.get()) => {
+                    let member = entry.get();
+                    if let Some(now) = self.access_time() {
+                        member.last_access.store(now, Ordering::Relaxed);
+                    }
+                    return func(&member.metric);
+                },
+                // a vacant entry, or an expired member which is replaced by a fresh metric
+                entry => {
                     if let Some(metric) = new_metric.take() {
-                        return func(entry.insert(metric));
+                        let member = entry.insert_entry(self.new_member(metric)).into_mut();
+                        return func(&member.metric);
                     } else {
                         drop(write_guard);
                         // Construct the metric outside the lock so expensive constructors cannot
@@ -415,6 +760,163 @@ impl<LS, M, S> Family<LS, M, S> {
             }
         }
     }
+
+    /// Removes the metric identified by `labels` from the family.
+    ///
+    /// Returns `true` if the metric was present (and not expired). Clones of the removed metric
+    /// keep working, but they are no longer encoded; a later [`Family::with_or_new`] with the same
+    /// labels creates a fresh metric.
+    pub fn remove(&self, labels: &LS) -> bool
+    where
+        LS: Eq + Hash,
+        S: BuildHasher,
+    {
+        let removed = self.write().remove(labels);
+        removed.is_some_and(|member| !self.expired_now()(&member))
+    }
+
+    /// Retains only the metrics for which `func` returns `true`, removing all others.
+    ///
+    /// The write lock is held for the whole pass, so no metric can be removed while another
+    /// thread is accessing it through this family. See [`Family::remove`] for what happens to
+    /// clones of the removed metrics. Expired metrics are removed without calling `func`.
+    pub fn retain<F>(&self, mut func: F)
+    where
+        F: FnMut(&LS, &M) -> bool,
+    {
+        let is_expired = self.expired_now();
+        self.write()
+            .retain(|labels, member| !is_expired(member) && func(labels, &member.metric));
+    }
+
+    /// Locks the family for reading and returns a guard to iterate over its label sets and
+    /// metrics.
+    ///
+    /// The iteration is consistent: no label set can be inserted or removed while the guard is
+    /// alive, so the guard should be dropped quickly. Expired metrics are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::{counter::Counter, family::Family};
+    /// let requests = Family::<u16, Counter>::default();
+    /// requests.with_or_new(&200, |counter| counter.inc_by(3));
+    /// requests.with_or_new(&404, |counter| counter.inc());
+    ///
+    /// let entries = requests.iter();
+    /// let mut totals = entries
+    ///     .iter()
+    ///     .map(|(status, counter)| (*status, counter.total()))
+    ///     .collect::<Vec<_>>();
+    /// totals.sort();
+    /// assert_eq!(totals, [(200, 3), (404, 1)]);
+    /// ```
+    pub fn iter(&self) -> FamilyIter<'_, LS, M, S> {
+        let now = self.expiry.as_ref().map(|expiry| expiry.now());
+        FamilyIter { guard: self.read(), expiry: self.expiry.as_deref(), now: now.unwrap_or(0) }
+    }
+
+    /// Removes all metrics from the family in a single critical section, returning the number
+    /// of removed label sets, not counting expired ones.
+    ///
+    /// The family is then encoded as empty until a label set is observed again, see
+    /// [`Family::remove`] for what happens to clones of the removed metrics.
+    pub fn clear(&self) -> usize {
+        let is_expired = self.expired_now();
+        let mut guard = self.write();
+        let removed = guard.values().filter(|member| !is_expired(member)).count();
+        guard.clear();
+        removed
+    }
+
+    /// Applies a function to every label set and metric in the family, collecting the results.
+    ///
+    /// The read lock is acquired once for the whole pass, so `func` should be cheap and must not
+    /// call back into this family to create new entries. Members are visited in unspecified
+    /// order, and expired ones are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::{counter::Counter, family::Family};
+    /// let requests = Family::<u16, Counter>::default();
+    /// requests.with_or_new(&200, |counter| counter.inc_by(3));
+    /// requests.with_or_new(&404, |counter| counter.inc());
+    ///
+    /// let total: u64 = requests.observe_all(|_, counter| counter.total()).into_iter().sum();
+    /// assert_eq!(total, 4);
+    /// ```
+    pub fn observe_all<T, F>(&self, mut func: F) -> Vec<T>
+    where
+        F: FnMut(&LS, &M) -> T,
+    {
+        let entries = self.iter();
+        entries.iter().map(|(labels, metric)| func(labels, metric)).collect()
+    }
+
+    /// Applies a function to every label set and metric in the family with mutable access to
+    /// the metrics.
+    ///
+    /// The write lock is held for the whole pass, blocking all other readers and writers of this
+    /// family until `func` has been applied to every member. Expired members are skipped.
+    pub fn observe_all_mut<F>(&self, mut func: F)
+    where
+        F: FnMut(&LS, &mut M),
+    {
+        let is_expired = self.expired_now();
+        self.write()
+            .iter_mut()
+            .filter(|(_, member)| !is_expired(member))
+            .for_each(|(labels, member)| func(labels, &mut member.metric));
+    }
+}
+
+/// A read guard over the members of a [`Family`], returned by [`Family::iter`].
+///
+/// Iterate over it by reference (or with [`FamilyIter::iter`]), the family stays read-locked
+/// until the guard is dropped.
+pub struct FamilyIter<'a, LS, M, S = RandomState> {
+    guard: RwLockReadGuard<'a, HashMap<LS, Member<M>, S>>,
+    expiry: Option<&'a Expiry>,
+    now: u64,
+}
+
+impl<LS, M, S> FamilyIter<'_, LS, M, S> {
+    /// Returns an iterator over the label sets and metrics of the family.
+    pub fn iter(&self) -> Entries<'_, LS, M> {
+        Entries { inner: self.guard.iter(), expiry: self.expiry, now: self.now }
+    }
+}
+
+impl<'g, LS, M, S> IntoIterator for &'g FamilyIter<'_, LS, M, S> {
+    type Item = (&'g LS, &'g M);
+    type IntoIter = Entries<'g, LS, M>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the label sets and metrics of a [`Family`], created by [`FamilyIter::iter`].
+pub struct Entries<'a, LS, M> {
+    inner: hash_map::Iter<'a, LS, Member<M>>,
+    expiry: Option<&'a Expiry>,
+    now: u64,
+}
+
+impl<'a, LS, M> Iterator for Entries<'a, LS, M> {
+    type Item = (&'a LS, &'a M);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().find_map(|(labels, member)| match self.expiry {
+            Some(expiry) if expiry.is_expired(member, self.now) => None,
+            _ => Some((labels, &member.metric)),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
 }
 
 impl<LS, M: TypedMetric, S> TypedMetric for Family<LS, M, S> {
@@ -423,6 +925,13 @@ impl<LS, M: TypedMetric, S> TypedMetric for Family<LS, M, S> {
 
 impl<LS: LabelSetSchema, M, S> MetricLabelSet for Family<LS, M, S> {
     type LabelSet = LS;
+
+    fn into_cardinality(self: Arc<Self>) -> Option<Arc<dyn HasCardinality + Send + Sync>>
+    where
+        Self: Send + Sync + 'static,
+    {
+        Some(self)
+    }
 }
 
 impl<LS, M, S> EncodeMetric for Family<LS, M, S>
@@ -432,20 +941,39 @@ where
     S: Send + Sync,
 {
     fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
-        let guard = self.read();
-        for (labels, metric) in guard.iter() {
-            encoder.encode(labels, metric)?;
+        let guard = match self.expiry {
+            Some(_) => {
+                let mut guard = self.write();
+                self.evict_expired(&mut guard);
+                RwLockWriteGuard::downgrade(guard)
+            },
+            None => self.read(),
+        };
+        for (labels, member) in guard.iter() {
+            encoder.encode(labels, &member.metric)?;
         }
         Ok(())
     }
 
     fn is_empty(&self) -> bool {
-        self.read().is_empty()
+        Family::is_empty(self)
+    }
+}
+
+impl<LS, M, S> HasCardinality for Family<LS, M, S> {
+    fn cardinality(&self) -> usize {
+        self.len()
+    }
+
+    fn cardinality_overflow(&self) -> Option<Counter> {
+        self.limit.as_ref().map(|limit| limit.overflow.clone())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use super::*;
     use crate::{
         encoder::{EncodeLabelSet, EncodeLabelValue, LabelEncoder, LabelSetEncoder},
@@ -454,6 +982,7 @@ mod tests {
             counter::{Counter, LazyCounter},
             histogram::{Histogram, exponential_buckets},
         },
+        registry::{ManualClock, Registry},
     };
 
     #[derive(Clone, PartialEq, Eq, Hash)]
@@ -589,8 +1118,14 @@ mod tests {
                     .register("http_requests", "Total HTTP requests", http_requests.clone())
                     .unwrap();
 
+                assert!(http_requests.is_empty());
+                assert_eq!(http_requests.len(), 0);
                 let labels = Labels { method: Method::Get, status: 200, error: None };
+                assert!(!http_requests.contains(&labels));
                 http_requests.with_or_new(&labels, |_| {});
+                assert!(!http_requests.is_empty());
+                assert_eq!(http_requests.len(), 1);
+                assert!(http_requests.contains(&labels));
             },
             |output| {
                 assert!(output.contains("# TYPE http_requests counter"));
@@ -600,6 +1135,237 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_remove_metric_from_family() {
+        let get = Labels { method: Method::Get, status: 200, error: None };
+        let put = Labels { method: Method::Put, status: 200, error: None };
+
+        check_text_encoding(
+            |registry| {
+                let http_requests = Family::<Labels, Counter>::default();
+                registry
+                    .register("http_requests", "Total HTTP requests", http_requests.clone())
+                    .unwrap();
+
+                http_requests.with_or_new(&get, |metric| metric.inc_by(2));
+                http_requests.with_or_new(&put, |metric| metric.inc());
+
+                let removed = http_requests.with(&get, |metric| metric.clone()).unwrap();
+                assert!(http_requests.remove(&get));
+                assert!(!http_requests.remove(&get));
+                assert_eq!(http_requests.with(&get, |metric| metric.total()), None);
+
+                // the removed metric is still usable, but is detached from the family
+                removed.inc();
+                assert_eq!(removed.total(), 3);
+            },
+            |output| {
+                assert!(!output.contains(r#"method="GET""#));
+                assert!(output.contains(r#"http_requests_total{method="PUT",status="200"} 1"#));
+            },
+        );
+
+        let http_requests = Family::<Labels, Counter>::default();
+        http_requests.with_or_new(&get, |metric| metric.inc());
+        assert!(http_requests.remove(&get));
+        assert_eq!(http_requests.with_or_new(&get, |metric| metric.total()), 0);
+    }
+
+    #[test]
+    fn test_clear_family() {
+        let get = Labels { method: Method::Get, status: 200, error: None };
+        let put = Labels { method: Method::Put, status: 200, error: None };
+
+        let http_requests = Family::<Labels, Counter>::default();
+        check_text_encoding(
+            |registry| {
+                registry
+                    .register("http_requests", "Total HTTP requests", http_requests.clone())
+                    .unwrap();
+
+                http_requests.with_or_new(&get, |metric| metric.inc_by(2));
+                http_requests.with_or_new(&put, |metric| metric.inc());
+
+                assert_eq!(http_requests.clear(), 2);
+                assert_eq!(http_requests.clear(), 0);
+                assert!(http_requests.is_empty());
+            },
+            |output| {
+                assert_eq!(output, "# EOF\n");
+            },
+        );
+
+        // cleared label sets start from scratch
+        assert_eq!(http_requests.with_or_new(&get, |metric| metric.total()), 0);
+        assert_eq!(http_requests.len(), 1);
+    }
+
+    #[test]
+    fn test_retain_family() {
+        check_text_encoding(
+            |registry| {
+                let http_requests = Family::<Labels, Counter>::default();
+                registry
+                    .register("http_requests", "Total HTTP requests", http_requests.clone())
+                    .unwrap();
+
+                for (method, status) in [
+                    (Method::Get, 200),
+                    (Method::Get, 404),
+                    (Method::Put, 200),
+                    (Method::Put, 500),
+                    (Method::Get, 500),
+                ] {
+                    let labels = Labels { method, status, error: None };
+                    http_requests.with_or_new(&labels, |metric| metric.inc());
+                }
+
+                http_requests.retain(|labels, _| labels.method == Method::Get);
+                assert_eq!(http_requests.len(), 3);
+            },
+            |output| {
+                assert!(!output.contains(r#"method="PUT""#), "{output}");
+                for status in [200, 404, 500] {
+                    let sample =
+                        format!(r#"http_requests_total{{method="GET",status="{status}"}} 1"#);
+                    assert!(output.contains(&sample), "{output}");
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn test_iter_family() {
+        let http_requests = Family::<Labels, Counter>::default();
+        assert_eq!(http_requests.iter().iter().count(), 0);
+
+        let get = Labels { method: Method::Get, status: 200, error: None };
+        let put = Labels { method: Method::Put, status: 404, error: Some(true) };
+        http_requests.with_or_new(&get, |metric| metric.inc_by(2));
+        http_requests.with_or_new(&put, |metric| metric.inc());
+
+        let entries = http_requests.iter();
+        let mut observed = Vec::new();
+        for (labels, metric) in &entries {
+            observed.push((labels.status, labels.error, metric.total()));
+        }
+        observed.sort();
+        assert_eq!(observed, [(200, None, 2), (404, Some(true), 1)]);
+    }
+
+    #[test]
+    fn test_family_ttl_evicts_idle_metrics() {
+        let clock = ManualClock::default();
+        let advance = |millis: u64| clock.advance_by(Duration::from_millis(millis));
+        let http_requests = Family::<Labels, Counter>::default()
+            .with_ttl_and_clock(Duration::from_millis(100), clock.clone());
+
+        let mut registry = Registry::default();
+        registry
+            .register("http_requests", "Total HTTP requests", http_requests.clone())
+            .unwrap();
+        let encode = |registry: &Registry| {
+            let mut output = String::new();
+            crate::format::text::encode(&mut output, registry, Default::default()).unwrap();
+            output
+        };
+
+        let get = Labels { method: Method::Get, status: 200, error: None };
+        let put = Labels { method: Method::Put, status: 200, error: None };
+        http_requests.with_or_new(&get, |metric| metric.inc());
+        http_requests.with_or_new(&put, |metric| metric.inc());
+
+        advance(60);
+        // accessing `get` refreshes it
+        http_requests.with(&get, |metric| metric.inc());
+        advance(60);
+
+        let output = encode(&registry);
+        assert!(output.contains(r#"http_requests_total{method="GET",status="200"} 2"#));
+        assert!(!output.contains(r#"method="PUT""#));
+        assert_eq!(http_requests.with(&put, |metric| metric.total()), None);
+
+        assert_eq!(http_requests.len(), 1);
+        assert!(http_requests.contains(&get));
+        advance(101);
+        assert!(!http_requests.contains(&get));
+        assert!(http_requests.is_empty());
+        assert_eq!(http_requests.len(), 0);
+        assert_eq!(encode(&registry), "# EOF\n");
+
+        // expired label sets start from scratch
+        assert_eq!(http_requests.with_or_new(&get, |metric| metric.total()), 0);
+    }
+
+    #[test]
+    fn test_family_ttl_applies_to_bulk_operations() {
+        let clock = ManualClock::default();
+        let family = Family::<u16, Counter>::default()
+            .with_ttl_and_clock(Duration::from_secs(10), clock.clone());
+        let populate = || {
+            family.with_or_new(&200, |counter| counter.inc());
+            clock.advance_by(Duration::from_secs(6));
+            family.with_or_new(&404, |counter| counter.inc());
+            // `200` expires, `404` doesn't
+            clock.advance_by(Duration::from_secs(6));
+        };
+
+        populate();
+        assert_eq!(family.observe_all(|status, _| *status), [404]);
+        let mut visited = Vec::new();
+        family.observe_all_mut(|status, _| visited.push(*status));
+        assert_eq!(visited, [404]);
+
+        populate();
+        let mut visited = Vec::new();
+        family.retain(|status, _| {
+            visited.push(*status);
+            true
+        });
+        assert_eq!(visited, [404]);
+
+        populate();
+        assert!(!family.remove(&200));
+        assert!(family.remove(&404));
+
+        populate();
+        assert_eq!(family.clear(), 1);
+    }
+
+    #[test]
+    fn test_family_ttl_sweeps_on_insertion_at_most_once_per_quarter_ttl() {
+        let clock = ManualClock::default();
+        let advance = |secs: u64| clock.advance_by(Duration::from_secs(secs));
+        let family = Family::<u16, Counter>::default()
+            .with_ttl_and_clock(Duration::from_secs(60), clock.clone());
+        let stored = || family.read().len();
+
+        family.with_or_new(&1, |counter| counter.inc());
+        advance(50);
+        // sweeps, but nothing has expired yet
+        family.with_or_new(&2, |counter| counter.inc());
+        assert_eq!(stored(), 2);
+
+        advance(11);
+        // `1` has expired, but the last sweep was only 11s ago
+        family.with_or_new(&3, |counter| counter.inc());
+        assert_eq!(stored(), 3);
+        assert_eq!(family.len(), 2);
+        assert!(!family.contains(&1));
+
+        advance(4);
+        family.with_or_new(&4, |counter| counter.inc());
+        assert_eq!(stored(), 3);
+        assert_eq!(family.len(), 3);
+
+        // existing label sets never sweep
+        advance(60);
+        family.with(&4, |counter| counter.inc());
+        family.with_or_new(&4, |counter| counter.inc());
+        assert_eq!(stored(), 3);
+        assert_eq!(family.len(), 1);
+    }
+
     #[test]
     fn test_new_uses_label_aware_factory() {
         let family = Family::<Labels, LazyCounter<u64>>::new_with_labels(|labels| {
@@ -630,4 +1396,155 @@ mod tests {
 
         assert_eq!(family.with(&labels_get, |counter| counter.fetch()), Some(1_200_u64));
     }
+
+    #[test]
+    fn test_new_with_labels_uses_label_dependent_buckets() {
+        let family = Family::<Labels, Histogram>::new_with_labels(|labels| match labels.method {
+            Method::Get => Histogram::new([0.1, 0.5, 1.0]),
+            Method::Put => Histogram::new([1_024.0, 1_048_576.0]),
+        });
+
+        let get = Labels { method: Method::Get, status: 200, error: None };
+        let put = Labels { method: Method::Put, status: 200, error: None };
+        family.with_or_new(&get, |histogram| histogram.observe(0.2));
+        family.with_or_new(&put, |histogram| histogram.observe(4_096.0));
+
+        let get_bounds = family.with(&get, |histogram| histogram.config()).unwrap();
+        let put_bounds = family.with(&put, |histogram| histogram.config()).unwrap();
+        assert_eq!(get_bounds.upper_bounds(), [0.1, 0.5, 1.0, f64::INFINITY]);
+        assert_eq!(put_bounds.upper_bounds(), [1_024.0, 1_048_576.0, f64::INFINITY]);
+
+        // the default constructor keeps using `Default::default`
+        let family = Family::<Labels, Counter>::default();
+        assert_eq!(family.with_or_new(&get, |counter| counter.total()), 0);
+    }
+
+    #[test]
+    fn test_observe_all_visits_every_member_once() {
+        let family = Family::<Labels, Counter>::default();
+        let all_labels = [
+            Labels { method: Method::Get, status: 200, error: None },
+            Labels { method: Method::Get, status: 404, error: Some(true) },
+            Labels { method: Method::Put, status: 200, error: None },
+        ];
+        for (i, labels) in all_labels.iter().enumerate() {
+            family.with_or_new(labels, |counter| counter.inc_by(i as u64 + 1));
+        }
+
+        let mut visited = family.observe_all(|labels, counter| (labels.clone(), counter.total()));
+        visited.sort_by_key(|(_, total)| *total);
+        assert_eq!(visited.len(), 3);
+        for (i, (labels, total)) in visited.into_iter().enumerate() {
+            assert!(labels == all_labels[i]);
+            assert_eq!(total, i as u64 + 1);
+        }
+
+        let mut visits = 0;
+        family.observe_all_mut(|_, counter| {
+            visits += 1;
+            *counter = Counter::default();
+        });
+        assert_eq!(visits, 3);
+        assert_eq!(family.observe_all(|_, counter| counter.total()), vec![0; 3]);
+    }
+
+    #[test]
+    fn test_family_with_dynamic_label_sets() {
+        check_text_encoding(
+            |registry| {
+                let requests = Family::<BTreeMap<String, String>, Counter>::default();
+                registry
+                    .register("requests", "Requests by dynamic labels", requests.clone())
+                    .unwrap();
+
+                let labels = BTreeMap::from([
+                    ("route".to_owned(), "/users".to_owned()),
+                    ("method".to_owned(), "GET".to_owned()),
+                ]);
+                requests.with_or_new(&labels, |counter| counter.inc());
+                requests.with_or_new(&BTreeMap::new(), |counter| counter.inc_by(2));
+
+                let pairs = Family::<Vec<(&'static str, u16)>, Counter>::default();
+                registry.register("responses", "Responses by status", pairs.clone()).unwrap();
+                pairs.with_or_new(&vec![("status", 200)], |counter| counter.inc());
+            },
+            |output| {
+                assert!(output.contains(r#"requests_total{method="GET",route="/users"} 1"#));
+                assert!(output.contains("requests_total 2\n"));
+                assert!(output.contains(r#"responses_total{status="200"} 1"#));
+            },
+        );
+    }
+
+    #[test]
+    fn test_family_cardinality_limit_blocks_new_label_sets() {
+        check_text_encoding(
+            |registry| {
+                let requests = Family::<Vec<(&'static str, u16)>, Counter>::default()
+                    .with_max_entries(10, CardinalityPolicy::Block);
+                registry.register("requests", "Requests by id", requests.clone()).unwrap();
+
+                for id in 0..11 {
+                    requests.with_or_new(&vec![("id", id)], |counter| counter.inc());
+                }
+                assert_eq!(requests.len(), 10);
+                assert_eq!(requests.max_entries(), Some(10));
+                assert!(!requests.contains(&vec![("id", 10)]));
+                // existing label sets are still updated
+                requests.with_or_new(&vec![("id", 0)], |counter| counter.inc());
+                assert_eq!(requests.with(&vec![("id", 0)], |counter| counter.total()), Some(2));
+                // rejected label sets share the discard sink, every rejected call is counted
+                let sink = requests.with_or_new(&vec![("id", 11)], |counter| {
+                    counter.inc();
+                    counter.total()
+                });
+                assert_eq!(sink, 2);
+                assert_eq!(requests.cardinality_overflows(), Some(2));
+            },
+            |output| {
+                assert_eq!(output.matches("requests_total{").count(), 10);
+                assert!(output.contains("requests_cardinality_overflow_total 2\n"));
+            },
+        );
+    }
+
+    #[test]
+    fn test_family_cardinality_limit_drops_oldest_label_sets() {
+        // access times come from the expiry clock when the family has a TTL
+        let clock = ManualClock::default();
+        let requests = Family::<u16, Counter>::default()
+            .with_ttl_and_clock(Duration::from_secs(3600), clock.clone())
+            .with_max_entries(2, CardinalityPolicy::DropOldest);
+
+        requests.with_or_new(&1, |counter| counter.inc());
+        requests.with_or_new(&2, |counter| counter.inc());
+        clock.advance_by(Duration::from_secs(1));
+        // refresh `1`, so `2` becomes the least recently used label set
+        requests.with(&1, |counter| counter.inc());
+        requests.with_or_new(&3, |counter| counter.inc());
+
+        assert_eq!(requests.len(), 2);
+        assert!(requests.contains(&1));
+        assert!(!requests.contains(&2));
+        assert!(requests.contains(&3));
+        assert_eq!(requests.cardinality_overflows(), Some(1));
+    }
+
+    #[test]
+    fn test_family_cardinality_overflow_registration_conflict() {
+        let mut registry = Registry::default();
+        registry
+            .register("requests_cardinality_overflow", "", <Counter>::default())
+            .unwrap();
+
+        let requests = Family::<Vec<(&'static str, u16)>, Counter>::default()
+            .with_max_entries(2, CardinalityPolicy::Block);
+        let err = registry
+            .register("requests", "Requests by id", requests)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::Duplicated);
+        // the family registration has been rolled back
+        assert_eq!(registry.all_metrics().count(), 1);
+    }
 }
@@ -0,0 +1,33 @@
+//! Shared internal storage for the exemplars of `Counter` and `Histogram`.
+
+use std::sync::OnceLock;
+
+use parking_lot::Mutex;
+
+/// Exemplar storage which is only allocated once the first exemplar is recorded.
+///
+/// Metrics embed it in their shared state, so clones see the same exemplars, while metrics which
+/// never record an exemplar pay neither an allocation nor a lock when encoding.
+pub(crate) struct ExemplarSlot<T> {
+    inner: OnceLock<Box<Mutex<T>>>,
+}
+
+impl<T> Default for ExemplarSlot<T> {
+    fn default() -> Self {
+        Self { inner: OnceLock::new() }
+    }
+}
+
+impl<T> ExemplarSlot<T> {
+    /// Returns the storage, or `None` if no exemplar has been recorded yet.
+    #[inline]
+    pub(crate) fn get(&self) -> Option<&Mutex<T>> {
+        self.inner.get().map(|inner| &**inner)
+    }
+
+    /// Returns the storage, allocating it with `init` first if necessary.
+    #[inline]
+    pub(crate) fn get_or_init(&self, init: impl FnOnce() -> T) -> &Mutex<T> {
+        self.inner.get_or_init(|| Box::new(Mutex::new(init())))
+    }
+}
@@ -3,10 +3,18 @@
 //! It exists to reduce duplication between `Histogram` and `GaugeHistogram`
 //! while keeping their externally-visible semantics intact.
 
-use std::sync::atomic::AtomicU64;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use parking_lot::Mutex;
 
-use crate::raw::Atomic;
 pub use crate::raw::bucket::Bucket;
+use crate::{
+    metrics::internal::exemplar::ExemplarSlot,
+    raw::{Atomic, exemplar::Exemplar},
+};
 
 /// Controls which bucket bounds are accepted.
 #[derive(Clone, Copy, Debug)]
@@ -17,6 +25,35 @@ pub enum BoundsFilter {
     RejectNegative,
 }
 
+/// Filters, sorts and dedups the bucket bounds, appending the `+Inf` bound if missing.
+pub fn normalize_bounds(buckets: impl IntoIterator<Item = f64>, filter: BoundsFilter) -> Vec<f64> {
+    let mut upper_bounds = buckets
+        .into_iter()
+        .filter(|upper_bound| {
+            if upper_bound.is_nan() {
+                return false;
+            }
+            match filter {
+                BoundsFilter::AllowNegative => true,
+                BoundsFilter::RejectNegative => upper_bound.is_sign_positive(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // sort and dedup the bounds
+    upper_bounds.sort_by(|a, b| a.partial_cmp(b).expect("upper_bound must not be NaN"));
+    upper_bounds.dedup();
+
+    // ensure +Inf bucket is included
+    match upper_bounds.last() {
+        Some(last) if last.is_finite() => upper_bounds.push(f64::INFINITY),
+        None => upper_bounds.push(f64::INFINITY),
+        _ => { /* already +Inf */ },
+    }
+
+    upper_bounds
+}
+
 /// Histogram-like core holding bucket counters plus `(count,sum)` accumulators.
 ///
 /// Notes:
@@ -24,81 +61,226 @@ pub enum BoundsFilter {
 /// - Bucket counts are **non-cumulative** (each observation increments exactly one bucket).
 /// - `sum` is stored as an `AtomicU64` containing the IEEE754 bits of an accumulated `f64` using
 ///   the crate's `raw::Atomic` extension methods.
+///
+/// Observations are recorded into one of two shards, the "hot" one. To read a consistent view of
+/// the buckets, count and sum, [`HistogramCore::snapshot`] swaps the hot and the cold shard,
+/// waits for the in-flight observations of the now cold shard to complete, and then folds the
+/// cold shard back into the hot one. Observers never take a lock.
 pub struct HistogramCore {
-    buckets: Vec<BucketCell>,
-    count: AtomicU64,
-    sum: AtomicU64,
+    upper_bounds: Vec<f64>,
+    // The highest bit is the index of the hot shard, the lower 63 bits count the observations
+    // that have been started.
+    count_and_hot: AtomicU64,
+    shards: [Shard; 2],
+    // The optional `created` timestamp, whose lock also serializes the readers and resets which
+    // swap the shards.
+    created: Mutex<Option<Duration>>,
+    // The exemplar of the latest observation recorded with one, per bucket.
+    exemplars: ExemplarSlot<Vec<Option<Exemplar>>>,
 }
 
-struct BucketCell {
-    upper_bound: f64,
+const HOT_BIT: u64 = 1 << 63;
+const COUNT_MASK: u64 = HOT_BIT - 1;
+
+struct Shard {
+    buckets: Vec<AtomicU64>,
+    sum: AtomicU64,
+    // Number of observations that have been completed in this shard.
     count: AtomicU64,
 }
 
-impl BucketCell {
-    fn new(upper_bound: f64) -> Self {
-        Self { upper_bound, count: AtomicU64::new(0) }
+impl Shard {
+    fn new(len: usize) -> Self {
+        Self {
+            buckets: (0..len).map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0f64.to_bits()),
+            count: AtomicU64::new(0),
+        }
     }
 
-    fn inc(&self) {
-        self.count.inc_by(1);
+    fn complete(&self, n: u64) {
+        // Publishes the bucket and sum updates to the reader waiting for this shard.
+        self.count.fetch_add(n, Ordering::Release);
     }
 
-    fn load(&self) -> Bucket {
-        Bucket::new(self.upper_bound, self.count.get())
+    /// Waits until all `started` observations of this (cold) shard have been completed.
+    fn wait_for(&self, started: u64) {
+        while self.count.load(Ordering::Acquire) != started {
+            std::thread::yield_now();
+        }
+    }
+
+    fn load(&self, upper_bounds: &[f64]) -> HistogramSnapshot {
+        let buckets = upper_bounds
+            .iter()
+            .zip(&self.buckets)
+            .map(|(&upper_bound, count)| Bucket::new(upper_bound, count.get()))
+            .collect();
+        let count = self.count.get();
+        let sum = self.sum.get();
+        HistogramSnapshot { buckets, count, sum, created: None }
+    }
+
+    fn add(&self, snapshot: &HistogramSnapshot) {
+        for (cell, bucket) in self.buckets.iter().zip(snapshot.buckets()) {
+            cell.inc_by(bucket.count());
+        }
+        self.sum.inc_by(snapshot.sum());
+    }
+
+    fn clear(&self) {
+        for cell in &self.buckets {
+            cell.set(0u64);
+        }
+        self.sum.set(0.0);
+        self.count.set(0u64);
     }
 }
 
 impl HistogramCore {
     pub fn from_bounds(buckets: impl IntoIterator<Item = f64>, filter: BoundsFilter) -> Self {
-        let mut upper_bounds = buckets
-            .into_iter()
-            .filter(|upper_bound| {
-                if upper_bound.is_nan() {
-                    return false;
-                }
-                match filter {
-                    BoundsFilter::AllowNegative => true,
-                    BoundsFilter::RejectNegative => upper_bound.is_sign_positive(),
-                }
-            })
-            .collect::<Vec<_>>();
-
-        // sort and dedup the bounds
-        upper_bounds.sort_by(|a, b| a.partial_cmp(b).expect("upper_bound must not be NaN"));
-        upper_bounds.dedup();
-
-        // ensure +Inf bucket is included
-        match upper_bounds.last() {
-            Some(last) if last.is_finite() => upper_bounds.push(f64::INFINITY),
-            None => upper_bounds.push(f64::INFINITY),
-            _ => { /* already +Inf */ },
+        let upper_bounds = normalize_bounds(buckets, filter);
+        let shards = [Shard::new(upper_bounds.len()), Shard::new(upper_bounds.len())];
+        Self {
+            upper_bounds,
+            count_and_hot: AtomicU64::new(0),
+            shards,
+            created: Mutex::new(None),
+            exemplars: ExemplarSlot::default(),
         }
+    }
 
-        let buckets = upper_bounds.into_iter().map(BucketCell::new).collect::<Vec<_>>();
+    pub fn with_created(self, created: Option<Duration>) -> Self {
+        *self.created.lock() = created;
+        self
+    }
 
-        Self { buckets, count: AtomicU64::new(0), sum: AtomicU64::new(0f64.to_bits()) }
+    pub fn created(&self) -> Option<Duration> {
+        *self.created.lock()
     }
 
-    pub fn observe(&self, value: f64) {
-        // Increment count and sum
-        self.count.inc_by(1);
-        self.sum.inc_by(value);
+    /// Records `exemplar` as the exemplar of the bucket at `idx`.
+    pub fn set_exemplar(&self, idx: usize, exemplar: Exemplar) {
+        let exemplars = self.exemplars.get_or_init(|| vec![None; self.upper_bounds.len()]);
+        exemplars.lock()[idx] = Some(exemplar);
+    }
+
+    /// Returns the exemplars per bucket, or `None` if no exemplar has been recorded.
+    pub fn exemplars(&self) -> Option<&Mutex<Vec<Option<Exemplar>>>> {
+        self.exemplars.get()
+    }
 
-        // Increment only the found bucket
+    /// Starts `n` observations, returning the shard they must be recorded into.
+    fn start(&self, n: u64) -> &Shard {
+        let count_and_hot = self.count_and_hot.fetch_add(n, Ordering::Relaxed);
+        &self.shards[(count_and_hot >> 63) as usize]
+    }
+
+    pub fn observe(&self, value: f64) {
         let idx = self.bucket_index(value);
-        self.buckets[idx].inc();
+        let shard = self.start(1);
+
+        // Increment only the found bucket, then the sum
+        shard.buckets[idx].inc_by(1);
+        shard.sum.inc_by(value);
+        shard.complete(1);
+    }
+
+    /// Observes all `values` with one atomic update per touched bucket, plus one for each of the
+    /// count and the sum.
+    ///
+    /// `values` are sorted in place so the buckets can be swept once.
+    pub fn observe_many(&self, values: &mut [f64]) {
+        if values.is_empty() {
+            return;
+        }
+        values.sort_unstable_by(f64::total_cmp);
+
+        let n = values.len() as u64;
+        let shard = self.start(n);
+        let mut idx = 0;
+        let mut pending = 0;
+        let mut sum = 0.0;
+        for &value in values.iter() {
+            // values are sorted, so the bucket index never goes backwards
+            while self.upper_bounds[idx] < value {
+                if pending > 0 {
+                    shard.buckets[idx].inc_by(pending);
+                    pending = 0;
+                }
+                idx += 1;
+            }
+            pending += 1;
+            sum += value;
+        }
+        shard.buckets[idx].inc_by(pending);
+        shard.sum.inc_by(sum);
+        shard.complete(n);
     }
 
     pub fn bucket_index(&self, value: f64) -> usize {
-        self.buckets.partition_point(|bucket| bucket.upper_bound < value)
+        self.upper_bounds.partition_point(|upper_bound| *upper_bound < value)
+    }
+
+    /// Swaps the hot and the cold shard, and waits for the observations already started in the
+    /// previously hot shard to complete. Returns the previously hot shard and the count of its
+    /// observations.
+    ///
+    /// Must be called with the `created` lock held.
+    fn swap(&self) -> (&Shard, &Shard, u64) {
+        let count_and_hot = self.count_and_hot.fetch_add(HOT_BIT, Ordering::Relaxed);
+        let started = count_and_hot & COUNT_MASK;
+        let cold_idx = (count_and_hot >> 63) as usize;
+        let cold = &self.shards[cold_idx];
+        let hot = &self.shards[cold_idx ^ 1];
+        cold.wait_for(started);
+        (cold, hot, started)
     }
 
+    /// Returns a consistent snapshot of the buckets, count, sum and `created` timestamp.
     pub fn snapshot(&self) -> HistogramSnapshot {
-        let buckets = self.buckets.iter().map(BucketCell::load).collect();
-        let count = self.count.get();
-        let sum = self.sum.get();
-        HistogramSnapshot { buckets, count, sum }
+        let created = self.created.lock();
+        let (cold, hot, count) = self.swap();
+        let snapshot = cold.load(&self.upper_bounds).with_created(*created);
+
+        // Fold the cold shard into the hot one, so the hot shard holds all observations again.
+        hot.add(&snapshot);
+        hot.complete(count);
+        cold.clear();
+        snapshot
+    }
+
+    pub fn upper_bounds(&self) -> &[f64] {
+        &self.upper_bounds
+    }
+
+    /// Adds the bucket counts, count and sum of `snapshot`, whose buckets must have the same
+    /// upper bounds, as if its observations were made on this histogram.
+    pub fn merge(&self, snapshot: &HistogramSnapshot) {
+        debug_assert_eq!(self.upper_bounds.len(), snapshot.buckets().len());
+        let shard = self.start(snapshot.count());
+        shard.add(snapshot);
+        shard.complete(snapshot.count());
+    }
+
+    /// Zeroes the buckets, count and sum, returning a snapshot of the state before the reset.
+    ///
+    /// Observations made concurrently with the reset land either in the returned snapshot or
+    /// after the reset, they are never lost. The `created` timestamp is replaced if `new_created`
+    /// is `Some`.
+    pub fn reset(&self, new_created: Option<Duration>) -> HistogramSnapshot {
+        let mut created = self.created.lock();
+        let (cold, _hot, count) = self.swap();
+        let snapshot = cold.load(&self.upper_bounds).with_created(*created);
+
+        // The hot shard only holds the observations started after the swap, so drop the count of
+        // the previously hot shard from the started observations.
+        cold.clear();
+        self.count_and_hot.fetch_sub(count, Ordering::Relaxed);
+        if new_created.is_some() {
+            *created = new_created;
+        }
+        snapshot
     }
 }
 
@@ -108,11 +290,13 @@ impl HistogramCore {
 /// - `buckets()` returns the current per-bucket counts (non-cumulative).
 /// - `count()` is the total number of observations.
 /// - `sum()` is the sum of all observed values.
-#[derive(Clone)]
+/// - `created()` is the `created` timestamp of the metric, if any.
+#[derive(Clone, Debug)]
 pub struct HistogramSnapshot {
     buckets: Vec<Bucket>,
     count: u64,
     sum: f64,
+    created: Option<Duration>,
 }
 
 impl HistogramSnapshot {
@@ -130,4 +314,53 @@ impl HistogramSnapshot {
     pub const fn sum(&self) -> f64 {
         self.sum
     }
+
+    /// Gets the `created` timestamp of the metric the snapshot was taken from.
+    pub const fn created(&self) -> Option<Duration> {
+        self.created
+    }
+
+    pub(crate) const fn with_created(mut self, created: Option<Duration>) -> Self {
+        self.created = created;
+        self
+    }
+
+    /// Estimates the `q`-quantile (`0 <= q <= 1`) of the observations from the buckets.
+    ///
+    /// Like Prometheus' `histogram_quantile`, the observations are assumed to be uniformly
+    /// distributed within a bucket, and the lower bound of the first bucket is taken as `0` if its
+    /// upper bound is positive. If the quantile falls into the `+Inf` bucket, the upper bound of the
+    /// highest finite bucket is returned.
+    ///
+    /// Returns `None` if there are no observations, or `q` is not within `[0, 1]`.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 || !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+
+        let rank = q * self.count as f64;
+        let mut cumulative = 0;
+        let mut lower_bound = None;
+        for bucket in &self.buckets {
+            let upper_bound = bucket.upper_bound();
+            let prev_cumulative = cumulative;
+            cumulative += bucket.count();
+            if (cumulative as f64) < rank || bucket.count() == 0 {
+                lower_bound = Some(upper_bound);
+                continue;
+            }
+
+            if upper_bound.is_infinite() {
+                return lower_bound;
+            }
+            let lower_bound = match lower_bound {
+                Some(lower_bound) => lower_bound,
+                None if upper_bound <= 0.0 => return Some(upper_bound),
+                None => 0.0,
+            };
+            let fraction = (rank - prev_cumulative as f64) / bucket.count() as f64;
+            return Some(lower_bound + (upper_bound - lower_bound) * fraction);
+        }
+        lower_bound
+    }
 }
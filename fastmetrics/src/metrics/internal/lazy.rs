@@ -22,6 +22,21 @@ use std::sync::Arc;
 pub(crate) trait LazySource<T>: Send + Sync {
     /// Returns the current value by evaluating the underlying source.
     fn load(&self) -> T;
+
+    /// Returns the current value, or `None` if the underlying source has no value to expose.
+    ///
+    /// Only fallible sources (e.g. derived from `LazyGroup::new_fallible`) return `None`.
+    fn try_load(&self) -> Option<T> {
+        Some(self.load())
+    }
+
+    /// Returns whether [`LazySource::try_load`] may return `None`.
+    ///
+    /// Encoders check whether a metric is empty before encoding it, so sources which always have a
+    /// value return `false` to avoid being evaluated more than once per scrape.
+    fn may_be_empty(&self) -> bool {
+        false
+    }
 }
 
 /// A simple [`LazySource`] implementation backed by a closure.
@@ -2,5 +2,7 @@
 //!
 //! This is a private implementation detail and must not be considered stable API.
 
+pub(crate) mod exemplar;
 pub(crate) mod histogram;
 pub(crate) mod lazy;
+pub(crate) mod quantile;
@@ -0,0 +1,149 @@
+//! Targeted quantile estimation over a stream of observations.
+//!
+//! This is an implementation of the CKMS algorithm for biased (targeted) quantiles, described in
+//! "Effective Computation of Biased Quantiles over Data Streams" by Cormode, Korn, Muthukrishnan
+//! and Srivastava. It keeps a compressed list of samples whose size only depends on the targeted
+//! quantiles and their allowed rank errors, not on the number of observations.
+
+/// Number of observations buffered before they're merged into the compressed samples.
+const BUFFER_CAPACITY: usize = 500;
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    value: f64,
+    // difference between the lowest possible rank of this sample and the previous one
+    width: f64,
+    // difference between the highest and the lowest possible rank of this sample
+    delta: f64,
+}
+
+/// A stream estimating a set of targeted `(quantile, error)` pairs.
+///
+/// For a target `(q, e)`, the estimated `q`-quantile has a rank within `[(q - e) * n, (q + e) * n]`
+/// of the `n` observations.
+#[derive(Clone, Debug)]
+pub struct QuantileStream {
+    targets: Vec<(f64, f64)>,
+    samples: Vec<Sample>,
+    buffer: Vec<f64>,
+    count: f64,
+}
+
+impl QuantileStream {
+    /// Creates an empty stream for the given `(quantile, error)` targets.
+    pub fn new(targets: Vec<(f64, f64)>) -> Self {
+        Self { targets, samples: Vec::new(), buffer: Vec::new(), count: 0.0 }
+    }
+
+    /// Inserts an observation into the stream.
+    pub fn insert(&mut self, value: f64) {
+        self.buffer.push(value);
+        if self.buffer.len() >= BUFFER_CAPACITY {
+            self.flush();
+        }
+    }
+
+    /// Estimates the value of the `q`-quantile, returns `NaN` if the stream is empty.
+    pub fn query(&mut self, q: f64) -> f64 {
+        if self.samples.is_empty() && !self.buffer.is_empty() {
+            // Fast path: nothing has been merged yet, so the exact quantile is available.
+            self.buffer.sort_unstable_by(f64::total_cmp);
+            let index = ((self.buffer.len() as f64 * q).ceil() as usize).saturating_sub(1);
+            return self.buffer[index.min(self.buffer.len() - 1)];
+        }
+
+        self.flush();
+        let Some((first, rest)) = self.samples.split_first() else {
+            return f64::NAN;
+        };
+
+        let mut t = (q * self.count).ceil();
+        t += (self.invariant(t) / 2.0).ceil();
+
+        let mut prev = first;
+        let mut rank = 0.0;
+        for sample in rest {
+            rank += prev.width;
+            if rank + sample.width + sample.delta > t {
+                return prev.value;
+            }
+            prev = sample;
+        }
+        prev.value
+    }
+
+    /// Discards all observations of the stream.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.buffer.clear();
+        self.count = 0.0;
+    }
+
+    // The maximum allowed `width + delta` of a sample at rank `r`.
+    fn invariant(&self, r: f64) -> f64 {
+        self.targets
+            .iter()
+            .map(|&(q, e)| {
+                if q * self.count <= r {
+                    2.0 * e * r / q
+                } else {
+                    2.0 * e * (self.count - r) / (1.0 - q)
+                }
+            })
+            .fold(f64::MAX, f64::min)
+    }
+
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.sort_unstable_by(f64::total_cmp);
+
+        let mut rank = 0.0;
+        let mut i = 0;
+        for &value in &buffer {
+            while i < self.samples.len() && self.samples[i].value <= value {
+                rank += self.samples[i].width;
+                i += 1;
+            }
+            let delta = if i == 0 || i == self.samples.len() {
+                0.0
+            } else {
+                (self.invariant(rank).floor() - 1.0).max(0.0)
+            };
+            self.samples.insert(i, Sample { value, width: 1.0, delta });
+            i += 1;
+            self.count += 1.0;
+            rank += 1.0;
+        }
+
+        // reuse the allocation of the buffer
+        buffer.clear();
+        self.buffer = buffer;
+
+        self.compress();
+    }
+
+    fn compress(&mut self) {
+        if self.samples.len() < 2 {
+            return;
+        }
+
+        let mut merged = self.samples.len() - 1;
+        let mut rank = self.count - 1.0 - self.samples[merged].width;
+        for i in (0..self.samples.len() - 1).rev() {
+            let current = self.samples[i];
+            let next = self.samples[merged];
+            if current.width + next.width + next.delta <= self.invariant(rank) {
+                self.samples[merged].width += current.width;
+                self.samples.remove(i);
+                merged -= 1;
+            } else {
+                merged = i;
+            }
+            rank -= current.width;
+        }
+    }
+}
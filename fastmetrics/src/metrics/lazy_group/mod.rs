@@ -16,10 +16,12 @@
 //! # Note
 //!
 //! This module intentionally only provides the *grouping* primitive. The concrete metric types are
-//! unified into `metrics::gauge::LazyGauge` and `metrics::counter::LazyCounter`.
+//! unified into `metrics::gauge::LazyGauge`, `metrics::counter::LazyCounter`,
+//! `metrics::histogram::LazyHistogram` and `metrics::state_set::LazyStateSet`.
 //!
-//! In other words, `LazyGroup::gauge(...)` returns a `LazyGauge`, and `LazyGroup::counter(...)`
-//! returns a `LazyCounter`.
+//! In other words, `LazyGroup::gauge(...)` returns a `LazyGauge`, `LazyGroup::counter(...)`
+//! returns a `LazyCounter`, `LazyGroup::histogram(...)` returns a `LazyHistogram`, and
+//! `LazyGroup::stateset(...)` returns a `LazyStateSet`.
 //!
 //! The actual grouping behavior is implemented by those types. This keeps the API ergonomic and
 //! avoids exposing extra "grouped" metric types.
@@ -31,6 +33,8 @@ use crate::{
     metrics::{
         counter::{CounterValue, LazyCounter},
         gauge::{GaugeValue, LazyGauge},
+        histogram::{HistogramConfig, HistogramSample, LazyHistogram},
+        state_set::{LazyStateSet, StateSetValue},
     },
 };
 
@@ -55,7 +59,7 @@ pub fn enter_scope() -> impl Drop {
 /// A group of lazily-evaluated metrics sharing a single sample per scrape.
 ///
 /// Create a `LazyGroup` with a sampler function producing some snapshot `S`, then derive multiple
-/// metrics from it via `gauge(...)` / `counter(...)`.
+/// metrics from it via `gauge(...)` / `counter(...)` / `histogram(...)` / `stateset(...)`.
 ///
 /// # Example
 ///
@@ -128,4 +132,226 @@ where
     {
         source::gauge_from_group(self.clone(), map)
     }
+
+    /// Creates a lazy histogram derived from the shared sample.
+    ///
+    /// The returned type is the standard [`LazyHistogram`], with an internal grouped source
+    /// so that all metrics derived from the same `LazyGroup` share one sample per scrape.
+    /// `map` must return one bucket count per [`HistogramConfig::upper_bounds`] entry.
+    pub fn histogram<M>(&self, map: M, config: Arc<HistogramConfig>) -> LazyHistogram
+    where
+        M: Fn(&S) -> HistogramSample + Send + Sync + 'static,
+    {
+        source::histogram_from_group(self.clone(), map, config)
+    }
+
+    /// Creates a lazy state set derived from the shared sample.
+    ///
+    /// The returned type is the standard [`LazyStateSet`], with an internal grouped source
+    /// so that all metrics derived from the same `LazyGroup` share one sample per scrape.
+    pub fn stateset<T, M>(&self, map: M) -> LazyStateSet<T>
+    where
+        M: Fn(&S) -> T + Send + Sync + 'static,
+        T: StateSetValue,
+    {
+        source::stateset_from_group(self.clone(), map)
+    }
+}
+
+impl<S> LazyGroup<Option<S>>
+where
+    S: Send + Sync + 'static,
+{
+    /// Creates a new `LazyGroup` with a sampler which may fail, e.g. because the `/proc`
+    /// filesystem is not available.
+    ///
+    /// The metrics derived via `fallible_counter(...)` / `fallible_gauge(...)` emit no samples
+    /// (not even their metadata) for the scrapes where the sampler returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fastmetrics::metrics::lazy_group::LazyGroup;
+    ///
+    /// struct Sample {
+    ///     open_fds: i64,
+    /// }
+    ///
+    /// let group = LazyGroup::new_fallible(|| {
+    ///     let open_fds = std::fs::read_dir("/proc/self/fd").ok()?.count();
+    ///     Some(Sample { open_fds: open_fds as i64 })
+    /// });
+    ///
+    /// let open_fds = group.fallible_gauge(|s| s.open_fds);
+    ///
+    /// // register `open_fds` as usual...
+    /// ```
+    pub fn new_fallible(sample: impl Fn() -> Option<S> + Send + Sync + 'static) -> Self {
+        Self::new(sample)
+    }
+
+    /// Creates a lazy counter derived from the shared sample, exposed only when the sampler
+    /// returns a sample.
+    pub fn fallible_counter<N, M>(&self, map: M) -> LazyCounter<N>
+    where
+        M: Fn(&S) -> N + Send + Sync + 'static,
+        N: EncodeCounterValue + CounterValue + 'static,
+    {
+        source::counter_from_fallible_group(self.clone(), map, None)
+    }
+
+    /// Creates a lazy gauge derived from the shared sample, exposed only when the sampler returns
+    /// a sample.
+    pub fn fallible_gauge<N, M>(&self, map: M) -> LazyGauge<N>
+    where
+        M: Fn(&S) -> N + Send + Sync + 'static,
+        N: EncodeGaugeValue + GaugeValue + 'static,
+    {
+        source::gauge_from_fallible_group(self.clone(), map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{
+        format::text::{self, TextProfile},
+        registry::Registry,
+    };
+
+    #[test]
+    fn test_grouped_histograms_share_one_sample_per_scrape() {
+        struct Sample {
+            read: HistogramSample,
+            write: HistogramSample,
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let group = LazyGroup::new({
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::Relaxed);
+                Sample {
+                    read: HistogramSample { buckets: vec![1, 0], sum: 0.5, count: 1 },
+                    write: HistogramSample { buckets: vec![0, 2], sum: 4.0, count: 2 },
+                }
+            }
+        });
+        let config = Arc::new(HistogramConfig::new([1.0]));
+
+        let mut registry = Registry::default();
+        let read = group.histogram(|s| s.read.clone(), config.clone());
+        let write = group.histogram(|s| s.write.clone(), config);
+        registry.register("read_latency", "Read latency", read).unwrap();
+        registry.register("write_latency", "Write latency", write).unwrap();
+
+        for scrape in 1..=2 {
+            let mut output = String::new();
+            text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+            assert!(output.contains("read_latency_count 1"), "{output}");
+            assert!(output.contains("write_latency_count 2"), "{output}");
+            assert_eq!(calls.load(Ordering::Relaxed), scrape);
+        }
+    }
+
+    #[test]
+    fn test_grouped_statesets_share_one_sample_per_scrape() {
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        enum Health {
+            Running,
+            Degraded,
+            Stopped,
+        }
+
+        impl StateSetValue for Health {
+            fn variants() -> &'static [Self] {
+                &[Self::Running, Self::Degraded, Self::Stopped]
+            }
+
+            fn as_str(&self) -> &str {
+                match self {
+                    Self::Running => "running",
+                    Self::Degraded => "degraded",
+                    Self::Stopped => "stopped",
+                }
+            }
+        }
+
+        struct Sample {
+            database: Health,
+            cache: Health,
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let group = LazyGroup::new({
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::Relaxed);
+                Sample { database: Health::Running, cache: Health::Degraded }
+            }
+        });
+
+        let mut registry = Registry::default();
+        let database = group.stateset(|s| s.database);
+        let cache = group.stateset(|s| s.cache);
+        registry.register("database", "Database health", database).unwrap();
+        registry.register("cache", "Cache health", cache).unwrap();
+
+        for scrape in 1..=2 {
+            let mut output = String::new();
+            text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+            assert!(output.contains("database{database=\"running\"} 1\n"), "{output}");
+            assert!(output.contains("database{database=\"stopped\"} 0\n"), "{output}");
+            assert!(output.contains("cache{cache=\"degraded\"} 1\n"), "{output}");
+            assert!(output.contains("cache{cache=\"running\"} 0\n"), "{output}");
+            assert_eq!(calls.load(Ordering::Relaxed), scrape);
+        }
+    }
+
+    #[test]
+    fn test_fallible_group_skips_missing_samples() {
+        struct Sample {
+            reads: u64,
+            open_fds: i64,
+        }
+
+        let available = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let group = LazyGroup::new_fallible({
+            let available = available.clone();
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::Relaxed);
+                available.load(Ordering::Relaxed).then_some(Sample { reads: 3, open_fds: 7 })
+            }
+        });
+
+        let mut registry = Registry::default();
+        registry
+            .register("reads", "Total reads", group.fallible_counter(|s| s.reads))
+            .unwrap();
+        registry
+            .register("open_fds", "Open fds", group.fallible_gauge(|s| s.open_fds))
+            .unwrap();
+
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+        assert_eq!(output, "# EOF\n");
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        available.store(true, Ordering::Relaxed);
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+        assert!(output.contains("reads_total 3\n"), "{output}");
+        assert!(output.contains("open_fds 7\n"), "{output}");
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        // outside of a scrape, the fallible metrics can be fetched directly
+        let gauge = group.fallible_gauge(|s| s.open_fds);
+        assert_eq!(gauge.try_fetch(), Some(7));
+        available.store(false, Ordering::Relaxed);
+        assert_eq!(gauge.try_fetch(), None);
+    }
 }
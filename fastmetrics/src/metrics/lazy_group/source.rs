@@ -4,7 +4,7 @@
 //! [`LazyGroup`]. They are crate-private and constructed by crate-internal glue.
 //
 // NOTE: This module is intentionally *not* user-facing. Users should only interact with `LazyGroup`
-// and `LazyGauge`/`LazyCounter`.
+// and `LazyGauge`/`LazyCounter`/`LazyHistogram`/`LazyStateSet`.
 
 use std::{marker::PhantomData, sync::Arc, time::Duration};
 
@@ -14,8 +14,10 @@ use crate::{
     metrics::{
         counter::{CounterValue, LazyCounter},
         gauge::{GaugeValue, LazyGauge},
+        histogram::{HistogramConfig, HistogramSample, LazyHistogram},
         internal::lazy::LazySource,
         lazy_group::LazyGroup,
+        state_set::{LazyStateSet, StateSetValue},
     },
 };
 
@@ -52,6 +54,65 @@ where
     LazyGauge::from_source(Arc::new(GroupedLazySource::<S, N, _>::new(group, Arc::new(map))))
 }
 
+/// Constructs a `LazyHistogram` derived from the shared `LazyGroup` sample.
+pub(crate) fn histogram_from_group<S, M>(
+    group: LazyGroup<S>,
+    map: M,
+    config: Arc<HistogramConfig>,
+) -> LazyHistogram
+where
+    S: Send + Sync + 'static,
+    M: Fn(&S) -> HistogramSample + Send + Sync + 'static,
+{
+    LazyHistogram::from_source(
+        Arc::new(GroupedLazySource::<S, HistogramSample, _>::new(group, Arc::new(map))),
+        config,
+    )
+}
+
+/// Constructs a `LazyStateSet` derived from the shared `LazyGroup` sample.
+pub(crate) fn stateset_from_group<S, T, M>(group: LazyGroup<S>, map: M) -> LazyStateSet<T>
+where
+    S: Send + Sync + 'static,
+    M: Fn(&S) -> T + Send + Sync + 'static,
+    T: StateSetValue,
+{
+    LazyStateSet::from_source(Arc::new(GroupedLazySource::<S, T, _>::new(group, Arc::new(map))))
+}
+
+/// Constructs a `LazyCounter` derived from the shared sample of a fallible `LazyGroup`.
+pub(crate) fn counter_from_fallible_group<S, N, M>(
+    group: LazyGroup<Option<S>>,
+    map: M,
+    created: Option<Duration>,
+) -> LazyCounter<N>
+where
+    S: Send + Sync + 'static,
+    M: Fn(&S) -> N + Send + Sync + 'static,
+    N: EncodeCounterValue + CounterValue + 'static,
+{
+    LazyCounter::from_source(
+        Arc::new(FallibleGroupedLazySource::<S, N, _>::new(group, Arc::new(map))),
+        created,
+    )
+}
+
+/// Constructs a `LazyGauge` derived from the shared sample of a fallible `LazyGroup`.
+pub(crate) fn gauge_from_fallible_group<S, N, M>(
+    group: LazyGroup<Option<S>>,
+    map: M,
+) -> LazyGauge<N>
+where
+    S: Send + Sync + 'static,
+    M: Fn(&S) -> N + Send + Sync + 'static,
+    N: EncodeGaugeValue + GaugeValue + 'static,
+{
+    LazyGauge::from_source(Arc::new(FallibleGroupedLazySource::<S, N, _>::new(
+        group,
+        Arc::new(map),
+    )))
+}
+
 /// A lazy source whose value is derived from a shared per-scrape sample.
 pub(crate) struct GroupedLazySource<S, N, M> {
     pub(crate) group: LazyGroup<S>,
@@ -87,3 +148,50 @@ where
         }
     }
 }
+
+/// A lazy source whose value is derived from a shared per-scrape sample which may be missing.
+pub(crate) struct FallibleGroupedLazySource<S, N, M> {
+    pub(crate) group: LazyGroup<Option<S>>,
+    pub(crate) map: Arc<M>,
+    pub(crate) _marker: PhantomData<N>,
+}
+
+impl<S, N, M> FallibleGroupedLazySource<S, N, M> {
+    #[inline]
+    pub(crate) fn new(group: LazyGroup<Option<S>>, map: Arc<M>) -> Self {
+        Self { group, map, _marker: PhantomData }
+    }
+}
+
+impl<S, N, M> LazySource<N> for FallibleGroupedLazySource<S, N, M>
+where
+    S: Send + Sync + 'static,
+    M: Fn(&S) -> N + Send + Sync + 'static,
+    N: Send + Sync + 'static,
+{
+    #[inline]
+    fn load(&self) -> N {
+        self.try_load().expect("the sampler of the lazy group returned no sample")
+    }
+
+    #[inline]
+    fn may_be_empty(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn try_load(&self) -> Option<N> {
+        let map = self.map.as_ref();
+
+        if let Some(r) = scrape_ctx::with_current(|ctx| {
+            let sample =
+                ctx.get_or_init::<Option<S>>(self.group.id, || (self.group.sample.as_ref())());
+            sample.as_ref().map(map)
+        }) {
+            r
+        } else {
+            let sample = (self.group.sample.as_ref())();
+            sample.as_ref().map(map)
+        }
+    }
+}
@@ -8,11 +8,13 @@
 //! - [Info]: Static key-value information about the target
 //! - [Histogram]: Statistical distribution of values
 //! - [GaugeHistogram]: Like histogram but values can decrease
-//! - [Summary] (Not Implemented): Similar to histogram, with quantiles
+//! - [Summary]: Similar to histogram, with client-side quantiles over a sliding window
 //!
 //! The module also provides:
 //!
 //! - [Family]: Collections of metrics with the same name but different labels
+//! - [ConcurrentFamily]: Like family, but with sharded locking for concurrent label insertion
+//!   (requires the `dashmap` feature)
 //!
 //! [Counter]: self::counter
 //! [Gauge]: self::gauge
@@ -22,7 +24,11 @@
 //! [GaugeHistogram]: self::gauge_histogram
 //! [Summary]: self::summary
 //! [Family]: self::family::Family
+//! [ConcurrentFamily]: self::concurrent_family::ConcurrentFamily
 
+#[cfg(feature = "dashmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dashmap")))]
+pub mod concurrent_family;
 pub mod family;
 mod internal;
 pub mod lazy_group;
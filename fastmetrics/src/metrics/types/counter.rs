@@ -1,6 +1,7 @@
 //! [Open Metrics Counter](https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#counter) metric type.
 //!
-//! See [`Counter`], [`ConstCounter`] and [`LazyCounter`] for more details.
+//! See [`Counter`], [`ShardedCounter`], [`LocalCounter`], [`ConstCounter`], [`LazyCounter`] and
+//! [`SlidingWindowCounter`] for more details.
 //!
 //! ## Overflow/underflow behavior
 //!
@@ -10,17 +11,25 @@
 //!   `inf`/`NaN`).
 
 use std::{
+    cell::Cell,
     fmt::{self, Debug},
     ops::AddAssign,
-    sync::{Arc, atomic::*},
-    time::Duration,
+    sync::{Arc, Weak, atomic::*},
+    time::{Duration, Instant},
 };
 
+use parking_lot::Mutex;
+
+pub use crate::raw::exemplar::Exemplar;
 use crate::{
-    encoder::{EncodeCounterValue, EncodeMetric, MetricEncoder},
+    encoder::{EncodeCounterValue, EncodeExemplar, EncodeMetric, MetricEncoder},
     error::Result,
-    metrics::internal::lazy::{LazySource, PlainLazySource},
-    raw::{Atomic, MetricLabelSet, MetricType, Number, TypedMetric},
+    metrics::internal::{
+        exemplar::ExemplarSlot,
+        lazy::{LazySource, PlainLazySource},
+    },
+    raw::{AsF64, Atomic, MetricLabelSet, MetricType, Number, TypedMetric, WrappingAdd},
+    registry::Clock,
 };
 
 /// A marker trait for **counter** metric value.
@@ -99,14 +108,27 @@ impl_saturating_counter_value_for_uint! { u32, u64, usize }
 /// assert!(counter.created().is_some());
 /// ```
 pub struct Counter<N: CounterValue = u64> {
-    total: Arc<N::Atomic>,
+    state: Arc<CounterState<N>>,
     // UNIX timestamp
     created: Option<Duration>,
 }
 
+// The state shared by all clones of a counter.
+struct CounterState<N: CounterValue> {
+    total: N::Atomic,
+    // The exemplar of the latest increment recorded with one
+    exemplar: ExemplarSlot<Option<Exemplar>>,
+}
+
+impl<N: CounterValue> Default for CounterState<N> {
+    fn default() -> Self {
+        Self { total: Default::default(), exemplar: Default::default() }
+    }
+}
+
 impl<N: CounterValue> Clone for Counter<N> {
     fn clone(&self) -> Self {
-        Self { total: self.total.clone(), created: self.created }
+        Self { state: self.state.clone(), created: self.created }
     }
 }
 
@@ -124,14 +146,14 @@ impl<N: CounterValue> Debug for Counter<N> {
 
 impl<N: CounterValue> Default for Counter<N> {
     fn default() -> Self {
-        Self { total: Arc::new(Default::default()), created: None }
+        Self { state: Default::default(), created: None }
     }
 }
 
 impl<N: CounterValue> Counter<N> {
     /// Creates a [`Counter`] with a `created` timestamp.
     pub fn with_created(created: Duration) -> Self {
-        Self { total: Default::default(), created: Some(created) }
+        Self { state: Default::default(), created: Some(created) }
     }
 
     /// Increases the [`Counter`] by 1.
@@ -140,7 +162,7 @@ impl<N: CounterValue> Counter<N> {
     /// Use [`Counter::saturating_inc`] if you need clamping semantics.
     #[inline]
     pub fn inc(&self) {
-        self.total.inc_by(N::ONE);
+        self.state.total.inc_by(N::ONE);
     }
 
     /// Increases the [`Counter`] by `v`.
@@ -154,7 +176,69 @@ impl<N: CounterValue> Counter<N> {
     #[inline]
     pub fn inc_by(&self, v: N) {
         assert!(v >= N::ZERO, "increment must be zero or positive");
-        self.total.inc_by(v);
+        self.state.total.inc_by(v);
+    }
+
+    /// Increases the [`Counter`] by 1, recording `exemplar` as the exemplar of the counter.
+    ///
+    /// The value of the exemplar is set to the increment. Only the exemplar of the latest
+    /// increment is kept, and it's only exposed by the OpenMetrics formats.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::counter::{Counter, Exemplar};
+    /// #
+    /// let requests = <Counter>::default();
+    /// requests.inc_with_exemplar(Exemplar::new([("trace_id", "abc123")], 0.0));
+    /// assert_eq!(requests.total(), 1);
+    /// assert_eq!(requests.exemplar().map(|e| e.value()), Some(1.0));
+    /// ```
+    pub fn inc_with_exemplar(&self, exemplar: Exemplar)
+    where
+        N: AsF64,
+    {
+        self.inc_by_with_exemplar(N::ONE, exemplar);
+    }
+
+    /// Increases the [`Counter`] by `v`, recording `exemplar` as the exemplar of the counter.
+    ///
+    /// See [`Counter::inc_with_exemplar`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the increment `v` is negative (i.e, not zero or positive).
+    pub fn inc_by_with_exemplar(&self, v: N, exemplar: Exemplar)
+    where
+        N: AsF64,
+    {
+        self.inc_by(v);
+        *self.state.exemplar.get_or_init(|| None).lock() = Some(exemplar.with_value(v.as_f64()));
+    }
+
+    /// Gets the exemplar of the latest increment recorded with one, if any.
+    pub fn exemplar(&self) -> Option<Exemplar> {
+        self.state.exemplar.get().and_then(|exemplar| exemplar.lock().clone())
+    }
+
+    /// Increases the [`Counter`] by 1 and returns the new total.
+    ///
+    /// This is a single atomic operation, unlike calling [`Counter::inc`] followed by
+    /// [`Counter::total`].
+    #[inline]
+    pub fn inc_and_get(&self) -> N {
+        self.state.total.inc_by_and_get(N::ONE)
+    }
+
+    /// Increases the [`Counter`] by `v` and returns the new total.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the increment `v` is negative (i.e, not zero or positive).
+    #[inline]
+    pub fn inc_by_and_get(&self, v: N) -> N {
+        assert!(v >= N::ZERO, "increment must be zero or positive");
+        self.state.total.inc_by_and_get(v)
     }
 
     /// Sets the [`Counter`] to `v`.
@@ -165,20 +249,78 @@ impl<N: CounterValue> Counter<N> {
     /// This is because counters must be monotonically increasing.
     #[inline]
     pub fn set(&self, v: N) {
-        assert!(v >= self.total.get(), "counter must be monotonically increasing");
-        self.total.set(v);
+        assert!(v >= self.state.total.get(), "counter must be monotonically increasing");
+        self.state.total.set(v);
     }
 
     /// Gets the current `total` value of the [`Counter`].
     #[inline]
     pub fn total(&self) -> N {
-        self.total.get()
+        self.state.total.get()
     }
 
-    /// Gets the optional `created` value of the [`Counter`].
+    /// Gets the optional `created` value (since the UNIX epoch) of the [`Counter`], e.g. to
+    /// compute the age of the counter.
+    ///
+    /// It returns `None` unless the counter was created with [`Counter::with_created`].
+    #[doc(alias = "created_at")]
     pub const fn created(&self) -> Option<Duration> {
         self.created
     }
+
+    /// Returns a guard which increases the [`Counter`] by 1 when dropped.
+    ///
+    /// The increment happens exactly once, on early returns and panics as well, which makes it
+    /// useful to count completions. The guard only holds a weak reference to the counter, so if
+    /// the counter (and all its clones) is dropped first, e.g. after being deregistered, dropping
+    /// the guard does nothing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::counter::Counter;
+    /// let completed = <Counter>::default();
+    /// {
+    ///     let _guard = completed.inc_on_drop();
+    ///     assert_eq!(completed.total(), 0);
+    /// }
+    /// assert_eq!(completed.total(), 1);
+    /// ```
+    pub fn inc_on_drop(&self) -> CounterGuard<N> {
+        CounterGuard { state: Arc::downgrade(&self.state) }
+    }
+
+    /// Resets the `total` of the [`Counter`] (and all its clones) to zero.
+    ///
+    /// This breaks the monotonicity of the counter, so it's only available in tests or with the
+    /// `testing-utils` feature, e.g. to isolate test cases sharing a static counter. The `created`
+    /// timestamp is left untouched.
+    #[cfg(any(test, feature = "testing-utils"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing-utils")))]
+    pub fn reset_for_testing(&self) {
+        self.state.total.set(N::ZERO);
+    }
+}
+
+/// A guard which increases a [`Counter`] by 1 when dropped, see [`Counter::inc_on_drop`].
+#[must_use = "the counter is increased as soon as the guard is dropped"]
+pub struct CounterGuard<N: CounterValue = u64> {
+    state: Weak<CounterState<N>>,
+}
+
+impl<N: CounterValue> Debug for CounterGuard<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.state.upgrade().map(|state| state.total.get());
+        f.debug_struct("CounterGuard").field("total", &total).finish()
+    }
+}
+
+impl<N: CounterValue> Drop for CounterGuard<N> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.upgrade() {
+            state.total.inc_by(N::ONE);
+        }
+    }
 }
 
 impl<N: SaturatingCounterValue> Counter<N> {
@@ -200,7 +342,7 @@ impl<N: SaturatingCounterValue> Counter<N> {
     #[inline]
     pub fn saturating_inc_by(&self, v: N) {
         assert!(v >= N::ZERO, "increment must be zero or positive");
-        self.total.update(|old| old.saturating_add(v));
+        self.state.total.update(|old| old.saturating_add(v));
     }
 }
 
@@ -213,6 +355,161 @@ impl<N: CounterValue> MetricLabelSet for Counter<N> {
 }
 
 impl<N: EncodeCounterValue + CounterValue> EncodeMetric for Counter<N> {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        let total = self.total();
+        let created = self.created();
+        match self.state.exemplar.get() {
+            Some(exemplar) => {
+                let exemplar = exemplar.lock();
+                let exemplar = exemplar.as_ref().map(|exemplar| exemplar as &dyn EncodeExemplar);
+                encoder.encode_counter(&total, exemplar, created)
+            },
+            None => encoder.encode_counter(&total, None, created),
+        }
+    }
+}
+
+/// A [`Counter`] spread over several cache-line aligned shards to avoid contention.
+///
+/// Every thread increments the shard picked by its thread index modulo the number of shards, so
+/// threads incrementing the counter concurrently mostly touch different cache lines. Reading the
+/// [`ShardedCounter::total`] sums all shards, which makes it slower than [`Counter::total`]; this
+/// trade-off pays off for counters incremented by many threads at a high rate.
+///
+/// It's encoded exactly like a [`Counter`], and clones share the same shards.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::metrics::counter::ShardedCounter;
+/// #
+/// let requests = <ShardedCounter>::default();
+/// std::thread::scope(|scope| {
+///     for _ in 0..4 {
+///         scope.spawn(|| requests.inc());
+///     }
+/// });
+/// assert_eq!(requests.total(), 4);
+/// ```
+pub struct ShardedCounter<N: CounterValue = u64> {
+    shards: Arc<[CachePadded<N::Atomic>]>,
+    // UNIX timestamp
+    created: Option<Duration>,
+}
+
+// Aligned to 128 bytes, since some CPUs prefetch cache lines in pairs.
+#[derive(Default)]
+#[repr(align(128))]
+struct CachePadded<T>(T);
+
+/// Returns the index of the current thread, assigned in the order threads first call it.
+fn thread_index() -> usize {
+    static NEXT_INDEX: AtomicUsize = AtomicUsize::new(0);
+    thread_local! {
+        static INDEX: usize = NEXT_INDEX.fetch_add(1, Ordering::Relaxed);
+    }
+    INDEX.with(|index| *index)
+}
+
+impl<N: CounterValue> Clone for ShardedCounter<N> {
+    fn clone(&self) -> Self {
+        Self { shards: self.shards.clone(), created: self.created }
+    }
+}
+
+impl<N: CounterValue> Debug for ShardedCounter<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedCounter")
+            .field("shards", &self.shards())
+            .field("total", &self.total())
+            .field("created", &self.created())
+            .finish()
+    }
+}
+
+impl<N: CounterValue> Default for ShardedCounter<N> {
+    fn default() -> Self {
+        let shards = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::new(shards)
+    }
+}
+
+impl<N: CounterValue> ShardedCounter<N> {
+    /// Creates a [`ShardedCounter`] with the given number of `shards`.
+    ///
+    /// The default is the number of logical CPUs, see [`std::thread::available_parallelism`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `shards` is zero.
+    pub fn new(shards: usize) -> Self {
+        assert!(shards > 0, "sharded counter must have at least one shard");
+        Self { shards: (0..shards).map(|_| CachePadded::default()).collect(), created: None }
+    }
+
+    /// Creates a [`ShardedCounter`] with the given number of `shards` and a `created` timestamp.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `shards` is zero.
+    pub fn with_created(shards: usize, created: Duration) -> Self {
+        Self { created: Some(created), ..Self::new(shards) }
+    }
+
+    #[inline]
+    fn shard(&self) -> &N::Atomic {
+        &self.shards[thread_index() % self.shards.len()].0
+    }
+
+    /// Increases the current thread's shard of the [`ShardedCounter`] by 1.
+    #[inline]
+    pub fn inc(&self) {
+        self.shard().inc_by(N::ONE);
+    }
+
+    /// Increases the current thread's shard of the [`ShardedCounter`] by `v`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the increment `v` is negative (i.e, not zero or positive).
+    #[inline]
+    pub fn inc_by(&self, v: N) {
+        assert!(v >= N::ZERO, "increment must be zero or positive");
+        self.shard().inc_by(v);
+    }
+
+    /// Gets the current `total` value of the [`ShardedCounter`], i.e. the sum of all shards.
+    ///
+    /// Increments made concurrently may or may not be included.
+    pub fn total(&self) -> N {
+        // sums with the same (e.g. wrapping) arithmetic as the shards
+        let total = N::Atomic::default();
+        for shard in self.shards.iter() {
+            total.inc_by(shard.0.get());
+        }
+        total.get()
+    }
+
+    /// Returns the number of shards of the [`ShardedCounter`].
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Gets the optional `created` value of the [`ShardedCounter`].
+    pub const fn created(&self) -> Option<Duration> {
+        self.created
+    }
+}
+
+impl<N: CounterValue> TypedMetric for ShardedCounter<N> {
+    const TYPE: MetricType = MetricType::Counter;
+}
+
+impl<N: CounterValue> MetricLabelSet for ShardedCounter<N> {
+    type LabelSet = ();
+}
+
+impl<N: EncodeCounterValue + CounterValue> EncodeMetric for ShardedCounter<N> {
     fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
         let total = self.total();
         let created = self.created();
@@ -220,6 +517,92 @@ impl<N: EncodeCounterValue + CounterValue> EncodeMetric for Counter<N> {
     }
 }
 
+/// A thread-local, non-atomic accumulator for a [`Counter`].
+///
+/// Hot loops (e.g. the workers of a batch job) can count into their own [`LocalCounter`] without
+/// any atomic operation, and periodically transfer the accumulated value into a shared
+/// [`Counter`] with [`LocalCounter::merge_into`]. A [`LocalCounter`] is not [`Sync`] and can't be
+/// registered, only the shared [`Counter`] is exposed.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::metrics::counter::{Counter, LocalCounter};
+/// #
+/// let processed = <Counter>::default();
+/// std::thread::scope(|scope| {
+///     for _ in 0..4 {
+///         scope.spawn(|| {
+///             let local = <LocalCounter>::default();
+///             for _ in 0..100 {
+///                 local.inc();
+///             }
+///             local.merge_into(&processed);
+///         });
+///     }
+/// });
+/// assert_eq!(processed.total(), 400);
+/// ```
+#[derive(Debug, Default)]
+pub struct LocalCounter<N: CounterValue = u64> {
+    total: Cell<N>,
+}
+
+impl<N: CounterValue> LocalCounter<N> {
+    /// Increases the [`LocalCounter`] by 1.
+    #[inline]
+    pub fn inc(&self)
+    where
+        N: WrappingAdd,
+    {
+        self.inc_by(N::ONE);
+    }
+
+    /// Increases the [`LocalCounter`] by `v`, wrapping around on integer overflow like
+    /// [`Counter::inc_by`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the increment `v` is negative (i.e, not zero or positive).
+    #[inline]
+    pub fn inc_by(&self, v: N)
+    where
+        N: WrappingAdd,
+    {
+        assert!(v >= N::ZERO, "increment must be zero or positive");
+        self.total.set(self.total.get().add_wrapping(v));
+    }
+
+    /// Gets the value accumulated since the last [`LocalCounter::merge_into`].
+    #[inline]
+    pub fn total(&self) -> N {
+        self.total.get()
+    }
+
+    /// Adds the accumulated value to `target`, resets the [`LocalCounter`] to zero and returns the
+    /// transferred amount.
+    ///
+    /// The value is added with a single [`Counter::inc_by`], so concurrent merges from several
+    /// threads into the same `target` never lose increments.
+    pub fn merge_into(&self, target: &Counter<N>) -> N {
+        let total = self.total.replace(N::ZERO);
+        if total > N::ZERO {
+            target.inc_by(total);
+        }
+        total
+    }
+
+    /// Discards the value accumulated in the [`LocalCounter`] without merging it anywhere.
+    ///
+    /// Only available in tests or with the `testing-utils` feature, like
+    /// [`Counter::reset_for_testing`].
+    #[cfg(any(test, feature = "testing-utils"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing-utils")))]
+    pub fn reset_for_testing(&self) {
+        self.total.set(N::ZERO);
+    }
+}
+
 /// A **constant** `Counter`, meaning it cannot be changed once created.
 ///
 /// # Example
@@ -286,6 +669,7 @@ impl<N: CounterValue> ConstCounter<N> {
 
     /// Gets the optional `created` value of the [`ConstCounter`].
     #[inline]
+    #[doc(alias = "created_at")]
     pub const fn created(&self) -> Option<Duration> {
         self.created
     }
@@ -365,15 +749,43 @@ impl<N: CounterValue + 'static> LazyCounter<N> {
     ///
     /// Mainly intended for debugging or tests; regular metric collection should
     /// let the encoder trigger the fetch during scrapes.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the counter is derived from a
+    /// [`LazyGroup::new_fallible`](crate::metrics::lazy_group::LazyGroup::new_fallible) group
+    /// whose sampler returns `None`, use [`LazyCounter::try_fetch`] instead.
     #[inline]
     pub fn fetch(&self) -> N {
         self.source.load()
     }
 
+    /// Evaluates the underlying fetcher and returns the current total, or `None` if the sampler of
+    /// the fallible [`LazyGroup`](crate::metrics::lazy_group::LazyGroup) the counter is derived
+    /// from returns `None`.
+    #[inline]
+    pub fn try_fetch(&self) -> Option<N> {
+        self.source.try_load()
+    }
+
     /// Gets the optional `created` value of the [`LazyCounter`].
     pub fn created(&self) -> Option<Duration> {
         self.created
     }
+
+    /// Always panics, the value of a [`LazyCounter`] is read-only and owned by its fetcher.
+    ///
+    /// This exists so that test helpers resetting counters generically fail loudly instead of
+    /// silently keeping stale values. Reset the underlying source instead.
+    ///
+    /// # Panics
+    ///
+    /// This function always panics.
+    #[cfg(any(test, feature = "testing-utils"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing-utils")))]
+    pub fn reset_for_testing(&self) {
+        panic!("LazyCounter is read-only and cannot be reset, reset its source instead");
+    }
 }
 
 impl<N> TypedMetric for LazyCounter<N> {
@@ -386,15 +798,159 @@ impl<N> MetricLabelSet for LazyCounter<N> {
 
 impl<N: EncodeCounterValue + CounterValue + 'static> EncodeMetric for LazyCounter<N> {
     fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
-        let total = self.fetch();
-        encoder.encode_counter(&total, None, self.created)
+        match self.try_fetch() {
+            Some(total) => encoder.encode_counter(&total, None, self.created),
+            None => Ok(()),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        // fallible grouped sources read the sample cached by the current scrape
+        self.source.may_be_empty() && self.try_fetch().is_none()
+    }
+}
+
+/// A counter that reports the number of events in the last `window` of time.
+///
+/// Unlike [`Counter`], this is not a monotonically increasing total. The window is split into
+/// `BUCKETS` slots, each covering `window / BUCKETS`, and events are recorded in the slot of the
+/// current time. Slots that fall out of the window are discarded, so the reported count has the
+/// granularity of one slot.
+///
+/// This is intended for values consumed directly (e.g. "errors in the last 60 seconds") without a
+/// Prometheus server computing rates, so it's exposed as a **gauge**.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::time::Duration;
+/// #
+/// # use fastmetrics::metrics::counter::SlidingWindowCounter;
+/// #
+/// // Count errors in the last 60 seconds, with 1-second granularity
+/// let errors = SlidingWindowCounter::<60>::new(Duration::from_secs(60));
+/// errors.inc();
+/// errors.inc_by(2);
+/// assert_eq!(errors.count(), 3);
+/// ```
+pub struct SlidingWindowCounter<const BUCKETS: usize> {
+    inner: Arc<SlidingWindow<BUCKETS>>,
+}
+
+struct SlidingWindow<const BUCKETS: usize> {
+    slot_width: Duration,
+    clock: Box<dyn Clock>,
+    // (slot epoch, count) pairs, indexed by `epoch % BUCKETS`
+    slots: Mutex<[(u64, u64); BUCKETS]>,
+}
+
+impl<const BUCKETS: usize> Clone for SlidingWindowCounter<BUCKETS> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<const BUCKETS: usize> Debug for SlidingWindowCounter<BUCKETS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlidingWindowCounter")
+            .field("window", &self.window())
+            .field("count", &self.count())
+            .finish()
+    }
+}
+
+impl<const BUCKETS: usize> SlidingWindowCounter<BUCKETS> {
+    /// Creates a [`SlidingWindowCounter`] covering the given `window`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `BUCKETS` is zero or `window / BUCKETS` is zero.
+    pub fn new(window: Duration) -> Self {
+        let start = Instant::now();
+        Self::with_clock(window, move || start.elapsed())
+    }
+
+    /// Creates a [`SlidingWindowCounter`] covering the given `window`, using `clock` as the time
+    /// source.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `BUCKETS` is zero or `window / BUCKETS` is zero.
+    pub fn with_clock(window: Duration, clock: impl Clock + 'static) -> Self {
+        assert!(BUCKETS > 0, "sliding window must have at least one bucket");
+        let slot_width = window / BUCKETS as u32;
+        assert!(!slot_width.is_zero(), "sliding window slot width must be greater than zero");
+
+        Self {
+            inner: Arc::new(SlidingWindow {
+                slot_width,
+                clock: Box::new(clock),
+                slots: Mutex::new([(0, 0); BUCKETS]),
+            }),
+        }
+    }
+
+    /// Returns the window covered by the [`SlidingWindowCounter`].
+    pub fn window(&self) -> Duration {
+        self.inner.slot_width * BUCKETS as u32
+    }
+
+    /// Records one event in the current slot.
+    #[inline]
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    /// Records `v` events in the current slot.
+    pub fn inc_by(&self, v: u64) {
+        let epoch = self.inner.current_epoch();
+        let mut slots = self.inner.slots.lock();
+        let slot = &mut slots[(epoch % BUCKETS as u64) as usize];
+        if slot.0 != epoch {
+            *slot = (epoch, 0);
+        }
+        slot.1 = slot.1.wrapping_add(v);
+    }
+
+    /// Returns the number of events recorded within the window.
+    pub fn count(&self) -> u64 {
+        let epoch = self.inner.current_epoch();
+        let slots = self.inner.slots.lock();
+        slots
+            .iter()
+            .filter(|(slot_epoch, _)| *slot_epoch <= epoch && epoch - slot_epoch < BUCKETS as u64)
+            .fold(0u64, |total, (_, count)| total.wrapping_add(*count))
+    }
+}
+
+impl<const BUCKETS: usize> SlidingWindow<BUCKETS> {
+    fn current_epoch(&self) -> u64 {
+        (self.clock.now().as_nanos() / self.slot_width.as_nanos()) as u64
+    }
+}
+
+impl<const BUCKETS: usize> TypedMetric for SlidingWindowCounter<BUCKETS> {
+    const TYPE: MetricType = MetricType::Gauge;
+}
+
+impl<const BUCKETS: usize> MetricLabelSet for SlidingWindowCounter<BUCKETS> {
+    type LabelSet = ();
+}
+
+impl<const BUCKETS: usize> EncodeMetric for SlidingWindowCounter<BUCKETS> {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        let count = i64::try_from(self.count()).unwrap_or(i64::MAX);
+        encoder.encode_gauge(&count)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{metrics::check_text_encoding, registry::Unit};
+    use crate::{
+        metrics::check_text_encoding,
+        registry::{ManualClock, Unit},
+    };
 
     #[test]
     fn test_counter_initialization() {
@@ -410,6 +966,92 @@ mod tests {
         assert!(counter.created().is_some());
     }
 
+    #[test]
+    fn test_local_counter_merge_into() {
+        let shared = <Counter>::default();
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let local = <LocalCounter>::default();
+                    for _ in 0..1000 {
+                        local.inc();
+                    }
+                    assert_eq!(local.total(), 1000);
+                    assert_eq!(local.merge_into(&shared), 1000);
+                    assert_eq!(local.total(), 0);
+                    // nothing left to transfer
+                    assert_eq!(local.merge_into(&shared), 0);
+                });
+            }
+        });
+        assert_eq!(shared.total(), 8000);
+
+        let shared = Counter::<f64>::default();
+        let local = LocalCounter::<f64>::default();
+        local.inc_by(1.5);
+        local.inc();
+        assert_eq!(local.merge_into(&shared), 2.5);
+        assert_eq!(shared.total(), 2.5);
+    }
+
+    #[test]
+    fn test_f64_counter_concurrent_inc_by() {
+        let counter = Counter::<f64>::default();
+
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..1000 {
+                        counter.inc_by(1.0);
+                    }
+                });
+            }
+        });
+        assert_eq!(counter.total(), 8000.0);
+    }
+
+    #[test]
+    fn test_counter_inc_on_drop() {
+        use std::panic::AssertUnwindSafe;
+
+        let counter = <Counter>::default();
+
+        // dropped normally
+        let guard = counter.inc_on_drop();
+        assert_eq!(counter.total(), 0);
+        drop(guard);
+        assert_eq!(counter.total(), 1);
+
+        // dropped while unwinding
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = counter.inc_on_drop();
+            panic!("guarded scope panicked");
+        }));
+        assert!(result.is_err());
+        assert_eq!(counter.total(), 2);
+
+        // dropped after the counter
+        let guard = counter.inc_on_drop();
+        drop(counter);
+        drop(guard);
+    }
+
+    #[test]
+    fn test_counter_created() {
+        assert_eq!(<Counter>::default().created(), None);
+        assert_eq!(ConstCounter::new(1_u64).created(), None);
+
+        let now = std::time::SystemTime::UNIX_EPOCH.elapsed().unwrap();
+        let counter = <Counter>::with_created(now);
+        let created = counter.created().unwrap();
+        assert_eq!(created, now);
+        let age = std::time::SystemTime::UNIX_EPOCH.elapsed().unwrap() - created;
+        assert!(age < Duration::from_secs(5));
+
+        let counter = ConstCounter::with_created(1_u64, now);
+        assert_eq!(counter.created(), Some(now));
+    }
+
     #[test]
     fn test_counter_inc() {
         let counter = <Counter>::default();
@@ -437,6 +1079,43 @@ mod tests {
         assert_eq!(counter.total(), 8);
     }
 
+    #[test]
+    fn test_counter_inc_and_get() {
+        let counter = <Counter>::default();
+
+        assert_eq!(counter.inc_and_get(), 1);
+        assert_eq!(counter.inc_by_and_get(4), 5);
+        assert_eq!(counter.total(), 5);
+
+        let counter = Counter::<f64>::default();
+        assert_eq!(counter.inc_by_and_get(1.5), 1.5);
+        assert_eq!(counter.inc_and_get(), 2.5);
+        assert_eq!(counter.total(), 2.5);
+
+        let counter = Counter::<u32>::default();
+        counter.set(u32::MAX);
+        assert_eq!(counter.inc_and_get(), 0);
+    }
+
+    #[test]
+    fn test_counter_inc_and_get_concurrent() {
+        let counter = <Counter>::default();
+
+        let mut seen = std::thread::scope(|s| {
+            let handles = (0..4)
+                .map(|_| {
+                    let counter = counter.clone();
+                    s.spawn(move || (0..100).map(|_| counter.inc_and_get()).collect::<Vec<_>>())
+                })
+                .collect::<Vec<_>>();
+            handles.into_iter().flat_map(|h| h.join().unwrap()).collect::<Vec<_>>()
+        });
+        seen.sort_unstable();
+
+        assert_eq!(seen, (1..=400).collect::<Vec<_>>());
+        assert_eq!(counter.total(), 400);
+    }
+
     #[test]
     fn test_counter_set() {
         let counter = <Counter>::default();
@@ -464,6 +1143,46 @@ mod tests {
         clone.set(10);
     }
 
+    #[test]
+    fn test_counter_reset_for_testing() {
+        let created = Duration::from_secs(123);
+        let counter = <Counter>::with_created(created);
+        let clone = counter.clone();
+
+        counter.inc_by(42);
+        clone.reset_for_testing();
+        assert_eq!(counter.total(), 0);
+        assert_eq!(counter.created(), Some(created));
+
+        counter.inc();
+        assert_eq!(clone.total(), 1);
+    }
+
+    #[test]
+    fn test_local_counter_wraps_on_overflow() {
+        let local = LocalCounter::<u32>::default();
+        local.inc_by(u32::MAX);
+        local.inc_by(2);
+        assert_eq!(local.total(), 1);
+    }
+
+    #[test]
+    fn test_local_counter_reset_for_testing() {
+        let shared = <Counter>::default();
+        let local = <LocalCounter>::default();
+        local.inc_by(42);
+        local.reset_for_testing();
+        assert_eq!(local.total(), 0);
+        assert_eq!(local.merge_into(&shared), 0);
+        assert_eq!(shared.total(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "LazyCounter is read-only")]
+    fn test_lazy_counter_reset_for_testing_panic() {
+        LazyCounter::new(|| 42u64).reset_for_testing();
+    }
+
     #[test]
     fn test_counter_thread_safe() {
         let counter = <Counter>::default();
@@ -511,6 +1230,80 @@ mod tests {
         assert_eq!(counter.total(), 9);
     }
 
+    #[test]
+    fn test_counter_exemplar_text_encoding() {
+        check_text_encoding(
+            |registry| {
+                let counter = <Counter>::default();
+                registry.register("requests", "Total requests", counter.clone()).unwrap();
+                counter.inc();
+                counter.inc_by_with_exemplar(2, Exemplar::new([("trace_id", "abc123")], 0.0));
+                assert_eq!(counter.exemplar().map(|e| e.value()), Some(2.0));
+            },
+            |output| {
+                let expected = indoc::indoc! {r#"
+                    # TYPE requests counter
+                    # HELP requests Total requests
+                    requests_total 3 # {trace_id="abc123"} 2.0
+                    # EOF
+                "#};
+                assert_eq!(expected, output);
+            },
+        );
+    }
+
+    #[test]
+    fn test_sharded_counter() {
+        let counter = ShardedCounter::<u64>::new(4);
+        assert_eq!(counter.shards(), 4);
+        assert_eq!(counter.total(), 0);
+        assert!(counter.created().is_none());
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                let counter = counter.clone();
+                scope.spawn(move || {
+                    for _ in 0..1000 {
+                        counter.inc();
+                    }
+                    counter.inc_by(5);
+                });
+            }
+        });
+        assert_eq!(counter.total(), 8 * 1005);
+
+        let counter = ShardedCounter::<f64>::with_created(2, Duration::from_secs(123));
+        counter.inc_by(1.5);
+        assert_eq!(counter.total(), 1.5);
+        assert_eq!(counter.created(), Some(Duration::from_secs(123)));
+
+        assert!(<ShardedCounter>::default().shards() > 0);
+    }
+
+    #[test]
+    fn test_sharded_counter_text_encoding() {
+        check_text_encoding(
+            |registry| {
+                let counter = <ShardedCounter>::default();
+                registry.register("requests", "Total requests", counter.clone()).unwrap();
+                std::thread::scope(|scope| {
+                    for _ in 0..4 {
+                        scope.spawn(|| counter.inc_by(3));
+                    }
+                });
+            },
+            |output| {
+                let expected = indoc::indoc! {r#"
+                    # TYPE requests counter
+                    # HELP requests Total requests
+                    requests_total 12
+                    # EOF
+                "#};
+                assert_eq!(expected, output);
+            },
+        );
+    }
+
     #[test]
     fn test_const_counter() {
         let counter = ConstCounter::new(42u64);
@@ -627,4 +1420,58 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn test_sliding_window_counter() {
+        let clock = ManualClock::default();
+        let counter = SlidingWindowCounter::<6>::with_clock(Duration::from_secs(60), clock.clone());
+        assert_eq!(counter.window(), Duration::from_secs(60));
+
+        counter.inc();
+        counter.inc_by(2);
+        assert_eq!(counter.count(), 3);
+
+        clock.advance_by(Duration::from_secs(10));
+        counter.inc();
+        assert_eq!(counter.count(), 4);
+
+        // the first slot is still within the window
+        clock.advance_by(Duration::from_secs(49));
+        assert_eq!(counter.count(), 4);
+
+        // the first slot falls out of the window
+        clock.advance_by(Duration::from_secs(1));
+        assert_eq!(counter.count(), 1);
+
+        // the first slot is reused
+        counter.inc_by(5);
+        assert_eq!(counter.count(), 6);
+
+        clock.advance_by(Duration::from_secs(120));
+        assert_eq!(counter.count(), 0);
+    }
+
+    #[test]
+    fn test_sliding_window_counter_text_encoding() {
+        check_text_encoding(
+            |registry| {
+                let clock = ManualClock::default();
+                let counter =
+                    SlidingWindowCounter::<6>::with_clock(Duration::from_secs(60), clock.clone());
+                registry
+                    .register("errors", "Errors in the last minute", counter.clone())
+                    .unwrap();
+                counter.inc_by(7);
+            },
+            |output| {
+                let expected = indoc::indoc! {r#"
+                    # TYPE errors gauge
+                    # HELP errors Errors in the last minute
+                    errors 7
+                    # EOF
+                "#};
+                assert_eq!(expected, output);
+            },
+        );
+    }
 }
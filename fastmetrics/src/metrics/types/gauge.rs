@@ -1,6 +1,7 @@
 //! [Open Metrics Gauge](https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#gauge) metric type.
 //!
-//! See [`Gauge`], [`ConstGauge`] and [`LazyGauge`] for more details.
+//! See [`Gauge`], [`LocalGauge`], [`ConstGauge`], [`RefreshGauge`] and [`LazyGauge`] for more
+//! details.
 //!
 //! ## Overflow/underflow behavior
 //!
@@ -10,6 +11,7 @@
 //!   `inf`/`-inf`/`NaN`).
 
 use std::{
+    cell::Cell,
     fmt::{self, Debug},
     ops::{AddAssign, SubAssign},
     sync::{Arc, atomic::*},
@@ -19,7 +21,7 @@ use crate::{
     encoder::{EncodeGaugeValue, EncodeMetric, MetricEncoder},
     error::Result,
     metrics::internal::lazy::{LazySource, PlainLazySource},
-    raw::{Atomic, MetricLabelSet, MetricType, Number, TypedMetric},
+    raw::{AsF64, Atomic, MetricLabelSet, MetricType, Number, TypedMetric},
 };
 
 /// A marker trait for **gauge** metric value.
@@ -189,6 +191,181 @@ impl<N: GaugeValue> Gauge<N> {
     pub fn get(&self) -> N {
         self.value.get()
     }
+
+    /// Atomically replaces the value of the [`Gauge`] with `f(current)`, returning the previous
+    /// value.
+    ///
+    /// This uses a CAS loop, so `f` may be called multiple times under contention.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::gauge::Gauge;
+    /// let gauge = Gauge::<f64>::new(100.0);
+    /// // apply a decay factor
+    /// let previous = gauge.fetch_and_update(|v| v * 0.5);
+    /// assert_eq!(previous, 100.0);
+    /// assert_eq!(gauge.get(), 50.0);
+    /// ```
+    #[inline]
+    pub fn fetch_and_update(&self, f: impl FnMut(N) -> N) -> N {
+        self.value.fetch_update(f)
+    }
+
+    /// Stores `new` into the [`Gauge`] if its current value is `current`.
+    ///
+    /// Returns the previous value on success, or the actual current value on failure. Float
+    /// values are compared by their bit patterns, so `0.0` and `-0.0` are different, while a
+    /// stored `NaN` matches the same `NaN`. A `NaN` is never stored: if `new` is `NaN`, the
+    /// exchange fails with the current value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::sync::atomic::Ordering;
+    /// #
+    /// # use fastmetrics::metrics::gauge::Gauge;
+    /// let gauge = <Gauge>::new(5);
+    /// assert_eq!(gauge.compare_exchange(5, 10, Ordering::AcqRel, Ordering::Acquire), Ok(5));
+    /// assert_eq!(gauge.compare_exchange(5, 20, Ordering::AcqRel, Ordering::Acquire), Err(10));
+    /// ```
+    #[inline]
+    pub fn compare_exchange(
+        &self,
+        current: N,
+        new: N,
+        success: Ordering,
+        failure: Ordering,
+    ) -> std::result::Result<N, N> {
+        if is_nan(new) {
+            return Err(self.get());
+        }
+        self.value.compare_exchange(current, new, success, failure)
+    }
+
+    /// Atomically replaces the value of the [`Gauge`] with `f(current)` until it succeeds, or `f`
+    /// returns `None`.
+    ///
+    /// Returns the previous value on success, or `Err(current)` if `f` aborted the update. Like
+    /// [`Gauge::compare_exchange`], returning `Some(NaN)` aborts the update as well, so `NaN` is
+    /// never stored. `f` may be called multiple times under contention.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::gauge::Gauge;
+    /// let in_flight = <Gauge>::new(9);
+    /// let limit = 10;
+    /// // increment only while below the limit
+    /// assert_eq!(in_flight.fetch_update(|v| (v < limit).then_some(v + 1)), Ok(9));
+    /// assert_eq!(in_flight.fetch_update(|v| (v < limit).then_some(v + 1)), Err(10));
+    /// ```
+    pub fn fetch_update(&self, mut f: impl FnMut(N) -> Option<N>) -> std::result::Result<N, N> {
+        let mut current = self.get();
+        loop {
+            let new = match f(current) {
+                Some(new) if !is_nan(new) => new,
+                _ => return Err(current),
+            };
+            match self.value.compare_exchange(current, new, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(previous) => return Ok(previous),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Atomically sets the [`Gauge`] to `v` if `v` is greater than the current value, returning
+    /// the previous value.
+    ///
+    /// This is useful for high-water marks. A `NaN` value never replaces the current value, while
+    /// a `NaN` current value is always replaced.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::gauge::Gauge;
+    /// let peak = <Gauge>::new(10);
+    /// assert_eq!(peak.set_if_greater(5), 10);
+    /// assert_eq!(peak.set_if_greater(20), 10);
+    /// assert_eq!(peak.get(), 20);
+    /// ```
+    #[inline]
+    pub fn set_if_greater(&self, v: N) -> N {
+        self.value
+            .fetch_update(|current| if replaces(v, current, |v, c| v > c) { v } else { current })
+    }
+
+    /// Atomically sets the [`Gauge`] to `v` if `v` is less than the current value, returning the
+    /// previous value.
+    ///
+    /// This is useful for low-water marks. A `NaN` value never replaces the current value, while
+    /// a `NaN` current value is always replaced.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::gauge::Gauge;
+    /// let low = Gauge::<f64>::new(1.5);
+    /// assert_eq!(low.set_if_less(f64::NAN), 1.5);
+    /// assert_eq!(low.set_if_less(0.5), 1.5);
+    /// assert_eq!(low.get(), 0.5);
+    /// ```
+    #[inline]
+    pub fn set_if_less(&self, v: N) -> N {
+        self.value
+            .fetch_update(|current| if replaces(v, current, |v, c| v < c) { v } else { current })
+    }
+
+    /// Increases the [`Gauge`] by 1 and returns a guard which decreases it by 1 when dropped.
+    ///
+    /// This is useful to track the number of in-flight requests or active tasks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::gauge::Gauge;
+    /// let in_flight = <Gauge>::default();
+    /// {
+    ///     let _guard = in_flight.track_in_flight();
+    ///     assert_eq!(in_flight.get(), 1);
+    /// }
+    /// assert_eq!(in_flight.get(), 0);
+    /// ```
+    pub fn track_in_flight(&self) -> InFlightGuard<N> {
+        self.inc();
+        InFlightGuard { gauge: self.clone() }
+    }
+}
+
+/// A guard which decreases a [`Gauge`] by 1 when dropped, see [`Gauge::track_in_flight`].
+#[must_use = "the gauge is decreased as soon as the guard is dropped"]
+pub struct InFlightGuard<N: GaugeValue = i64> {
+    gauge: Gauge<N>,
+}
+
+impl<N: GaugeValue> Debug for InFlightGuard<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InFlightGuard").field("gauge", &self.gauge).finish()
+    }
+}
+
+impl<N: GaugeValue> Drop for InFlightGuard<N> {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
+
+// `NaN` is the only value not equal to itself.
+#[inline]
+#[allow(clippy::eq_op)]
+fn is_nan<N: PartialOrd>(v: N) -> bool {
+    v != v
+}
+
+// Whether `v` should replace `current`: `NaN` never wins.
+#[inline]
+fn replaces<N: PartialOrd + Copy>(v: N, current: N, wins: impl FnOnce(&N, &N) -> bool) -> bool {
+    !is_nan(v) && (is_nan(current) || wins(&v, &current))
 }
 
 impl<N: GaugeValue> TypedMetric for Gauge<N> {
@@ -239,8 +416,111 @@ impl<N: EncodeGaugeValue + GaugeValue> EncodeMetric for Gauge<N> {
     }
 }
 
+/// A single-producer gauge for high-frequency writes by one thread.
+///
+/// Updates are plain (non-atomic) writes, so a [`LocalGauge`] can't be shared between threads or
+/// registered. Instead, its value is pushed into a shared [`Gauge`] with [`LocalGauge::flush`],
+/// e.g. by each worker at the end of a unit of work, or by a finalizer before scraping.
+///
+/// Unlike [`Gauge`], integer overflow follows the standard arithmetic operators instead of
+/// wrapping.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::metrics::gauge::{FlushMode, Gauge, LocalGauge};
+/// #
+/// let queue_len = <Gauge>::default();
+///
+/// let local = <LocalGauge>::default();
+/// local.inc_by(10);
+/// local.dec();
+/// local.flush(&queue_len, FlushMode::Keep);
+/// assert_eq!(queue_len.get(), 9);
+/// assert_eq!(local.get(), 9);
+/// ```
+#[derive(Default)]
+pub struct LocalGauge<N: GaugeValue = i64> {
+    value: Cell<N>,
+}
+
+/// What [`LocalGauge::flush`] does with the local value after storing it into the target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlushMode {
+    /// Resets the local value to zero.
+    Reset,
+    /// Keeps the local value.
+    Keep,
+}
+
+impl<N: GaugeValue> Debug for LocalGauge<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalGauge").field("value", &self.get()).finish()
+    }
+}
+
+impl<N: GaugeValue> LocalGauge<N> {
+    /// Creates a new [`LocalGauge`] with an initial value.
+    pub const fn new(value: N) -> Self {
+        Self { value: Cell::new(value) }
+    }
+
+    /// Increases the [`LocalGauge`] by 1.
+    #[inline]
+    pub fn inc(&self) {
+        self.inc_by(N::ONE);
+    }
+
+    /// Increases the [`LocalGauge`] by `v`.
+    #[inline]
+    pub fn inc_by(&self, v: N) {
+        let mut value = self.value.get();
+        value += v;
+        self.value.set(value);
+    }
+
+    /// Decreases the [`LocalGauge`] by 1.
+    #[inline]
+    pub fn dec(&self) {
+        self.dec_by(N::ONE);
+    }
+
+    /// Decreases the [`LocalGauge`] by `v`.
+    #[inline]
+    pub fn dec_by(&self, v: N) {
+        let mut value = self.value.get();
+        value -= v;
+        self.value.set(value);
+    }
+
+    /// Sets the [`LocalGauge`] to `v`.
+    #[inline]
+    pub fn set(&self, v: N) {
+        self.value.set(v);
+    }
+
+    /// Gets the current value of the [`LocalGauge`].
+    #[inline]
+    pub fn get(&self) -> N {
+        self.value.get()
+    }
+
+    /// Atomically stores the local value into `target`, then resets or keeps the local value
+    /// according to `mode`.
+    pub fn flush(&self, target: &Gauge<N>, mode: FlushMode) {
+        target.set(self.get());
+        if mode == FlushMode::Reset {
+            self.set(N::ZERO);
+        }
+    }
+}
+
 /// A **constant** `Gauge`, meaning it cannot be changed once created.
 ///
+/// A value that is static at runtime but should be read when it's encoded rather than captured at
+/// construction (e.g. a memory limit read from a cgroup file) can be provided with a
+/// [`RefreshGauge`] instead.
+///
 /// # Example
 ///
 /// ```rust
@@ -266,6 +546,38 @@ impl<N: GaugeValue> ConstGauge<N> {
     pub const fn get(&self) -> N {
         self.value
     }
+
+    /// Creates a new [`ConstGauge`] by applying `f` to the value of this one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::gauge::ConstGauge;
+    /// let kib = ConstGauge::new(2048_i64);
+    /// let mib = kib.map(|v| v / 1024);
+    /// assert_eq!(mib.get(), 2);
+    /// ```
+    pub fn map<M: GaugeValue>(&self, f: impl FnOnce(N) -> M) -> ConstGauge<M> {
+        ConstGauge::new(f(self.get()))
+    }
+
+    /// Creates a new `f64` [`ConstGauge`] whose value is the value of this one multiplied by
+    /// `scale`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::gauge::ConstGauge;
+    /// let bytes = ConstGauge::new(1_500_000_i64);
+    /// let megabytes = bytes.map_f64(1e-6);
+    /// assert_eq!(megabytes.get(), 1.5);
+    /// ```
+    pub fn map_f64(&self, scale: f64) -> ConstGauge<f64>
+    where
+        N: AsF64,
+    {
+        self.map(|v| v.as_f64() * scale)
+    }
 }
 
 impl<N> TypedMetric for ConstGauge<N> {
@@ -282,6 +594,100 @@ impl<N: EncodeGaugeValue + GaugeValue> EncodeMetric for ConstGauge<N> {
     }
 }
 
+/// A **constant** `Gauge` whose value is read by calling a function every time it's encoded.
+///
+/// This suits values that don't change at runtime but can't be captured at construction, e.g. a
+/// memory limit read from a cgroup file. Unlike [`LazyGauge`], the value is never cached or shared
+/// within a scrape, which keeps it suitable for cheap reads only.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::metrics::gauge::RefreshGauge;
+/// let cpus = RefreshGauge::new(|| {
+///     std::thread::available_parallelism().map_or(1, |n| n.get() as i64)
+/// });
+/// assert!(cpus.get() >= 1);
+/// ```
+#[doc(alias = "with_refresh")]
+#[derive(Clone)]
+pub struct RefreshGauge<N = i64> {
+    refresh: Arc<dyn Fn() -> N + Send + Sync>,
+}
+
+impl<N: GaugeValue> Debug for RefreshGauge<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RefreshGauge").field("value", &self.get()).finish()
+    }
+}
+
+impl<N: GaugeValue> RefreshGauge<N> {
+    /// Creates a new [`RefreshGauge`] reading its value from `f`.
+    pub fn new(f: impl Fn() -> N + Send + Sync + 'static) -> Self {
+        Self { refresh: Arc::new(f) }
+    }
+
+    /// Gets the current value of the [`RefreshGauge`], by calling its function.
+    #[inline]
+    pub fn get(&self) -> N {
+        (self.refresh)()
+    }
+
+    /// Creates a new [`RefreshGauge`] applying `f` to every value read by this one.
+    ///
+    /// Unlike [`ConstGauge::map`], `f` is called on every read, so the mapped gauge keeps
+    /// following the source value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::sync::{
+    /// #     Arc,
+    /// #     atomic::{AtomicI64, Ordering},
+    /// # };
+    /// # use fastmetrics::metrics::gauge::RefreshGauge;
+    /// let limit = Arc::new(AtomicI64::new(2048));
+    /// let kib = RefreshGauge::new({
+    ///     let limit = limit.clone();
+    ///     move || limit.load(Ordering::Relaxed)
+    /// });
+    /// let mib = kib.map(|v| v / 1024);
+    /// assert_eq!(mib.get(), 2);
+    /// limit.store(4096, Ordering::Relaxed);
+    /// assert_eq!(mib.get(), 4);
+    /// ```
+    pub fn map<M: GaugeValue>(&self, f: impl Fn(N) -> M + Send + Sync + 'static) -> RefreshGauge<M>
+    where
+        N: 'static,
+    {
+        let refresh = self.refresh.clone();
+        RefreshGauge::new(move || f(refresh()))
+    }
+
+    /// Creates a new `f64` [`RefreshGauge`] whose value is the value of this one multiplied by
+    /// `scale`, see [`RefreshGauge::map`].
+    pub fn map_f64(&self, scale: f64) -> RefreshGauge<f64>
+    where
+        N: AsF64 + 'static,
+    {
+        self.map(move |v| v.as_f64() * scale)
+    }
+}
+
+impl<N> TypedMetric for RefreshGauge<N> {
+    const TYPE: MetricType = MetricType::Gauge;
+}
+
+impl<N> MetricLabelSet for RefreshGauge<N> {
+    type LabelSet = ();
+}
+
+impl<N: EncodeGaugeValue + GaugeValue> EncodeMetric for RefreshGauge<N> {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        encoder.encode_gauge(&self.get())
+    }
+}
+
 /// A `Gauge` whose value is produced lazily every time it is encoded.
 ///
 /// This is ideal for process or system metrics that should only consult the OS
@@ -333,10 +739,24 @@ impl<N: GaugeValue + 'static> LazyGauge<N> {
     ///
     /// Mainly intended for debugging or tests; regular metric collection should
     /// let the encoder trigger the fetch during scrapes.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the gauge is derived from a
+    /// [`LazyGroup::new_fallible`](crate::metrics::lazy_group::LazyGroup::new_fallible) group
+    /// whose sampler returns `None`, use [`LazyGauge::try_fetch`] instead.
     #[inline]
     pub fn fetch(&self) -> N {
         self.source.load()
     }
+
+    /// Evaluates the underlying fetcher and returns the current value, or `None` if the sampler of
+    /// the fallible [`LazyGroup`](crate::metrics::lazy_group::LazyGroup) the gauge is derived
+    /// from returns `None`.
+    #[inline]
+    pub fn try_fetch(&self) -> Option<N> {
+        self.source.try_load()
+    }
 }
 
 impl<N> TypedMetric for LazyGauge<N> {
@@ -349,8 +769,15 @@ impl<N> MetricLabelSet for LazyGauge<N> {
 
 impl<N: EncodeGaugeValue + GaugeValue + 'static> EncodeMetric for LazyGauge<N> {
     fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
-        let value = self.fetch();
-        encoder.encode_gauge(&value)
+        match self.try_fetch() {
+            Some(value) => encoder.encode_gauge(&value),
+            None => Ok(()),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        // fallible grouped sources read the sample cached by the current scrape
+        self.source.may_be_empty() && self.try_fetch().is_none()
     }
 }
 
@@ -451,6 +878,218 @@ mod tests {
         assert_eq!(gauge.get(), 12);
     }
 
+    #[test]
+    fn test_gauge_fetch_and_update() {
+        let gauge = <Gauge>::new(3);
+        assert_eq!(gauge.fetch_and_update(|v| v * 2), 3);
+        assert_eq!(gauge.get(), 6);
+
+        let gauge = Gauge::<f64>::new(1.5);
+        assert_eq!(gauge.fetch_and_update(|v| v - 2.0), 1.5);
+        assert_eq!(gauge.get(), -0.5);
+
+        // concurrent multiplications must not lose any update
+        let gauge = <Gauge>::new(1);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                let gauge = gauge.clone();
+                s.spawn(move || {
+                    for _ in 0..10 {
+                        gauge.fetch_and_update(|v| v * 2);
+                    }
+                });
+            }
+        });
+        assert_eq!(gauge.get(), 1 << 40);
+    }
+
+    #[test]
+    fn test_gauge_compare_exchange() {
+        let gauge = <Gauge>::new(1);
+        assert_eq!(gauge.compare_exchange(1, 2, Ordering::AcqRel, Ordering::Acquire), Ok(1));
+        assert_eq!(gauge.compare_exchange(1, 3, Ordering::AcqRel, Ordering::Acquire), Err(2));
+        assert_eq!(gauge.get(), 2);
+
+        let gauge = Gauge::<f64>::new(0.5);
+        assert_eq!(gauge.compare_exchange(0.5, 1.5, Ordering::Relaxed, Ordering::Relaxed), Ok(0.5));
+        // NaN is never stored
+        assert_eq!(
+            gauge.compare_exchange(1.5, f64::NAN, Ordering::Relaxed, Ordering::Relaxed),
+            Err(1.5)
+        );
+        assert_eq!(gauge.get(), 1.5);
+        // floats are compared by their bits
+        gauge.set(0.0);
+        assert_eq!(
+            gauge.compare_exchange(-0.0, 1.0, Ordering::Relaxed, Ordering::Relaxed),
+            Err(0.0)
+        );
+        gauge.set(f64::NAN);
+        assert!(
+            gauge
+                .compare_exchange(f64::NAN, 1.0, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        );
+        assert_eq!(gauge.get(), 1.0);
+    }
+
+    #[test]
+    fn test_gauge_fetch_update() {
+        let gauge = <Gauge>::new(3);
+        assert_eq!(gauge.fetch_update(|v| Some(v * 2)), Ok(3));
+        assert_eq!(gauge.fetch_update(|v| (v > 10).then_some(0)), Err(6));
+        assert_eq!(gauge.get(), 6);
+
+        let gauge = Gauge::<f64>::new(2.0);
+        assert_eq!(gauge.fetch_update(|_| Some(f64::NAN)), Err(2.0));
+        assert_eq!(gauge.fetch_update(|v| Some(v.sqrt() * v.sqrt())), Ok(2.0));
+        assert!((gauge.get() - 2.0).abs() < 1e-12);
+
+        // a high-water mark without an external lock
+        let peak = <Gauge>::default();
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                let peak = peak.clone();
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        let v = i * 4 + t;
+                        let _ = peak.fetch_update(|current| (v > current).then_some(v));
+                    }
+                });
+            }
+        });
+        assert_eq!(peak.get(), 999 * 4 + 3);
+    }
+
+    #[test]
+    fn test_gauge_set_if_greater_or_less() {
+        let gauge = <Gauge>::new(10);
+        assert_eq!(gauge.set_if_greater(5), 10);
+        assert_eq!(gauge.get(), 10);
+        assert_eq!(gauge.set_if_greater(15), 10);
+        assert_eq!(gauge.get(), 15);
+        assert_eq!(gauge.set_if_less(20), 15);
+        assert_eq!(gauge.get(), 15);
+        assert_eq!(gauge.set_if_less(-3), 15);
+        assert_eq!(gauge.get(), -3);
+
+        let gauge = Gauge::<f64>::new(1.0);
+        assert_eq!(gauge.set_if_greater(f64::NAN), 1.0);
+        assert_eq!(gauge.set_if_less(f64::NAN), 1.0);
+        assert_eq!(gauge.get(), 1.0);
+        gauge.set(f64::NAN);
+        assert!(gauge.set_if_greater(-1.0).is_nan());
+        assert_eq!(gauge.get(), -1.0);
+
+        // concurrent writers must end up with the extreme values
+        let max = <Gauge>::default();
+        let min = <Gauge>::default();
+        std::thread::scope(|s| {
+            for t in 0..8 {
+                let (max, min) = (max.clone(), min.clone());
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        let v = if i % 2 == 0 { i * 8 + t } else { -(i * 8 + t) };
+                        max.set_if_greater(v);
+                        min.set_if_less(v);
+                    }
+                });
+            }
+        });
+        assert_eq!(max.get(), 998 * 8 + 7);
+        assert_eq!(min.get(), -(999 * 8 + 7));
+    }
+
+    #[test]
+    fn test_gauge_set_if_greater_or_less_stress() {
+        // xorshift64, seeded per thread, so the test doesn't depend on a RNG crate
+        fn values(seed: u64) -> impl Iterator<Item = f64> {
+            let mut state = seed;
+            std::iter::repeat_with(move || {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % 2_000_000) as f64 / 1000.0 - 1000.0
+            })
+        }
+
+        let max = Gauge::<f64>::new(f64::NAN);
+        let min = Gauge::<f64>::new(f64::NAN);
+        let extremes = std::thread::scope(|s| {
+            let handles = (1..=8u64)
+                .map(|seed| {
+                    let (max, min) = (max.clone(), min.clone());
+                    s.spawn(move || {
+                        let (mut local_max, mut local_min) = (f64::MIN, f64::MAX);
+                        for v in values(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)).take(10_000) {
+                            max.set_if_greater(v);
+                            min.set_if_less(v);
+                            local_max = local_max.max(v);
+                            local_min = local_min.min(v);
+                        }
+                        (local_max, local_min)
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+        });
+
+        let true_max = extremes.iter().map(|(max, _)| *max).fold(f64::MIN, f64::max);
+        let true_min = extremes.iter().map(|(_, min)| *min).fold(f64::MAX, f64::min);
+        assert_eq!(max.get(), true_max);
+        assert_eq!(min.get(), true_min);
+    }
+
+    #[test]
+    fn test_local_gauge_flush() {
+        let main = <Gauge>::default();
+
+        std::thread::scope(|s| {
+            let worker = s.spawn(|| {
+                let local = <LocalGauge>::default();
+                local.inc_by(5);
+                local.dec();
+                local.flush(&main, FlushMode::Keep);
+                local.get()
+            });
+            assert_eq!(worker.join().unwrap(), 4);
+            assert_eq!(main.get(), 4);
+
+            let worker = s.spawn(|| {
+                let local = LocalGauge::<i64>::new(10);
+                local.inc();
+                local.flush(&main, FlushMode::Reset);
+                local.get()
+            });
+            assert_eq!(worker.join().unwrap(), 0);
+            assert_eq!(main.get(), 11);
+        });
+
+        let local = LocalGauge::<f64>::new(1.5);
+        local.set(2.5);
+        let target = Gauge::<f64>::default();
+        local.flush(&target, FlushMode::Reset);
+        assert_eq!(target.get(), 2.5);
+        assert_eq!(local.get(), 0.0);
+    }
+
+    #[test]
+    fn test_gauge_track_in_flight() {
+        let gauge = <Gauge>::default();
+
+        let guard1 = gauge.track_in_flight();
+        let guard2 = gauge.track_in_flight();
+        let guard3 = gauge.track_in_flight();
+        assert_eq!(gauge.get(), 3);
+
+        drop(guard1);
+        drop(guard2);
+        assert_eq!(gauge.get(), 1);
+
+        drop(guard3);
+        assert_eq!(gauge.get(), 0);
+    }
+
     #[test]
     fn test_gauge_thread_safe() {
         let gauge = <Gauge>::default();
@@ -479,6 +1118,58 @@ mod tests {
         assert_eq!(clone.get(), 42);
     }
 
+    #[test]
+    fn test_refresh_gauge() {
+        use crate::{
+            format::text::{self, TextProfile},
+            registry::Registry,
+        };
+
+        let limit = Arc::new(AtomicI64::new(512));
+        let gauge = RefreshGauge::new({
+            let limit = limit.clone();
+            move || limit.load(Ordering::Relaxed)
+        });
+
+        let mut registry = Registry::default();
+        registry.register("memory_limit", "Memory limit", gauge.clone()).unwrap();
+        let encode = || {
+            let mut output = String::new();
+            text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+            output
+        };
+
+        // every encode reads the current value
+        assert!(encode().contains("memory_limit 512\n"));
+        limit.store(1024, Ordering::Relaxed);
+        assert!(encode().contains("memory_limit 1024\n"));
+        assert_eq!(gauge.clone().get(), 1024);
+
+        // mapped gauges keep reading the source
+        let kib = gauge.map(|v| v / 1024);
+        let bytes = gauge.map_f64(1024.0);
+        limit.store(2048, Ordering::Relaxed);
+        assert_eq!(kib.get(), 2);
+        assert_eq!(bytes.get(), 2_097_152.0);
+    }
+
+    #[test]
+    fn test_const_gauge_map() {
+        check_text_encoding(
+            |registry| {
+                let bytes = ConstGauge::new(3_145_728_i64);
+                let mebibytes = bytes.map(|v| v / (1 << 20));
+                let megabytes = bytes.map_f64(1e-6);
+                registry.register("memory_mebibytes", "Memory in MiB", mebibytes).unwrap();
+                registry.register("memory_megabytes", "Memory in MB", megabytes).unwrap();
+            },
+            |output| {
+                assert!(output.contains("memory_mebibytes 3\n"));
+                assert!(output.contains("memory_megabytes 3.145728\n"));
+            },
+        );
+    }
+
     #[test]
     fn test_lazy_gauge() {
         let value = Arc::new(AtomicI64::new(10));
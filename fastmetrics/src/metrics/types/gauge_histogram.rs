@@ -9,12 +9,15 @@ use std::{
 
 use crate::{
     encoder::{EncodeMetric, MetricEncoder},
-    error::Result,
+    error::{Error, Result},
     metrics::internal::histogram::{BoundsFilter, HistogramCore},
     raw::{MetricLabelSet, MetricType, TypedMetric},
 };
 pub use crate::{metrics::internal::histogram::HistogramSnapshot, raw::bucket::*};
 
+/// A snapshot of a [`GaugeHistogram`], see [`GaugeHistogram::snapshot`].
+pub type GaugeHistogramSnapshot = HistogramSnapshot;
+
 /// Open Metrics [`GaugeHistogram`] metric, which samples observations and counts them in
 /// configurable buckets.
 ///
@@ -118,6 +121,64 @@ impl GaugeHistogram {
         let snapshot = self.inner.snapshot();
         func(&snapshot)
     }
+
+    /// Returns a consistent snapshot of the buckets, `gcount` and `gsum` of the
+    /// [`GaugeHistogram`].
+    pub fn snapshot(&self) -> GaugeHistogramSnapshot {
+        self.inner.snapshot()
+    }
+
+    /// Replaces the current distribution with the one of `snapshot`, returning a snapshot of the
+    /// distribution before the replacement.
+    ///
+    /// Unlike a [`Histogram`](crate::metrics::histogram::Histogram), the distribution of a gauge
+    /// histogram may go down as well as up, e.g. when it tracks the age of the items currently
+    /// held in a queue. Observations made concurrently with the replacement are kept on top of
+    /// `snapshot`.
+    ///
+    /// Returns an error if the bucket boundaries of the snapshot differ from this gauge
+    /// histogram's.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use fastmetrics::{error::Result, metrics::gauge_histogram::GaugeHistogram};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let queue_age = GaugeHistogram::new([1.0, 10.0]);
+    /// queue_age.observe(0.5);
+    /// queue_age.observe(5.0);
+    ///
+    /// // recompute the distribution of the items still queued
+    /// let current = GaugeHistogram::new([1.0, 10.0]);
+    /// current.observe(7.0);
+    /// queue_age.reset_to(&current.snapshot())?;
+    ///
+    /// let snapshot = queue_age.snapshot();
+    /// assert_eq!(snapshot.count(), 1);
+    /// assert_eq!(snapshot.sum(), 7.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reset_to(&self, snapshot: &GaugeHistogramSnapshot) -> Result<GaugeHistogramSnapshot> {
+        let upper_bounds = self.inner.upper_bounds();
+        let matches = upper_bounds.len() == snapshot.buckets().len()
+            && upper_bounds.iter().zip(snapshot.buckets()).all(|(a, b)| *a == b.upper_bound());
+        if !matches {
+            return Err(Error::invalid("gauge histogram bucket boundaries don't match")
+                .with_context("expected_buckets", format!("{upper_bounds:?}"))
+                .with_context(
+                    "actual_buckets",
+                    format!(
+                        "{:?}",
+                        snapshot.buckets().iter().map(Bucket::upper_bound).collect::<Vec<_>>()
+                    ),
+                ));
+        }
+        let previous = self.inner.reset(None);
+        self.inner.merge(snapshot);
+        Ok(previous)
+    }
 }
 
 impl TypedMetric for GaugeHistogram {
@@ -227,6 +288,39 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_gauge_histogram_reset_to() {
+        let hist = GaugeHistogram::new(vec![1.0, 10.0]);
+        for value in [0.5, 5.0, 50.0] {
+            hist.observe(value);
+        }
+
+        let current = GaugeHistogram::new(vec![1.0, 10.0]);
+        current.observe(2.0);
+        current.observe(3.0);
+
+        let previous = hist.reset_to(&current.snapshot()).unwrap();
+        assert_eq!(previous.count(), 3);
+        assert_eq!(previous.sum(), 55.5);
+
+        // the distribution went down
+        let snapshot = hist.snapshot();
+        let counts = snapshot.buckets().iter().map(Bucket::count).collect::<Vec<_>>();
+        assert_eq!(counts, [0, 2, 0]);
+        assert_eq!(snapshot.count(), 2);
+        assert_eq!(snapshot.sum(), 5.0);
+
+        // observations keep adding up after the replacement
+        hist.observe(-1.0);
+        assert_eq!(hist.snapshot().count(), 3);
+        assert_eq!(hist.snapshot().sum(), 4.0);
+
+        let other = GaugeHistogram::new(vec![1.0, 2.0]);
+        let err = hist.reset_to(&other.snapshot()).unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::Invalid);
+        assert_eq!(hist.snapshot().count(), 3);
+    }
+
     #[test]
     fn test_text_encoding() {
         check_text_encoding(
@@ -9,12 +9,18 @@ use std::{
 };
 
 use crate::{
-    encoder::{EncodeMetric, MetricEncoder},
-    error::Result,
-    metrics::internal::histogram::{BoundsFilter, HistogramCore},
+    encoder::{EncodeExemplar, EncodeMetric, MetricEncoder},
+    error::{Error, Result},
+    metrics::internal::{
+        histogram::{BoundsFilter, HistogramCore, normalize_bounds},
+        lazy::{LazySource, PlainLazySource},
+    },
     raw::{MetricLabelSet, MetricType, TypedMetric},
 };
-pub use crate::{metrics::internal::histogram::HistogramSnapshot, raw::bucket::*};
+pub use crate::{
+    metrics::internal::histogram::HistogramSnapshot,
+    raw::{bucket::*, exemplar::Exemplar},
+};
 
 /// Open Metrics [`Histogram`] metric, which samples observations and counts them in configurable
 /// buckets.
@@ -62,19 +68,16 @@ pub use crate::{metrics::internal::histogram::HistogramSnapshot, raw::bucket::*}
 #[derive(Clone)]
 pub struct Histogram {
     inner: Arc<HistogramCore>,
-    // UNIX timestamp
-    created: Option<Duration>,
 }
 
 impl Debug for Histogram {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let created = self.created();
         self.with_snapshot(|snapshot| {
             f.debug_struct("Histogram")
                 .field("buckets", &snapshot.buckets())
                 .field("sum", &snapshot.sum())
                 .field("count", &snapshot.count())
-                .field("created", &created)
+                .field("created", &snapshot.created())
                 .finish()
         })
     }
@@ -89,18 +92,120 @@ impl Default for Histogram {
 impl Histogram {
     /// Creates a new [`Histogram`] with the given bucket boundaries.
     pub fn new(buckets: impl IntoIterator<Item = f64>) -> Self {
-        Self {
-            inner: Arc::new(HistogramCore::from_bounds(buckets, BoundsFilter::RejectNegative)),
-            created: None,
-        }
+        Self { inner: Arc::new(HistogramCore::from_bounds(buckets, BoundsFilter::RejectNegative)) }
     }
 
     /// Creates a [`Histogram`] with a `created` timestamp.
     pub fn with_created(buckets: impl IntoIterator<Item = f64>, created: Duration) -> Self {
         Self {
-            inner: Arc::new(HistogramCore::from_bounds(buckets, BoundsFilter::RejectNegative)),
-            created: Some(created),
+            inner: Arc::new(
+                HistogramCore::from_bounds(buckets, BoundsFilter::RejectNegative)
+                    .with_created(Some(created)),
+            ),
+        }
+    }
+
+    /// Creates a new [`Histogram`] with the bucket layout of `config`.
+    pub fn from_config(config: &HistogramConfig) -> Self {
+        Self::new(config.upper_bounds().iter().copied())
+    }
+
+    /// Creates a new [`Histogram`] with `count` linearly spaced buckets, see [`linear_buckets`].
+    ///
+    /// Returns an error if `width` is not positive or `count` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{error::Result, metrics::histogram::Histogram};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// // 0, 5, 10 and +Inf
+    /// let queue_depth = Histogram::with_linear_buckets(0.0, 5.0, 3)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_linear_buckets(start: f64, width: f64, count: usize) -> Result<Self> {
+        if !start.is_finite() {
+            return Err(Error::invalid("linear buckets must have a finite start value")
+                .with_context("start", start));
+        }
+        if !(width > 0.0 && width.is_finite()) {
+            return Err(Error::invalid("linear buckets must have a width greater than 0")
+                .with_context("width", width));
+        }
+        if count == 0 {
+            return Err(Error::invalid("linear buckets must have at least 1 bucket"));
+        }
+        Ok(Self::new(linear_buckets(start, width, count)))
+    }
+
+    /// Creates a new [`Histogram`] with `count` exponentially spaced buckets, see
+    /// [`exponential_buckets`].
+    ///
+    /// Returns an error if `start` is not positive, `factor` is not greater than 1 or `count` is
+    /// 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{error::Result, metrics::histogram::Histogram};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// // 0.001, 0.002, 0.004, ..., 0.512 and +Inf
+    /// let latency = Histogram::with_exponential_buckets(0.001, 2.0, 10)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_exponential_buckets(start: f64, factor: f64, count: usize) -> Result<Self> {
+        if !(start > 0.0 && start.is_finite()) {
+            return Err(Error::invalid("exponential buckets must have a positive start value")
+                .with_context("start", start));
+        }
+        if !(factor > 1.0 && factor.is_finite()) {
+            return Err(Error::invalid("exponential buckets must have a factor greater than 1")
+                .with_context("factor", factor));
+        }
+        if count == 0 {
+            return Err(Error::invalid("exponential buckets must have at least 1 bucket"));
         }
+        Ok(Self::new(exponential_buckets(start, factor, count)))
+    }
+
+    /// Creates a new [`Histogram`] with `count` exponentially spaced buckets from `min` to
+    /// `max`, see [`exponential_buckets_range`].
+    ///
+    /// Returns an error if `min` is not positive, `max` is not greater than `min` or `count` is
+    /// less than 2.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{error::Result, metrics::histogram::Histogram};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// // 1, 10, 100, 1000 and +Inf
+    /// let sizes = Histogram::with_exponential_buckets_range(1.0, 1000.0, 4)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_exponential_buckets_range(min: f64, max: f64, count: usize) -> Result<Self> {
+        if !(min > 0.0 && min.is_finite()) {
+            return Err(Error::invalid("exponential buckets must have a positive min value")
+                .with_context("min", min));
+        }
+        if !(max > min && max.is_finite()) {
+            return Err(Error::invalid(
+                "exponential buckets must have a max value greater than min",
+            )
+            .with_context("min", min)
+            .with_context("max", max));
+        }
+        if count < 2 {
+            return Err(Error::invalid("exponential buckets range must have at least 2 buckets")
+                .with_context("count", count));
+        }
+        Ok(Self::new(exponential_buckets_range(min, max, count)))
     }
 
     /// Observes a value, incrementing the appropriate buckets.
@@ -113,6 +218,72 @@ impl Histogram {
         self.inner.observe(value);
     }
 
+    /// Observes a value like [`Histogram::observe`] does, recording `exemplar` as the exemplar of
+    /// the bucket the value falls into.
+    ///
+    /// The value of the exemplar is set to the observed value. Only the exemplar of the latest
+    /// observation is kept per bucket, and exemplars are only exposed by the OpenMetrics formats.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::histogram::{Exemplar, Histogram, linear_buckets};
+    /// #
+    /// let latency = Histogram::new(linear_buckets(0.1, 0.1, 3));
+    /// latency.observe_with_exemplar(0.25, Exemplar::new([("trace_id", "abc123")], 0.0));
+    ///
+    /// let exemplars = latency.exemplars();
+    /// assert_eq!(exemplars[2].as_ref().map(|e| e.value()), Some(0.25));
+    /// ```
+    pub fn observe_with_exemplar(&self, value: f64, exemplar: Exemplar) {
+        // value MUST NOT be NaN or negative
+        if value.is_nan() || value.is_sign_negative() {
+            return;
+        }
+
+        self.inner.observe(value);
+        let idx = self.inner.bucket_index(value);
+        self.inner.set_exemplar(idx, exemplar.with_value(value));
+    }
+
+    /// Returns the exemplar of each bucket, including the `+Inf` bucket, see
+    /// [`Histogram::observe_with_exemplar`].
+    pub fn exemplars(&self) -> Vec<Option<Exemplar>> {
+        match self.inner.exemplars() {
+            Some(exemplars) => exemplars.lock().clone(),
+            None => vec![None; self.inner.upper_bounds().len()],
+        }
+    }
+
+    /// Observes a batch of values.
+    ///
+    /// This is equivalent to calling [`Histogram::observe`] for each value, but the values are
+    /// sorted and swept through the buckets once, so each touched bucket, the count and the sum
+    /// are updated only once for the whole batch. This reduces contention when recording many
+    /// values at once, e.g. the latencies of every item of a batch response.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::histogram::{Histogram, linear_buckets};
+    /// #
+    /// let hist = Histogram::new(linear_buckets(1.0, 1.0, 3));
+    /// hist.observe_many([0.5, 2.5, 0.7, 10.0]);
+    ///
+    /// hist.with_snapshot(|s| {
+    ///     assert_eq!(s.count(), 4);
+    ///     assert_eq!(s.buckets()[0].count(), 2);
+    /// });
+    /// ```
+    pub fn observe_many(&self, values: impl IntoIterator<Item = f64>) {
+        // values MUST NOT be NaN or negative
+        let mut values = values
+            .into_iter()
+            .filter(|value| !value.is_nan() && !value.is_sign_negative())
+            .collect::<Vec<_>>();
+        self.inner.observe_many(&mut values);
+    }
+
     /// Provides temporary access to a snapshot of the histogram's current state.
     ///
     /// # Arguments
@@ -140,13 +311,128 @@ impl Histogram {
     where
         F: FnOnce(&HistogramSnapshot) -> R,
     {
-        let snapshot = self.inner.snapshot();
+        let snapshot = self.snapshot();
         func(&snapshot)
     }
 
+    /// Takes a consistent snapshot of the histogram's buckets, sum, count and `created` timestamp.
+    ///
+    /// Observations made concurrently with the snapshot are either fully included or not at all,
+    /// so the bucket counts always add up to `count()` and `sum()` matches them.
+    ///
+    /// The snapshot can be encoded on its own, since it implements [`EncodeMetric`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use fastmetrics::metrics::histogram::{Histogram, linear_buckets};
+    /// #
+    /// let hist = Histogram::new(linear_buckets(1.0, 1.0, 3));
+    /// hist.observe_many([0.5, 1.5, 2.5]);
+    ///
+    /// let snapshot = hist.snapshot();
+    /// assert_eq!(snapshot.count(), 3);
+    /// assert_eq!(snapshot.sum(), 4.5);
+    /// assert_eq!(snapshot.quantile(0.5), Some(1.5));
+    /// ```
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        self.inner.snapshot()
+    }
+
+    /// Resets the buckets, sum and count of the [`Histogram`] (and all its clones) to zero,
+    /// keeping the bucket boundaries and the `created` timestamp.
+    ///
+    /// This is useful to isolate test cases sharing a histogram, or to aggregate observations over
+    /// rolling windows. Observations made concurrently with the reset are never lost: they are
+    /// either part of the returned snapshot of the previous window or recorded after the reset.
+    ///
+    /// Note that Prometheus treats a decreasing `_count` as a counter reset, so use
+    /// [`Histogram::reset_with_created`] to also signal the reset through the `created` timestamp.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use fastmetrics::metrics::histogram::{Histogram, linear_buckets};
+    /// #
+    /// let hist = Histogram::new(linear_buckets(1.0, 1.0, 3));
+    /// hist.observe_many([0.5, 1.5]);
+    ///
+    /// let window = hist.reset();
+    /// assert_eq!(window.count(), 2);
+    /// assert_eq!(hist.snapshot().count(), 0);
+    /// ```
+    pub fn reset(&self) -> HistogramSnapshot {
+        self.inner.reset(None)
+    }
+
+    /// Resets the [`Histogram`] like [`Histogram::reset`] does, and sets its `created` timestamp
+    /// to `created`.
+    pub fn reset_with_created(&self, created: Duration) -> HistogramSnapshot {
+        self.inner.reset(Some(created))
+    }
+
+    /// Adds the bucket counts, sum and count of `other` into this [`Histogram`].
+    ///
+    /// This allows aggregating shards, e.g. a histogram per worker thread, into the registered
+    /// histogram before scraping. `other` is read through a consistent [`Histogram::snapshot`]
+    /// and left unchanged; use [`Histogram::reset`] and merge the returned snapshot instead with
+    /// [`Histogram::merge_snapshot`] to move the observations.
+    ///
+    /// Returns an error if the bucket boundaries of the histograms differ.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use fastmetrics::{error::Result, metrics::histogram::{Histogram, linear_buckets}};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let total = Histogram::new(linear_buckets(1.0, 1.0, 3));
+    /// // one shard per worker, not sharing observations with `total` unlike its clones
+    /// let shards = [Histogram::from_config(&total.config()), Histogram::from_config(&total.config())];
+    /// shards[0].observe(0.5);
+    /// shards[1].observe(2.5);
+    ///
+    /// for shard in &shards {
+    ///     total.merge_from(shard)?;
+    /// }
+    /// assert_eq!(total.snapshot().count(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge_from(&self, other: &Histogram) -> Result<()> {
+        self.merge_snapshot(&other.snapshot())
+    }
+
+    /// Adds the bucket counts, sum and count of `snapshot` into this [`Histogram`].
+    ///
+    /// Returns an error if the bucket boundaries of the snapshot differ from this histogram's.
+    pub fn merge_snapshot(&self, snapshot: &HistogramSnapshot) -> Result<()> {
+        let upper_bounds = self.inner.upper_bounds();
+        let matches = upper_bounds.len() == snapshot.buckets().len()
+            && upper_bounds.iter().zip(snapshot.buckets()).all(|(a, b)| *a == b.upper_bound());
+        if !matches {
+            return Err(Error::invalid("histogram bucket boundaries don't match")
+                .with_context("expected_buckets", format!("{upper_bounds:?}"))
+                .with_context(
+                    "actual_buckets",
+                    format!(
+                        "{:?}",
+                        snapshot.buckets().iter().map(Bucket::upper_bound).collect::<Vec<_>>()
+                    ),
+                ));
+        }
+        self.inner.merge(snapshot);
+        Ok(())
+    }
+
+    /// Returns the bucket layout of the [`Histogram`], including the `+Inf` bound.
+    pub fn config(&self) -> HistogramConfig {
+        HistogramConfig::new(self.inner.upper_bounds().iter().copied())
+    }
+
     /// Gets the optional `created` value of the [`Histogram`].
-    pub const fn created(&self) -> Option<Duration> {
-        self.created
+    pub fn created(&self) -> Option<Duration> {
+        self.inner.created()
     }
 }
 
@@ -160,12 +446,173 @@ impl MetricLabelSet for Histogram {
 
 impl EncodeMetric for Histogram {
     fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
-        let created = self.created();
-        self.with_snapshot(|s| {
-            let buckets = s.buckets();
-            let exemplars = None;
-            encoder.encode_histogram(buckets, exemplars, s.count(), s.sum(), created)
-        })
+        let snapshot = self.snapshot();
+        let Some(exemplars) = self.inner.exemplars() else {
+            return snapshot.encode(encoder);
+        };
+
+        let exemplars = exemplars.lock();
+        let exemplars = exemplars
+            .iter()
+            .map(|exemplar| exemplar.as_ref().map(|exemplar| exemplar as &dyn EncodeExemplar))
+            .collect::<Vec<_>>();
+        encoder.encode_histogram(
+            snapshot.buckets(),
+            Some(&exemplars),
+            snapshot.count(),
+            snapshot.sum(),
+            snapshot.created(),
+        )
+    }
+}
+
+impl TypedMetric for HistogramSnapshot {
+    const TYPE: MetricType = MetricType::Histogram;
+}
+
+impl MetricLabelSet for HistogramSnapshot {
+    type LabelSet = ();
+}
+
+impl EncodeMetric for HistogramSnapshot {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        encoder.encode_histogram(self.buckets(), None, self.count(), self.sum(), self.created())
+    }
+}
+
+/// Bucket layout of histograms, e.g. to create [`Histogram`] shards with the same buckets, or
+/// for histograms whose observations are recorded elsewhere, such as [`LazyHistogram`].
+///
+/// The bounds are normalized like [`Histogram::new`] does: NaN and negative bounds are dropped,
+/// the remaining ones are sorted and deduplicated, and a `+Inf` bound is always included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramConfig {
+    upper_bounds: Vec<f64>,
+}
+
+impl Default for HistogramConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUCKETS)
+    }
+}
+
+impl HistogramConfig {
+    /// Creates a new [`HistogramConfig`] with the given bucket boundaries.
+    pub fn new(buckets: impl IntoIterator<Item = f64>) -> Self {
+        Self { upper_bounds: normalize_bounds(buckets, BoundsFilter::RejectNegative) }
+    }
+
+    /// Returns the normalized bucket upper bounds, including the `+Inf` bound.
+    pub fn upper_bounds(&self) -> &[f64] {
+        &self.upper_bounds
+    }
+}
+
+/// A histogram sample produced by the fetcher of a [`LazyHistogram`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HistogramSample {
+    /// Non-cumulative bucket counts, one per [`HistogramConfig::upper_bounds`] entry.
+    pub buckets: Vec<u64>,
+    /// Sum of all observations.
+    pub sum: f64,
+    /// Number of observations.
+    pub count: u64,
+}
+
+/// A histogram whose sample is computed lazily at scrape time.
+///
+/// This is useful when the distribution is already tracked by another system (e.g. the OS or a
+/// runtime), and should only be read when metrics are collected.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// #
+/// # use fastmetrics::metrics::histogram::{HistogramConfig, HistogramSample, LazyHistogram};
+/// #
+/// let config = Arc::new(HistogramConfig::new([0.1, 1.0]));
+/// let lazy = LazyHistogram::new(
+///     || HistogramSample { buckets: vec![3, 1, 0], sum: 0.9, count: 4 },
+///     config,
+/// );
+/// assert_eq!(lazy.fetch().count, 4);
+/// ```
+///
+/// # Grouped sampling
+///
+/// When constructed via [`crate::metrics::lazy_group::LazyGroup`], multiple lazy histograms can
+/// share a single expensive sample per scrape.
+pub struct LazyHistogram {
+    source: Arc<dyn LazySource<HistogramSample>>,
+    config: Arc<HistogramConfig>,
+}
+
+impl Clone for LazyHistogram {
+    fn clone(&self) -> Self {
+        Self { source: self.source.clone(), config: self.config.clone() }
+    }
+}
+
+impl LazyHistogram {
+    /// Internal: constructs a lazy histogram from a source.
+    ///
+    /// This is used by crate-internal glue (e.g. `metrics::lazy_group`) to build a
+    /// `LazyHistogram` without exposing additional public types.
+    pub(crate) fn from_source(
+        source: Arc<dyn LazySource<HistogramSample>>,
+        config: Arc<HistogramConfig>,
+    ) -> Self {
+        Self { source, config }
+    }
+
+    /// Creates a new [`LazyHistogram`] from the provided fetcher function or closure.
+    pub fn new(
+        fetch: impl Fn() -> HistogramSample + Send + Sync + 'static,
+        config: Arc<HistogramConfig>,
+    ) -> Self {
+        Self::from_source(Arc::new(PlainLazySource::new(Arc::new(fetch))), config)
+    }
+
+    /// Evaluates the underlying fetcher and returns the current sample.
+    ///
+    /// Mainly intended for debugging or tests; regular metric collection should
+    /// let the encoder trigger the fetch during scrapes.
+    #[inline]
+    pub fn fetch(&self) -> HistogramSample {
+        self.source.load()
+    }
+
+    /// Returns the bucket layout of the histogram.
+    pub fn config(&self) -> &HistogramConfig {
+        &self.config
+    }
+}
+
+impl TypedMetric for LazyHistogram {
+    const TYPE: MetricType = MetricType::Histogram;
+}
+
+impl MetricLabelSet for LazyHistogram {
+    type LabelSet = ();
+}
+
+impl EncodeMetric for LazyHistogram {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        let sample = self.fetch();
+        let upper_bounds = self.config.upper_bounds();
+        if sample.buckets.len() != upper_bounds.len() {
+            return Err(Error::invalid("histogram sample doesn't match the configured buckets")
+                .with_context("expected_buckets", upper_bounds.len())
+                .with_context("actual_buckets", sample.buckets.len()));
+        }
+
+        let buckets = upper_bounds
+            .iter()
+            .zip(&sample.buckets)
+            .map(|(&upper_bound, &count)| Bucket::new(upper_bound, count))
+            .collect::<Vec<_>>();
+        encoder.encode_histogram(&buckets, None, sample.count, sample.sum, None)
     }
 }
 
@@ -204,6 +651,56 @@ mod tests {
         assert!(hist.created().is_some());
     }
 
+    #[test]
+    fn test_histogram_with_linear_buckets() {
+        let hist = Histogram::with_linear_buckets(0.0, 5.0, 3).unwrap();
+        hist.observe(7.0);
+        hist.with_snapshot(|s| {
+            let buckets =
+                s.buckets().iter().map(|b| (b.upper_bound(), b.count())).collect::<Vec<_>>();
+            assert_eq!(buckets, [(0.0, 0), (5.0, 0), (10.0, 1), (f64::INFINITY, 0)]);
+        });
+
+        for (start, width, count) in
+            [(0.0, 0.0, 3), (0.0, -1.0, 3), (f64::NAN, 1.0, 3), (0.0, 1.0, 0)]
+        {
+            let err = Histogram::with_linear_buckets(start, width, count).unwrap_err();
+            assert_eq!(err.kind(), crate::error::ErrorKind::Invalid);
+        }
+    }
+
+    #[test]
+    fn test_histogram_with_exponential_buckets() {
+        let hist = Histogram::with_exponential_buckets(0.001, 2.0, 10).unwrap();
+        hist.with_snapshot(|s| {
+            let bounds = s.buckets().iter().map(|b| b.upper_bound()).collect::<Vec<_>>();
+            assert_eq!(bounds.len(), 11);
+            assert_eq!(bounds.iter().filter(|bound| bound.is_finite()).count(), 10);
+            assert_eq!(bounds[0], 0.001);
+            assert!((bounds[9] - 0.512).abs() < 1e-12);
+            assert_eq!(bounds[10], f64::INFINITY);
+        });
+
+        let hist = Histogram::with_exponential_buckets_range(1.0, 1000.0, 4).unwrap();
+        hist.with_snapshot(|s| {
+            let bounds = s.buckets().iter().map(|b| b.upper_bound()).collect::<Vec<_>>();
+            assert_eq!(bounds.len(), 5);
+            assert_eq!(bounds[0], 1.0);
+            assert!((bounds[3] - 1000.0).abs() < 1e-9);
+        });
+
+        for (start, factor, count) in
+            [(0.0, 2.0, 10), (f64::NAN, 2.0, 10), (1.0, 1.0, 10), (1.0, 2.0, 0)]
+        {
+            let err = Histogram::with_exponential_buckets(start, factor, count).unwrap_err();
+            assert_eq!(err.kind(), crate::error::ErrorKind::Invalid);
+        }
+        for (min, max, count) in [(0.0, 10.0, 4), (10.0, 10.0, 4), (1.0, 10.0, 1)] {
+            let err = Histogram::with_exponential_buckets_range(min, max, count).unwrap_err();
+            assert_eq!(err.kind(), crate::error::ErrorKind::Invalid);
+        }
+    }
+
     #[test]
     fn test_histogram_observe() {
         let hist = Histogram::new(vec![1.0, 2.0, 5.0]);
@@ -224,6 +721,28 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_histogram_observe_many() {
+        let values = [6.0, 1.5, 0.5, 2.0, f64::INFINITY, 3.0, 1.0, -1.0, f64::NAN, 0.0];
+
+        let single = Histogram::new(vec![1.0, 2.0, 5.0]);
+        for value in values {
+            single.observe(value);
+        }
+        let batch = Histogram::new(vec![1.0, 2.0, 5.0]);
+        batch.observe_many(values);
+        batch.observe_many([]);
+
+        let counts = |hist: &Histogram| {
+            hist.with_snapshot(|s| {
+                let buckets = s.buckets().iter().map(|b| b.count()).collect::<Vec<_>>();
+                (buckets, s.count(), s.sum())
+            })
+        };
+        assert_eq!(counts(&batch), (vec![3, 2, 1, 2], 8, f64::INFINITY));
+        assert_eq!(counts(&batch), counts(&single));
+    }
+
     #[test]
     fn test_histogram_invalid_observations() {
         let hist = Histogram::default();
@@ -260,6 +779,155 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_histogram_snapshot() {
+        let hist = Histogram::new(linear_buckets(10.0, 10.0, 10));
+        for i in 1..=100 {
+            hist.observe(i as f64);
+        }
+
+        let snapshot = hist.snapshot();
+        assert_eq!(snapshot.count(), 100);
+        assert_eq!(snapshot.sum(), (1..=100).sum::<u64>() as f64);
+        assert_eq!(snapshot.buckets().iter().map(|b| b.count()).sum::<u64>(), 100);
+        assert_eq!(snapshot.created(), None);
+        assert_eq!(snapshot.quantile(0.5), Some(50.0));
+        assert_eq!(snapshot.quantile(0.95), Some(95.0));
+        assert_eq!(snapshot.quantile(1.5), None);
+
+        // snapshots don't consume the observations
+        hist.observe(1000.0);
+        let snapshot = hist.snapshot();
+        assert_eq!(snapshot.count(), 101);
+        assert_eq!(snapshot.quantile(1.0), Some(100.0));
+
+        let created = Duration::from_secs(12345);
+        let hist = Histogram::with_created([1.0], created);
+        assert_eq!(hist.snapshot().created(), Some(created));
+        assert_eq!(hist.snapshot().quantile(0.5), None);
+    }
+
+    #[test]
+    fn test_histogram_reset() {
+        let hist = Histogram::new(linear_buckets(1.0, 1.0, 3));
+        hist.observe_many([0.5, 1.5, 2.5, 10.0]);
+
+        let window = hist.reset();
+        assert_eq!(window.count(), 4);
+        assert_eq!(window.sum(), 14.5);
+        hist.with_snapshot(|s| {
+            assert_eq!(s.count(), 0);
+            assert_eq!(s.sum(), 0.0);
+            assert!(s.buckets().iter().all(|b| b.count() == 0));
+            assert_eq!(s.buckets().len(), 4);
+        });
+
+        hist.observe(2.0);
+        hist.observe(3.0);
+        hist.with_snapshot(|s| {
+            assert_eq!(s.count(), 2);
+            assert_eq!(s.sum(), 5.0);
+            assert_eq!(s.buckets()[1].count(), 1);
+            assert_eq!(s.buckets()[2].count(), 1);
+        });
+
+        // the created timestamp is only replaced on request
+        assert_eq!(hist.created(), None);
+        let created = Duration::from_secs(100);
+        let window = hist.reset_with_created(created);
+        assert_eq!(window.count(), 2);
+        assert_eq!(window.created(), None);
+        assert_eq!(hist.created(), Some(created));
+        hist.reset();
+        assert_eq!(hist.snapshot().created(), Some(created));
+    }
+
+    #[test]
+    fn test_histogram_reset_concurrent() {
+        let hist = Histogram::new([1.0]);
+
+        let windows = std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let hist = hist.clone();
+                scope.spawn(move || {
+                    for _ in 0..10_000 {
+                        hist.observe(1.0);
+                    }
+                });
+            }
+
+            (0..100).map(|_| hist.reset().count()).sum::<u64>()
+        });
+
+        // every observation is either in one of the reset windows or in the current one
+        assert_eq!(windows + hist.snapshot().count(), 40_000);
+        assert_eq!(hist.snapshot().count() as f64, hist.snapshot().sum());
+    }
+
+    #[test]
+    fn test_histogram_merge_from() {
+        let config = HistogramConfig::new([1.0, 2.0]);
+        let total = Histogram::from_config(&config);
+        total.observe(0.5);
+
+        let shards = [Histogram::from_config(&config), Histogram::from_config(&config)];
+        shards[0].observe_many([0.5, 1.5]);
+        shards[1].observe(5.0);
+        for shard in &shards {
+            total.merge_from(shard).unwrap();
+        }
+
+        total.with_snapshot(|s| {
+            let counts = s.buckets().iter().map(|b| b.count()).collect::<Vec<_>>();
+            assert_eq!(counts, [2, 1, 1]);
+            assert_eq!(s.count(), 4);
+            assert_eq!(s.sum(), 7.5);
+        });
+        // the shards are left unchanged
+        assert_eq!(shards[0].snapshot().count(), 2);
+        assert_eq!(total.config(), config);
+
+        // moving the observations of a shard
+        total.merge_snapshot(&shards[0].reset()).unwrap();
+        assert_eq!(total.snapshot().count(), 6);
+        assert_eq!(shards[0].snapshot().count(), 0);
+
+        let err = total.merge_from(&Histogram::new([1.0, 3.0])).unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::Invalid);
+        let err = total.merge_from(&Histogram::new([1.0])).unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::Invalid);
+        assert_eq!(total.snapshot().count(), 6);
+    }
+
+    #[test]
+    fn test_histogram_snapshot_consistency() {
+        let hist = Histogram::new([1.0, 2.0, 4.0]);
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let hist = hist.clone();
+                scope.spawn(move || {
+                    for i in 0..10_000 {
+                        hist.observe(if i % 2 == 0 { 0.5 } else { 3.0 });
+                    }
+                });
+            }
+
+            for _ in 0..100 {
+                let snapshot = hist.snapshot();
+                let count = snapshot.buckets().iter().map(|b| b.count()).sum::<u64>();
+                assert_eq!(count, snapshot.count());
+                let buckets = snapshot.buckets();
+                let sum = buckets[0].count() as f64 * 0.5 + buckets[2].count() as f64 * 3.0;
+                assert_eq!(snapshot.sum(), sum);
+            }
+        });
+
+        let snapshot = hist.snapshot();
+        assert_eq!(snapshot.count(), 40_000);
+        assert_eq!(snapshot.sum(), 20_000.0 * 0.5 + 20_000.0 * 3.0);
+    }
+
     #[test]
     fn test_text_encoding() {
         check_text_encoding(
@@ -288,4 +956,69 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn test_exemplar_text_encoding() {
+        check_text_encoding(
+            |registry| {
+                let hist = Histogram::new([1.0, 2.0]);
+                registry.register("latency", "Request latency", hist.clone()).unwrap();
+                hist.observe(0.5);
+                hist.observe_with_exemplar(1.5, Exemplar::new([("trace_id", "abc")], 0.0));
+                hist.observe_with_exemplar(1.25, Exemplar::new([("trace_id", "def")], 0.0));
+                assert!(hist.exemplars()[0].is_none());
+            },
+            |output| {
+                let expected = indoc::indoc! {r#"
+                    # TYPE latency histogram
+                    # HELP latency Request latency
+                    latency_bucket{le="1.0"} 1
+                    latency_bucket{le="2.0"} 3 # {trace_id="def"} 1.25
+                    latency_bucket{le="+Inf"} 3
+                    latency_count 3
+                    latency_sum 3.25
+                    # EOF
+                "#};
+                assert_eq!(expected, output);
+            },
+        );
+    }
+
+    #[test]
+    fn test_lazy_histogram() {
+        let config = Arc::new(HistogramConfig::new([1.0, 0.1, f64::NAN, -1.0, 1.0]));
+        assert_eq!(config.upper_bounds(), [0.1, 1.0, f64::INFINITY]);
+
+        let lazy = LazyHistogram::new(
+            || HistogramSample { buckets: vec![3, 1, 1], sum: 10.5, count: 5 },
+            config,
+        );
+        check_text_encoding(
+            |registry| {
+                registry.register("lazy", "Lazy histogram", lazy.clone()).unwrap();
+            },
+            |output| {
+                let expected = indoc::indoc! {r#"
+                    # TYPE lazy histogram
+                    # HELP lazy Lazy histogram
+                    lazy_bucket{le="0.1"} 3
+                    lazy_bucket{le="1.0"} 4
+                    lazy_bucket{le="+Inf"} 5
+                    lazy_count 5
+                    lazy_sum 10.5
+                    # EOF
+                "#};
+                assert_eq!(expected, output);
+            },
+        );
+
+        // mismatched bucket counts are rejected at encoding time
+        let lazy = LazyHistogram::new(HistogramSample::default, Arc::default());
+        let mut registry = crate::registry::Registry::default();
+        registry.register("lazy", "Lazy histogram", lazy).unwrap();
+        let mut output = String::new();
+        let err =
+            crate::format::text::encode(&mut output, &registry, Default::default()).unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::Invalid);
+    }
 }
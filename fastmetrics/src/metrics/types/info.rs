@@ -1,4 +1,13 @@
 //! [Open Metrics Info](https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#info) metric type.
+//!
+//! See [`Info`] and [`MutableInfo`] for more details.
+
+use std::{
+    fmt::{self, Debug},
+    sync::Arc,
+};
+
+use parking_lot::RwLock;
 
 use crate::{
     encoder::{EncodeLabelSet, EncodeMetric, MetricEncoder},
@@ -42,6 +51,81 @@ where
     }
 }
 
+/// An [`Info`] metric whose label set can be replaced at runtime, e.g. for a service version
+/// after a hot reload, feature flags or service discovery data.
+///
+/// Clones share the same label set.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::metrics::info::MutableInfo;
+/// #
+/// let build = MutableInfo::new(vec![("version", "1.0")]);
+/// let previous = build.set(vec![("version", "1.1")]);
+/// assert_eq!(previous, vec![("version", "1.0")]);
+/// assert_eq!(build.get(), vec![("version", "1.1")]);
+/// ```
+pub struct MutableInfo<LS> {
+    label_set: Arc<RwLock<LS>>,
+}
+
+impl<LS> Clone for MutableInfo<LS> {
+    fn clone(&self) -> Self {
+        Self { label_set: self.label_set.clone() }
+    }
+}
+
+impl<LS: Debug> Debug for MutableInfo<LS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MutableInfo")
+            .field("label_set", &*self.label_set.read())
+            .finish()
+    }
+}
+
+impl<LS> MutableInfo<LS> {
+    /// Creates a [`MutableInfo`] metric with the given initial label set.
+    pub fn new(label_set: LS) -> Self {
+        Self { label_set: Arc::new(RwLock::new(label_set)) }
+    }
+
+    /// Replaces the label set of the [`MutableInfo`], returning the previous one.
+    pub fn set(&self, label_set: LS) -> LS {
+        std::mem::replace(&mut *self.label_set.write(), label_set)
+    }
+
+    /// Updates the label set of the [`MutableInfo`] in place.
+    pub fn update(&self, f: impl FnOnce(&mut LS)) {
+        f(&mut self.label_set.write())
+    }
+
+    /// Gets a copy of the current label set of the [`MutableInfo`].
+    pub fn get(&self) -> LS
+    where
+        LS: Clone,
+    {
+        self.label_set.read().clone()
+    }
+}
+
+impl<LS> TypedMetric for MutableInfo<LS> {
+    const TYPE: MetricType = MetricType::Info;
+}
+
+impl<LS> MetricLabelSet for MutableInfo<LS> {
+    type LabelSet = ();
+}
+
+impl<LS> EncodeMetric for MutableInfo<LS>
+where
+    LS: EncodeLabelSet + Send + Sync,
+{
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        encoder.encode_info(&*self.label_set.read())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,4 +151,29 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn test_mutable_info_text_encoding() {
+        use crate::{
+            format::text::{self, TextProfile},
+            registry::Registry,
+        };
+
+        let info = MutableInfo::new(vec![("version", "1.0")]);
+        let mut registry = Registry::default();
+        registry
+            .register("release_version", "My release version", info.clone())
+            .unwrap();
+        let encode = || {
+            let mut output = String::new();
+            text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+            output
+        };
+
+        assert!(encode().contains("release_version_info{version=\"1.0\"} 1\n"));
+        assert_eq!(info.set(vec![("version", "1.1")]), vec![("version", "1.0")]);
+        assert!(encode().contains("release_version_info{version=\"1.1\"} 1\n"));
+        info.update(|labels| labels.push(("channel", "beta")));
+        assert!(encode().contains("release_version_info{version=\"1.1\",channel=\"beta\"} 1\n"));
+    }
 }
@@ -1,6 +1,6 @@
 //! [Open Metrics StateSet](https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#stateset) metric type.
 //!
-//! See [`StateSet`] and [`ConstStateSet`] for more details.
+//! See [`StateSet`], [`ConstStateSet`] and [`LazyStateSet`] for more details.
 
 use std::{
     fmt::{self, Debug},
@@ -11,6 +11,7 @@ use std::{
 use crate::{
     encoder::{EncodeMetric, MetricEncoder},
     error::Result,
+    metrics::internal::lazy::{LazySource, PlainLazySource},
     raw::{MetricLabelSet, MetricType, TypedMetric},
 };
 
@@ -84,8 +85,8 @@ pub struct StateSet<T> {
 
 impl<T: StateSetValue + Debug> Debug for StateSet<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let state = self.get();
-        f.debug_struct("StateSet").field("state", state).finish()
+        let state = self.current();
+        f.debug_struct("StateSet").field("state", &state).finish()
     }
 }
 
@@ -96,7 +97,9 @@ impl<T: StateSetValue + Default> Default for StateSet<T> {
 }
 
 impl<T: StateSetValue> StateSet<T> {
-    /// Creates a [`StateSet`] with the given initial state.
+    /// Creates a [`StateSet`] with the given initial state, e.g. the starting state of a state
+    /// machine whose default variant isn't meaningful.
+    #[doc(alias = "with_initial")]
     pub fn new(initial_state: T) -> Self {
         let pos = find_position(initial_state);
         Self { current_state: Arc::new(AtomicU8::new(pos)), _marker: PhantomData }
@@ -108,16 +111,88 @@ impl<T: StateSetValue> StateSet<T> {
         self.current_state.store(pos, Ordering::Relaxed);
     }
 
+    /// Atomically changes the current state from `from` to `to`.
+    ///
+    /// Returns `true` if the state was changed, or `false` (leaving the state unchanged) if the
+    /// current state was not `from`. This allows guarded transitions of a state machine, e.g. a
+    /// job may only become `Completed` if it's `Running`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::metrics::state_set::{StateSet, StateSetValue};
+    /// #
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # enum JobState { Pending, Running, Completed }
+    /// #
+    /// # impl StateSetValue for JobState {
+    /// #     fn variants() -> &'static [Self] {
+    /// #         &[Self::Pending, Self::Running, Self::Completed]
+    /// #     }
+    /// #
+    /// #     fn as_str(&self) -> &str {
+    /// #         match self {
+    /// #             Self::Pending => "Pending",
+    /// #             Self::Running => "Running",
+    /// #             Self::Completed => "Completed",
+    /// #         }
+    /// #     }
+    /// # }
+    /// #
+    /// let state = StateSet::new(JobState::Pending);
+    /// assert!(!state.transition(JobState::Running, JobState::Completed));
+    /// assert!(state.transition(JobState::Pending, JobState::Running));
+    /// assert_eq!(state.get(), &JobState::Running);
+    /// ```
+    pub fn transition(&self, from: T, to: T) -> bool {
+        let (from, to) = (find_position(from), find_position(to));
+        self.current_state
+            .compare_exchange(from, to, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
     /// Gets the current state.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if no state is active after [`StateSet::clear`], use
+    /// [`StateSet::active_state`] if that's possible.
     pub fn get(&self) -> &T {
+        self.current().expect("stateset must have an active state")
+    }
+
+    fn current(&self) -> Option<&'static T> {
         let index = self.current_state.load(Ordering::Relaxed) as usize;
-        T::variants().get(index).expect("Invalid state index")
+        T::variants().get(index)
     }
 
-    /// Returns the all states with exactly one Boolean value being true.
+    /// Returns a copy of the currently active state, or `None` if no state is active after
+    /// [`StateSet::clear`].
+    pub fn active_state(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.current().cloned()
+    }
+
+    /// Returns `true` if `state` is currently active.
+    pub fn is_in_state(&self, state: &T) -> bool {
+        self.current() == Some(state)
+    }
+
+    /// Deactivates all states, e.g. for an "unknown" or "initializing" phase.
+    ///
+    /// All states are then encoded as `false` until the next [`StateSet::set`].
+    pub fn clear(&self) {
+        self.current_state.store(NO_STATE, Ordering::Relaxed);
+    }
+
+    /// Returns the all states with at most one Boolean value being true.
     pub fn states(&self) -> Vec<(&str, bool)> {
-        let current = self.get();
-        gen_states(current)
+        match self.current() {
+            Some(current) => gen_states(current),
+            None => T::variants().iter().map(|variant| (variant.as_str(), false)).collect(),
+        }
     }
 }
 
@@ -218,6 +293,94 @@ impl<T: StateSetValue> EncodeMetric for ConstStateSet<T> {
     }
 }
 
+/// A [`StateSet`] whose state is computed lazily at scrape time.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::metrics::state_set::{LazyStateSet, StateSetValue};
+/// #
+/// #[derive(Copy, Clone, Debug, PartialEq)]
+/// enum Health {
+///     Running,
+///     Degraded,
+///     Stopped,
+/// }
+///
+/// impl StateSetValue for Health {
+///     fn variants() -> &'static [Self] {
+///         &[Self::Running, Self::Degraded, Self::Stopped]
+///     }
+///
+///     fn as_str(&self) -> &str {
+///         match self {
+///             Self::Running => "running",
+///             Self::Degraded => "degraded",
+///             Self::Stopped => "stopped",
+///         }
+///     }
+/// }
+///
+/// let health = LazyStateSet::new(|| Health::Degraded);
+/// assert_eq!(health.fetch(), Health::Degraded);
+/// ```
+///
+/// # Grouped sampling
+///
+/// When constructed via [`crate::metrics::lazy_group::LazyGroup`], multiple lazy state sets can
+/// share a single expensive sample per scrape.
+pub struct LazyStateSet<T> {
+    source: Arc<dyn LazySource<T>>,
+}
+
+impl<T> Clone for LazyStateSet<T> {
+    fn clone(&self) -> Self {
+        Self { source: self.source.clone() }
+    }
+}
+
+impl<T: StateSetValue> LazyStateSet<T> {
+    /// Internal: constructs a lazy state set from a source.
+    ///
+    /// This is used by crate-internal glue (e.g. `metrics::lazy_group`) to build a
+    /// `LazyStateSet` without exposing additional public types.
+    pub(crate) fn from_source(source: Arc<dyn LazySource<T>>) -> Self {
+        Self { source }
+    }
+
+    /// Creates a new [`LazyStateSet`] from the provided fetcher function or closure.
+    pub fn new(fetch: impl Fn() -> T + Send + Sync + 'static) -> Self {
+        Self::from_source(Arc::new(PlainLazySource::new(Arc::new(fetch))))
+    }
+
+    /// Evaluates the underlying fetcher and returns the current state.
+    ///
+    /// Mainly intended for debugging or tests; regular metric collection should
+    /// let the encoder trigger the fetch during scrapes.
+    #[inline]
+    pub fn fetch(&self) -> T {
+        self.source.load()
+    }
+}
+
+impl<T: StateSetValue> TypedMetric for LazyStateSet<T> {
+    const TYPE: MetricType = MetricType::StateSet;
+}
+
+impl<T: StateSetValue> MetricLabelSet for LazyStateSet<T> {
+    type LabelSet = ();
+}
+
+impl<T: StateSetValue> EncodeMetric for LazyStateSet<T> {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        let state = self.fetch();
+        encoder.encode_stateset(gen_states(&state))
+    }
+}
+
+// The position stored by `StateSet::clear`, which is never a valid variant position.
+const NO_STATE: u8 = u8::MAX;
+
 fn find_position<T: StateSetValue>(state: T) -> u8 {
     T::variants()
         .iter()
@@ -271,6 +434,10 @@ mod tests {
 
         let state = StateSet::new(TestState::Running);
         assert_eq!(state.get(), &TestState::Running);
+
+        let state = StateSet::new(TestState::Completed);
+        assert_eq!(state.get(), &TestState::Completed);
+        assert!(state.is_in_state(&TestState::Completed));
     }
 
     #[test]
@@ -286,6 +453,77 @@ mod tests {
         assert_eq!(state.get(), &TestState::Completed);
     }
 
+    #[test]
+    fn test_stateset_transition() {
+        let state = StateSet::new(TestState::Pending);
+
+        // a transition from an incorrect state fails without changing the state
+        assert!(!state.transition(TestState::Running, TestState::Completed));
+        assert_eq!(state.get(), &TestState::Pending);
+
+        assert!(state.transition(TestState::Pending, TestState::Running));
+        assert_eq!(state.get(), &TestState::Running);
+
+        // only one of the concurrent transitions from the same state succeeds
+        let succeeded = std::thread::scope(|s| {
+            let handles = [TestState::Completed, TestState::Failed].map(|to| {
+                let state = &state;
+                s.spawn(move || state.transition(TestState::Running, to))
+            });
+            handles.map(|handle| handle.join().unwrap())
+        });
+        assert_eq!(succeeded.iter().filter(|ok| **ok).count(), 1);
+        assert_ne!(state.get(), &TestState::Running);
+    }
+
+    #[test]
+    fn test_stateset_query_and_clear() {
+        let state = StateSet::new(TestState::Running);
+        assert!(state.is_in_state(&TestState::Running));
+        assert!(!state.is_in_state(&TestState::Pending));
+        assert_eq!(state.active_state(), Some(TestState::Running));
+
+        state.clear();
+        assert_eq!(state.active_state(), None);
+        assert!(TestState::variants().iter().all(|variant| !state.is_in_state(variant)));
+        assert!(state.states().iter().all(|(_, enabled)| !enabled));
+        assert!(!state.transition(TestState::Running, TestState::Completed));
+
+        state.set(TestState::Failed);
+        assert!(state.is_in_state(&TestState::Failed));
+        assert_eq!(state.active_state(), Some(TestState::Failed));
+        assert_eq!(state.get(), &TestState::Failed);
+
+        // a stateset with a single state
+        #[derive(Clone, Debug, PartialEq)]
+        struct Up;
+        impl StateSetValue for Up {
+            fn variants() -> &'static [Self] {
+                &[Up]
+            }
+
+            fn as_str(&self) -> &str {
+                "up"
+            }
+        }
+
+        let up = StateSet::new(Up);
+        assert!(up.is_in_state(&Up));
+        up.clear();
+        assert_eq!(up.active_state(), None);
+        assert_eq!(up.states(), vec![("up", false)]);
+        up.set(Up);
+        assert_eq!(up.states(), vec![("up", true)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "stateset must have an active state")]
+    fn test_stateset_get_cleared_panic() {
+        let state = StateSet::new(TestState::Running);
+        state.clear();
+        state.get();
+    }
+
     #[test]
     fn test_stateset_states() {
         let state = StateSet::new(TestState::Running);
@@ -364,6 +602,25 @@ mod tests {
             },
         );
 
+        check_text_encoding(
+            |registry| {
+                let stateset = StateSet::new(TestState::Completed);
+                registry.register("my_stateset", "My stateset help", stateset.clone()).unwrap();
+            },
+            |output| {
+                let expected = indoc::indoc! {r#"
+                    # TYPE my_stateset stateset
+                    # HELP my_stateset My stateset help
+                    my_stateset{my_stateset="pending"} 0
+                    my_stateset{my_stateset="running"} 0
+                    my_stateset{my_stateset="completed"} 1
+                    my_stateset{my_stateset="failed"} 0
+                    # EOF
+                "#};
+                assert_eq!(expected, output);
+            },
+        );
+
         check_text_encoding(
             |registry| {
                 let stateset = ConstStateSet::new(TestState::Running);
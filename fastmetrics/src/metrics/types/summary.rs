@@ -1,11 +1,509 @@
 //! [Open Metrics Summary](https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#summary) metric type.
 //!
-//! This metric is intentionally left unimplemented because summaries require client-side
-//! quantile estimation, stateful streams per label set, and export payloads that cannot
-//! be merged server-side. These requirements conflict with this crate’s design goals:
-//! keeping collectors zero-allocation in the hot path, offloading aggregation to the backend,
-//! and favoring histograms for percentile analysis.
+//! Summaries estimate quantiles on the client side, over a sliding time window. Unlike histogram
+//! buckets, the quantiles of several summaries (e.g. from different instances) cannot be
+//! aggregated meaningfully on the server side, and every observation takes a lock. Prefer
+//! [`Histogram`](crate::metrics::histogram::Histogram) for percentile analysis unless accurate
+//! quantiles of a single process are needed.
 //!
-//! Downstream users who still need Summary semantics can implement their own type by adhering
-//! to the same metric traits used by other built-in metric types.
-//! This allows them to reuse this crate’s encoders without forking or duplicating logic.
+//! See [`Summary`] for more details.
+
+use std::{
+    fmt::{self, Debug},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+pub use crate::raw::quantile::*;
+use crate::{
+    encoder::{EncodeMetric, MetricEncoder},
+    error::Result,
+    metrics::internal::quantile::QuantileStream,
+    raw::{MetricLabelSet, MetricType, TypedMetric},
+    registry::Clock,
+};
+
+/// The default `(quantile, error)` pairs of a [`Summary`].
+pub const DEFAULT_QUANTILES: [(f64, f64); 3] = [(0.5, 0.05), (0.9, 0.01), (0.99, 0.001)];
+
+/// The default duration for which observations stay relevant to a [`Summary`].
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// The default number of buckets used to rotate out observations older than the max age.
+pub const DEFAULT_AGE_BUCKETS: u32 = 5;
+
+/// Configuration of a [`Summary`].
+///
+/// # Example
+///
+/// ```rust
+/// # use std::time::Duration;
+/// #
+/// # use fastmetrics::metrics::summary::{Summary, SummaryConfig};
+/// #
+/// // Estimate the median and the 99th percentile over the last minute,
+/// // rotating out old observations every 15 seconds.
+/// let config = SummaryConfig::new()
+///     .with_quantiles([(0.5, 0.05), (0.99, 0.001)])
+///     .with_max_age(Duration::from_secs(60))
+///     .with_age_buckets(4);
+/// let summary = Summary::new(config);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SummaryConfig {
+    quantiles: Vec<(f64, f64)>,
+    max_age: Duration,
+    age_buckets: u32,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        Self {
+            quantiles: DEFAULT_QUANTILES.to_vec(),
+            max_age: DEFAULT_MAX_AGE,
+            age_buckets: DEFAULT_AGE_BUCKETS,
+        }
+    }
+}
+
+impl SummaryConfig {
+    /// Creates a [`SummaryConfig`] with the default quantiles, max age and age buckets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `(quantile, error)` pairs to estimate.
+    ///
+    /// The estimated `quantile` has a rank within `quantile ± error` of the observations.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if a quantile or an error is not within `[0, 1]`.
+    pub fn with_quantiles(mut self, quantiles: impl IntoIterator<Item = (f64, f64)>) -> Self {
+        let mut quantiles = quantiles.into_iter().collect::<Vec<_>>();
+        for (quantile, error) in &quantiles {
+            assert!((0.0..=1.0).contains(quantile), "quantile must be within [0, 1]");
+            assert!((0.0..=1.0).contains(error), "quantile error must be within [0, 1]");
+        }
+        quantiles.sort_by(|a, b| a.0.total_cmp(&b.0));
+        quantiles.dedup_by(|a, b| a.0 == b.0);
+        self.quantiles = quantiles;
+        self
+    }
+
+    /// Sets the duration for which observations stay relevant.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `max_age` is zero.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        assert!(!max_age.is_zero(), "summary max age must be greater than zero");
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets the number of buckets used to rotate out observations older than the max age.
+    ///
+    /// Observations are discarded one bucket (`max_age / age_buckets`) at a time, so more buckets
+    /// mean a smoother window at the cost of memory and observation time.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `age_buckets` is zero.
+    pub fn with_age_buckets(mut self, age_buckets: u32) -> Self {
+        assert!(age_buckets > 0, "summary must have at least one age bucket");
+        self.age_buckets = age_buckets;
+        self
+    }
+
+    /// Returns the `(quantile, error)` pairs to estimate, ordered by quantile.
+    pub fn quantiles(&self) -> &[(f64, f64)] {
+        &self.quantiles
+    }
+
+    /// Returns the duration for which observations stay relevant.
+    pub const fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Returns the number of age buckets.
+    pub const fn age_buckets(&self) -> u32 {
+        self.age_buckets
+    }
+}
+
+/// Open Metrics [`Summary`] metric, which samples observations and estimates configurable
+/// quantiles over a sliding time window.
+///
+/// Every observation is recorded in `age_buckets` quantile streams, started one age bucket apart.
+/// The oldest stream covers at most the last `max_age` of observations and is used for the
+/// quantile estimates; when it expires it is reset and the next one takes over. The `count` and
+/// `sum` are cumulative over the lifetime of the summary.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::metrics::summary::Summary;
+/// #
+/// let summary = Summary::default();
+/// for value in 1..=100 {
+///     summary.observe(value as f64);
+/// }
+///
+/// summary.with_snapshot(|s| {
+///     assert_eq!(s.count(), 100);
+///     assert_eq!(s.sum(), 5050.0);
+///     // median, estimated within 5% of the rank
+///     let median = s.quantiles()[0];
+///     assert_eq!(median.quantile(), 0.5);
+///     assert!((45.0..=55.0).contains(&median.value()));
+/// });
+/// ```
+#[derive(Clone)]
+pub struct Summary {
+    inner: Arc<SummaryCore>,
+    // UNIX timestamp
+    created: Option<Duration>,
+}
+
+struct SummaryCore {
+    quantiles: Vec<f64>,
+    stream_duration: Duration,
+    clock: Box<dyn Clock>,
+    state: Mutex<SummaryState>,
+}
+
+struct SummaryState {
+    streams: Vec<QuantileStream>,
+    // index of the oldest stream, used for queries
+    head: usize,
+    head_expires_at: Duration,
+    count: u64,
+    sum: f64,
+}
+
+impl SummaryCore {
+    fn new(config: SummaryConfig, clock: Box<dyn Clock>) -> Self {
+        let stream_duration = config.max_age / config.age_buckets;
+        assert!(
+            !stream_duration.is_zero(),
+            "summary age bucket duration must be greater than zero"
+        );
+
+        let streams = (0..config.age_buckets)
+            .map(|_| QuantileStream::new(config.quantiles.clone()))
+            .collect();
+        let head_expires_at = clock.now() + stream_duration;
+        Self {
+            quantiles: config.quantiles.iter().map(|(quantile, _)| *quantile).collect(),
+            stream_duration,
+            clock,
+            state: Mutex::new(SummaryState {
+                streams,
+                head: 0,
+                head_expires_at,
+                count: 0,
+                sum: 0.0,
+            }),
+        }
+    }
+
+    fn rotate(&self, state: &mut SummaryState) {
+        let now = self.clock.now();
+        if now < state.head_expires_at {
+            return;
+        }
+
+        let streams = state.streams.len();
+        let expired =
+            (now - state.head_expires_at).as_nanos() / self.stream_duration.as_nanos() + 1;
+        for _ in 0..expired.min(streams as u128) {
+            state.streams[state.head].reset();
+            state.head = (state.head + 1) % streams;
+        }
+        // `expired` fits in `u32` unless the clock jumps by centuries
+        state.head_expires_at += self.stream_duration * u32::try_from(expired).unwrap_or(u32::MAX);
+    }
+}
+
+impl Debug for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with_snapshot(|snapshot| {
+            f.debug_struct("Summary")
+                .field("quantiles", &snapshot.quantiles())
+                .field("sum", &snapshot.sum())
+                .field("count", &snapshot.count())
+                .field("created", &snapshot.created())
+                .finish()
+        })
+    }
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self::new(SummaryConfig::default())
+    }
+}
+
+impl Summary {
+    /// Creates a new [`Summary`] with the given configuration.
+    pub fn new(config: SummaryConfig) -> Self {
+        let start = Instant::now();
+        Self::with_clock(config, move || start.elapsed())
+    }
+
+    /// Creates a [`Summary`] with the given configuration and a `created` timestamp.
+    pub fn with_created(config: SummaryConfig, created: Duration) -> Self {
+        Self { created: Some(created), ..Self::new(config) }
+    }
+
+    /// Creates a [`Summary`] with the given configuration, using `clock` as the time source of
+    /// the sliding window.
+    pub fn with_clock(config: SummaryConfig, clock: impl Clock + 'static) -> Self {
+        Self { inner: Arc::new(SummaryCore::new(config, Box::new(clock))), created: None }
+    }
+
+    /// Observes a value.
+    pub fn observe(&self, value: f64) {
+        // value MUST NOT be NaN or negative
+        if value.is_nan() || value.is_sign_negative() {
+            return;
+        }
+
+        let mut state = self.inner.state.lock();
+        self.inner.rotate(&mut state);
+        for stream in state.streams.iter_mut() {
+            stream.insert(value);
+        }
+        state.count += 1;
+        state.sum += value;
+    }
+
+    /// Provides temporary access to a snapshot of the summary's current state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use fastmetrics::metrics::summary::Summary;
+    /// #
+    /// let summary = Summary::default();
+    /// summary.observe(2.5);
+    ///
+    /// summary.with_snapshot(|s| {
+    ///     assert_eq!(s.count(), 1);
+    ///     assert_eq!(s.sum(), 2.5);
+    ///     assert!(s.quantiles().iter().all(|q| q.value() == 2.5));
+    /// });
+    /// ```
+    pub fn with_snapshot<F, R>(&self, func: F) -> R
+    where
+        F: FnOnce(&SummarySnapshot) -> R,
+    {
+        let snapshot = {
+            let mut state = self.inner.state.lock();
+            self.inner.rotate(&mut state);
+            let head = state.head;
+            let stream = &mut state.streams[head];
+            let quantiles = self
+                .inner
+                .quantiles
+                .iter()
+                .map(|&quantile| Quantile::new(quantile, stream.query(quantile)))
+                .collect();
+            SummarySnapshot { quantiles, sum: state.sum, count: state.count, created: self.created }
+        };
+        func(&snapshot)
+    }
+
+    /// Gets the optional `created` value of the [`Summary`].
+    pub const fn created(&self) -> Option<Duration> {
+        self.created
+    }
+}
+
+/// A snapshot of a [`Summary`] at a point in time.
+#[derive(Clone, Debug)]
+pub struct SummarySnapshot {
+    quantiles: Vec<Quantile>,
+    sum: f64,
+    count: u64,
+    created: Option<Duration>,
+}
+
+impl SummarySnapshot {
+    /// Gets the estimated quantiles, `NaN` if there are no observations within the max age.
+    pub fn quantiles(&self) -> &[Quantile] {
+        &self.quantiles
+    }
+
+    /// Gets the current `sum` of all observed values.
+    pub const fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Gets the current total `count` of all observations.
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Gets the optional `created` value of the summary.
+    pub const fn created(&self) -> Option<Duration> {
+        self.created
+    }
+}
+
+impl TypedMetric for Summary {
+    const TYPE: MetricType = MetricType::Summary;
+}
+
+impl MetricLabelSet for Summary {
+    type LabelSet = ();
+}
+
+impl EncodeMetric for Summary {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        self.with_snapshot(|s| {
+            encoder.encode_summary(s.quantiles(), s.sum(), s.count(), s.created())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{metrics::check_text_encoding, registry::ManualClock};
+
+    #[test]
+    fn test_summary_quantiles_within_error_bound() {
+        const N: u64 = 10_000;
+        let targets = [(0.5, 0.05), (0.9, 0.01), (0.99, 0.001)];
+        let summary = Summary::new(SummaryConfig::new().with_quantiles(targets));
+
+        // observe a permutation of 1..=N, i.e. a uniform distribution
+        for i in 0..N {
+            summary.observe((i * 7919 % N + 1) as f64);
+        }
+
+        summary.with_snapshot(|s| {
+            assert_eq!(s.count(), N);
+            assert_eq!(s.sum(), (N * (N + 1) / 2) as f64);
+            assert_eq!(s.quantiles().len(), targets.len());
+            for (quantile, (target, error)) in s.quantiles().iter().zip(targets) {
+                assert_eq!(quantile.quantile(), target);
+                // the value of an observation is its rank
+                let rank = quantile.value();
+                let (low, high) = ((target - error) * N as f64, (target + error) * N as f64);
+                assert!(
+                    (low..=high).contains(&rank),
+                    "quantile {target}: {rank} not within [{low}, {high}]"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_summary_sliding_window() {
+        let clock = ManualClock::default();
+        let config = SummaryConfig::new()
+            .with_quantiles([(0.5, 0.01)])
+            .with_max_age(Duration::from_secs(60))
+            .with_age_buckets(4);
+        let summary = Summary::with_clock(config, clock.clone());
+
+        let median = |summary: &Summary| summary.with_snapshot(|s| s.quantiles()[0].value());
+        assert!(median(&summary).is_nan());
+
+        summary.observe(1.0);
+        assert_eq!(median(&summary), 1.0);
+
+        // still within the window
+        clock.advance_by(Duration::from_secs(45));
+        summary.observe(100.0);
+        summary.observe(100.0);
+        assert_eq!(median(&summary), 100.0);
+
+        // the first observation is older than the max age
+        clock.advance_by(Duration::from_secs(30));
+        summary.observe(10.0);
+        let remaining = summary.with_snapshot(|s| s.quantiles()[0].value());
+        assert_eq!(remaining, 100.0);
+
+        // count and sum are cumulative
+        summary.with_snapshot(|s| {
+            assert_eq!(s.count(), 4);
+            assert_eq!(s.sum(), 211.0);
+        });
+
+        // all observations are older than the max age
+        clock.advance_by(Duration::from_secs(3600));
+        assert!(median(&summary).is_nan());
+    }
+
+    #[test]
+    fn test_summary_age_buckets_rotation() {
+        let clock = ManualClock::default();
+        let config = SummaryConfig::new()
+            .with_quantiles([(0.0, 0.0), (1.0, 0.0)])
+            .with_max_age(Duration::from_secs(60))
+            .with_age_buckets(4);
+        let summary = Summary::with_clock(config, clock.clone());
+
+        // one observation per age bucket, the window slides one age bucket at a time
+        for step in 0..12_u32 {
+            summary.observe(step as f64);
+            let (min, max) =
+                summary.with_snapshot(|s| (s.quantiles()[0].value(), s.quantiles()[1].value()));
+            assert_eq!(min, step.saturating_sub(3) as f64, "step {step}");
+            assert_eq!(max, step as f64, "step {step}");
+            clock.advance_by(Duration::from_secs(15));
+        }
+    }
+
+    #[test]
+    fn test_summary_ignores_invalid_values() {
+        let summary = Summary::default();
+        summary.observe(f64::NAN);
+        summary.observe(-1.0);
+        summary.with_snapshot(|s| {
+            assert_eq!(s.count(), 0);
+            assert_eq!(s.sum(), 0.0);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "quantile must be within [0, 1]")]
+    fn test_summary_config_invalid_quantile() {
+        let _ = SummaryConfig::new().with_quantiles([(1.5, 0.01)]);
+    }
+
+    #[test]
+    fn test_text_encoding() {
+        check_text_encoding(
+            |registry| {
+                let config = SummaryConfig::new().with_quantiles([(0.5, 0.05), (0.99, 0.001)]);
+                let summary = Summary::with_created(config, Duration::from_secs(12345));
+                registry
+                    .register("request_duration", "Request duration", summary.clone())
+                    .unwrap();
+                for value in [1.0, 2.0, 3.0] {
+                    summary.observe(value);
+                }
+            },
+            |output| {
+                let expected = indoc::indoc! {r#"
+                    # TYPE request_duration summary
+                    # HELP request_duration Request duration
+                    request_duration{quantile="0.5"} 2.0
+                    request_duration{quantile="0.99"} 3.0
+                    request_duration_count 3
+                    request_duration_sum 6.0
+                    request_duration_created 12345.0
+                    # EOF
+                "#};
+                assert_eq!(expected, output);
+            },
+        );
+    }
+}
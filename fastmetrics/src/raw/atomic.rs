@@ -1,4 +1,4 @@
-use std::sync::atomic::*;
+use std::{ops::AddAssign, sync::atomic::*};
 
 use crate::raw::number::Number;
 
@@ -17,6 +17,22 @@ pub trait Atomic<N: Number>: Default + Send + Sync {
     /// Increase the value by `v`.
     fn inc_by(&self, v: N);
 
+    /// Increase the value by `v`, returning the new value.
+    ///
+    /// The default implementation is a CAS loop built on [`Atomic::update`].
+    fn inc_by_and_get(&self, v: N) -> N
+    where
+        N: AddAssign,
+    {
+        let mut new = v;
+        self.update(|old| {
+            new = old;
+            new += v;
+            new
+        });
+        new
+    }
+
     /// Decrease the value by `v`.
     fn dec_by(&self, v: N);
 
@@ -25,6 +41,43 @@ pub trait Atomic<N: Number>: Default + Send + Sync {
     where
         F: FnMut(N) -> N;
 
+    /// Atomically updates the current value, returning the previous value.
+    ///
+    /// `f` may be called multiple times if the value is concurrently modified. The default
+    /// implementation is built on [`Atomic::update`].
+    fn fetch_update<F>(&self, mut f: F) -> N
+    where
+        F: FnMut(N) -> N,
+    {
+        let mut previous = N::ZERO;
+        self.update(|old| {
+            previous = old;
+            f(old)
+        });
+        previous
+    }
+
+    /// Stores `new` if the current value is `current`, returning the previous value on success
+    /// and the actual current value on failure.
+    ///
+    /// Floating-point values are compared by their bit patterns by the built-in atomics. The
+    /// default implementation is built on [`Atomic::update`] instead: it compares values with
+    /// [`PartialEq`] and ignores the orderings.
+    fn compare_exchange(
+        &self,
+        current: N,
+        new: N,
+        _success: Ordering,
+        _failure: Ordering,
+    ) -> Result<N, N> {
+        let mut previous = current;
+        self.update(|old| {
+            previous = old;
+            if old == current { new } else { old }
+        });
+        if previous == current { Ok(previous) } else { Err(previous) }
+    }
+
     /// Set the value.
     fn set(&self, v: N);
 
@@ -40,6 +93,11 @@ macro_rules! impl_atomic_for_integer {
                 self.fetch_add(v, Ordering::Relaxed);
             }
 
+            #[inline(always)]
+            fn inc_by_and_get(&self, v: $ty) -> $ty {
+                self.fetch_add(v, Ordering::Relaxed).wrapping_add(v)
+            }
+
             #[inline(always)]
             fn dec_by(&self, v: $ty) {
                 self.fetch_sub(v, Ordering::Relaxed);
@@ -53,6 +111,26 @@ macro_rules! impl_atomic_for_integer {
                 let _ = self.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| Some(f(old)));
             }
 
+            #[inline]
+            fn fetch_update<F>(&self, mut f: F) -> $ty
+            where
+                F: FnMut($ty) -> $ty,
+            {
+                $atomic::fetch_update(self, Ordering::Relaxed, Ordering::Relaxed, |old| Some(f(old)))
+                    .unwrap_or_else(|old| old)
+            }
+
+            #[inline]
+            fn compare_exchange(
+                &self,
+                current: $ty,
+                new: $ty,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$ty, $ty> {
+                $atomic::compare_exchange(self, current, new, success, failure)
+            }
+
             #[inline(always)]
             fn set(&self, v: $ty) {
                 self.store(v, Ordering::Relaxed);
@@ -87,6 +165,17 @@ macro_rules! impl_atomic_for_float  {
                 });
             }
 
+            #[inline(always)]
+            fn inc_by_and_get(&self, v: $ty) -> $ty {
+                let old_bits = self
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old_bits| {
+                        let old_value = $ty::from_bits(old_bits);
+                        Some($ty::to_bits(old_value + v))
+                    })
+                    .unwrap_or_else(|bits| bits);
+                $ty::from_bits(old_bits) + v
+            }
+
             #[inline(always)]
             fn dec_by(&self, v: $ty) {
                 let _ = self.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old_bits| {
@@ -107,6 +196,40 @@ macro_rules! impl_atomic_for_float  {
                 });
             }
 
+            #[inline]
+            fn fetch_update<F>(&self, mut f: F) -> $ty
+            where
+                F: FnMut($ty) -> $ty,
+            {
+                let old_bits = $atomic::fetch_update(
+                    self,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                    |old_bits| Some($ty::to_bits(f($ty::from_bits(old_bits)))),
+                )
+                .unwrap_or_else(|bits| bits);
+                $ty::from_bits(old_bits)
+            }
+
+            #[inline]
+            fn compare_exchange(
+                &self,
+                current: $ty,
+                new: $ty,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$ty, $ty> {
+                $atomic::compare_exchange(
+                    self,
+                    $ty::to_bits(current),
+                    $ty::to_bits(new),
+                    success,
+                    failure,
+                )
+                .map($ty::from_bits)
+                .map_err($ty::from_bits)
+            }
+
             #[inline]
             fn set(&self, v: $ty) {
                 self.store($ty::to_bits(v), Ordering::Relaxed);
@@ -189,4 +312,48 @@ mod tests {
         <AtomicU64 as Atomic<u64>>::update(&value, |old| old.saturating_sub(1));
         assert_eq!(<AtomicU64 as Atomic<u64>>::get(&value), 0);
     }
+
+    // An implementation outside of this crate, providing only the required methods.
+    #[derive(Default)]
+    struct MinimalAtomic(AtomicU64);
+
+    impl Atomic<u64> for MinimalAtomic {
+        fn inc_by(&self, v: u64) {
+            self.0.fetch_add(v, Ordering::Relaxed);
+        }
+
+        fn dec_by(&self, v: u64) {
+            self.0.fetch_sub(v, Ordering::Relaxed);
+        }
+
+        fn update<F>(&self, mut f: F)
+        where
+            F: FnMut(u64) -> u64,
+        {
+            let _ = self.0.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| Some(f(old)));
+        }
+
+        fn set(&self, v: u64) {
+            self.0.store(v, Ordering::Relaxed);
+        }
+
+        fn get(&self) -> u64 {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn test_atomic_default_methods() {
+        let value = MinimalAtomic::default();
+        assert_eq!(value.inc_by_and_get(2), 2);
+        assert_eq!(value.inc_by_and_get(3), 5);
+        assert_eq!(value.get(), 5);
+
+        assert_eq!(value.fetch_update(|v| v * 2), 5);
+        assert_eq!(value.get(), 10);
+
+        assert_eq!(value.compare_exchange(10, 1, Ordering::Relaxed, Ordering::Relaxed), Ok(10));
+        assert_eq!(value.compare_exchange(10, 2, Ordering::Relaxed, Ordering::Relaxed), Err(1));
+        assert_eq!(value.get(), 1);
+    }
 }
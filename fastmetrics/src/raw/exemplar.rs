@@ -0,0 +1,275 @@
+//! Provides the exemplar type attached to counters and histogram buckets.
+
+use std::{borrow::Cow, error, fmt, time::Duration};
+
+use crate::{
+    encoder::{
+        EncodeExemplar, EncodeLabel, EncodeLabelSet, ExemplarEncoder, LabelEncoder, LabelSetEncoder,
+    },
+    error::{Error, Result},
+};
+
+/// The maximum number of UTF-8 characters of the label names and values of an exemplar, see the
+/// [OpenMetrics specification](https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#exemplars).
+pub const MAX_EXEMPLAR_LABELS_CHARS: usize = 128;
+
+/// The reason why an exemplar is invalid.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExemplarError {
+    /// The label names and values have more than [`MAX_EXEMPLAR_LABELS_CHARS`] characters in
+    /// total.
+    LabelsTooLong {
+        /// Total number of characters of the label names and values.
+        chars: usize,
+    },
+    /// The value is NaN or infinite.
+    NonFiniteValue(f64),
+}
+
+impl fmt::Display for ExemplarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LabelsTooLong { chars } => write!(
+                f,
+                "exemplar labels have {chars} characters, more than {MAX_EXEMPLAR_LABELS_CHARS}"
+            ),
+            Self::NonFiniteValue(value) => write!(f, "exemplar value {value} is not finite"),
+        }
+    }
+}
+
+impl error::Error for ExemplarError {}
+
+impl From<ExemplarError> for Error {
+    fn from(err: ExemplarError) -> Self {
+        Error::invalid(err.to_string())
+    }
+}
+
+/// Checks that `labels` and `value` form a valid exemplar.
+pub(crate) fn validate(
+    labels: &dyn EncodeLabelSet,
+    value: f64,
+) -> std::result::Result<(), ExemplarError> {
+    if !value.is_finite() {
+        return Err(ExemplarError::NonFiniteValue(value));
+    }
+    let mut counter = LabelCharCounter { chars: 0 };
+    // counting never fails
+    let _ = labels.encode(&mut counter);
+    if counter.chars > MAX_EXEMPLAR_LABELS_CHARS {
+        return Err(ExemplarError::LabelsTooLong { chars: counter.chars });
+    }
+    Ok(())
+}
+
+/// An exemplar, i.e. a reference to data outside of the metric set, such as the trace ID of a
+/// request that incremented a counter or fell into a histogram bucket.
+///
+/// Exemplars are only exposed by the OpenMetrics formats.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::raw::exemplar::Exemplar;
+/// #
+/// let exemplar = Exemplar::new([("trace_id", "4bf92f3577b34da6a3ce929d0e0e4736")], 0.25);
+/// assert_eq!(exemplar.value(), 0.25);
+/// assert_eq!(exemplar.labels()[0].0, "trace_id");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Exemplar {
+    labels: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    value: f64,
+    // UNIX timestamp
+    timestamp: Option<Duration>,
+}
+
+impl Exemplar {
+    /// Creates a new [`Exemplar`] with the given labels and value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, this function will panic if the exemplar is invalid, see
+    /// [`Exemplar::new_validated`].
+    pub fn new<N, V>(labels: impl IntoIterator<Item = (N, V)>, value: f64) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+    {
+        let labels = labels.into_iter().map(|(name, value)| (name.into(), value.into())).collect();
+        let exemplar = Self { labels, value, timestamp: None };
+        debug_assert_eq!(validate(&exemplar.labels, value), Ok(()), "invalid exemplar");
+        exemplar
+    }
+
+    /// Creates a new [`Exemplar`] with the given labels, value and optional UNIX timestamp.
+    ///
+    /// Returns an error if the label names and values have more than
+    /// [`MAX_EXEMPLAR_LABELS_CHARS`] characters in total, or if the value is NaN or infinite.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::raw::exemplar::{Exemplar, ExemplarError};
+    /// #
+    /// let exemplar = Exemplar::new_validated([("trace_id", "abc123")], 0.5, None);
+    /// assert!(exemplar.is_ok());
+    ///
+    /// let exemplar = Exemplar::new_validated([("trace_id", "abc123")], f64::NAN, None);
+    /// assert!(matches!(exemplar, Err(ExemplarError::NonFiniteValue(_))));
+    /// ```
+    pub fn new_validated<N, V>(
+        labels: impl IntoIterator<Item = (N, V)>,
+        value: f64,
+        timestamp: Option<Duration>,
+    ) -> std::result::Result<Self, ExemplarError>
+    where
+        N: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+    {
+        let labels = labels.into_iter().map(|(name, value)| (name.into(), value.into())).collect();
+        let exemplar = Self { labels, value, timestamp };
+        validate(&exemplar.labels, value)?;
+        Ok(exemplar)
+    }
+
+    /// Sets the value of the [`Exemplar`].
+    pub fn with_value(mut self, value: f64) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Sets the UNIX timestamp of the [`Exemplar`].
+    pub fn with_timestamp(mut self, timestamp: Duration) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Returns the labels of the [`Exemplar`].
+    pub fn labels(&self) -> &[(Cow<'static, str>, Cow<'static, str>)] {
+        &self.labels
+    }
+
+    /// Returns the value of the [`Exemplar`].
+    pub const fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the optional UNIX timestamp of the [`Exemplar`].
+    pub const fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+}
+
+impl EncodeExemplar for Exemplar {
+    fn encode(&self, encoder: &mut dyn ExemplarEncoder) -> Result<()> {
+        encoder.encode(&self.labels, self.value, self.timestamp)
+    }
+}
+
+/// A label encoder counting the characters of the label names and values.
+struct LabelCharCounter {
+    chars: usize,
+}
+
+impl fmt::Write for LabelCharCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.chars += s.chars().count();
+        Ok(())
+    }
+}
+
+impl LabelSetEncoder for LabelCharCounter {
+    fn encode(&mut self, label: &dyn EncodeLabel) -> Result<()> {
+        label.encode(self)
+    }
+}
+
+macro_rules! count_display_value_impls {
+    ($($ty:ident),*) => (
+        paste::paste! { $(
+            fn [<encode_ $ty _value>](&mut self, value: $ty) -> Result<()> {
+                self.encode_display_value(&value)
+            }
+        )* }
+    )
+}
+
+impl LabelEncoder for LabelCharCounter {
+    fn encode_label_name(&mut self, name: &str) -> Result<()> {
+        self.chars += name.chars().count();
+        Ok(())
+    }
+
+    fn encode_str_value(&mut self, value: &str) -> Result<()> {
+        self.chars += value.chars().count();
+        Ok(())
+    }
+
+    fn encode_display_value(&mut self, value: &dyn fmt::Display) -> Result<()> {
+        fmt::Write::write_fmt(self, format_args!("{value}"))?;
+        Ok(())
+    }
+
+    count_display_value_impls! {
+        bool,
+        i8, i16, i32, i64, i128, isize,
+        u8, u16, u32, u64, u128, usize,
+        f32, f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exemplar_new_validated() {
+        let exemplar = Exemplar::new_validated([("trace_id", "abc123")], 1.5, None).unwrap();
+        assert_eq!(exemplar.value(), 1.5);
+        assert_eq!(exemplar.timestamp(), None);
+
+        // exactly 128 characters in total is still valid
+        let value = "a".repeat(MAX_EXEMPLAR_LABELS_CHARS - "trace_id".len());
+        assert!(Exemplar::new_validated([("trace_id", value)], 1.0, None).is_ok());
+
+        // the characters are counted, not the bytes
+        let value = "é".repeat(MAX_EXEMPLAR_LABELS_CHARS - "trace_id".len());
+        assert!(Exemplar::new_validated([("trace_id", value)], 1.0, None).is_ok());
+    }
+
+    #[test]
+    fn test_exemplar_new_validated_errors() {
+        let value = "a".repeat(MAX_EXEMPLAR_LABELS_CHARS);
+        assert_eq!(
+            Exemplar::new_validated([("trace_id", value)], 1.0, None),
+            Err(ExemplarError::LabelsTooLong { chars: 136 })
+        );
+        let labels = [("trace_id", "a".repeat(100)), ("span_id", "b".repeat(20))];
+        assert_eq!(
+            Exemplar::new_validated(labels, 1.0, None),
+            Err(ExemplarError::LabelsTooLong { chars: 135 })
+        );
+
+        let err = Exemplar::new_validated([("trace_id", "abc")], f64::NAN, None).unwrap_err();
+        assert!(matches!(err, ExemplarError::NonFiniteValue(value) if value.is_nan()));
+        assert_eq!(
+            Exemplar::new_validated([("trace_id", "abc")], f64::INFINITY, None),
+            Err(ExemplarError::NonFiniteValue(f64::INFINITY))
+        );
+        assert_eq!(
+            Exemplar::new_validated([("trace_id", "abc")], f64::NEG_INFINITY, None),
+            Err(ExemplarError::NonFiniteValue(f64::NEG_INFINITY))
+        );
+    }
+
+    #[test]
+    fn test_validate_label_set() {
+        assert_eq!(validate(&[("status", 200)], 1.0), Ok(()));
+        // 1 + 39 characters
+        assert_eq!(validate(&[("n", u128::MAX)], 1.0), Ok(()));
+        let labels = [("a", u128::MAX), ("b", u128::MAX), ("c", u128::MAX), ("d", u128::MAX)];
+        assert_eq!(validate(&labels, 1.0), Err(ExemplarError::LabelsTooLong { chars: 160 }));
+    }
+}
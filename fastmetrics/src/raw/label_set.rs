@@ -7,6 +7,10 @@
 //! Together they form the foundation for reasoning about whether a metric
 //! supports labels, and if so, which label names it expects.
 
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::metrics::family::HasCardinality;
+
 /// Describes the schema (names) of a label set.
 ///
 /// Implement this trait for every label set structure.
@@ -24,6 +28,24 @@ impl LabelSetSchema for () {
     }
 }
 
+/// Label sets with dynamic label names, e.g. `BTreeMap<String, String>`, have no static schema.
+///
+/// Their label names are only known at observation time, so they're validated when encoding
+/// instead of at registration time.
+impl<K, V> LabelSetSchema for BTreeMap<K, V> {
+    fn names() -> Option<&'static [&'static str]> {
+        None
+    }
+}
+
+/// See the [`BTreeMap`] implementation, label names of `(name, value)` pairs are only known
+/// at observation time.
+impl<K, V> LabelSetSchema for Vec<(K, V)> {
+    fn names() -> Option<&'static [&'static str]> {
+        None
+    }
+}
+
 /// Declares the label set schema associated with a metric type.
 ///
 /// Metric implementations should set `LabelSet` to the label structure they
@@ -31,4 +53,17 @@ impl LabelSetSchema for () {
 pub trait MetricLabelSet {
     /// The label set schema used by this metric.
     type LabelSet: LabelSetSchema;
+
+    /// Returns the metric as a [`HasCardinality`] if it's a metric family.
+    ///
+    /// A [`Registry`](crate::registry::Registry) calls it when the metric is registered, to
+    /// report its label sets and register its cardinality overflow counter. By default, this
+    /// method returns `None`.
+    #[doc(hidden)]
+    fn into_cardinality(self: Arc<Self>) -> Option<Arc<dyn HasCardinality + Send + Sync>>
+    where
+        Self: Send + Sync + 'static,
+    {
+        None
+    }
 }
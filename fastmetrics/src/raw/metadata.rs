@@ -8,6 +8,7 @@
 
 use std::{
     borrow::Cow,
+    fmt,
     hash::{Hash, Hasher},
 };
 
@@ -82,6 +83,32 @@ impl Metadata {
     pub fn unit(&self) -> Option<&Unit> {
         self.unit.as_ref()
     }
+
+    /// Returns the fully-qualified name of the metric family, i.e. `[{namespace}_]{name}[_{unit}]`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::raw::{Metadata, MetricType, Unit};
+    /// let metadata = Metadata::new("request_duration", "", MetricType::Histogram, Some(Unit::Seconds));
+    /// assert_eq!(metadata.to_string(), "request_duration_seconds");
+    /// assert_eq!(metadata.qualified_name(Some("myapp")), "myapp_request_duration_seconds");
+    /// ```
+    pub fn qualified_name(&self, namespace: Option<&str>) -> String {
+        match namespace {
+            Some(namespace) => format!("{namespace}_{self}"),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.unit {
+            Some(unit) => write!(f, "{}_{}", self.name, unit.as_str()),
+            None => f.write_str(&self.name),
+        }
+    }
 }
 
 /// The standard measurement units according to the [OpenMetrics specification].
@@ -6,10 +6,12 @@
 
 mod atomic;
 pub mod bucket;
+pub mod exemplar;
 mod label_set;
 pub mod metadata;
 mod number;
 pub mod quantile;
 mod types;
 
+pub(crate) use self::number::{AsF64, WrappingAdd};
 pub use self::{atomic::Atomic, label_set::*, metadata::*, number::Number, types::*};
@@ -8,12 +8,59 @@ pub trait Number: Copy + PartialOrd + Debug + Send + Sync {
     const ONE: Self;
 }
 
+/// Lossy conversion of the built-in number types to `f64`.
+///
+/// It's kept out of [`Number`] (and isn't exported) so that custom number types don't need it.
+pub trait AsF64: Number {
+    /// Converts the number to `f64`, which may lose precision for large integers.
+    fn as_f64(self) -> f64;
+}
+
+/// Addition of the built-in number types, wrapping around on integer overflow.
+///
+/// Like [`AsF64`], it's kept out of [`Number`] (and isn't exported), it matches the overflow
+/// behavior of the atomic `inc_by` used by counters for non-atomic accumulators.
+pub trait WrappingAdd: Number {
+    /// Adds `rhs`, wrapping around at the boundary of integer types.
+    fn add_wrapping(self, rhs: Self) -> Self;
+}
+
+macro_rules! impl_wrapping_add {
+    (integer: $($int:ty),*; float: $($float:ty),*) => {
+        $(
+            impl WrappingAdd for $int {
+                #[inline]
+                fn add_wrapping(self, rhs: Self) -> Self {
+                    self.wrapping_add(rhs)
+                }
+            }
+        )*
+        $(
+            impl WrappingAdd for $float {
+                #[inline]
+                fn add_wrapping(self, rhs: Self) -> Self {
+                    self + rhs
+                }
+            }
+        )*
+    };
+}
+
+impl_wrapping_add!(integer: i32, i64, isize, u32, u64, usize; float: f32, f64);
+
 macro_rules! impl_number {
     ($($num:ty => $zero:expr, $one:expr);* $(;)?) => ($(
         impl Number for $num {
             const ZERO: Self = $zero;
             const ONE: Self = $one;
         }
+
+        impl AsF64 for $num {
+            #[inline]
+            fn as_f64(self) -> f64 {
+                self as f64
+            }
+        }
     )*)
 }
 
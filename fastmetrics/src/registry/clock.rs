@@ -0,0 +1,90 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+/// A source of the current time, used by a [`Registry`](super::Registry) (see
+/// [`RegistryBuilder::with_clock`](super::RegistryBuilder::with_clock)) and by the metrics which
+/// measure elapsed time, e.g. [`Family::with_ttl_and_clock`](crate::metrics::family::Family::with_ttl_and_clock).
+///
+/// The time is a duration since a fixed starting point, which must never go backwards. Timestamps
+/// (e.g. [`Registry::now`](super::Registry::now)) count from the UNIX epoch, while metrics which
+/// only measure elapsed time accept any starting point.
+///
+/// Closures returning a [`Duration`] implement this trait as well.
+pub trait Clock: Send + Sync {
+    /// Returns the current time as the duration since the starting point of the clock.
+    fn now(&self) -> Duration;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> Duration + Send + Sync,
+{
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// The default [`Clock`], reading the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::UNIX_EPOCH.elapsed().unwrap_or_default()
+    }
+}
+
+/// A [`Clock`] which only moves when told to, useful to simulate time passing in tests.
+///
+/// Clones share the same time.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use fastmetrics::registry::{Clock, ManualClock};
+/// #
+/// let clock = ManualClock::new(Duration::from_secs(1_000));
+/// clock.advance_by(Duration::from_secs(30));
+/// assert_eq!(clock.now(), Duration::from_secs(1_030));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    // nanoseconds since the UNIX epoch
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Creates a [`ManualClock`] starting at `now` (since the UNIX epoch).
+    pub fn new(now: Duration) -> Self {
+        Self { nanos: Arc::new(AtomicU64::new(duration_to_nanos(now))) }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance_by(&self, duration: Duration) {
+        self.nanos.fetch_add(duration_to_nanos(duration), Ordering::Relaxed);
+    }
+
+    /// Sets the clock to `now` (since the UNIX epoch).
+    ///
+    /// The clock never goes backwards, if `now` is earlier than the current time of the clock, the
+    /// clock is left unchanged.
+    pub fn set(&self, now: Duration) {
+        self.nanos.fetch_max(duration_to_nanos(now), Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+fn duration_to_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
@@ -6,8 +6,12 @@
 //!
 //! See [`Registry`] for more details.
 
+mod clock;
 mod global;
+mod redact;
 mod register;
+mod snapshot;
+mod source;
 mod validate;
 
 use std::{
@@ -16,14 +20,32 @@ use std::{
         HashSet,
         hash_map::{self, HashMap},
     },
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
 };
 
-pub(crate) use self::validate::{is_legacy_label_name, is_legacy_metric_name};
-pub use self::{global::*, register::*, validate::NameRule};
+pub use self::{
+    clock::{Clock, ManualClock, SystemClock},
+    global::*,
+    redact::{LabelRedactor, RedactRule},
+    register::*,
+    snapshot::{DiffReport, RegistrySnapshot},
+    source::MetricSource,
+    validate::NameRule,
+};
+pub(crate) use self::{
+    snapshot::metric_label_names,
+    validate::{is_legacy_label_name, is_legacy_metric_name},
+};
 pub use crate::raw::Unit;
 use crate::{
     encoder::EncodeMetric,
-    error::{Error, Result},
+    error::{Error, ErrorKind, Result},
+    format::text::TextProfile,
+    metrics::family::HasCardinality,
     raw::{
         LabelSetSchema, Metadata, MetricLabelSet, MetricType, TypedMetric, bucket::BUCKET_LABEL,
         quantile::QUANTILE_LABEL,
@@ -101,8 +123,42 @@ pub struct Registry {
     namespace: Option<Cow<'static, str>>,
     name_rule: NameRule,
     const_labels: Vec<(Cow<'static, str>, Cow<'static, str>)>,
-    pub(crate) metrics: HashMap<Metadata, Box<dyn EncodeMetric + 'static>>,
+    pub(crate) metrics: HashMap<Metadata, Arc<dyn EncodeMetric + 'static>>,
+    // The metric families among `metrics`
+    families: HashMap<Metadata, Arc<dyn HasCardinality + Send + Sync>>,
     pub(crate) subsystems: HashMap<Cow<'static, str>, Registry>,
+    sources: Vec<(Cow<'static, str>, Arc<dyn MetricSource>)>,
+    clock: Option<Arc<dyn Clock>>,
+    // Shared by the registry and all its subsystems
+    sealed: Arc<AtomicBool>,
+    metric_limit: Option<Arc<MetricLimit>>,
+}
+
+/// A type-erased metric, and the same metric as a [`HasCardinality`] if it's a metric family.
+struct DynMetric {
+    metric: Arc<dyn EncodeMetric>,
+    family: Option<Arc<dyn HasCardinality + Send + Sync>>,
+}
+
+/// The maximum number of metrics of a registry tree, and the number of registered metrics.
+struct MetricLimit {
+    max: usize,
+    count: AtomicUsize,
+}
+
+impl MetricLimit {
+    /// Reserves room for `n` more metrics, returns `false` if that would exceed the limit.
+    fn try_reserve(&self, n: usize) -> bool {
+        self.count
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                count.checked_add(n).filter(|&count| count <= self.max)
+            })
+            .is_ok()
+    }
+
+    fn release(&self, n: usize) {
+        self.count.fetch_sub(n, Ordering::Relaxed);
+    }
 }
 
 /// A builder for constructing [`Registry`] instances with custom configuration.
@@ -111,6 +167,8 @@ pub struct RegistryBuilder {
     namespace: Option<Cow<'static, str>>,
     name_rule: NameRule,
     const_labels: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    clock: Option<Arc<dyn Clock>>,
+    metric_limit: usize,
 }
 
 impl RegistryBuilder {
@@ -119,11 +177,20 @@ impl RegistryBuilder {
     /// # Note
     ///
     /// The namespace cannot be an empty string and must satisfy the OpenMetrics metric name rules.
+    #[doc(alias = "with_prefix")]
     pub fn with_namespace(mut self, namespace: impl Into<Cow<'static, str>>) -> Self {
         self.namespace = Some(namespace.into());
         self
     }
 
+    /// Sets a `prefix` for all metrics in the [`Registry`].
+    ///
+    /// This is an alias for [`RegistryBuilder::with_namespace`].
+    #[doc(alias = "with_namespace")]
+    pub fn with_prefix(self, prefix: impl Into<Cow<'static, str>>) -> Self {
+        self.with_namespace(prefix)
+    }
+
     /// Sets the metric/label name rule at registration time.
     ///
     /// Defaults to [`NameRule::Legacy`].
@@ -147,6 +214,29 @@ impl RegistryBuilder {
         self
     }
 
+    /// Sets the [`Clock`] of the [`Registry`], inherited by its subsystems.
+    ///
+    /// Defaults to [`SystemClock`]. The clock is only read through [`Registry::now`] and
+    /// [`Registry::clock`], registered metrics are not rewired to it. `created` timestamps and the
+    /// time of TTL families only follow a [`ManualClock`] when the metrics are created with
+    /// `registry.now()` or `registry.clock()`, see [`Registry::clock`].
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Limits the total number of metrics registered into the [`Registry`] and all its
+    /// subsystems to `n`.
+    ///
+    /// Once the limit is reached, registering a metric returns a
+    /// [`LimitExceeded`](ErrorKind::LimitExceeded) error and leaves the registry unchanged, which
+    /// catches metrics accumulating across dynamically created subsystems. Deregistered metrics
+    /// free their slot. `0` disables the limit, which is the default.
+    pub fn with_metric_limit(mut self, n: usize) -> Self {
+        self.metric_limit = n;
+        self
+    }
+
     /// Builds a [`Registry`] instance.
     ///
     /// # Errors
@@ -176,7 +266,14 @@ impl RegistryBuilder {
             name_rule: self.name_rule,
             const_labels: self.const_labels,
             metrics: HashMap::default(),
+            families: HashMap::default(),
             subsystems: HashMap::default(),
+            sources: Vec::new(),
+            clock: self.clock,
+            sealed: Arc::new(AtomicBool::new(false)),
+            metric_limit: (self.metric_limit > 0).then(|| {
+                Arc::new(MetricLimit { max: self.metric_limit, count: AtomicUsize::new(0) })
+            }),
         })
     }
 }
@@ -188,10 +285,19 @@ impl Registry {
     }
 
     /// Returns the current `namespace` of [`Registry`].
+    #[doc(alias = "prefix")]
     pub fn namespace(&self) -> Option<&str> {
         self.namespace.as_deref()
     }
 
+    /// Returns the current `prefix` of [`Registry`].
+    ///
+    /// This is an alias for [`Registry::namespace`].
+    #[doc(alias = "namespace")]
+    pub fn prefix(&self) -> Option<&str> {
+        self.namespace()
+    }
+
     /// Returns the `constant labels` of [`Registry`].
     pub fn constant_labels(&self) -> &[(Cow<'static, str>, Cow<'static, str>)] {
         &self.const_labels
@@ -201,6 +307,171 @@ impl Registry {
     pub fn name_rule(&self) -> NameRule {
         self.name_rule
     }
+
+    /// Returns the maximum number of metrics of [`Registry`] and all its subsystems, if limited.
+    ///
+    /// See [`RegistryBuilder::with_metric_limit`].
+    pub fn metric_limit(&self) -> Option<usize> {
+        self.metric_limit.as_ref().map(|limit| limit.max)
+    }
+
+    /// Returns the [`Clock`] of [`Registry`].
+    ///
+    /// Registering a metric doesn't change its time source. Time-dependent metrics, e.g. families
+    /// with a TTL, have to be created with this clock explicitly for their time to be controlled
+    /// through [`RegistryBuilder::with_clock`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use fastmetrics::{
+    /// #     error::Result,
+    /// #     metrics::{counter::Counter, family::Family},
+    /// #     registry::{Clock, ManualClock, Registry},
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let clock = ManualClock::new(Duration::from_secs(1_000));
+    /// let mut registry = Registry::builder().with_clock(clock.clone()).build()?;
+    ///
+    /// let registry_clock = registry.clock();
+    /// let jobs = Family::<Vec<(&str, &str)>, Counter>::default()
+    ///     .with_ttl_and_clock(Duration::from_secs(60), move || registry_clock.now());
+    /// registry.register("jobs", "Total jobs", jobs.clone())?;
+    ///
+    /// jobs.with_or_new(&vec![("kind", "backup")], |counter| counter.inc());
+    /// clock.advance_by(Duration::from_secs(61));
+    /// // the idle label set has expired, without sleeping
+    /// assert!(jobs.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone().unwrap_or_else(|| Arc::new(SystemClock))
+    }
+
+    /// Returns the current time of the [`Clock`] of [`Registry`], as the duration since the UNIX
+    /// epoch.
+    ///
+    /// This is typically used as the created timestamp of metrics, e.g.
+    /// `Counter::with_created(registry.now())`.
+    pub fn now(&self) -> Duration {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemClock.now(),
+        }
+    }
+}
+
+// introspection
+impl Registry {
+    /// Returns an iterator over the metrics registered directly in this [`Registry`], along with
+    /// their metadata.
+    ///
+    /// Metrics registered in subsystems are not included, see [`Registry::all_metrics`].
+    #[doc(alias = "iter")]
+    pub fn metrics(&self) -> impl Iterator<Item = (&Metadata, &dyn EncodeMetric)> {
+        self.metrics.iter().map(|(metadata, metric)| (metadata, metric.as_ref()))
+    }
+
+    /// Returns an iterator over the direct subsystems of this [`Registry`] and their names.
+    pub fn subsystems(&self) -> impl Iterator<Item = (&str, &Registry)> {
+        self.subsystems.iter().map(|(name, subsystem)| (name.as_ref(), subsystem))
+    }
+
+    /// Returns an iterator over all metrics in this [`Registry`] and its subsystems, walking the
+    /// subsystems depth-first.
+    ///
+    /// Each item carries the fully-qualified metric name, i.e. including the namespace,
+    /// subsystem names and unit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{error::Result, metrics::counter::Counter, registry::Registry};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::builder().with_namespace("myapp").build()?;
+    /// registry.subsystem("db")?.register("queries", "Total queries", <Counter>::default())?;
+    ///
+    /// let names = registry.all_metrics().map(|(name, _, _)| name).collect::<Vec<_>>();
+    /// assert_eq!(names, ["myapp_db_queries"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn all_metrics(&self) -> impl Iterator<Item = (String, &Metadata, &dyn EncodeMetric)> {
+        self.all_metrics_boxed()
+    }
+
+    fn all_metrics_boxed(
+        &self,
+    ) -> Box<dyn Iterator<Item = (String, &Metadata, &dyn EncodeMetric)> + '_> {
+        let metrics = self.metrics().map(|(metadata, metric)| {
+            (metadata.qualified_name(self.namespace()), metadata, metric)
+        });
+        let subsystems = self.subsystems.values().flat_map(Registry::all_metrics_boxed);
+        Box::new(metrics.chain(subsystems))
+    }
+
+    /// Returns the number of label sets of every metric family in this [`Registry`] and its
+    /// subsystems, sorted by count in descending order.
+    ///
+    /// Only metrics implementing [`HasCardinality`](crate::metrics::family::HasCardinality), i.e.
+    /// metric families, are reported, except for families registered type-erased with
+    /// [`Registry::register_boxed`]. Each family is locked just long enough to be counted, so the
+    /// report is cheap enough to be produced periodically, e.g. to find label cardinality
+    /// explosions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{
+    /// #     error::Result,
+    /// #     metrics::{counter::Counter, family::Family},
+    /// #     registry::{CardinalityEntry, Registry},
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    ///
+    /// let requests = Family::<Vec<(&'static str, u16)>, Counter>::default();
+    /// registry.register("requests", "Requests by status", requests.clone())?;
+    /// registry.register("connections", "Total connections", <Counter>::default())?;
+    ///
+    /// requests.with_or_new(&vec![("status", 200)], |counter| counter.inc());
+    /// requests.with_or_new(&vec![("status", 404)], |counter| counter.inc());
+    ///
+    /// let report = registry.cardinality_report();
+    /// assert_eq!(report, [CardinalityEntry { name: "requests".into(), count: 2 }]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cardinality_report(&self) -> Vec<CardinalityEntry> {
+        let mut report = Vec::new();
+        self.collect_cardinality(&mut report);
+        report.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+        report
+    }
+
+    fn collect_cardinality(&self, report: &mut Vec<CardinalityEntry>) {
+        report.extend(self.families.iter().map(|(metadata, family)| CardinalityEntry {
+            name: metadata.qualified_name(self.namespace()),
+            count: family.cardinality(),
+        }));
+        for subsystem in self.subsystems.values() {
+            subsystem.collect_cardinality(report);
+        }
+    }
+}
+
+/// The number of label sets of a metric family, see [`Registry::cardinality_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CardinalityEntry {
+    /// Fully-qualified name of the metric family.
+    pub name: String,
+    /// Current number of label sets.
+    pub count: usize,
 }
 
 // register
@@ -239,6 +510,58 @@ impl Registry {
         self.register_metric(name, help, None::<Unit>, metric)
     }
 
+    /// Registers a metric without a unit into [`Registry`], ignoring duplicate registrations.
+    ///
+    /// Returns `Ok(true)` if the metric was registered, or `Ok(false)` if a metric with the same
+    /// name, type and help text already exists without a unit, in which case the previously
+    /// registered metric is kept. This is useful for library code that may register its metrics
+    /// more than once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metric is invalid (e.g., invalid name or help text), or a
+    /// [`Duplicated`](ErrorKind::Duplicated) error if the existing metric has a different help
+    /// text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{
+    /// #    error::Result,
+    /// #    metrics::counter::Counter,
+    /// #    registry::Registry,
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    ///
+    /// assert!(registry.register_once("requests", "Total requests", <Counter>::default())?);
+    /// assert!(!registry.register_once("requests", "Total requests", <Counter>::default())?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_once<M: Metric>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        help: impl Into<Cow<'static, str>>,
+        metric: M,
+    ) -> Result<bool> {
+        let name = name.into();
+        let help = help.into();
+        let metadata = Metadata::new(name.clone(), help.clone(), <M as TypedMetric>::TYPE, None);
+        match self.register(name, help, metric) {
+            Ok(_) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::Duplicated => {
+                // only an identical registration is ignored, a different help text is an error
+                match self.metrics.get_key_value(&metadata) {
+                    Some((existing, _)) if existing.help() == metadata.help() => Ok(false),
+                    _ => Err(err),
+                }
+            },
+            Err(err) => Err(err),
+        }
+    }
+
     /// Registers a metric with the specified unit into [`Registry`].
     ///
     /// # Example
@@ -311,6 +634,73 @@ impl Registry {
         unit: Option<impl Into<Unit>>,
         metric: M,
     ) -> Result<&mut Self> {
+        let metric = Arc::new(metric);
+        let family = <M as MetricLabelSet>::into_cardinality(metric.clone());
+        self.register_dyn_metric(
+            name,
+            help,
+            unit.map(Into::into),
+            <M as TypedMetric>::TYPE,
+            <M::LabelSet as LabelSetSchema>::names(),
+            DynMetric { metric, family },
+        )
+    }
+
+    /// Registers a type-erased metric with caller-supplied metadata into [`Registry`].
+    ///
+    /// Unlike [`Registry::register`], the metric does not need to implement [`TypedMetric`] or
+    /// [`MetricLabelSet`], so metrics stored as `Arc<dyn EncodeMetric>` (e.g. by plugin systems
+    /// or adapters for foreign metric types) can be registered as well.
+    ///
+    /// `label_names` describes the variable label names of the metric, and it's validated the
+    /// same way as [`LabelSetSchema::names`].
+    ///
+    /// Since the metric is type-erased, a metric family registered this way is neither included
+    /// in [`Registry::cardinality_report`] nor gets a cardinality overflow counter.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use fastmetrics::{
+    /// #     encoder::EncodeMetric,
+    /// #     error::Result,
+    /// #     metrics::counter::Counter,
+    /// #     raw::MetricType,
+    /// #     registry::Registry,
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    ///
+    /// let requests: Arc<dyn EncodeMetric> = Arc::new(<Counter>::default());
+    /// registry.register_boxed("requests", "Total requests", MetricType::Counter, None, requests)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_boxed(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        help: impl Into<Cow<'static, str>>,
+        metric_type: MetricType,
+        label_names: Option<&[&'static str]>,
+        metric: Arc<dyn EncodeMetric>,
+    ) -> Result<&mut Self> {
+        let metric = DynMetric { metric, family: None };
+        self.register_dyn_metric(name, help, None, metric_type, label_names, metric)
+    }
+
+    fn register_dyn_metric(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        help: impl Into<Cow<'static, str>>,
+        unit: Option<Unit>,
+        metric_type: MetricType,
+        label_names: Option<&[&'static str]>,
+        DynMetric { metric, family }: DynMetric,
+    ) -> Result<&mut Self> {
+        self.check_unsealed()?;
+
         // Check the metric name
         let name: Cow<'static, str> = name.into();
         validate_metric_name_with_rule(&name, self.namespace().is_none(), self.name_rule)
@@ -325,8 +715,6 @@ impl Registry {
         })?;
 
         // Check the metric unit format
-        let unit = unit.map(Into::into);
-        let metric_type = <M as TypedMetric>::TYPE;
         if let Some(Unit::Other(unit)) = unit.as_ref() {
             validate_unit(unit.as_ref()).map_err(|err| {
                 Error::invalid(err.to_string())
@@ -369,7 +757,7 @@ impl Registry {
 
         // Check the variable metric labels
         let mut variable_label_names = HashSet::new();
-        if let Some(names) = <M::LabelSet as LabelSetSchema>::names() {
+        if let Some(names) = label_names {
             for name in names.iter().copied() {
                 if let Err(err) = validate_label_name_with_rule(name, self.name_rule) {
                     return Err(Error::invalid(err.to_string()).with_context("label", name));
@@ -391,16 +779,301 @@ impl Registry {
             }
         }
 
+        let overflow = family.as_ref().and_then(|family| family.cardinality_overflow());
         let metadata = Metadata::new(name.clone(), help.clone(), metric_type, unit);
-        match self.metrics.entry(metadata) {
+        match self.metrics.entry(metadata.clone()) {
             hash_map::Entry::Vacant(entry) => {
-                entry.insert(Box::new(metric));
-                Ok(self)
+                if let Some(limit) = &self.metric_limit {
+                    if !limit.try_reserve(1) {
+                        return Err(Error::limit_exceeded("metric limit reached")
+                            .with_context("metric", metadata.qualified_name(self.namespace()))
+                            .with_context("limit", limit.max));
+                    }
+                }
+                entry.insert(metric);
+                if let Some(family) = family {
+                    self.families.insert(metadata.clone(), family);
+                }
             },
-            hash_map::Entry::Occupied(_) => {
-                Err(Error::duplicated("metric already exists").with_context("metric", name))
+            hash_map::Entry::Occupied(entry) => {
+                return Err(Error::duplicated("metric already exists").with_context(
+                    "metric",
+                    entry.key().qualified_name(self.namespace.as_deref()),
+                ));
             },
         }
+
+        // Register the overflow counter of a cardinality limited family alongside, and roll back
+        // the family registration if that fails.
+        if let Some(overflow) = overflow {
+            let overflow_name = format!("{name}_cardinality_overflow");
+            let overflow_help = format!(
+                "Calls rejected or evicting label sets over the cardinality limit of {name}"
+            );
+            if let Err(err) = self.register(overflow_name, overflow_help, overflow) {
+                self.metrics.remove(&metadata);
+                self.families.remove(&metadata);
+                if let Some(limit) = &self.metric_limit {
+                    limit.release(1);
+                }
+                return Err(err);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Removes a previously registered metric identified by its `name` and `unit`.
+    ///
+    /// Returns `true` if a metric was registered under that name and unit and has been removed.
+    /// Handles of the removed metric keep working, they are just no longer encoded.
+    ///
+    /// Removing a family limited by [`Family::with_max_entries`](crate::metrics::family::Family::with_max_entries)
+    /// also removes its `<name>_cardinality_overflow` counter.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{error::Result, metrics::counter::Counter, registry::Registry};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    ///
+    /// let connections = <Counter>::default();
+    /// registry.register("pool_connections", "Total pool connections", connections.clone())?;
+    ///
+    /// assert!(registry.deregister("pool_connections", None)?);
+    /// assert!(!registry.deregister("pool_connections", None)?);
+    ///
+    /// // the handle is still usable
+    /// connections.inc();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deregister(&mut self, name: &str, unit: Option<Unit>) -> Result<bool> {
+        validate_metric_name_with_rule(name, self.namespace().is_none(), self.name_rule)
+            .map_err(|err| Error::invalid(err.to_string()).with_context("metric", name))?;
+
+        let len = self.metrics.len();
+        let is_removed =
+            |metadata: &Metadata| metadata.name() == name && metadata.unit() == unit.as_ref();
+        self.metrics.retain(|metadata, _| !is_removed(metadata));
+        let mut has_overflow = false;
+        self.families.retain(|metadata, family| {
+            let removed = is_removed(metadata);
+            has_overflow |= removed && family.cardinality_overflow().is_some();
+            !removed
+        });
+        // the overflow counter registered alongside a cardinality limited family goes with it
+        if has_overflow {
+            let overflow_name = format!("{name}_cardinality_overflow");
+            self.metrics.retain(|metadata, _| {
+                metadata.name() != overflow_name || metadata.metric_type() != MetricType::Counter
+            });
+        }
+        if let Some(limit) = &self.metric_limit {
+            limit.release(len - self.metrics.len());
+        }
+        Ok(self.metrics.len() != len)
+    }
+
+    /// Moves all metrics and subsystems of `other` into this registry.
+    ///
+    /// Subsystems existing in both registries are merged recursively. Both registries (and the
+    /// subsystems being merged) must share the same namespace, constant labels and name rule.
+    ///
+    /// The merge is all-or-nothing: if any metric of `other` has the same name and unit as a
+    /// metric of this registry, a [`Duplicated`](ErrorKind::Duplicated) error is returned and
+    /// this registry is left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{error::Result, metrics::counter::Counter, registry::Registry};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    /// registry.register("http_requests", "Total HTTP requests", <Counter>::default())?;
+    ///
+    /// let mut db = Registry::default();
+    /// db.subsystem("db")?.register("queries", "Total queries", <Counter>::default())?;
+    ///
+    /// registry.merge(db)?;
+    /// assert_eq!(registry.all_metrics().count(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge(&mut self, other: Registry) -> Result<()> {
+        self.check_mergeable(&other)?;
+        if let Some(limit) = &self.metric_limit {
+            let n = other.all_metrics().count();
+            if !limit.try_reserve(n) {
+                return Err(Error::limit_exceeded("metric limit reached")
+                    .with_context("metrics", n)
+                    .with_context("limit", limit.max));
+            }
+        }
+        self.merge_unchecked(other);
+        Ok(())
+    }
+
+    fn check_mergeable(&self, other: &Registry) -> Result<()> {
+        self.check_unsealed()?;
+        if self.namespace != other.namespace
+            || self.const_labels != other.const_labels
+            || self.name_rule != other.name_rule
+        {
+            return Err(Error::invalid(
+                "cannot merge registries with different namespaces, constant labels or name rules",
+            )
+            .with_context("namespace", self.namespace().unwrap_or_default())
+            .with_context("other_namespace", other.namespace().unwrap_or_default()));
+        }
+
+        for metadata in other.metrics.keys() {
+            let duplicated = self.metrics.keys().any(|existing| {
+                existing.name() == metadata.name() && existing.unit() == metadata.unit()
+            });
+            if duplicated {
+                return Err(Error::duplicated("metric already exists")
+                    .with_context("metric", metadata.qualified_name(self.namespace())));
+            }
+        }
+
+        for (name, _) in &other.sources {
+            if self.sources.iter().any(|(existing, _)| existing == name) {
+                return Err(
+                    Error::duplicated("metric source already exists").with_context("source", name)
+                );
+            }
+        }
+
+        for (name, subsystem) in &other.subsystems {
+            if let Some(existing) = self.subsystems.get(name) {
+                existing.check_mergeable(subsystem)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_unchecked(&mut self, other: Registry) {
+        self.metrics.extend(other.metrics);
+        self.families.extend(other.families);
+        self.sources.extend(other.sources);
+        for (name, mut subsystem) in other.subsystems {
+            match self.subsystems.entry(name) {
+                hash_map::Entry::Occupied(mut entry) => entry.get_mut().merge_unchecked(subsystem),
+                hash_map::Entry::Vacant(entry) => {
+                    // merged subsystems share the clock, seal and metric limit of this registry
+                    subsystem.inherit(&self.clock, &self.sealed, &self.metric_limit);
+                    entry.insert(subsystem);
+                },
+            }
+        }
+    }
+
+    fn inherit(
+        &mut self,
+        clock: &Option<Arc<dyn Clock>>,
+        sealed: &Arc<AtomicBool>,
+        metric_limit: &Option<Arc<MetricLimit>>,
+    ) {
+        self.clock = clock.clone();
+        self.sealed = sealed.clone();
+        self.metric_limit = metric_limit.clone();
+        for subsystem in self.subsystems.values_mut() {
+            subsystem.inherit(clock, sealed, metric_limit);
+        }
+    }
+}
+
+// text encoding
+impl Registry {
+    /// Encodes the metrics of [`Registry`] into a `String` with the given text `profile`.
+    ///
+    /// This is a shorthand for [`text::encode`](crate::format::text::encode) into a new `String`,
+    /// typically used with an OpenMetrics profile.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{
+    /// #     error::Result,
+    /// #     format::text::TextProfile,
+    /// #     metrics::counter::Counter,
+    /// #     registry::Registry,
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    /// registry.register("requests", "Total requests", <Counter>::default())?;
+    ///
+    /// let output = registry.to_openmetrics_text(TextProfile::default())?;
+    /// assert!(output.ends_with("# EOF\n"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_openmetrics_text(&self, profile: TextProfile) -> Result<String> {
+        let mut output = String::new();
+        crate::format::text::encode(&mut output, self, profile)?;
+        Ok(output)
+    }
+
+    /// Encodes the metrics of [`Registry`] into a `String` with the
+    /// [`PrometheusV0_0_4`](TextProfile::PrometheusV0_0_4) text profile.
+    pub fn to_prometheus_text(&self) -> Result<String> {
+        self.to_openmetrics_text(TextProfile::PrometheusV0_0_4)
+    }
+}
+
+// seal
+impl Registry {
+    /// Seals the registry, so that no more metrics can be registered into it.
+    ///
+    /// This is useful to make sure that all metrics are registered during the initialization of
+    /// the application. Afterwards, registering a metric, creating or accessing a subsystem, or
+    /// merging another registry returns a [`Sealed`](ErrorKind::Sealed) error. Already registered
+    /// metrics are still encoded as usual.
+    ///
+    /// The seal is shared by the whole registry tree: sealing a registry also seals its parent and
+    /// all its subsystems, including the ones created before. Sealing is idempotent and can't be
+    /// undone.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{
+    /// #     error::{ErrorKind, Result},
+    /// #     metrics::counter::Counter,
+    /// #     registry::Registry,
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    /// registry.register("http_requests", "Total HTTP requests", <Counter>::default())?;
+    ///
+    /// registry.seal();
+    /// assert!(registry.is_sealed());
+    ///
+    /// let err = registry.register("connections", "Total connections", <Counter>::default());
+    /// assert_eq!(err.err().map(|err| err.kind()), Some(ErrorKind::Sealed));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn seal(&self) {
+        self.sealed.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` if the registry has been sealed by [`Registry::seal`].
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.load(Ordering::Acquire)
+    }
+
+    fn check_unsealed(&self) -> Result<()> {
+        if self.is_sealed() {
+            return Err(Error::sealed("registry is sealed")
+                .with_context("namespace", self.namespace().unwrap_or_default()));
+        }
+        Ok(())
     }
 }
 
@@ -495,6 +1168,36 @@ impl Registry {
         let name = name.into();
         RegistrySubsystemBuilder::new(self, name)
     }
+
+    /// Returns the direct subsystem named `name`, or `None` if it hasn't been created.
+    ///
+    /// Unlike [`subsystem`](Registry::subsystem), this never creates the subsystem.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{error::Result, registry::Registry};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::builder().with_namespace("myapp").build()?;
+    /// assert!(registry.subsystem_ref("database").is_none());
+    ///
+    /// registry.subsystem("database")?;
+    /// let db = registry.subsystem_ref("database").unwrap();
+    /// assert_eq!(db.namespace(), Some("myapp_database"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subsystem_ref(&self, name: &str) -> Option<&Registry> {
+        self.subsystems.get(name)
+    }
+
+    /// Returns the direct subsystem named `name` mutably, or `None` if it hasn't been created.
+    ///
+    /// Unlike [`subsystem`](Registry::subsystem), this never creates the subsystem.
+    pub fn subsystem_mut(&mut self, name: &str) -> Option<&mut Registry> {
+        self.subsystems.get_mut(name)
+    }
 }
 
 /// A builder for constructing subsystems with custom configuration.
@@ -564,6 +1267,7 @@ impl<'a> RegistrySubsystemBuilder<'a> {
     /// - Constant labels merged from parent and subsystem-specific labels
     pub fn build(self) -> Result<&'a mut Registry> {
         let RegistrySubsystemBuilder { parent, name, const_labels } = self;
+        parent.check_unsealed()?;
 
         // Check if the subsystem name is valid
         if name.is_empty() {
@@ -609,11 +1313,14 @@ impl<'a> RegistrySubsystemBuilder<'a> {
                     None => parent.const_labels.clone(),
                 };
 
-                let registry = Registry::builder()
+                let mut registry = Registry::builder()
                     .with_namespace(namespace)
                     .with_name_rule(parent.name_rule)
                     .with_const_labels(const_labels)
                     .build()?;
+                registry.clock = parent.clock.clone();
+                registry.sealed = parent.sealed.clone();
+                registry.metric_limit = parent.metric_limit.clone();
 
                 Ok(entry.insert(registry))
             },
@@ -662,7 +1369,10 @@ mod tests {
     use std::time::Duration;
 
     use super::*;
-    use crate::{encoder::MetricEncoder, error::ErrorKind};
+    use crate::{
+        encoder::MetricEncoder,
+        metrics::{counter::Counter, family::Family},
+    };
 
     #[test]
     fn test_registry_subsystem() -> Result<()> {
@@ -688,6 +1398,85 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_registry_subsystem_ref_and_mut() -> Result<()> {
+        let mut registry = Registry::builder().with_namespace("myapp").build()?;
+        assert!(registry.subsystem_ref("db").is_none());
+        assert!(registry.subsystem_mut("db").is_none());
+        // looking a subsystem up doesn't create it
+        assert_eq!(registry.subsystems().count(), 0);
+
+        registry.subsystem("db")?.subsystem("mysql")?;
+        assert_eq!(registry.subsystem_ref("db").unwrap().namespace(), Some("myapp_db"));
+        assert!(registry.subsystem_ref("mysql").is_none());
+
+        let db = registry.subsystem_mut("db").unwrap();
+        assert_eq!(db.subsystem_ref("mysql").unwrap().namespace(), Some("myapp_db_mysql"));
+        db.register("queries", "Total queries", <Counter>::default())?;
+        assert_eq!(registry.subsystem_ref("db").unwrap().metrics().count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_clock() -> Result<()> {
+        let before = SystemClock.now();
+        assert!(Registry::default().now() >= before);
+
+        let clock = ManualClock::new(Duration::from_secs(1_000));
+        let mut registry = Registry::builder().with_clock(clock.clone()).build()?;
+        assert_eq!(registry.now(), Duration::from_secs(1_000));
+
+        // subsystems and snapshots share the clock of the parent registry
+        let db = registry.subsystem("db")?;
+        let db_clock = db.clock();
+        let queries = Family::<Vec<(&str, &str)>, Counter>::default()
+            .with_ttl_and_clock(Duration::from_secs(60), move || db_clock.now());
+        db.register("queries", "Total queries", queries.clone())?;
+        queries.with_or_new(&vec![("kind", "select")], |counter| counter.inc());
+
+        clock.advance_by(Duration::from_secs(30));
+        assert_eq!(registry.subsystem("db")?.now(), Duration::from_secs(1_030));
+        assert_eq!(registry.snapshot()?.now(), Duration::from_secs(1_030));
+        assert_eq!(queries.len(), 1);
+
+        clock.advance_by(Duration::from_secs(31));
+        assert!(queries.is_empty());
+
+        clock.set(Duration::from_secs(1_100));
+        assert_eq!(registry.now(), Duration::from_secs(1_100));
+        // the clock never goes backwards
+        clock.set(Duration::from_secs(5));
+        assert_eq!(registry.now(), Duration::from_secs(1_100));
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_prefix_is_alias_of_with_namespace() -> Result<()> {
+        use crate::{
+            format::text::{self, TextProfile},
+            metrics::counter::Counter,
+        };
+
+        let encode = |builder: RegistryBuilder| -> Result<String> {
+            let mut registry = builder.build()?;
+            registry.register("requests", "Total requests", <Counter>::default())?;
+            let mut output = String::new();
+            text::encode(&mut output, &registry, TextProfile::default())?;
+            Ok(output)
+        };
+
+        let registry = Registry::builder().with_prefix("myapp").build()?;
+        assert_eq!(registry.prefix(), Some("myapp"));
+        assert_eq!(registry.namespace(), Some("myapp"));
+
+        assert_eq!(
+            encode(Registry::builder().with_prefix("myapp"))?,
+            encode(Registry::builder().with_namespace("myapp"))?,
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_name_rule_default_is_legacy() {
         let registry = Registry::default();
@@ -861,6 +1650,216 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_register_once() -> Result<()> {
+        use crate::metrics::counter::Counter;
+
+        let mut registry = Registry::default();
+
+        let first = <Counter>::default();
+        let second = <Counter>::default();
+        assert!(registry.register_once("requests", "Total requests", first.clone())?);
+        assert!(!registry.register_once("requests", "Total requests", second.clone())?);
+
+        // a conflicting registration is still reported
+        let err = registry.register_once("requests", "Another help", <Counter>::default());
+        assert_eq!(err.unwrap_err().kind(), ErrorKind::Duplicated);
+
+        first.inc_by(1);
+        second.inc_by(2);
+        let mut output = String::new();
+        crate::format::text::encode(&mut output, &registry, Default::default())?;
+        assert!(output.contains("requests_total 1\n"));
+        assert!(!output.contains("requests_total 2\n"));
+
+        // invalid metrics are still reported
+        assert!(registry.register_once("invalid name", "", <Counter>::default()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deregister() -> Result<()> {
+        use crate::metrics::counter::Counter;
+
+        let mut registry = Registry::default();
+
+        let requests = <Counter>::default();
+        let latency = <Counter>::default();
+        registry.register("requests", "Total requests", requests.clone())?;
+        registry.register_with_unit("latency", "Total latency", Unit::Seconds, latency.clone())?;
+        let db = registry.subsystem("db")?;
+        let queries = <Counter>::default();
+        db.register("queries", "Total queries", queries.clone())?;
+
+        assert!(registry.deregister("requests", None)?);
+        assert!(!registry.deregister("requests", None)?);
+        assert!(!registry.deregister("latency", None)?);
+        assert!(registry.subsystem("db")?.deregister("queries", None)?);
+        assert!(registry.deregister("invalid name", None).is_err());
+
+        requests.inc();
+        queries.inc();
+        latency.inc();
+        let mut output = String::new();
+        crate::format::text::encode(&mut output, &registry, Default::default())?;
+        assert!(!output.contains("requests"));
+        assert!(!output.contains("queries"));
+        assert!(output.contains("latency_seconds_total 1\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deregister_removes_cardinality_overflow() -> Result<()> {
+        use crate::metrics::{
+            counter::Counter,
+            family::{CardinalityPolicy, Family},
+        };
+
+        let mut registry = Registry::builder().with_metric_limit(2).build()?;
+        let limited = || {
+            Family::<Vec<(&'static str, u16)>, Counter>::default()
+                .with_max_entries(1, CardinalityPolicy::Block)
+        };
+
+        registry.register("requests", "Total requests", limited())?;
+        assert_eq!(registry.all_metrics().count(), 2);
+        assert!(registry.deregister("requests", None)?);
+        assert_eq!(registry.all_metrics().count(), 0);
+
+        // re-registering works, and fits in the metric limit again
+        let requests = limited();
+        registry.register("requests", "Total requests", requests.clone())?;
+        requests.with_or_new(&vec![("id", 1)], |counter| counter.inc());
+        requests.with_or_new(&vec![("id", 2)], |counter| counter.inc());
+        let output = registry.to_openmetrics_text(Default::default())?;
+        assert!(output.contains("requests_cardinality_overflow_total 1\n"), "{output}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge() -> Result<()> {
+        use crate::{format::text, metrics::counter::Counter};
+
+        let mut registry = Registry::builder().with_namespace("myapp").build()?;
+        registry.register("requests", "Total requests", <Counter>::default())?;
+        registry
+            .subsystem("db")?
+            .register("queries", "Total queries", <Counter>::default())?;
+
+        let mut other = Registry::builder().with_namespace("myapp").build()?;
+        other.register("errors", "Total errors", <Counter>::default())?;
+        let db = other.subsystem("db")?;
+        db.register("errors", "Total query errors", <Counter>::default())?;
+        db.subsystem("mysql")?.register(
+            "connections",
+            "Total connections",
+            <Counter>::default(),
+        )?;
+
+        registry.merge(other)?;
+
+        let mut output = String::new();
+        text::encode(&mut output, &registry, Default::default())?;
+        for name in [
+            "myapp_requests",
+            "myapp_errors",
+            "myapp_db_queries",
+            "myapp_db_errors",
+            "myapp_db_mysql_connections",
+        ] {
+            assert!(output.contains(&format!("# TYPE {name} counter")), "{output}");
+        }
+
+        // conflicts leave the registry unchanged
+        let mut other = Registry::builder().with_namespace("myapp").build()?;
+        other.register("fresh", "Fresh metric", <Counter>::default())?;
+        other
+            .subsystem("db")?
+            .register("queries", "Total queries", <Counter>::default())?;
+        let err = registry.merge(other).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Duplicated);
+        assert!(err.to_string().contains("myapp_db_queries"));
+        assert_eq!(registry.all_metrics().count(), 5);
+
+        let err = registry.merge(Registry::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Invalid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_introspection() -> Result<()> {
+        use crate::metrics::counter::Counter;
+
+        let mut registry = Registry::builder().with_namespace("myapp").build()?;
+        registry.register("requests", "Total requests", <Counter>::default())?;
+        let db = registry.subsystem("db")?;
+        db.register_with_unit("latency", "Query latency", Unit::Seconds, <Counter>::default())?;
+        db.subsystem("mysql")?
+            .register("queries", "Total queries", <Counter>::default())?;
+
+        let metrics = registry.metrics().collect::<Vec<_>>();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].0.name(), "requests");
+        assert_eq!(metrics[0].0.metric_type(), MetricType::Counter);
+
+        let subsystems = registry.subsystems().map(|(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(subsystems, ["db"]);
+
+        let mut names = registry.all_metrics().map(|(name, _, _)| name).collect::<Vec<_>>();
+        // depth-first: a registry's own metrics come before its subsystems'
+        assert_eq!(names[0], "myapp_requests");
+        names.sort();
+        assert_eq!(names, ["myapp_db_latency_seconds", "myapp_db_mysql_queries", "myapp_requests"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_same_metric_reports_qualified_name() -> Result<()> {
+        let mut registry = Registry::builder().with_namespace("myapp").build()?;
+        registry.register_with_unit("dummy", "", Unit::Seconds, DummyCounter)?;
+
+        let err = registry
+            .register_with_unit("dummy", "", Unit::Seconds, DummyCounter)
+            .err()
+            .expect("duplicated metric");
+        assert_eq!(err.kind(), ErrorKind::Duplicated);
+        assert!(err.to_string().contains("myapp_dummy_seconds"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_boxed() -> Result<()> {
+        use crate::{
+            format::text::{self, TextProfile},
+            metrics::counter::Counter,
+        };
+
+        let mut registry = Registry::default();
+        let counter = <Counter>::default();
+        counter.inc_by(3);
+        let metric: Arc<dyn EncodeMetric> = Arc::new(counter);
+        registry.register_boxed("requests", "Total requests", MetricType::Counter, None, metric)?;
+
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default())?;
+        assert!(output.contains("# TYPE requests counter"));
+        assert!(output.contains("requests_total 3"));
+
+        let metric: Arc<dyn EncodeMetric> = Arc::new(DummyCounter);
+        let err = registry
+            .register_boxed("dummy", "", MetricType::Histogram, Some(&["le"]), metric)
+            .err()
+            .expect("reserved label name");
+        assert_eq!(err.kind(), ErrorKind::Invalid);
+
+        Ok(())
+    }
+
     #[test]
     fn test_custom_unit_accepts_metricname_chars() {
         let mut registry = Registry::default();
@@ -876,4 +1875,172 @@ mod tests {
                 .is_ok()
         );
     }
+
+    #[test]
+    fn test_cardinality_report() {
+        use crate::metrics::{counter::Counter, family::Family, gauge::Gauge};
+
+        type Labels = Vec<(&'static str, u16)>;
+
+        let mut registry = Registry::builder().with_namespace("app").build().unwrap();
+        let requests = Family::<Labels, Counter>::default();
+        registry.register("requests", "Requests by status", requests.clone()).unwrap();
+        registry.register("uptime", "Uptime", <Gauge>::default()).unwrap();
+        let queries = Family::<Labels, Counter>::default();
+        let pools = Family::<Labels, Gauge>::default();
+        let empty = Family::<Labels, Gauge>::default();
+        let db = registry.subsystem("db").unwrap();
+        db.register("queries", "Queries by shard", queries.clone()).unwrap();
+        db.register("pools", "Pools by shard", pools.clone()).unwrap();
+        db.register("empty", "Never observed", empty).unwrap();
+
+        for status in [200, 404] {
+            requests.with_or_new(&vec![("status", status)], |counter| counter.inc());
+        }
+        for shard in 0..3 {
+            queries.with_or_new(&vec![("shard", shard)], |counter| counter.inc());
+            pools.with_or_new(&vec![("shard", shard)], |gauge| gauge.set(1));
+        }
+
+        let report = registry
+            .cardinality_report()
+            .into_iter()
+            .map(|entry| (entry.name, entry.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            report,
+            [
+                ("app_db_pools".to_owned(), 3),
+                ("app_db_queries".to_owned(), 3),
+                ("app_requests".to_owned(), 2),
+                ("app_db_empty".to_owned(), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cardinality_report_follows_merge_and_deregister() -> Result<()> {
+        use crate::metrics::{counter::Counter, family::Family};
+
+        let requests = Family::<Vec<(&'static str, u16)>, Counter>::default();
+        requests.with_or_new(&vec![("status", 200)], |counter| counter.inc());
+        let mut other = Registry::default();
+        other.register("requests", "Requests by status", requests)?;
+
+        let mut registry = Registry::default();
+        registry.merge(other)?;
+        assert_eq!(
+            registry.cardinality_report(),
+            [CardinalityEntry { name: "requests".into(), count: 1 }]
+        );
+
+        assert!(registry.deregister("requests", None)?);
+        assert!(registry.cardinality_report().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sealed_registry() {
+        use crate::{
+            format::text::{self, TextProfile},
+            metrics::counter::Counter,
+        };
+
+        let mut registry = Registry::default();
+        let requests = <Counter>::default();
+        registry.register("requests", "Total requests", requests.clone()).unwrap();
+        registry.subsystem("db").unwrap();
+
+        registry.seal();
+        registry.seal();
+        assert!(registry.is_sealed());
+
+        let err = registry.register("connections", "", <Counter>::default()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Sealed);
+        let err = registry.subsystem("db").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Sealed);
+        let err = registry.merge(Registry::default()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Sealed);
+
+        // existing subsystems share the seal of the root
+        let db = registry.subsystem_mut("db").unwrap();
+        assert!(db.is_sealed());
+        let err = db.register("queries", "", <Counter>::default()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Sealed);
+
+        requests.inc();
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+        assert!(output.contains("requests_total 1\n"));
+        assert!(!output.contains("connections"));
+    }
+
+    #[test]
+    fn test_metric_limit() -> Result<()> {
+        use crate::format::text::{self, TextProfile};
+
+        assert_eq!(Registry::default().metric_limit(), None);
+        assert_eq!(Registry::builder().with_metric_limit(0).build()?.metric_limit(), None);
+
+        let mut registry = Registry::builder().with_metric_limit(3).build()?;
+        assert_eq!(registry.metric_limit(), Some(3));
+        registry.register("requests", "Total requests", <Counter>::default())?;
+        // subsystems share the limit of the parent registry
+        registry
+            .subsystem("db")?
+            .register("queries", "Total queries", <Counter>::default())?;
+        registry.subsystem("db")?.subsystem("pool")?.register(
+            "connections",
+            "Total connections",
+            <Counter>::default(),
+        )?;
+
+        let err = registry.register("errors", "Total errors", <Counter>::default()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+        assert_eq!(err.message(), "metric limit reached");
+        let err = registry
+            .subsystem("cache")?
+            .register("hits", "Total hits", <Counter>::default())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+
+        // the registered metrics are left untouched
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default())?;
+        assert!(output.contains("requests_total 0\n"), "{output}");
+        assert!(output.contains("db_queries_total 0\n"), "{output}");
+        assert!(output.contains("db_pool_connections_total 0\n"), "{output}");
+        assert!(!output.contains("errors") && !output.contains("hits"), "{output}");
+        assert_eq!(registry.all_metrics().count(), 3);
+
+        // duplicates are reported as such, without consuming the limit
+        assert!(registry.deregister("requests", None)?);
+        let err = registry.subsystem("db")?.register("queries", "", <Counter>::default());
+        assert_eq!(err.err().map(|err| err.kind()), Some(ErrorKind::Duplicated));
+        registry.register("errors", "Total errors", <Counter>::default())?;
+
+        let mut other = Registry::default();
+        other.subsystem("cache")?.register("hits", "Total hits", <Counter>::default())?;
+        assert_eq!(registry.merge(other).unwrap_err().kind(), ErrorKind::LimitExceeded);
+        assert_eq!(registry.all_metrics().count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_text() -> Result<()> {
+        let mut registry = Registry::default();
+        let requests = <Counter>::default();
+        registry.register("requests", "Total requests", requests.clone())?;
+        requests.inc();
+
+        let openmetrics = registry.to_openmetrics_text(TextProfile::default())?;
+        assert!(openmetrics.contains("requests_total 1\n"), "{openmetrics}");
+        assert!(openmetrics.ends_with("# EOF\n"), "{openmetrics}");
+
+        let prometheus = registry.to_prometheus_text()?;
+        assert!(prometheus.contains("# TYPE requests counter\n"), "{prometheus}");
+        assert!(!prometheus.contains("# EOF"), "{prometheus}");
+        Ok(())
+    }
 }
@@ -0,0 +1,504 @@
+use std::{
+    fmt,
+    ops::Deref,
+    sync::{Arc, atomic::AtomicBool},
+    time::Duration,
+};
+
+use super::{MetricSource, Registry, validate::validate_label_name_with_rule};
+use crate::{
+    encoder::{
+        EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel, EncodeLabelSet,
+        EncodeMetric, EncodeUnknownValue, ExemplarEncoder, LabelEncoder, LabelSetEncoder,
+        MetricEncoder, MetricFamilyEncoder, RawSample,
+    },
+    error::{Error, Result},
+    raw::{Metadata, bucket::Bucket, quantile::Quantile},
+};
+
+/// A rule replacing the values of a label, see [`LabelRedactor`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RedactRule {
+    /// Name of the label whose values are replaced.
+    pub label_name: String,
+    /// Value written instead of the original label value.
+    pub replacement: String,
+}
+
+impl RedactRule {
+    /// Creates a rule replacing the values of the `label_name` label with `replacement`.
+    pub fn new(label_name: impl Into<String>, replacement: impl Into<String>) -> Self {
+        Self { label_name: label_name.into(), replacement: replacement.into() }
+    }
+}
+
+/// A view of a [`Registry`] with the values of some labels redacted, e.g. labels which may
+/// contain personal data such as email or IP addresses.
+///
+/// The redactor shares the metrics of the registry, and label values are replaced while the
+/// metrics are encoded, so every encoding reports the current metric values. It dereferences to a
+/// [`Registry`], so it can be passed to any encoder. Metrics and sources registered (or removed)
+/// after the redactor was created aren't reflected, create a new redactor for them.
+///
+/// Only label values are replaced, the metric values and the label names are kept as is. The
+/// rules apply to the labels of all metrics (including exemplar labels), and to the constant
+/// labels of the registry and its subsystems.
+///
+/// # Example
+///
+/// ```rust
+/// # use fastmetrics::{
+/// #     error::Result,
+/// #     format::text::{self, TextProfile},
+/// #     metrics::{counter::Counter, family::Family},
+/// #     registry::{LabelRedactor, RedactRule, Registry},
+/// # };
+/// #
+/// # fn main() -> Result<()> {
+/// let mut registry = Registry::default();
+/// let logins = Family::<Vec<(&'static str, String)>, Counter>::default();
+/// registry.register("logins", "Total logins", logins.clone())?;
+///
+/// let redactor = LabelRedactor::new(&registry, vec![RedactRule::new("email", "[REDACTED]")])?;
+/// logins.with_or_new(&vec![("email", "alice@example.com".to_owned())], |c| c.inc());
+///
+/// let mut output = String::new();
+/// text::encode(&mut output, &redactor, TextProfile::default())?;
+/// assert!(output.contains("logins_total{email=\"[REDACTED]\"} 1\n"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct LabelRedactor {
+    registry: Registry,
+}
+
+impl LabelRedactor {
+    /// Creates a view of `registry` replacing the values of the labels matching `rules`.
+    ///
+    /// If several rules match the same label name, the first one is used. An error is returned if
+    /// the label name of a rule is invalid under the name rule of the registry.
+    pub fn new(registry: &Registry, rules: Vec<RedactRule>) -> Result<Self> {
+        for rule in &rules {
+            validate_label_name_with_rule(&rule.label_name, registry.name_rule).map_err(|err| {
+                Error::invalid(err.to_string()).with_context("label", &rule.label_name)
+            })?;
+        }
+        Ok(Self { registry: redact_registry(registry, &Arc::from(rules)) })
+    }
+}
+
+impl Deref for LabelRedactor {
+    type Target = Registry;
+
+    fn deref(&self) -> &Self::Target {
+        &self.registry
+    }
+}
+
+/// Mirrors `registry`, wrapping its metrics and sources to redact their labels when encoded.
+fn redact_registry(registry: &Registry, rules: &Arc<[RedactRule]>) -> Registry {
+    let metrics = registry
+        .metrics
+        .iter()
+        .map(|(metadata, metric)| {
+            let redacted: Arc<dyn EncodeMetric> =
+                Arc::new(RedactedMetric { metric: metric.clone(), rules: rules.clone() });
+            (metadata.clone(), redacted)
+        })
+        .collect();
+
+    let sources = registry
+        .sources
+        .iter()
+        .map(|(name, source)| {
+            let redacted: Arc<dyn MetricSource> =
+                Arc::new(RedactedSource { source: source.clone(), rules: rules.clone() });
+            (name.clone(), redacted)
+        })
+        .collect();
+
+    let subsystems = registry
+        .subsystems
+        .iter()
+        .map(|(name, subsystem)| (name.clone(), redact_registry(subsystem, rules)))
+        .collect();
+
+    let const_labels = registry
+        .const_labels
+        .iter()
+        .map(|(name, value)| match find_rule(rules, name) {
+            Some(rule) => (name.clone(), rule.replacement.clone().into()),
+            None => (name.clone(), value.clone()),
+        })
+        .collect();
+
+    Registry {
+        namespace: registry.namespace.clone(),
+        name_rule: registry.name_rule,
+        const_labels,
+        metrics,
+        families: registry.families.clone(),
+        subsystems,
+        sources,
+        sealed: Arc::new(AtomicBool::new(registry.is_sealed())),
+        clock: registry.clock.clone(),
+        metric_limit: registry.metric_limit.clone(),
+    }
+}
+
+fn find_rule<'a>(rules: &'a [RedactRule], label_name: &str) -> Option<&'a RedactRule> {
+    rules.iter().find(|rule| rule.label_name == label_name)
+}
+
+struct RedactedMetric {
+    metric: Arc<dyn EncodeMetric>,
+    rules: Arc<[RedactRule]>,
+}
+
+impl EncodeMetric for RedactedMetric {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        RedactedMetricRef { metric: self.metric.as_ref(), rules: &self.rules }.encode(encoder)
+    }
+
+    fn timestamp(&self) -> Option<Duration> {
+        self.metric.timestamp()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.metric.is_empty()
+    }
+}
+
+struct RedactedSource {
+    source: Arc<dyn MetricSource>,
+    rules: Arc<[RedactRule]>,
+}
+
+impl MetricSource for RedactedSource {
+    fn collect(&self, encoder: &mut dyn MetricFamilyEncoder) -> Result<()> {
+        self.source.collect(&mut RedactingFamilyEncoder { encoder, rules: &self.rules })
+    }
+}
+
+struct RedactingFamilyEncoder<'a> {
+    encoder: &'a mut dyn MetricFamilyEncoder,
+    rules: &'a [RedactRule],
+}
+
+impl MetricFamilyEncoder for RedactingFamilyEncoder<'_> {
+    fn encode(&mut self, metadata: &Metadata, metric: &dyn EncodeMetric) -> Result<()> {
+        self.encoder.encode(metadata, &RedactedMetricRef { metric, rules: self.rules })
+    }
+}
+
+struct RedactedMetricRef<'a> {
+    metric: &'a dyn EncodeMetric,
+    rules: &'a [RedactRule],
+}
+
+impl EncodeMetric for RedactedMetricRef<'_> {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        self.metric.encode(&mut RedactingEncoder { encoder, rules: self.rules })
+    }
+
+    fn timestamp(&self) -> Option<Duration> {
+        self.metric.timestamp()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.metric.is_empty()
+    }
+}
+
+/// A [`MetricEncoder`] forwarding all calls, with the label values matching the rules replaced.
+struct RedactingEncoder<'a> {
+    encoder: &'a mut dyn MetricEncoder,
+    rules: &'a [RedactRule],
+}
+
+impl MetricEncoder for RedactingEncoder<'_> {
+    fn encode_unknown(&mut self, value: &dyn EncodeUnknownValue) -> Result<()> {
+        self.encoder.encode_unknown(value)
+    }
+
+    fn encode_gauge(&mut self, value: &dyn EncodeGaugeValue) -> Result<()> {
+        self.encoder.encode_gauge(value)
+    }
+
+    fn encode_counter(
+        &mut self,
+        total: &dyn EncodeCounterValue,
+        exemplar: Option<&dyn EncodeExemplar>,
+        created: Option<Duration>,
+    ) -> Result<()> {
+        let exemplar = exemplar.map(|exemplar| RedactedExemplar { exemplar, rules: self.rules });
+        self.encoder.encode_counter(total, exemplar.as_ref().map(|e| e as _), created)
+    }
+
+    fn encode_stateset(&mut self, states: Vec<(&str, bool)>) -> Result<()> {
+        self.encoder.encode_stateset(states)
+    }
+
+    fn encode_info(&mut self, label_set: &dyn EncodeLabelSet) -> Result<()> {
+        self.encoder.encode_info(&RedactedLabelSet { label_set, rules: self.rules })
+    }
+
+    fn encode_histogram(
+        &mut self,
+        buckets: &[Bucket],
+        exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+        created: Option<Duration>,
+    ) -> Result<()> {
+        let redacted = exemplars.map(|exemplars| redact_exemplars(exemplars, self.rules));
+        let exemplars = redacted.as_ref().map(|redacted| as_exemplars(redacted));
+        self.encoder
+            .encode_histogram(buckets, exemplars.as_deref(), count, sum, created)
+    }
+
+    fn encode_gauge_histogram(
+        &mut self,
+        buckets: &[Bucket],
+        exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+    ) -> Result<()> {
+        let redacted = exemplars.map(|exemplars| redact_exemplars(exemplars, self.rules));
+        let exemplars = redacted.as_ref().map(|redacted| as_exemplars(redacted));
+        self.encoder.encode_gauge_histogram(buckets, exemplars.as_deref(), count, sum)
+    }
+
+    fn encode_summary(
+        &mut self,
+        quantiles: &[Quantile],
+        sum: f64,
+        count: u64,
+        created: Option<Duration>,
+    ) -> Result<()> {
+        self.encoder.encode_summary(quantiles, sum, count, created)
+    }
+
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        let labels = samples
+            .iter()
+            .map(|sample| RedactedLabelSet { label_set: sample.labels, rules: self.rules })
+            .collect::<Vec<_>>();
+        let samples = samples
+            .iter()
+            .zip(&labels)
+            .map(|(sample, labels)| RawSample { labels, ..*sample })
+            .collect::<Vec<_>>();
+        self.encoder.encode_raw(&samples)
+    }
+
+    fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
+        self.encoder.encode(
+            &RedactedLabelSet { label_set, rules: self.rules },
+            &RedactedMetricRef { metric, rules: self.rules },
+        )
+    }
+}
+
+fn redact_exemplars<'a>(
+    exemplars: &[Option<&'a dyn EncodeExemplar>],
+    rules: &'a [RedactRule],
+) -> Vec<Option<RedactedExemplar<'a>>> {
+    exemplars
+        .iter()
+        .map(|exemplar| exemplar.map(|exemplar| RedactedExemplar { exemplar, rules }))
+        .collect()
+}
+
+fn as_exemplars<'a>(
+    redacted: &'a [Option<RedactedExemplar<'_>>],
+) -> Vec<Option<&'a dyn EncodeExemplar>> {
+    redacted.iter().map(|exemplar| exemplar.as_ref().map(|e| e as _)).collect()
+}
+
+struct RedactedExemplar<'a> {
+    exemplar: &'a dyn EncodeExemplar,
+    rules: &'a [RedactRule],
+}
+
+impl EncodeExemplar for RedactedExemplar<'_> {
+    fn encode(&self, encoder: &mut dyn ExemplarEncoder) -> Result<()> {
+        self.exemplar
+            .encode(&mut RedactingExemplarEncoder { encoder, rules: self.rules })
+    }
+}
+
+struct RedactingExemplarEncoder<'a> {
+    encoder: &'a mut dyn ExemplarEncoder,
+    rules: &'a [RedactRule],
+}
+
+impl ExemplarEncoder for RedactingExemplarEncoder<'_> {
+    fn encode(
+        &mut self,
+        label_set: &dyn EncodeLabelSet,
+        value: f64,
+        timestamp: Option<Duration>,
+    ) -> Result<()> {
+        self.encoder
+            .encode(&RedactedLabelSet { label_set, rules: self.rules }, value, timestamp)
+    }
+}
+
+struct RedactedLabelSet<'a> {
+    label_set: &'a dyn EncodeLabelSet,
+    rules: &'a [RedactRule],
+}
+
+impl EncodeLabelSet for RedactedLabelSet<'_> {
+    fn encode(&self, encoder: &mut dyn LabelSetEncoder) -> Result<()> {
+        self.label_set
+            .encode(&mut RedactingLabelSetEncoder { encoder, rules: self.rules })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.label_set.is_empty()
+    }
+}
+
+struct RedactingLabelSetEncoder<'a> {
+    encoder: &'a mut dyn LabelSetEncoder,
+    rules: &'a [RedactRule],
+}
+
+impl LabelSetEncoder for RedactingLabelSetEncoder<'_> {
+    fn encode(&mut self, label: &dyn EncodeLabel) -> Result<()> {
+        self.encoder.encode(&RedactedLabel { label, rules: self.rules })
+    }
+}
+
+struct RedactedLabel<'a> {
+    label: &'a dyn EncodeLabel,
+    rules: &'a [RedactRule],
+}
+
+impl EncodeLabel for RedactedLabel<'_> {
+    fn encode(&self, encoder: &mut dyn LabelEncoder) -> Result<()> {
+        self.label.encode(&mut RedactingLabelEncoder {
+            encoder,
+            rules: self.rules,
+            replacement: None,
+        })
+    }
+}
+
+/// A [`LabelEncoder`] writing the replacement of the rule matching the label name instead of the
+/// label value.
+struct RedactingLabelEncoder<'a> {
+    encoder: &'a mut dyn LabelEncoder,
+    rules: &'a [RedactRule],
+    replacement: Option<&'a str>,
+}
+
+macro_rules! redact_value {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(&mut self, value: $ty) -> Result<()> {
+                match self.replacement {
+                    Some(replacement) => self.encoder.encode_str_value(replacement),
+                    None => self.encoder.$method(value),
+                }
+            }
+        )*
+    };
+}
+
+impl LabelEncoder for RedactingLabelEncoder<'_> {
+    fn encode_label_name(&mut self, name: &str) -> Result<()> {
+        self.replacement = find_rule(self.rules, name).map(|rule| rule.replacement.as_str());
+        self.encoder.encode_label_name(name)
+    }
+
+    redact_value! {
+        encode_str_value(&str),
+        encode_bool_value(bool),
+        encode_i8_value(i8),
+        encode_i16_value(i16),
+        encode_i32_value(i32),
+        encode_i64_value(i64),
+        encode_i128_value(i128),
+        encode_isize_value(isize),
+        encode_u8_value(u8),
+        encode_u16_value(u16),
+        encode_u32_value(u32),
+        encode_u64_value(u64),
+        encode_u128_value(u128),
+        encode_usize_value(usize),
+        encode_f32_value(f32),
+        encode_f64_value(f64),
+        encode_display_value(&dyn fmt::Display),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format::text::{self, TextProfile},
+        metrics::{counter::Counter, family::Family},
+    };
+
+    #[test]
+    fn test_label_redactor() {
+        let mut registry = Registry::builder()
+            .with_const_labels([("instance", "10.0.0.1"), ("env", "prod")])
+            .build()
+            .unwrap();
+        let logins = Family::<Vec<(&'static str, &'static str)>, Counter>::default();
+        registry.register("logins", "Total logins", logins.clone()).unwrap();
+        logins.with_or_new(&vec![("email", "alice@example.com"), ("method", "password")], |c| {
+            c.inc_by(3)
+        });
+
+        let rules =
+            vec![RedactRule::new("email", "[REDACTED]"), RedactRule::new("instance", "[REDACTED]")];
+        let redactor = LabelRedactor::new(&registry, rules).unwrap();
+
+        let mut output = String::new();
+        text::encode(&mut output, &redactor, TextProfile::default()).unwrap();
+        let labels = r#"instance="[REDACTED]",env="prod",email="[REDACTED]",method="password""#;
+        assert!(output.contains(&format!("logins_total{{{labels}}} 3\n")), "{output}");
+        assert!(!output.contains("alice@example.com"));
+        assert!(!output.contains("10.0.0.1"));
+
+        // the registry itself is left untouched
+        let mut output = String::new();
+        text::encode(&mut output, &registry, TextProfile::default()).unwrap();
+        assert!(output.contains("email=\"alice@example.com\""));
+    }
+
+    #[test]
+    fn test_label_redactor_encodes_current_values() {
+        let mut registry = Registry::default();
+        let logins = Family::<Vec<(&'static str, &'static str)>, Counter>::default();
+        registry.register("logins", "Total logins", logins.clone()).unwrap();
+
+        let redactor =
+            LabelRedactor::new(&registry, vec![RedactRule::new("email", "[REDACTED]")]).unwrap();
+        let encode = || {
+            let mut output = String::new();
+            text::encode(&mut output, &redactor, TextProfile::default()).unwrap();
+            output
+        };
+
+        let labels = vec![("email", "alice@example.com")];
+        logins.with_or_new(&labels, |c| c.inc());
+        assert!(encode().contains("logins_total{email=\"[REDACTED]\"} 1\n"));
+
+        logins.with_or_new(&labels, |c| c.inc_by(2));
+        let output = encode();
+        assert!(output.contains("logins_total{email=\"[REDACTED]\"} 3\n"), "{output}");
+        assert!(!output.contains("alice@example.com"));
+    }
+
+    #[test]
+    fn test_label_redactor_invalid_rule() {
+        let registry = Registry::default();
+        assert!(LabelRedactor::new(&registry, vec![RedactRule::new("e-mail", "x")]).is_err());
+    }
+}
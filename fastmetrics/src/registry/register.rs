@@ -43,3 +43,62 @@ pub trait Register {
         with_global_registry_mut(|registry| self.register(registry))
     }
 }
+
+/// Implementation details of `#[derive(Register)]`, not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    use crate::{
+        error::Result,
+        registry::{Metric, Register, Registry},
+    };
+
+    /// A struct field registered by `#[derive(Register)]` without any `#[register(...)]` attribute.
+    ///
+    /// Fields implementing [`Register`] delegate to it, other fields are registered as metrics.
+    /// The choice is made at compile time with autoref-based specialization: the derive calls
+    /// `(&RegisterField(&self.field)).register_field(..)`, which resolves to
+    /// [`RegisterViaRegister`] if the field implements [`Register`], and to [`RegisterViaMetric`]
+    /// through the additional autoref otherwise.
+    pub struct RegisterField<'a, T>(pub &'a T);
+
+    pub trait RegisterViaRegister {
+        fn register_field(
+            &self,
+            registry: &mut Registry,
+            name: &'static str,
+            help: &'static str,
+        ) -> Result<()>;
+    }
+
+    impl<T: Register> RegisterViaRegister for RegisterField<'_, T> {
+        fn register_field(
+            &self,
+            registry: &mut Registry,
+            _name: &'static str,
+            _help: &'static str,
+        ) -> Result<()> {
+            self.0.register(registry)
+        }
+    }
+
+    pub trait RegisterViaMetric {
+        fn register_field(
+            &self,
+            registry: &mut Registry,
+            name: &'static str,
+            help: &'static str,
+        ) -> Result<()>;
+    }
+
+    impl<T: Metric + Clone> RegisterViaMetric for &RegisterField<'_, T> {
+        fn register_field(
+            &self,
+            registry: &mut Registry,
+            name: &'static str,
+            help: &'static str,
+        ) -> Result<()> {
+            registry.register(name, help, self.0.clone())?;
+            Ok(())
+        }
+    }
+}
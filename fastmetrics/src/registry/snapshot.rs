@@ -0,0 +1,912 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::Deref,
+    sync::{Arc, atomic::AtomicBool},
+    time::Duration,
+};
+
+use crate::{
+    encoder::{
+        CounterValueEncoder, EncodeCounterValue, EncodeExemplar, EncodeGaugeValue, EncodeLabel,
+        EncodeLabelSet, EncodeMetric, EncodeUnknownValue, ExemplarEncoder, GaugeValueEncoder,
+        LabelEncoder, LabelSetEncoder, MetricEncoder, MetricFamilyEncoder, RawSample,
+        UnknownValueEncoder,
+    },
+    error::{Error, Result},
+    format::text,
+    raw::{Metadata, bucket::Bucket, quantile::Quantile},
+    registry::Registry,
+};
+
+/// An immutable view of a [`Registry`], with all metric values frozen when
+/// [`Registry::snapshot`] was called.
+///
+/// The snapshot owns all of its data, so it can be sent to another thread and encoded there,
+/// while the live registry keeps being updated. It dereferences to a [`Registry`], so it can be
+/// passed to any encoder.
+pub struct RegistrySnapshot {
+    registry: Registry,
+}
+
+impl Deref for RegistrySnapshot {
+    type Target = Registry;
+
+    fn deref(&self) -> &Self::Target {
+        &self.registry
+    }
+}
+
+impl Registry {
+    /// Takes an immutable snapshot of the current values of all metrics, including the metrics
+    /// of subsystems.
+    ///
+    /// Metrics are recorded by encoding them once, so lazy metrics are sampled at this point,
+    /// and an error is returned if any metric fails to encode.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{
+    /// #     error::Result,
+    /// #     format::text::{self, TextProfile},
+    /// #     metrics::counter::Counter,
+    /// #     registry::Registry,
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    /// let requests = <Counter>::default();
+    /// registry.register("requests", "Total requests", requests.clone())?;
+    ///
+    /// requests.inc();
+    /// let snapshot = registry.snapshot()?;
+    /// requests.inc();
+    ///
+    /// let mut output = String::new();
+    /// text::encode(&mut output, &snapshot, TextProfile::PrometheusV0_0_4)?;
+    /// assert!(output.contains("requests 1\n"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn snapshot(&self) -> Result<RegistrySnapshot> {
+        Ok(RegistrySnapshot { registry: self.freeze()? })
+    }
+
+    fn freeze(&self) -> Result<Registry> {
+        let mut metrics = HashMap::with_capacity(self.metrics.len());
+        for (metadata, metric) in &self.metrics {
+            let frozen: Arc<dyn EncodeMetric> = Arc::new(FrozenMetric::record(metric.as_ref())?);
+            metrics.insert(metadata.clone(), frozen);
+        }
+        // the families of sources become regular (frozen) metrics of the snapshot
+        self.collect_sources(&mut SourceFreezer { metrics: &mut metrics })?;
+
+        let subsystems = self
+            .subsystems
+            .iter()
+            .map(|(name, subsystem)| Ok((name.clone(), subsystem.freeze()?)))
+            .collect::<Result<_>>()?;
+
+        Ok(Registry {
+            namespace: self.namespace.clone(),
+            name_rule: self.name_rule,
+            const_labels: self.const_labels.clone(),
+            metrics,
+            families: HashMap::new(),
+            subsystems,
+            sources: Vec::new(),
+            sealed: Arc::new(AtomicBool::new(self.is_sealed())),
+            clock: self.clock.clone(),
+            metric_limit: self.metric_limit.clone(),
+        })
+    }
+}
+
+/// The differences between two [`RegistrySnapshot`]s, returned by [`Registry::diff`].
+///
+/// Metric families are identified by their fully-qualified names, and all lists are sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiffReport {
+    /// Metric families only present in the `after` snapshot.
+    pub added: Vec<String>,
+    /// Metric families only present in the `before` snapshot.
+    pub removed: Vec<String>,
+    /// Metric families present in both snapshots, whose samples (or metadata) differ.
+    pub changed: Vec<String>,
+}
+
+impl DiffReport {
+    /// Returns `true` if the snapshots have the same metric families and values.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("no changes\n");
+        }
+        for name in &self.added {
+            writeln!(f, "+ {name}")?;
+        }
+        for name in &self.removed {
+            writeln!(f, "- {name}")?;
+        }
+        for name in &self.changed {
+            writeln!(f, "~ {name}")?;
+        }
+        Ok(())
+    }
+}
+
+// diff
+impl Registry {
+    /// Compares two snapshots, e.g. taken before and after a deployment, and reports which metric
+    /// families have been added, removed or changed.
+    ///
+    /// A metric family is changed if any of its samples differs, e.g. a counter has been
+    /// incremented (or reset), or a label set has appeared or disappeared.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{
+    /// #     error::Result,
+    /// #     metrics::{counter::Counter, gauge::Gauge},
+    /// #     registry::Registry,
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    /// let requests = <Counter>::default();
+    /// registry.register("requests", "Total requests", requests.clone())?;
+    /// registry.register("uptime", "Uptime", <Gauge>::default())?;
+    ///
+    /// let before = registry.snapshot()?;
+    /// requests.inc();
+    /// let after = registry.snapshot()?;
+    ///
+    /// let diff = Registry::diff(&before, &after)?;
+    /// assert_eq!(diff.changed, ["requests"]);
+    /// assert_eq!(diff.to_string(), "~ requests\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn diff(before: &RegistrySnapshot, after: &RegistrySnapshot) -> Result<DiffReport> {
+        let before = text::encode_families(before)?.into_iter().collect::<HashMap<_, _>>();
+        let after = text::encode_families(after)?.into_iter().collect::<HashMap<_, _>>();
+
+        let mut report = DiffReport::default();
+        for (name, lines) in &after {
+            match before.get(name) {
+                None => report.added.push(name.clone()),
+                Some(before_lines) if before_lines != lines => report.changed.push(name.clone()),
+                Some(_) => {},
+            }
+        }
+        report.removed = before.into_keys().filter(|name| !after.contains_key(name)).collect();
+
+        report.added.sort_unstable();
+        report.removed.sort_unstable();
+        report.changed.sort_unstable();
+        Ok(report)
+    }
+}
+
+struct SourceFreezer<'a> {
+    metrics: &'a mut HashMap<Metadata, Arc<dyn EncodeMetric>>,
+}
+
+impl MetricFamilyEncoder for SourceFreezer<'_> {
+    fn encode(&mut self, metadata: &Metadata, metric: &dyn EncodeMetric) -> Result<()> {
+        let frozen: Arc<dyn EncodeMetric> = Arc::new(FrozenMetric::record(metric)?);
+        self.metrics.insert(metadata.clone(), frozen);
+        Ok(())
+    }
+}
+
+/// A metric replaying the encoder calls recorded from a live metric.
+struct FrozenMetric {
+    calls: Vec<Call>,
+    timestamp: Option<Duration>,
+    is_empty: bool,
+}
+
+enum Call {
+    Unknown(Number),
+    Gauge(Number),
+    Counter {
+        total: Number,
+        exemplar: Option<FrozenExemplar>,
+        created: Option<Duration>,
+    },
+    StateSet(Vec<(String, bool)>),
+    Info(FrozenLabelSet),
+    Histogram {
+        buckets: Vec<Bucket>,
+        exemplars: Option<Vec<Option<FrozenExemplar>>>,
+        count: u64,
+        sum: f64,
+        created: Option<Duration>,
+    },
+    GaugeHistogram {
+        buckets: Vec<Bucket>,
+        exemplars: Option<Vec<Option<FrozenExemplar>>>,
+        count: u64,
+        sum: f64,
+    },
+    Summary {
+        quantiles: Vec<Quantile>,
+        sum: f64,
+        count: u64,
+        created: Option<Duration>,
+    },
+    Raw(Vec<FrozenRawSample>),
+    Labeled(FrozenLabelSet, FrozenMetric),
+}
+
+struct FrozenRawSample {
+    suffix: Option<String>,
+    labels: FrozenLabelSet,
+    value: f64,
+    timestamp: Option<Duration>,
+}
+
+impl FrozenMetric {
+    fn record(metric: &dyn EncodeMetric) -> Result<Self> {
+        let mut recorder = MetricRecorder { calls: Vec::new() };
+        metric.encode(&mut recorder)?;
+        Ok(Self {
+            calls: recorder.calls,
+            timestamp: metric.timestamp(),
+            is_empty: metric.is_empty(),
+        })
+    }
+
+    fn collect_label_names(&self, names: &mut Vec<String>) {
+        for call in &self.calls {
+            match call {
+                Call::Info(label_set) => {
+                    names.extend(label_set.labels.iter().map(|label| label.name.clone()));
+                },
+                Call::Raw(samples) => {
+                    for sample in samples {
+                        names.extend(sample.labels.labels.iter().map(|label| label.name.clone()));
+                    }
+                },
+                Call::Labeled(label_set, metric) => {
+                    names.extend(label_set.labels.iter().map(|label| label.name.clone()));
+                    metric.collect_label_names(names);
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+/// Returns the names of the labels a metric encodes, excluding the constant labels of its registry
+/// and the labels added by the format encoders (e.g. `le` or `quantile`).
+pub(crate) fn metric_label_names(metric: &dyn EncodeMetric) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    FrozenMetric::record(metric)?.collect_label_names(&mut names);
+    names.sort_unstable();
+    names.dedup();
+    Ok(names)
+}
+
+impl EncodeMetric for FrozenMetric {
+    fn encode(&self, encoder: &mut dyn MetricEncoder) -> Result<()> {
+        for call in &self.calls {
+            match call {
+                Call::Unknown(value) => encoder.encode_unknown(value)?,
+                Call::Gauge(value) => encoder.encode_gauge(value)?,
+                Call::Counter { total, exemplar, created } => encoder.encode_counter(
+                    total,
+                    exemplar.as_ref().map(|exemplar| exemplar as &dyn EncodeExemplar),
+                    *created,
+                )?,
+                Call::StateSet(states) => encoder.encode_stateset(
+                    states.iter().map(|(state, enabled)| (state.as_str(), *enabled)).collect(),
+                )?,
+                Call::Info(label_set) => encoder.encode_info(label_set)?,
+                Call::Histogram { buckets, exemplars, count, sum, created } => {
+                    let exemplars = exemplars.as_ref().map(|exemplars| as_dyn_exemplars(exemplars));
+                    encoder.encode_histogram(
+                        buckets,
+                        exemplars.as_deref(),
+                        *count,
+                        *sum,
+                        *created,
+                    )?
+                },
+                Call::GaugeHistogram { buckets, exemplars, count, sum } => {
+                    let exemplars = exemplars.as_ref().map(|exemplars| as_dyn_exemplars(exemplars));
+                    encoder.encode_gauge_histogram(buckets, exemplars.as_deref(), *count, *sum)?
+                },
+                Call::Summary { quantiles, sum, count, created } => {
+                    encoder.encode_summary(quantiles, *sum, *count, *created)?
+                },
+                Call::Raw(samples) => {
+                    let samples = samples
+                        .iter()
+                        .map(|sample| RawSample {
+                            suffix: sample.suffix.as_deref(),
+                            labels: &sample.labels,
+                            value: sample.value,
+                            timestamp: sample.timestamp,
+                        })
+                        .collect::<Vec<_>>();
+                    encoder.encode_raw(&samples)?
+                },
+                Call::Labeled(label_set, metric) => encoder.encode(label_set, metric)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+
+    fn is_empty(&self) -> bool {
+        self.is_empty
+    }
+}
+
+fn as_dyn_exemplars(exemplars: &[Option<FrozenExemplar>]) -> Vec<Option<&dyn EncodeExemplar>> {
+    exemplars
+        .iter()
+        .map(|exemplar| exemplar.as_ref().map(|exemplar| exemplar as &dyn EncodeExemplar))
+        .collect()
+}
+
+struct MetricRecorder {
+    calls: Vec<Call>,
+}
+
+impl MetricEncoder for MetricRecorder {
+    fn encode_unknown(&mut self, value: &dyn EncodeUnknownValue) -> Result<()> {
+        let mut recorder = NumberRecorder::default();
+        value.encode(&mut recorder)?;
+        self.calls.push(Call::Unknown(recorder.finish()?));
+        Ok(())
+    }
+
+    fn encode_gauge(&mut self, value: &dyn EncodeGaugeValue) -> Result<()> {
+        let mut recorder = NumberRecorder::default();
+        value.encode(&mut recorder)?;
+        self.calls.push(Call::Gauge(recorder.finish()?));
+        Ok(())
+    }
+
+    fn encode_counter(
+        &mut self,
+        total: &dyn EncodeCounterValue,
+        exemplar: Option<&dyn EncodeExemplar>,
+        created: Option<Duration>,
+    ) -> Result<()> {
+        let mut recorder = NumberRecorder::default();
+        total.encode(&mut recorder)?;
+        let exemplar = exemplar.map(FrozenExemplar::record).transpose()?.flatten();
+        self.calls.push(Call::Counter { total: recorder.finish()?, exemplar, created });
+        Ok(())
+    }
+
+    fn encode_stateset(&mut self, states: Vec<(&str, bool)>) -> Result<()> {
+        let states = states.into_iter().map(|(state, enabled)| (state.to_owned(), enabled));
+        self.calls.push(Call::StateSet(states.collect()));
+        Ok(())
+    }
+
+    fn encode_info(&mut self, label_set: &dyn EncodeLabelSet) -> Result<()> {
+        self.calls.push(Call::Info(FrozenLabelSet::record(label_set)?));
+        Ok(())
+    }
+
+    fn encode_histogram(
+        &mut self,
+        buckets: &[Bucket],
+        exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+        created: Option<Duration>,
+    ) -> Result<()> {
+        let exemplars = exemplars.map(record_exemplars).transpose()?;
+        self.calls.push(Call::Histogram {
+            buckets: buckets.to_vec(),
+            exemplars,
+            count,
+            sum,
+            created,
+        });
+        Ok(())
+    }
+
+    fn encode_gauge_histogram(
+        &mut self,
+        buckets: &[Bucket],
+        exemplars: Option<&[Option<&dyn EncodeExemplar>]>,
+        count: u64,
+        sum: f64,
+    ) -> Result<()> {
+        let exemplars = exemplars.map(record_exemplars).transpose()?;
+        self.calls
+            .push(Call::GaugeHistogram { buckets: buckets.to_vec(), exemplars, count, sum });
+        Ok(())
+    }
+
+    fn encode_summary(
+        &mut self,
+        quantiles: &[Quantile],
+        sum: f64,
+        count: u64,
+        created: Option<Duration>,
+    ) -> Result<()> {
+        self.calls
+            .push(Call::Summary { quantiles: quantiles.to_vec(), sum, count, created });
+        Ok(())
+    }
+
+    fn encode_raw(&mut self, samples: &[RawSample<'_>]) -> Result<()> {
+        let samples = samples
+            .iter()
+            .map(|sample| {
+                Ok(FrozenRawSample {
+                    suffix: sample.suffix.map(str::to_owned),
+                    labels: FrozenLabelSet::record(sample.labels)?,
+                    value: sample.value,
+                    timestamp: sample.timestamp,
+                })
+            })
+            .collect::<Result<_>>()?;
+        self.calls.push(Call::Raw(samples));
+        Ok(())
+    }
+
+    fn encode(&mut self, label_set: &dyn EncodeLabelSet, metric: &dyn EncodeMetric) -> Result<()> {
+        let label_set = FrozenLabelSet::record(label_set)?;
+        self.calls.push(Call::Labeled(label_set, FrozenMetric::record(metric)?));
+        Ok(())
+    }
+}
+
+fn record_exemplars(
+    exemplars: &[Option<&dyn EncodeExemplar>],
+) -> Result<Vec<Option<FrozenExemplar>>> {
+    exemplars
+        .iter()
+        .map(|exemplar| Ok(exemplar.map(FrozenExemplar::record).transpose()?.flatten()))
+        .collect()
+}
+
+/// A recorded gauge, counter or unknown value, keeping its representation.
+#[derive(Clone, Copy)]
+enum Number {
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+}
+
+#[derive(Default)]
+struct NumberRecorder {
+    value: Option<Number>,
+}
+
+impl NumberRecorder {
+    fn finish(self) -> Result<Number> {
+        self.value.ok_or_else(|| Error::unexpected("metric value was not encoded"))
+    }
+}
+
+impl UnknownValueEncoder for NumberRecorder {
+    fn encode_i32(&mut self, value: i32) -> Result<()> {
+        UnknownValueEncoder::encode_i64(self, value.into())
+    }
+
+    fn encode_i64(&mut self, value: i64) -> Result<()> {
+        self.value = Some(Number::I64(value));
+        Ok(())
+    }
+
+    fn encode_isize(&mut self, value: isize) -> Result<()> {
+        UnknownValueEncoder::encode_i64(self, value as i64)
+    }
+
+    fn encode_u32(&mut self, value: u32) -> Result<()> {
+        self.value = Some(Number::U64(value.into()));
+        Ok(())
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        self.value = Some(Number::F32(value));
+        Ok(())
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        self.value = Some(Number::F64(value));
+        Ok(())
+    }
+}
+
+impl GaugeValueEncoder for NumberRecorder {
+    fn encode_i32(&mut self, value: i32) -> Result<()> {
+        UnknownValueEncoder::encode_i32(self, value)
+    }
+
+    fn encode_i64(&mut self, value: i64) -> Result<()> {
+        UnknownValueEncoder::encode_i64(self, value)
+    }
+
+    fn encode_isize(&mut self, value: isize) -> Result<()> {
+        UnknownValueEncoder::encode_isize(self, value)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        UnknownValueEncoder::encode_f32(self, value)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        UnknownValueEncoder::encode_f64(self, value)
+    }
+}
+
+impl CounterValueEncoder for NumberRecorder {
+    fn encode_u32(&mut self, value: u32) -> Result<()> {
+        UnknownValueEncoder::encode_u32(self, value)
+    }
+
+    fn encode_u64(&mut self, value: u64) -> Result<()> {
+        self.value = Some(Number::U64(value));
+        Ok(())
+    }
+
+    fn encode_usize(&mut self, value: usize) -> Result<()> {
+        self.encode_u64(value as u64)
+    }
+
+    fn encode_f32(&mut self, value: f32) -> Result<()> {
+        UnknownValueEncoder::encode_f32(self, value)
+    }
+
+    fn encode_f64(&mut self, value: f64) -> Result<()> {
+        UnknownValueEncoder::encode_f64(self, value)
+    }
+}
+
+impl EncodeUnknownValue for Number {
+    fn encode(&self, encoder: &mut dyn UnknownValueEncoder) -> Result<()> {
+        match *self {
+            Self::I64(value) => encoder.encode_i64(value),
+            // unknown values are only recorded as unsigned from `u32`
+            Self::U64(value) => match u32::try_from(value) {
+                Ok(value) => encoder.encode_u32(value),
+                Err(_) => encoder.encode_f64(value as f64),
+            },
+            Self::F32(value) => encoder.encode_f32(value),
+            Self::F64(value) => encoder.encode_f64(value),
+        }
+    }
+}
+
+impl EncodeGaugeValue for Number {
+    fn encode(&self, encoder: &mut dyn GaugeValueEncoder) -> Result<()> {
+        match *self {
+            Self::I64(value) => encoder.encode_i64(value),
+            // gauge values are never recorded as unsigned
+            Self::U64(value) => match i64::try_from(value) {
+                Ok(value) => encoder.encode_i64(value),
+                Err(_) => encoder.encode_f64(value as f64),
+            },
+            Self::F32(value) => encoder.encode_f32(value),
+            Self::F64(value) => encoder.encode_f64(value),
+        }
+    }
+}
+
+impl EncodeCounterValue for Number {
+    fn encode(&self, encoder: &mut dyn CounterValueEncoder) -> Result<()> {
+        match *self {
+            // counter values are never recorded as signed
+            Self::I64(value) => match u64::try_from(value) {
+                Ok(value) => encoder.encode_u64(value),
+                Err(_) => encoder.encode_f64(value as f64),
+            },
+            Self::U64(value) => encoder.encode_u64(value),
+            Self::F32(value) => encoder.encode_f32(value),
+            Self::F64(value) => encoder.encode_f64(value),
+        }
+    }
+}
+
+struct FrozenExemplar {
+    label_set: FrozenLabelSet,
+    value: f64,
+    timestamp: Option<Duration>,
+}
+
+impl FrozenExemplar {
+    /// Records an exemplar, returns `None` if the exemplar doesn't encode anything.
+    fn record(exemplar: &dyn EncodeExemplar) -> Result<Option<Self>> {
+        let mut recorder = ExemplarRecorder { exemplar: None };
+        exemplar.encode(&mut recorder)?;
+        Ok(recorder.exemplar)
+    }
+}
+
+impl EncodeExemplar for FrozenExemplar {
+    fn encode(&self, encoder: &mut dyn ExemplarEncoder) -> Result<()> {
+        encoder.encode(&self.label_set, self.value, self.timestamp)
+    }
+}
+
+struct ExemplarRecorder {
+    exemplar: Option<FrozenExemplar>,
+}
+
+impl ExemplarEncoder for ExemplarRecorder {
+    fn encode(
+        &mut self,
+        label_set: &dyn EncodeLabelSet,
+        value: f64,
+        timestamp: Option<Duration>,
+    ) -> Result<()> {
+        let label_set = FrozenLabelSet::record(label_set)?;
+        self.exemplar = Some(FrozenExemplar { label_set, value, timestamp });
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+enum LabelValue {
+    Str(String),
+    Bool(bool),
+    Int(i128),
+    UInt(u128),
+    F32(f32),
+    F64(f64),
+}
+
+struct FrozenLabel {
+    name: String,
+    value: LabelValue,
+}
+
+struct FrozenLabelSet {
+    labels: Vec<FrozenLabel>,
+}
+
+impl FrozenLabelSet {
+    fn record(label_set: &dyn EncodeLabelSet) -> Result<Self> {
+        let mut recorder = LabelSetRecorder { labels: Vec::new() };
+        label_set.encode(&mut recorder)?;
+        Ok(Self { labels: recorder.labels })
+    }
+}
+
+impl EncodeLabelSet for FrozenLabelSet {
+    fn encode(&self, encoder: &mut dyn LabelSetEncoder) -> Result<()> {
+        let labels = self.labels.iter().map(|label| label as &dyn EncodeLabel).collect::<Vec<_>>();
+        encoder.encode_all(&labels)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+impl EncodeLabel for FrozenLabel {
+    fn encode(&self, encoder: &mut dyn LabelEncoder) -> Result<()> {
+        encoder.encode_label_name(&self.name)?;
+        match &self.value {
+            LabelValue::Str(value) => encoder.encode_str_value(value),
+            LabelValue::Bool(value) => encoder.encode_bool_value(*value),
+            LabelValue::Int(value) => encoder.encode_i128_value(*value),
+            LabelValue::UInt(value) => encoder.encode_u128_value(*value),
+            LabelValue::F32(value) => encoder.encode_f32_value(*value),
+            LabelValue::F64(value) => encoder.encode_f64_value(*value),
+        }
+    }
+}
+
+struct LabelSetRecorder {
+    labels: Vec<FrozenLabel>,
+}
+
+impl LabelSetEncoder for LabelSetRecorder {
+    fn encode(&mut self, label: &dyn EncodeLabel) -> Result<()> {
+        let mut recorder = LabelRecorder { name: None, value: None };
+        label.encode(&mut recorder)?;
+        match recorder {
+            LabelRecorder { name: Some(name), value: Some(value) } => {
+                self.labels.push(FrozenLabel { name, value });
+            },
+            // skipped label
+            LabelRecorder { name: None, value: None } => {},
+            _ => return Err(Error::unexpected("label must have both a name and a value")),
+        }
+        Ok(())
+    }
+}
+
+struct LabelRecorder {
+    name: Option<String>,
+    value: Option<LabelValue>,
+}
+
+impl LabelRecorder {
+    fn value(&mut self, value: LabelValue) -> Result<()> {
+        self.value = Some(value);
+        Ok(())
+    }
+}
+
+impl LabelEncoder for LabelRecorder {
+    fn encode_label_name(&mut self, name: &str) -> Result<()> {
+        self.name = Some(name.to_owned());
+        Ok(())
+    }
+
+    fn encode_str_value(&mut self, value: &str) -> Result<()> {
+        self.value(LabelValue::Str(value.to_owned()))
+    }
+
+    fn encode_bool_value(&mut self, value: bool) -> Result<()> {
+        self.value(LabelValue::Bool(value))
+    }
+
+    fn encode_i8_value(&mut self, value: i8) -> Result<()> {
+        self.value(LabelValue::Int(value.into()))
+    }
+
+    fn encode_i16_value(&mut self, value: i16) -> Result<()> {
+        self.value(LabelValue::Int(value.into()))
+    }
+
+    fn encode_i32_value(&mut self, value: i32) -> Result<()> {
+        self.value(LabelValue::Int(value.into()))
+    }
+
+    fn encode_i64_value(&mut self, value: i64) -> Result<()> {
+        self.value(LabelValue::Int(value.into()))
+    }
+
+    fn encode_i128_value(&mut self, value: i128) -> Result<()> {
+        self.value(LabelValue::Int(value))
+    }
+
+    fn encode_isize_value(&mut self, value: isize) -> Result<()> {
+        self.value(LabelValue::Int(value as i128))
+    }
+
+    fn encode_u8_value(&mut self, value: u8) -> Result<()> {
+        self.value(LabelValue::UInt(value.into()))
+    }
+
+    fn encode_u16_value(&mut self, value: u16) -> Result<()> {
+        self.value(LabelValue::UInt(value.into()))
+    }
+
+    fn encode_u32_value(&mut self, value: u32) -> Result<()> {
+        self.value(LabelValue::UInt(value.into()))
+    }
+
+    fn encode_u64_value(&mut self, value: u64) -> Result<()> {
+        self.value(LabelValue::UInt(value.into()))
+    }
+
+    fn encode_u128_value(&mut self, value: u128) -> Result<()> {
+        self.value(LabelValue::UInt(value))
+    }
+
+    fn encode_usize_value(&mut self, value: usize) -> Result<()> {
+        self.value(LabelValue::UInt(value as u128))
+    }
+
+    fn encode_f32_value(&mut self, value: f32) -> Result<()> {
+        self.value(LabelValue::F32(value))
+    }
+
+    fn encode_f64_value(&mut self, value: f64) -> Result<()> {
+        self.value(LabelValue::F64(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format::text::TextProfile,
+        metrics::{counter::Counter, family::Family, gauge::Gauge, histogram::Histogram},
+        raw::LabelSetSchema,
+    };
+
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct Kind(&'static str);
+
+    impl LabelSetSchema for Kind {
+        fn names() -> Option<&'static [&'static str]> {
+            Some(&["kind"])
+        }
+    }
+
+    impl EncodeLabelSet for Kind {
+        fn encode(&self, encoder: &mut dyn LabelSetEncoder) -> Result<()> {
+            encoder.encode(&("kind", self.0))
+        }
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_snapshot_is_frozen() -> Result<()> {
+        assert_send_sync::<RegistrySnapshot>();
+
+        let mut registry = Registry::builder().with_namespace("myapp").build()?;
+        let requests = <Counter>::default();
+        registry.register("requests", "Total requests", requests.clone())?;
+        let inflight = <Gauge>::default();
+        let db = registry.subsystem("db")?;
+        db.register("inflight", "In-flight queries", inflight.clone())?;
+        let latency = Histogram::new([0.1, 1.0]);
+        db.register("latency", "Query latency", latency.clone())?;
+        let errors = Family::<Kind, Counter>::default();
+        db.register("errors", "Total query errors", errors.clone())?;
+
+        requests.inc_by(3);
+        inflight.set(2);
+        latency.observe(0.5);
+        errors.with_or_new(&Kind("timeout"), |counter| counter.inc());
+
+        let snapshot = registry.snapshot()?;
+        let encode = |registry: &Registry| -> Result<String> {
+            let mut output = String::new();
+            text::encode(&mut output, registry, TextProfile::PrometheusV0_0_4)?;
+            Ok(output)
+        };
+
+        requests.inc();
+        inflight.dec();
+        latency.observe(5.0);
+        errors.with_or_new(&Kind("timeout"), |counter| counter.inc());
+        errors.with_or_new(&Kind("refused"), |counter| counter.inc());
+
+        // the snapshot is encoded on another thread
+        let frozen = std::thread::spawn(move || encode(&snapshot)).join().unwrap()?;
+        assert!(frozen.contains("myapp_requests 3\n"), "{frozen}");
+        assert!(frozen.contains("myapp_db_inflight 2\n"), "{frozen}");
+        assert!(frozen.contains("myapp_db_latency_count 1\n"), "{frozen}");
+        assert!(frozen.contains("myapp_db_errors{kind=\"timeout\"} 1\n"), "{frozen}");
+        assert!(!frozen.contains("refused"), "{frozen}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let mut registry = Registry::default();
+        let requests = <Counter>::default();
+        registry.register("requests", "Total requests", requests.clone()).unwrap();
+        registry.register("temperature", "Temperature", <Gauge>::default()).unwrap();
+        registry.register("legacy", "Legacy counter", <Counter>::default()).unwrap();
+
+        let before = registry.snapshot().unwrap();
+        assert!(Registry::diff(&before, &registry.snapshot().unwrap()).unwrap().is_empty());
+
+        requests.inc();
+        let after = registry.snapshot().unwrap();
+        let diff = Registry::diff(&before, &after).unwrap();
+        assert_eq!(diff, DiffReport { changed: vec!["requests".into()], ..Default::default() });
+
+        registry.deregister("legacy", None).unwrap();
+        registry
+            .register("connections", "Total connections", <Counter>::default())
+            .unwrap();
+        let diff = Registry::diff(&after, &registry.snapshot().unwrap()).unwrap();
+        assert_eq!(diff.added, ["connections"]);
+        assert_eq!(diff.removed, ["legacy"]);
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.to_string(), "+ connections\n- legacy\n");
+    }
+}
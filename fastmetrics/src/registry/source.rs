@@ -0,0 +1,162 @@
+use std::{borrow::Cow, sync::Arc};
+
+use super::Registry;
+use crate::{
+    encoder::MetricFamilyEncoder,
+    error::{Error, Result},
+};
+
+/// A collector producing metric families on demand, each time the registry is encoded.
+///
+/// Unlike registered metrics, the metric families of a source don't need to be known upfront,
+/// which makes sources a good fit for bridging external metric systems (e.g. metrics queried from
+/// a database or another runtime). Every family is passed to the [`MetricFamilyEncoder`] together
+/// with its [`Metadata`](crate::raw::Metadata); the namespace and constant labels of the registry
+/// the source is registered into are applied as for any other metric.
+///
+/// Closures with the signature of [`MetricSource::collect`] implement this trait as well.
+pub trait MetricSource: Send + Sync {
+    /// Encodes the current metric families of this source.
+    fn collect(&self, encoder: &mut dyn MetricFamilyEncoder) -> Result<()>;
+}
+
+impl<F> MetricSource for F
+where
+    F: Fn(&mut dyn MetricFamilyEncoder) -> Result<()> + Send + Sync,
+{
+    fn collect(&self, encoder: &mut dyn MetricFamilyEncoder) -> Result<()> {
+        self(encoder)
+    }
+}
+
+// source
+impl Registry {
+    /// Registers a [`MetricSource`] identified by `name` into [`Registry`].
+    ///
+    /// The source is queried every time the registry is encoded, after the registered metrics of
+    /// the same (sub)registry. Since the families of a source are only known at that point, they
+    /// are neither validated at registration time nor included in [`Registry::all_metrics`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fastmetrics::{
+    /// #     encoder::MetricFamilyEncoder,
+    /// #     error::Result,
+    /// #     format::text::{self, TextProfile},
+    /// #     metrics::gauge::ConstGauge,
+    /// #     raw::{Metadata, MetricType},
+    /// #     registry::Registry,
+    /// # };
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut registry = Registry::default();
+    ///
+    /// registry.register_source("db", |encoder: &mut dyn MetricFamilyEncoder| {
+    ///     // e.g. the result of `SELECT count(*) FROM sessions`
+    ///     let sessions = <ConstGauge>::new(3);
+    ///     let metadata = Metadata::new("db_sessions", "Open sessions", MetricType::Gauge, None);
+    ///     encoder.encode(&metadata, &sessions)
+    /// })?;
+    ///
+    /// let mut output = String::new();
+    /// text::encode(&mut output, &registry, TextProfile::default())?;
+    /// assert!(output.contains("db_sessions 3\n"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_source(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        source: impl MetricSource + 'static,
+    ) -> Result<&mut Self> {
+        self.check_unsealed()?;
+
+        let name = name.into();
+        if self.sources.iter().any(|(existing, _)| *existing == name) {
+            return Err(
+                Error::duplicated("metric source already exists").with_context("source", name)
+            );
+        }
+        self.sources.push((name, Arc::new(source)));
+        Ok(self)
+    }
+
+    /// Queries the sources of this registry (but not of its subsystems) with `encoder`.
+    pub(crate) fn collect_sources(&self, encoder: &mut dyn MetricFamilyEncoder) -> Result<()> {
+        for (_, source) in &self.sources {
+            source.collect(encoder)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        encoder::EncodeMetric,
+        format::text::{self, TextProfile},
+        metrics::{counter::Counter, family::Family, gauge::Gauge},
+        raw::{Metadata, MetricType},
+    };
+
+    struct ConnectionPools {
+        pools: Vec<(&'static str, i64)>,
+    }
+
+    impl MetricSource for ConnectionPools {
+        fn collect(&self, encoder: &mut dyn MetricFamilyEncoder) -> Result<()> {
+            let connections = Family::<Vec<(&'static str, &'static str)>, Gauge>::default();
+            for (pool, size) in &self.pools {
+                connections.with_or_new(&vec![("pool", *pool)], |gauge| gauge.set(*size));
+            }
+            let metadata =
+                Metadata::new("connections", "Open connections", MetricType::Gauge, None);
+            encoder.encode(&metadata, &connections as &dyn EncodeMetric)
+        }
+    }
+
+    fn encode(registry: &Registry) -> String {
+        let mut output = String::new();
+        text::encode(&mut output, registry, TextProfile::default()).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_register_source() {
+        let mut registry = Registry::builder().with_namespace("app").build().unwrap();
+        registry.register("requests", "Total requests", <Counter>::default()).unwrap();
+        registry
+            .subsystem("db")
+            .unwrap()
+            .register_source(
+                "pools",
+                ConnectionPools { pools: vec![("primary", 4), ("replica", 2)] },
+            )
+            .unwrap();
+
+        let output = encode(&registry);
+        assert!(output.contains("app_requests_total 0\n"));
+        assert!(output.contains("# TYPE app_db_connections gauge\n"));
+        assert!(output.contains("app_db_connections{pool=\"primary\"} 4\n"));
+        assert!(output.contains("app_db_connections{pool=\"replica\"} 2\n"));
+
+        // sources are collected again for every encoding, and frozen by snapshots
+        let snapshot = registry.snapshot().unwrap();
+        let mut lines = output.lines().collect::<Vec<_>>();
+        let snapshot_output = encode(&snapshot);
+        let mut snapshot_lines = snapshot_output.lines().collect::<Vec<_>>();
+        lines.sort_unstable();
+        snapshot_lines.sort_unstable();
+        assert_eq!(snapshot_lines, lines);
+
+        let err = registry
+            .subsystem("db")
+            .unwrap()
+            .register_source("pools", ConnectionPools { pools: vec![] })
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), crate::error::ErrorKind::Duplicated);
+    }
+}